@@ -0,0 +1,85 @@
+use macroquad::prelude::*;
+use crate::texture_viewer::TextureViewer;
+
+const MAP_SIZE: usize = 256;
+const MAP_SCALE: f32 = 1.5;
+const PADDING: f32 = 10.0;
+
+pub struct BgMapViewer {
+    pub show: bool,
+    pub window_pos: Vec2,
+    texture: Option<TextureViewer>,
+}
+
+impl Default for BgMapViewer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BgMapViewer {
+    pub fn new() -> Self {
+        Self {
+            show: false,
+            window_pos: Vec2::new(10.0, 200.0),
+            texture: None,
+        }
+    }
+
+    pub fn handle_input(&mut self) {
+        // Toggle background map viewer with F6
+        if is_key_pressed(KeyCode::F6) {
+            self.show = !self.show;
+        }
+    }
+
+    /// Draws a pre-rendered 256x256 map of shade values (viewport outline included).
+    /// Should be called after the frame has completed so the map reflects VBlank state.
+    /// `map_1` mirrors LCDC bit 3, `unsigned_tiles` mirrors LCDC bit 4.
+    pub fn draw(&mut self, map: &[u8], map_1: bool, unsigned_tiles: bool, scx: u8, scy: u8) {
+        if !self.show {
+            return;
+        }
+
+        let texture = self.texture.get_or_insert_with(|| TextureViewer::new(MAP_SIZE as u16, MAP_SIZE as u16));
+        texture.update_from_shades(map);
+        texture.upload();
+
+        let x = self.window_pos.x;
+        let y = self.window_pos.y;
+        let map_size = MAP_SIZE as f32 * MAP_SCALE;
+        let width = PADDING * 2.0 + map_size;
+        let height = PADDING * 2.0 + 50.0 + map_size + 20.0;
+
+        // Background
+        draw_rectangle(x, y, width, height, Color::new(0.2, 0.2, 0.2, 0.9));
+        draw_rectangle_lines(x, y, width, height, 2.0, WHITE);
+        draw_text("BG Map Viewer (F6)", x + PADDING, y + PADDING + 14.0, 18.0, WHITE);
+
+        let info_text = format!(
+            "Map {} (${}) Tiles ${} SCX:{:02X} SCY:{:02X}",
+            if map_1 { 1 } else { 0 },
+            if map_1 { "9C00" } else { "9800" },
+            if unsigned_tiles { "8000" } else { "8800" },
+            scx,
+            scy
+        );
+        draw_text(&info_text, x + PADDING, y + PADDING + 34.0, 14.0, LIGHTGRAY);
+
+        let map_x = x + PADDING;
+        let map_y = y + PADDING + 50.0;
+        texture.draw(map_x, map_y, MAP_SCALE);
+
+        // Hover readout of the tile map entry under the cursor
+        let mouse_pos = mouse_position();
+        let info_y = map_y + map_size + 16.0;
+        if mouse_pos.0 >= map_x && mouse_pos.0 < map_x + map_size &&
+           mouse_pos.1 >= map_y && mouse_pos.1 < map_y + map_size {
+            let tile_x = ((mouse_pos.0 - map_x) / (8.0 * MAP_SCALE)) as usize;
+            let tile_y = ((mouse_pos.1 - map_y) / (8.0 * MAP_SCALE)) as usize;
+            let entry_addr = if map_1 { 0x9C00 } else { 0x9800 } + tile_y * 32 + tile_x;
+            let text = format!("Tile ({:02}, {:02}) @ ${:04X}", tile_x, tile_y, entry_addr);
+            draw_text(&text, x + PADDING, info_y, 14.0, YELLOW);
+        }
+    }
+}
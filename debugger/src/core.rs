@@ -1,8 +1,49 @@
+use std::collections::HashMap;
+use std::path::Path;
+use rgb::rgb::memory::{MemAccess, MemorySnapshot, WatchKind};
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DebuggerState {
     Running,
     Paused,
     Stepping,
+    StepFrame, // Running until the next VBlank, then Paused
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RegisterName {
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    H,
+    L,
+}
+
+impl RegisterName {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "A" => Some(RegisterName::A),
+            "B" => Some(RegisterName::B),
+            "C" => Some(RegisterName::C),
+            "D" => Some(RegisterName::D),
+            "E" => Some(RegisterName::E),
+            "F" => Some(RegisterName::F),
+            "H" => Some(RegisterName::H),
+            "L" => Some(RegisterName::L),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a register assignment like `A=3F` (hex value, case-insensitive)
+pub fn parse_register_assignment(text: &str) -> Option<(RegisterName, u8)> {
+    let (name, value) = text.split_once('=')?;
+    let register = RegisterName::from_name(name.trim())?;
+    let value = u8::from_str_radix(value.trim(), 16).ok()?;
+    Some((register, value))
 }
 
 #[derive(Debug, Clone)]
@@ -32,6 +73,39 @@ pub struct MemoryInspection {
     pub timestamp: std::time::Instant,
 }
 
+/// A watched address that counts how often its value changes, checked once per frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeWatch {
+    pub address: u16,
+    pub last_value: u8,
+    pub previous_value: Option<u8>, // Value before the last change, None until one is seen
+    pub change_count: u64,
+    pub break_on_change: bool, // Pause the debugger when the value changes
+    primed: bool, // False until last_value has been read once
+}
+
+impl ChangeWatch {
+    pub fn new(address: u16, break_on_change: bool) -> Self {
+        Self {
+            address,
+            last_value: 0,
+            previous_value: None,
+            change_count: 0,
+            break_on_change,
+            primed: false,
+        }
+    }
+}
+
+pub const UNDO_DEPTH: usize = 20; // Snapshots kept on the debugger's undo stack
+
+/// Cartridge header fields pre-formatted for display, with any checksum warnings
+#[derive(Debug, Clone, Default)]
+pub struct RomInfo {
+    pub fields: Vec<(String, String)>,
+    pub warnings: Vec<String>,
+}
+
 pub struct Debugger {
     pub state: DebuggerState,
     pub step_count: u64,
@@ -39,9 +113,22 @@ pub struct Debugger {
     pub current_snapshot: Option<CpuSnapshot>,
     pub memory_watches: Vec<u16>,
     pub memory_values: Vec<MemoryInspection>,
+    pub change_watches: Vec<ChangeWatch>,
     pub breakpoints: Vec<u16>,
+    pub temporary_breakpoints: Vec<u16>, // Removed once hit
+    pub last_watchpoint_hit: Option<(u16, u8, WatchKind)>, // (address, value, access)
+    pub rom_info: Option<RomInfo>,
     pub instruction_history: Vec<(u16, u8)>, // (PC, opcode)
+    pub recent_accesses: Vec<MemAccess>, // Copy of the memory map's access log, oldest first
+    pub access_filter: (u16, u16), // Inclusive address range shown in the access panel
     pub history_size: usize,
+    pub memory_map_export_requested: bool, // Set by the UI, handled by the emulator which owns memory
+    // Applied to the CPU by the emulator at the end of the frame, then cleared
+    pub set_pc_request: Option<u16>,
+    pub set_register_request: HashMap<RegisterName, u8>,
+    // Memory before each recent step, newest last; the emulator pushes and restores them
+    pub undo_stack: Vec<MemorySnapshot>,
+    pub undo_requested: bool, // Set by the UI, handled by the emulator like the requests above
 }
 
 impl Debugger {
@@ -53,9 +140,20 @@ impl Debugger {
             current_snapshot: None,
             memory_watches: Vec::new(),
             memory_values: Vec::new(),
+            change_watches: Vec::new(),
             breakpoints: Vec::new(),
+            temporary_breakpoints: Vec::new(),
+            last_watchpoint_hit: None,
+            rom_info: None,
             instruction_history: Vec::new(),
+            recent_accesses: Vec::new(),
+            access_filter: (0x0000, 0xFFFF),
             history_size: 50,
+            memory_map_export_requested: false,
+            set_pc_request: None,
+            set_register_request: HashMap::new(),
+            undo_stack: Vec::new(),
+            undo_requested: false,
         }
     }
     
@@ -65,14 +163,30 @@ impl Debugger {
         self.step_count += 1;
     }
     
+    /// Runs until the emulator reports the next VBlank through `notify_vblank`, then pauses
+    pub fn step_frame(&mut self) {
+        self.state = DebuggerState::StepFrame;
+        self.instructions_to_run = None;
+    }
+    
+    /// Called by the emulator when the PPU enters VBlank; ends a frame step
+    pub fn notify_vblank(&mut self) {
+        if self.state == DebuggerState::StepFrame {
+            self.state = DebuggerState::Paused;
+        }
+    }
+    
     pub fn step_multiple(&mut self, count: u64) {
         self.state = DebuggerState::Stepping;
         self.instructions_to_run = Some(count);
     }
     
+    /// Resumes execution. The instruction history starts over so it only shows
+    /// instructions from the new run.
     pub fn resume(&mut self) {
         self.state = DebuggerState::Running;
         self.instructions_to_run = None;
+        self.instruction_history.clear();
     }
     
     pub fn pause(&mut self) {
@@ -82,7 +196,7 @@ impl Debugger {
     
     pub fn should_execute(&mut self) -> bool {
         match self.state {
-            DebuggerState::Running => true,
+            DebuggerState::Running | DebuggerState::StepFrame => true,
             DebuggerState::Paused => false,
             DebuggerState::Stepping => {
                 if let Some(count) = self.instructions_to_run {
@@ -102,6 +216,33 @@ impl Debugger {
         }
     }
     
+    /// Moves execution to `address` at the end of the frame, without the stack a CALL would push
+    pub fn set_pc(&mut self, address: u16) {
+        self.set_pc_request = Some(address);
+    }
+    
+    pub fn set_register(&mut self, register: RegisterName, value: u8) {
+        self.set_register_request.insert(register, value);
+    }
+    
+    /// True while a step, step-N or step-frame request is running
+    pub fn is_stepping(&self) -> bool {
+        matches!(self.state, DebuggerState::Stepping | DebuggerState::StepFrame)
+    }
+    
+    /// Saves memory from before a step, dropping the oldest snapshot past UNDO_DEPTH
+    pub fn push_undo(&mut self, snapshot: MemorySnapshot) {
+        if self.undo_stack.len() >= UNDO_DEPTH {
+            self.undo_stack.remove(0);
+        }
+        self.undo_stack.push(snapshot);
+    }
+    
+    /// The most recent snapshot, removed from the stack
+    pub fn pop_undo(&mut self) -> Option<MemorySnapshot> {
+        self.undo_stack.pop()
+    }
+    
     pub fn add_breakpoint(&mut self, address: u16) {
         if !self.breakpoints.contains(&address) {
             self.breakpoints.push(address);
@@ -112,8 +253,27 @@ impl Debugger {
         self.breakpoints.retain(|&bp| bp != address);
     }
     
+    /// Breaks at `address` the next time it is reached, then forgets it
+    pub fn add_temporary_breakpoint(&mut self, address: u16) {
+        if !self.temporary_breakpoints.contains(&address) {
+            self.temporary_breakpoints.push(address);
+        }
+    }
+    
     pub fn check_breakpoint(&self, pc: u16) -> bool {
-        self.breakpoints.contains(&pc)
+        self.breakpoints.contains(&pc) || self.temporary_breakpoints.contains(&pc)
+    }
+    
+    /// Like `check_breakpoint`, but consumes a temporary breakpoint at `pc`
+    pub fn hit_breakpoint(&mut self, pc: u16) -> bool {
+        let temporary_len = self.temporary_breakpoints.len();
+        self.temporary_breakpoints.retain(|&bp| bp != pc);
+        self.breakpoints.contains(&pc) || self.temporary_breakpoints.len() != temporary_len
+    }
+    
+    pub fn watchpoint_hit(&mut self, address: u16, value: u8, access: WatchKind) {
+        self.last_watchpoint_hit = Some((address, value, access));
+        self.pause();
     }
     
     pub fn add_memory_watch(&mut self, address: u16) {
@@ -126,9 +286,43 @@ impl Debugger {
         self.memory_watches.retain(|&addr| addr != address);
     }
     
+    /// Watches `address` for value changes; the first read only records its current value
+    pub fn add_change_watch(&mut self, address: u16, break_on_change: bool) {
+        if !self.change_watches.iter().any(|watch| watch.address == address) {
+            self.change_watches.push(ChangeWatch::new(address, break_on_change));
+        }
+    }
+    
+    pub fn remove_change_watch(&mut self, address: u16) {
+        self.change_watches.retain(|watch| watch.address != address);
+    }
+    
+    pub fn toggle_break_on_change(&mut self, address: u16) {
+        if let Some(watch) = self.change_watches.iter_mut().find(|watch| watch.address == address) {
+            watch.break_on_change = !watch.break_on_change;
+        }
+    }
+    
+    /// Records watched values and counts changes, pausing if a changed watch has
+    /// break-on-change set
     pub fn update_memory_watches<F>(&mut self, read_memory: F) 
     where F: Fn(u16) -> u8 
     {
+        let mut break_requested = false;
+        for watch in &mut self.change_watches {
+            let value = read_memory(watch.address);
+            if watch.primed && value != watch.last_value {
+                watch.previous_value = Some(watch.last_value);
+                watch.change_count += 1;
+                break_requested |= watch.break_on_change;
+            }
+            watch.last_value = value;
+            watch.primed = true;
+        }
+        if break_requested {
+            self.pause();
+        }
+        
         for &address in &self.memory_watches {
             let value = read_memory(address);
             self.memory_values.push(MemoryInspection {
@@ -160,4 +354,33 @@ impl Debugger {
     pub fn get_instruction_history(&self) -> &[(u16, u8)] {
         &self.instruction_history
     }
+    
+    pub fn update_access_log(&mut self, accesses: Vec<MemAccess>) {
+        self.recent_accesses = accesses;
+    }
+    
+    pub fn set_access_filter(&mut self, start: u16, end: u16) {
+        self.access_filter = (start.min(end), start.max(end));
+    }
+    
+    /// The newest `count` accesses inside the filter range, oldest first
+    pub fn filtered_accesses(&self, count: usize) -> Vec<MemAccess> {
+        let (start, end) = self.access_filter;
+        let mut accesses: Vec<MemAccess> = self.recent_accesses.iter().rev()
+            .filter(|access| (start..=end).contains(&access.addr))
+            .take(count)
+            .copied()
+            .collect();
+        accesses.reverse();
+        accesses
+    }
+    
+    /// Writes a color-coded HTML page of the whole address space, read through `read_fn`
+    pub fn export_memory_map_html(&self, path: &Path, read_fn: impl Fn(u16) -> u8) -> std::io::Result<()> {
+        let title = match self.current_snapshot {
+            Some(ref snapshot) => format!("Game Boy memory map (PC=${:04X})", snapshot.pc),
+            None => "Game Boy memory map".to_string(),
+        };
+        crate::memory_map_html::write_memory_map_html(path, &title, read_fn)
+    }
 }
\ No newline at end of file
@@ -1,5 +1,19 @@
 pub mod core;
 pub mod ui;
+pub mod sprite_viewer;
+pub mod texture_viewer;
+pub mod tile_viewer;
+pub mod bg_map_viewer;
+pub mod memory_editor;
+pub mod memory_map_html;
+pub mod mini_assembler;
 
 pub use core::*;
-pub use ui::*;
\ No newline at end of file
+pub use ui::*;
+pub use sprite_viewer::*;
+pub use texture_viewer::*;
+pub use tile_viewer::*;
+pub use bg_map_viewer::*;
+pub use memory_editor::*;
+pub use memory_map_html::*;
+pub use mini_assembler::*;
\ No newline at end of file
@@ -0,0 +1,81 @@
+use macroquad::prelude::*;
+
+pub const MEMORY_PAGE_SIZE: u16 = 256;
+const BYTES_PER_ROW: usize = 16;
+const ROW_HEIGHT: f32 = 16.0;
+const PADDING: f32 = 10.0;
+
+pub struct MemoryEditor {
+    pub show: bool,
+    pub page_start: u16,
+    pub window_pos: Vec2,
+    page: Vec<u8>,
+}
+
+impl Default for MemoryEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryEditor {
+    pub fn new() -> Self {
+        Self {
+            show: false,
+            page_start: 0xC000,
+            window_pos: Vec2::new(240.0, 300.0),
+            page: Vec::new(),
+        }
+    }
+
+    pub fn handle_input(&mut self) {
+        // Toggle memory editor with F10
+        if is_key_pressed(KeyCode::F10) {
+            self.show = !self.show;
+        }
+        if !self.show {
+            return;
+        }
+
+        // Page through the address space, wrapping at 0xFFFF
+        if is_key_pressed(KeyCode::PageDown) {
+            self.page_start = self.page_start.wrapping_add(MEMORY_PAGE_SIZE);
+        }
+        if is_key_pressed(KeyCode::PageUp) {
+            self.page_start = self.page_start.wrapping_sub(MEMORY_PAGE_SIZE);
+        }
+    }
+
+    /// Replaces the displayed page; `data` should be `MEMORY_PAGE_SIZE` bytes read from `page_start`.
+    /// Called once per frame by the emulator.
+    pub fn set_page(&mut self, data: Vec<u8>) {
+        self.page = data;
+    }
+
+    pub fn draw(&self) {
+        if !self.show {
+            return;
+        }
+
+        let x = self.window_pos.x;
+        let y = self.window_pos.y;
+        let rows = MEMORY_PAGE_SIZE as usize / BYTES_PER_ROW;
+        let width = PADDING * 2.0 + 400.0;
+        let height = PADDING * 2.0 + 40.0 + rows as f32 * ROW_HEIGHT;
+
+        // Background
+        draw_rectangle(x, y, width, height, Color::new(0.2, 0.2, 0.2, 0.9));
+        draw_rectangle_lines(x, y, width, height, 2.0, WHITE);
+        draw_text("Memory Editor (F10, PgUp/PgDn)", x + PADDING, y + PADDING + 14.0, 18.0, WHITE);
+
+        let mut current_y = y + PADDING + 40.0;
+        for (row, bytes) in self.page.chunks(BYTES_PER_ROW).enumerate() {
+            let row_addr = self.page_start.wrapping_add((row * BYTES_PER_ROW) as u16);
+            draw_text(format!("{:04X}:", row_addr), x + PADDING, current_y, 14.0, LIGHTGRAY);
+
+            let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+            draw_text(hex.join(" "), x + PADDING + 45.0, current_y, 14.0, WHITE);
+            current_y += ROW_HEIGHT;
+        }
+    }
+}
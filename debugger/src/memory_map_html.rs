@@ -0,0 +1,110 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const BYTES_PER_CELL: usize = 16;
+const CELLS_PER_ROW: usize = 16; // 256 bytes per table row
+
+/// A named area of the Game Boy address space
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoryRegion {
+    pub name: &'static str,
+    pub class: &'static str, // CSS class used for the region's color
+    pub start: u16,
+    pub end: u16, // Inclusive
+}
+
+pub const MEMORY_REGIONS: [MemoryRegion; 11] = [
+    MemoryRegion { name: "ROM bank 0", class: "rom0", start: 0x0000, end: 0x3FFF },
+    MemoryRegion { name: "ROM bank N", class: "romn", start: 0x4000, end: 0x7FFF },
+    MemoryRegion { name: "VRAM", class: "vram", start: 0x8000, end: 0x9FFF },
+    MemoryRegion { name: "External RAM", class: "eram", start: 0xA000, end: 0xBFFF },
+    MemoryRegion { name: "WRAM", class: "wram", start: 0xC000, end: 0xDFFF },
+    MemoryRegion { name: "Echo RAM", class: "echo", start: 0xE000, end: 0xFDFF },
+    MemoryRegion { name: "OAM", class: "oam", start: 0xFE00, end: 0xFE9F },
+    MemoryRegion { name: "Not usable", class: "unusable", start: 0xFEA0, end: 0xFEFF },
+    MemoryRegion { name: "I/O registers", class: "io", start: 0xFF00, end: 0xFF7F },
+    MemoryRegion { name: "HRAM", class: "hram", start: 0xFF80, end: 0xFFFE },
+    MemoryRegion { name: "IE", class: "ie", start: 0xFFFF, end: 0xFFFF },
+];
+
+const STYLE: &str = "\
+body { font-family: monospace; background: #222; color: #ddd; }
+table { border-collapse: collapse; }
+td { border: 1px solid #444; padding: 2px 4px; font-size: 10px; cursor: pointer; color: #000; }
+td.addr, td.label { background: #222; color: #ddd; cursor: default; }
+#info { position: fixed; top: 8px; right: 8px; padding: 8px; background: #333; border: 1px solid #888; white-space: pre; }
+.rom0 { background: #3c3; } .romn { background: #9e9; } .vram { background: #69f; }
+.eram { background: #ee5; } .wram { background: #f93; } .echo { background: #999; }
+.oam { background: #b7e; } .unusable { background: #666; } .io { background: #e55; }
+.hram { background: #5ee; } .ie { background: #fff; }
+";
+
+const SCRIPT: &str = "\
+function show(cell) { document.getElementById('info').textContent = cell.title + '\\n' + cell.dataset.bytes; }
+";
+
+pub fn region_for(address: u16) -> &'static MemoryRegion {
+    MEMORY_REGIONS.iter()
+        .find(|region| (region.start..=region.end).contains(&address))
+        .expect("memory regions cover the whole address space")
+}
+
+/// Renders the 64 KB address space as a self-contained HTML page: one table row per
+/// 256 bytes, each cell holding 16 bytes colored by region. Clicking a cell shows its bytes.
+pub fn memory_map_html(title: &str, read_fn: impl Fn(u16) -> u8) -> String {
+    let mut html = String::new();
+    let _ = write!(html, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n", title);
+    let _ = write!(html, "<style>\n{}</style>\n<script>\n{}</script>\n</head>\n<body>\n", STYLE, SCRIPT);
+    let _ = writeln!(html, "<h1>{}</h1>", title);
+    html.push_str("<div id=\"info\">Click a cell to show its bytes</div>\n");
+
+    // Legend
+    html.push_str("<p>");
+    for region in &MEMORY_REGIONS {
+        let _ = write!(html, "<span class=\"{}\" style=\"color: #000; padding: 0 4px\">{} ${:04X}-${:04X}</span> ",
+                       region.class, region.name, region.start, region.end);
+    }
+    html.push_str("</p>\n<table>\n");
+
+    for row_start in (0..=0xFFFFusize).step_by(BYTES_PER_CELL * CELLS_PER_ROW) {
+        let _ = write!(html, "<tr><td class=\"addr\">${:04X}</td>", row_start);
+        for cell in 0..CELLS_PER_ROW {
+            let cell_start = row_start + cell * BYTES_PER_CELL;
+            let region = region_for(cell_start as u16);
+            let bytes: Vec<String> = (cell_start..cell_start + BYTES_PER_CELL)
+                .map(|address| format!("{:02X}", read_fn(address as u16)))
+                .collect();
+
+            // Bytes from a different region than the cell's first byte (e.g. IE at $FFFF) get their own color
+            let mut contents = String::new();
+            for (offset, byte) in bytes.iter().enumerate() {
+                let byte_region = region_for((cell_start + offset) as u16);
+                if byte_region.class == region.class {
+                    let _ = write!(contents, "{} ", byte);
+                } else {
+                    let _ = write!(contents, "<span class=\"{}\">{}</span> ", byte_region.class, byte);
+                }
+            }
+
+            let _ = write!(html, "<td class=\"{}\" title=\"${:04X}-${:04X} {}\" data-bytes=\"{}\" onclick=\"show(this)\">{}</td>",
+                           region.class, cell_start, cell_start + BYTES_PER_CELL - 1, region.name,
+                           bytes.join(" "), contents.trim_end());
+        }
+
+        // Name the region(s) the row falls in
+        let names: Vec<&str> = MEMORY_REGIONS.iter()
+            .filter(|region| region.start as usize <= row_start + 0xFF && region.end as usize >= row_start)
+            .map(|region| region.name)
+            .collect();
+        let _ = writeln!(html, "<td class=\"label\">{}</td></tr>", names.join(", "));
+    }
+
+    html.push_str("</table>\n</body>\n</html>\n");
+    html
+}
+
+pub fn write_memory_map_html(path: &Path, title: &str, read_fn: impl Fn(u16) -> u8) -> io::Result<()> {
+    fs::write(path, memory_map_html(title, read_fn))
+}
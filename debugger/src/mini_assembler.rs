@@ -0,0 +1,211 @@
+// Mini Assembler
+// Assembles one line of Game Boy assembly, for injecting instructions from the REPL.
+// Covers the common loads, 8/16-bit arithmetic, stack, jump and flag instructions;
+// CB-prefixed instructions aren't supported.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operand {
+    R8(u8),        // B C D E H L (HL) A, in opcode order
+    R16(&'static str),
+    HlIncrement,   // (HL+)
+    HlDecrement,   // (HL-)
+    Indirect16(&'static str), // (BC) or (DE)
+    IndirectC,     // (C), i.e. 0xFF00 + C
+    Address(u16),  // (nn)
+    Number(i32),
+}
+
+const R8_NAMES: [&str; 8] = ["b", "c", "d", "e", "h", "l", "(hl)", "a"];
+const ALU_MNEMONICS: [&str; 8] = ["add", "adc", "sub", "sbc", "and", "xor", "or", "cp"];
+const CONDITIONS: [&str; 4] = ["nz", "z", "nc", "c"];
+
+/// Assembles one instruction like `ld a, 0x42` or `JP NZ, $C000` to its bytes. Numbers
+/// may be decimal, `0x` hex or `$` hex; `jr` takes a signed offset. None if the line
+/// isn't an instruction this assembler knows.
+pub fn assemble_line(line: &str) -> Option<Vec<u8>> {
+    let line = line.trim().to_ascii_lowercase();
+    let (mnemonic, rest) = line.split_once(char::is_whitespace).unwrap_or((&line, ""));
+    let args: Vec<&str> = rest.split(',').map(str::trim).filter(|arg| !arg.is_empty()).collect();
+
+    if let Some(opcode) = implied_opcode(mnemonic) {
+        return args.is_empty().then(|| opcode.to_vec());
+    }
+    if let Some(op) = ALU_MNEMONICS.iter().position(|&alu| alu == mnemonic) {
+        return assemble_alu(op as u8, &args);
+    }
+    match mnemonic {
+        "jp" | "jr" | "call" | "ret" => return assemble_flow(mnemonic, &args),
+        _ => {}
+    }
+
+    let operands: Option<Vec<Operand>> = args.iter().map(|arg| parse_operand(arg)).collect();
+    match (mnemonic, operands?.as_slice()) {
+        ("ld", [dest, src]) => assemble_ld(*dest, *src),
+        ("ldh", [Operand::R8(7), Operand::Address(addr)]) => high_ram_offset(*addr).map(|n| vec![0xF0, n]),
+        ("ldh", [Operand::Address(addr), Operand::R8(7)]) => high_ram_offset(*addr).map(|n| vec![0xE0, n]),
+        ("inc", [Operand::R8(r)]) => Some(vec![0x04 | r << 3]),
+        ("dec", [Operand::R8(r)]) => Some(vec![0x05 | r << 3]),
+        ("inc", [Operand::R16(rr)]) => pair_index(rr, "sp").map(|i| vec![0x03 | i << 4]),
+        ("dec", [Operand::R16(rr)]) => pair_index(rr, "sp").map(|i| vec![0x0B | i << 4]),
+        ("push", [Operand::R16(rr)]) => pair_index(rr, "af").map(|i| vec![0xC5 | i << 4]),
+        ("pop", [Operand::R16(rr)]) => pair_index(rr, "af").map(|i| vec![0xC1 | i << 4]),
+        ("rst", [Operand::Number(n)]) if (0..=0x38).contains(n) && n % 8 == 0 => Some(vec![0xC7 | *n as u8]),
+        _ => None,
+    }
+}
+
+fn implied_opcode(mnemonic: &str) -> Option<&'static [u8]> {
+    Some(match mnemonic {
+        "nop" => &[0x00],
+        "stop" => &[0x10, 0x00],
+        "halt" => &[0x76],
+        "di" => &[0xF3],
+        "ei" => &[0xFB],
+        "reti" => &[0xD9],
+        "rlca" => &[0x07],
+        "rrca" => &[0x0F],
+        "rla" => &[0x17],
+        "rra" => &[0x1F],
+        "daa" => &[0x27],
+        "cpl" => &[0x2F],
+        "scf" => &[0x37],
+        "ccf" => &[0x3F],
+        _ => return None,
+    })
+}
+
+fn parse_operand(arg: &str) -> Option<Operand> {
+    if let Some(r) = R8_NAMES.iter().position(|&name| name == arg) {
+        return Some(Operand::R8(r as u8));
+    }
+    let operand = match arg {
+        "bc" => Operand::R16("bc"),
+        "de" => Operand::R16("de"),
+        "hl" => Operand::R16("hl"),
+        "sp" => Operand::R16("sp"),
+        "af" => Operand::R16("af"),
+        "(hl+)" | "(hli)" => Operand::HlIncrement,
+        "(hl-)" | "(hld)" => Operand::HlDecrement,
+        "(bc)" => Operand::Indirect16("bc"),
+        "(de)" => Operand::Indirect16("de"),
+        "(c)" => Operand::IndirectC,
+        _ => match arg.strip_prefix('(').and_then(|inner| inner.strip_suffix(')')) {
+            Some(inner) => Operand::Address(u16::try_from(parse_number(inner)?).ok()?),
+            None => Operand::Number(parse_number(arg)?),
+        },
+    };
+    Some(operand)
+}
+
+fn parse_number(text: &str) -> Option<i32> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    let value = if let Some(hex) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix('$')) {
+        i32::from_str_radix(hex, 16).ok()?
+    } else {
+        digits.parse().ok()?
+    };
+    Some(if negative { -value } else { value })
+}
+
+fn byte(value: i32) -> Option<u8> {
+    (-128..=0xFF).contains(&value).then_some(value as u8)
+}
+
+fn word(value: i32) -> Option<[u8; 2]> {
+    u16::try_from(value).ok().map(u16::to_le_bytes)
+}
+
+// Index of a register pair in opcode order, where the last slot is SP or AF
+fn pair_index(pair: &str, last: &str) -> Option<u8> {
+    ["bc", "de", "hl", last].iter().position(|&name| name == pair).map(|i| i as u8)
+}
+
+// LDH accepts the full address, e.g. (0xFF44), or just the offset
+fn high_ram_offset(addr: u16) -> Option<u8> {
+    match addr {
+        0xFF00..=0xFFFF => Some(addr as u8),
+        0x00..=0xFF => Some(addr as u8),
+        _ => None,
+    }
+}
+
+fn assemble_ld(dest: Operand, src: Operand) -> Option<Vec<u8>> {
+    use Operand::*;
+    let bytes = match (dest, src) {
+        (R8(6), R8(6)) => return None, // That encoding is HALT
+        (R8(d), R8(s)) => vec![0x40 | d << 3 | s],
+        (R8(d), Number(n)) => vec![0x06 | d << 3, byte(n)?],
+        (R16(rr), Number(n)) => {
+            let [low, high] = word(n)?;
+            vec![0x01 | pair_index(rr, "sp")? << 4, low, high]
+        }
+        (Indirect16(rr), R8(7)) => vec![0x02 | pair_index(rr, "sp")? << 4],
+        (R8(7), Indirect16(rr)) => vec![0x0A | pair_index(rr, "sp")? << 4],
+        (HlIncrement, R8(7)) => vec![0x22],
+        (R8(7), HlIncrement) => vec![0x2A],
+        (HlDecrement, R8(7)) => vec![0x32],
+        (R8(7), HlDecrement) => vec![0x3A],
+        (IndirectC, R8(7)) => vec![0xE2],
+        (R8(7), IndirectC) => vec![0xF2],
+        (Address(addr), R8(7)) => [&[0xEA][..], &addr.to_le_bytes()].concat(),
+        (R8(7), Address(addr)) => [&[0xFA][..], &addr.to_le_bytes()].concat(),
+        (Address(addr), R16("sp")) => [&[0x08][..], &addr.to_le_bytes()].concat(),
+        (R16("sp"), R16("hl")) => vec![0xF9],
+        _ => return None,
+    };
+    Some(bytes)
+}
+
+// `add a, b`, `add b` and `add a, 0x10` all work; ADD also covers HL and SP
+fn assemble_alu(op: u8, args: &[&str]) -> Option<Vec<u8>> {
+    let operands: Vec<Operand> = args.iter().map(|arg| parse_operand(arg)).collect::<Option<_>>()?;
+    let src = match operands.as_slice() {
+        [src] | [Operand::R8(7), src] => *src,
+        [Operand::R16("hl"), Operand::R16(rr)] if op == 0 => return pair_index(rr, "sp").map(|i| vec![0x09 | i << 4]),
+        [Operand::R16("sp"), Operand::Number(n)] if op == 0 => return Some(vec![0xE8, i8::try_from(*n).ok()? as u8]),
+        _ => return None,
+    };
+    match src {
+        Operand::R8(r) => Some(vec![0x80 | op << 3 | r]),
+        Operand::Number(n) => Some(vec![0xC6 | op << 3, byte(n)?]),
+        _ => None,
+    }
+}
+
+// Jumps, calls and returns, whose "c" operand is the carry condition, not register C
+fn assemble_flow(mnemonic: &str, args: &[&str]) -> Option<Vec<u8>> {
+    let (condition, target) = match args {
+        [] => (None, None),
+        [only] => match CONDITIONS.iter().position(|&cc| cc == *only) {
+            Some(cc) if mnemonic == "ret" => (Some(cc as u8), None),
+            _ => (None, Some(*only)),
+        },
+        [cc, target] => (Some(CONDITIONS.iter().position(|&name| name == *cc)? as u8), Some(*target)),
+        _ => return None,
+    };
+
+    let bytes = match (mnemonic, condition, target) {
+        ("ret", None, None) => vec![0xC9],
+        ("ret", Some(cc), None) => vec![0xC0 | cc << 3],
+        ("jp", None, Some("hl" | "(hl)")) => vec![0xE9],
+        ("jp" | "call", _, Some(target)) => {
+            let [low, high] = word(parse_number(target)?)?;
+            let opcode = match (mnemonic, condition) {
+                ("jp", None) => 0xC3,
+                ("jp", Some(cc)) => 0xC2 | cc << 3,
+                (_, None) => 0xCD,
+                (_, Some(cc)) => 0xC4 | cc << 3,
+            };
+            vec![opcode, low, high]
+        }
+        ("jr", _, Some(target)) => {
+            let offset = i8::try_from(parse_number(target)?).ok()? as u8;
+            vec![condition.map_or(0x18, |cc| 0x20 | cc << 3), offset]
+        }
+        _ => return None,
+    };
+    Some(bytes)
+}
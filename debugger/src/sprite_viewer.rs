@@ -0,0 +1,136 @@
+use macroquad::prelude::*;
+use crate::texture_viewer::shade_color;
+use rgb::rgb::ppu::decode_tile_row;
+
+const SPRITE_COUNT: usize = 40;
+const SPRITE_SCALE: f32 = 3.0;
+const GRID_COLUMNS: usize = 8;
+const CELL_WIDTH: f32 = 8.0 * SPRITE_SCALE + 12.0;
+const CELL_HEIGHT: f32 = 16.0 * SPRITE_SCALE + 12.0;
+const PADDING: f32 = 10.0;
+
+pub struct SpriteViewer {
+    pub show: bool,
+    pub selected: Option<usize>,
+    pub window_pos: Vec2,
+}
+
+impl Default for SpriteViewer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpriteViewer {
+    pub fn new() -> Self {
+        Self {
+            show: false,
+            selected: None,
+            window_pos: Vec2::new(10.0, 10.0),
+        }
+    }
+
+    pub fn handle_input(&mut self) {
+        // Toggle sprite viewer with F7
+        if is_key_pressed(KeyCode::F7) {
+            self.show = !self.show;
+        }
+    }
+
+    /// Draws all 40 OAM entries with their tile graphics.
+    /// `tall_sprites` mirrors LCDC bit 2 (8x16 sprite mode).
+    pub fn draw(&mut self, oam: &[u8], vram: &[u8], obp0: u8, obp1: u8, tall_sprites: bool) {
+        if !self.show {
+            return;
+        }
+
+        let x = self.window_pos.x;
+        let y = self.window_pos.y;
+        let rows = SPRITE_COUNT.div_ceil(GRID_COLUMNS);
+        let width = PADDING * 2.0 + GRID_COLUMNS as f32 * CELL_WIDTH;
+        let height = PADDING * 2.0 + 30.0 + rows as f32 * CELL_HEIGHT + 60.0;
+
+        // Background
+        draw_rectangle(x, y, width, height, Color::new(0.2, 0.2, 0.2, 0.9));
+        draw_rectangle_lines(x, y, width, height, 2.0, WHITE);
+        draw_text("Sprite Viewer (F7)", x + PADDING, y + PADDING + 14.0, 18.0, WHITE);
+
+        let grid_y = y + PADDING + 30.0;
+        let sprite_height = if tall_sprites { 16 } else { 8 };
+        let mouse_pos = mouse_position();
+        let clicked = is_mouse_button_pressed(MouseButton::Left);
+
+        for index in 0..SPRITE_COUNT {
+            let base = index * 4;
+            if base + 3 >= oam.len() {
+                break;
+            }
+            let flags = oam[base + 3];
+            let tile = if tall_sprites { oam[base + 2] & 0xFE } else { oam[base + 2] };
+            let palette = if (flags & 0x10) != 0 { obp1 } else { obp0 };
+            let flip_x = (flags & 0x20) != 0;
+            let flip_y = (flags & 0x40) != 0;
+
+            let cell_x = x + PADDING + (index % GRID_COLUMNS) as f32 * CELL_WIDTH;
+            let cell_y = grid_y + (index / GRID_COLUMNS) as f32 * CELL_HEIGHT;
+
+            // Tile graphic, drawn with the sprite's own palette and flip flags
+            for row in 0..sprite_height {
+                let source_row = if flip_y { sprite_height - 1 - row } else { row };
+                let pixels = decode_tile_row(vram, tile as usize * 16, source_row);
+                for col in 0..8 {
+                    let color_index = pixels[if flip_x { 7 - col } else { col }];
+                    let shade = (palette >> (color_index * 2)) & 0x03;
+                    // Color 0 is transparent for sprites - draw it dimmed
+                    let color = if color_index == 0 { DARKGRAY } else { shade_color(shade) };
+                    draw_rectangle(
+                        cell_x + col as f32 * SPRITE_SCALE,
+                        cell_y + row as f32 * SPRITE_SCALE,
+                        SPRITE_SCALE,
+                        SPRITE_SCALE,
+                        color,
+                    );
+                }
+            }
+
+            let is_hovered = mouse_pos.0 >= cell_x && mouse_pos.0 <= cell_x + CELL_WIDTH &&
+                             mouse_pos.1 >= cell_y && mouse_pos.1 <= cell_y + CELL_HEIGHT;
+            if is_hovered && clicked {
+                self.selected = Some(index);
+            }
+
+            let outline = if self.selected == Some(index) { YELLOW } else { GRAY };
+            draw_rectangle_lines(cell_x - 1.0, cell_y - 1.0, 8.0 * SPRITE_SCALE + 2.0,
+                                 sprite_height as f32 * SPRITE_SCALE + 2.0, 1.0, outline);
+            draw_text(format!("{:02}", index), cell_x, cell_y + sprite_height as f32 * SPRITE_SCALE + 10.0, 12.0, WHITE);
+        }
+
+        // Details for the selected OAM slot
+        let info_y = grid_y + rows as f32 * CELL_HEIGHT + 10.0;
+        if let Some(index) = self.selected {
+            let base = index * 4;
+            if base + 3 < oam.len() {
+                let bytes_text = format!(
+                    "OAM #{:02} ${:04X}: {:02X} {:02X} {:02X} {:02X}",
+                    index, 0xFE00 + base, oam[base], oam[base + 1], oam[base + 2], oam[base + 3]
+                );
+                draw_text(&bytes_text, x + PADDING, info_y, 16.0, YELLOW);
+
+                let flags = oam[base + 3];
+                let detail_text = format!(
+                    "X:{} Y:{} Tile:{:02X} Pal:OBP{} FlipX:{} FlipY:{} BG:{}",
+                    oam[base + 1] as i16 - 8,
+                    oam[base] as i16 - 16,
+                    oam[base + 2],
+                    if (flags & 0x10) != 0 { 1 } else { 0 },
+                    if (flags & 0x20) != 0 { "1" } else { "0" },
+                    if (flags & 0x40) != 0 { "1" } else { "0" },
+                    if (flags & 0x80) != 0 { "1" } else { "0" }
+                );
+                draw_text(&detail_text, x + PADDING, info_y + 20.0, 14.0, WHITE);
+            }
+        } else {
+            draw_text("Click a sprite to inspect it", x + PADDING, info_y, 14.0, LIGHTGRAY);
+        }
+    }
+}
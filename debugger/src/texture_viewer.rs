@@ -0,0 +1,62 @@
+use macroquad::prelude::*;
+
+// DMG green shades, matching the main emulator screen
+pub fn shade_color(shade: u8) -> Color {
+    match shade {
+        0 => Color::new(0.616, 0.733, 0.059, 1.0),
+        1 => Color::new(0.541, 0.675, 0.059, 1.0),
+        2 => Color::new(0.188, 0.384, 0.188, 1.0),
+        3 => Color::new(0.063, 0.247, 0.063, 1.0),
+        4 => WHITE, // Overlay marker (BG map viewport)
+        _ => MAGENTA,
+    }
+}
+
+/// A CPU-side image of shade values (0-3) mirrored into a GPU texture.
+/// Debug viewers write pixels, then call `upload()` once per refresh.
+pub struct TextureViewer {
+    pub width: u16,
+    pub height: u16,
+    image: Image,
+    texture: Texture2D,
+}
+
+impl TextureViewer {
+    pub fn new(width: u16, height: u16) -> Self {
+        let image = Image::gen_image_color(width, height, shade_color(0));
+        let texture = Texture2D::from_image(&image);
+        texture.set_filter(FilterMode::Nearest);
+        Self { width, height, image, texture }
+    }
+
+    pub fn set_shade(&mut self, x: u32, y: u32, shade: u8) {
+        if x < self.width as u32 && y < self.height as u32 {
+            self.image.set_pixel(x, y, shade_color(shade));
+        }
+    }
+
+    /// Replace the whole image from a row-major buffer of shade values
+    pub fn update_from_shades(&mut self, shades: &[u8]) {
+        let width = self.width as usize;
+        for (i, &shade) in shades.iter().enumerate().take(width * self.height as usize) {
+            self.image.set_pixel((i % width) as u32, (i / width) as u32, shade_color(shade));
+        }
+    }
+
+    pub fn upload(&self) {
+        self.texture.update(&self.image);
+    }
+
+    pub fn draw(&self, x: f32, y: f32, scale: f32) {
+        draw_texture_ex(
+            &self.texture,
+            x,
+            y,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(vec2(self.width as f32 * scale, self.height as f32 * scale)),
+                ..Default::default()
+            },
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use macroquad::prelude::*;
+use crate::texture_viewer::TextureViewer;
+use rgb::rgb::ppu::decode_tile_row;
+
+const TILE_COUNT: usize = 384;
+const GRID_COLUMNS: usize = 24;
+const GRID_ROWS: usize = TILE_COUNT / GRID_COLUMNS;
+const TILE_SCALE: f32 = 2.0;
+const PADDING: f32 = 10.0;
+
+/// Maps a VRAM tile slot (0-383) to the tile ID the PPU would use to reach it.
+/// Unsigned mode (LCDC bit 4 = 1) addresses $8000-$8FFF, signed mode addresses
+/// $8800-$97FF with $9000 as tile 0. Returns None if the slot is unreachable.
+pub fn tile_id_for_slot(slot: usize, signed_addressing: bool) -> Option<u8> {
+    if signed_addressing {
+        match slot {
+            128..=255 => Some(slot as u8),        // $8800-$8FFF: IDs 0x80-0xFF (-128..-1)
+            256..=383 => Some((slot - 256) as u8), // $9000-$97FF: IDs 0x00-0x7F
+            _ => None,
+        }
+    } else if slot < 256 {
+        Some(slot as u8)
+    } else {
+        None
+    }
+}
+
+pub struct TileViewer {
+    pub show: bool,
+    pub signed_addressing: bool,
+    pub window_pos: Vec2,
+    texture: Option<TextureViewer>,
+}
+
+impl Default for TileViewer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TileViewer {
+    pub fn new() -> Self {
+        Self {
+            show: false,
+            signed_addressing: false,
+            window_pos: Vec2::new(370.0, 10.0),
+            texture: None,
+        }
+    }
+
+    pub fn handle_input(&mut self) {
+        // Toggle tile viewer with F8
+        if is_key_pressed(KeyCode::F8) {
+            self.show = !self.show;
+        }
+    }
+
+    /// Re-decodes all 384 tiles into the texture using the BG palette.
+    /// Called once per frame, after the frame has finished (VBlank).
+    fn refresh(&mut self, vram: &[u8], bgp: u8) {
+        let texture = self.texture.get_or_insert_with(|| {
+            TextureViewer::new((GRID_COLUMNS * 8) as u16, (GRID_ROWS * 8) as u16)
+        });
+
+        for slot in 0..TILE_COUNT {
+            let origin_x = (slot % GRID_COLUMNS) * 8;
+            let origin_y = (slot / GRID_COLUMNS) * 8;
+            for row in 0..8 {
+                let pixels = decode_tile_row(vram, slot * 16, row);
+                for (col, &color_index) in pixels.iter().enumerate() {
+                    let shade = (bgp >> (color_index * 2)) & 0x03;
+                    texture.set_shade((origin_x + col) as u32, (origin_y + row) as u32, shade);
+                }
+            }
+        }
+        texture.upload();
+    }
+
+    /// Draws the tile grid. `lcdc_unsigned` mirrors LCDC bit 4 so the
+    /// selected addressing mode can be compared against the live setting.
+    pub fn draw(&mut self, vram: &[u8], bgp: u8, lcdc_unsigned: bool) {
+        if !self.show {
+            return;
+        }
+
+        self.refresh(vram, bgp);
+
+        let x = self.window_pos.x;
+        let y = self.window_pos.y;
+        let grid_width = GRID_COLUMNS as f32 * 8.0 * TILE_SCALE;
+        let grid_height = GRID_ROWS as f32 * 8.0 * TILE_SCALE;
+        let width = PADDING * 2.0 + grid_width;
+        let height = PADDING * 2.0 + 60.0 + grid_height + 30.0;
+
+        // Background
+        draw_rectangle(x, y, width, height, Color::new(0.2, 0.2, 0.2, 0.9));
+        draw_rectangle_lines(x, y, width, height, 2.0, WHITE);
+        draw_text("Tile Viewer (F8)", x + PADDING, y + PADDING + 14.0, 18.0, WHITE);
+
+        // Addressing mode toggle
+        let button_x = x + PADDING;
+        let button_y = y + PADDING + 24.0;
+        let button_width = 120.0;
+        let button_height = 22.0;
+        let mode_text = if self.signed_addressing { "Signed $9000" } else { "Unsigned $8000" };
+        let mouse_pos = mouse_position();
+        let button_hovered = mouse_pos.0 >= button_x && mouse_pos.0 <= button_x + button_width &&
+                             mouse_pos.1 >= button_y && mouse_pos.1 <= button_y + button_height;
+        draw_rectangle(button_x, button_y, button_width, button_height,
+                       if button_hovered { LIGHTGRAY } else { GRAY });
+        draw_rectangle_lines(button_x, button_y, button_width, button_height, 1.0, WHITE);
+        draw_text(mode_text, button_x + 6.0, button_y + 16.0, 16.0, BLACK);
+        if button_hovered && is_mouse_button_pressed(MouseButton::Left) {
+            self.signed_addressing = !self.signed_addressing;
+        }
+
+        let matches_lcdc = self.signed_addressing != lcdc_unsigned;
+        let lcdc_text = format!("LCDC.4={}", if lcdc_unsigned { 1 } else { 0 });
+        draw_text(&lcdc_text, button_x + button_width + 10.0, button_y + 16.0, 16.0,
+                  if matches_lcdc { GREEN } else { ORANGE });
+
+        let grid_x = x + PADDING;
+        let grid_y = y + PADDING + 54.0;
+        if let Some(texture) = &self.texture {
+            texture.draw(grid_x, grid_y, TILE_SCALE);
+        }
+        draw_rectangle_lines(grid_x - 1.0, grid_y - 1.0, grid_width + 2.0, grid_height + 2.0, 1.0, GRAY);
+
+        // Hover tooltip with the tile index and address
+        let info_y = grid_y + grid_height + 20.0;
+        let tile_size = 8.0 * TILE_SCALE;
+        if mouse_pos.0 >= grid_x && mouse_pos.0 < grid_x + grid_width &&
+           mouse_pos.1 >= grid_y && mouse_pos.1 < grid_y + grid_height {
+            let col = ((mouse_pos.0 - grid_x) / tile_size) as usize;
+            let row = ((mouse_pos.1 - grid_y) / tile_size) as usize;
+            let slot = row * GRID_COLUMNS + col;
+
+            draw_rectangle_lines(grid_x + col as f32 * tile_size, grid_y + row as f32 * tile_size,
+                                 tile_size, tile_size, 1.0, YELLOW);
+
+            let id_text = match tile_id_for_slot(slot, self.signed_addressing) {
+                Some(id) => format!("ID ${:02X}", id),
+                None => "ID n/a".to_string(),
+            };
+            let tooltip = format!("Tile ${:03X} @ ${:04X}  {}", slot, 0x8000 + slot * 16, id_text);
+            draw_text(&tooltip, x + PADDING, info_y, 16.0, YELLOW);
+        } else {
+            draw_text("Hover a tile to inspect it", x + PADDING, info_y, 14.0, LIGHTGRAY);
+        }
+    }
+}
@@ -1,18 +1,45 @@
 use macroquad::prelude::*;
-use crate::core::{Debugger, DebuggerState, CpuSnapshot};
+use crate::core::{parse_register_assignment, Debugger, DebuggerState, CpuSnapshot};
+use rgb::rgb::memory::WatchKind;
+use crate::sprite_viewer::SpriteViewer;
+use crate::tile_viewer::TileViewer;
+use crate::bg_map_viewer::BgMapViewer;
+use crate::memory_editor::MemoryEditor;
 
 const DEBUGGER_WINDOW_WIDTH: f32 = 400.0;
 const DEBUGGER_WINDOW_HEIGHT: f32 = 600.0;
 const BUTTON_WIDTH: f32 = 80.0;
 const BUTTON_HEIGHT: f32 = 30.0;
 const PADDING: f32 = 10.0;
+const HISTORY_WIDTH: f32 = 220.0;
+const HISTORY_ROWS: usize = 20;
+const HISTORY_LINE_HEIGHT: f32 = 16.0;
+const ACCESS_LOG_ROWS: usize = 20;
+
+/// Debugger window placement, persisted between sessions by the emulator
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowState {
+    pub x: f32,
+    pub y: f32,
+    pub show: bool,
+}
 
 pub struct DebuggerUI {
     pub show: bool,
     pub input_buffer: String,
     pub memory_address_input: String,
+    pub memory_address_input_focused: bool,
     pub breakpoint_input: String,
+    pub set_pc_input: String, // Hex PC, or a register assignment like A=3F
+    pub set_pc_input_focused: bool,
+    pub set_pc_message: Option<(String, Color)>,
     pub window_pos: Vec2,
+    pub rom_info_expanded: bool,
+    pub history_scroll: usize, // Rows scrolled back from the newest instruction
+    pub sprite_viewer: SpriteViewer,
+    pub tile_viewer: TileViewer,
+    pub bg_map_viewer: BgMapViewer,
+    pub memory_editor: MemoryEditor,
 }
 
 impl DebuggerUI {
@@ -21,11 +48,34 @@ impl DebuggerUI {
             show: true,
             input_buffer: String::new(),
             memory_address_input: String::new(),
+            memory_address_input_focused: false,
             breakpoint_input: String::new(),
+            set_pc_input: String::new(),
+            set_pc_input_focused: false,
+            set_pc_message: None,
             window_pos: Vec2::new(650.0, 50.0),
+            rom_info_expanded: false,
+            history_scroll: 0,
+            sprite_viewer: SpriteViewer::new(),
+            tile_viewer: TileViewer::new(),
+            bg_map_viewer: BgMapViewer::new(),
+            memory_editor: MemoryEditor::new(),
         }
     }
     
+    pub fn get_window_state(&self) -> WindowState {
+        WindowState {
+            x: self.window_pos.x,
+            y: self.window_pos.y,
+            show: self.show,
+        }
+    }
+
+    pub fn set_window_state(&mut self, state: WindowState) {
+        self.window_pos = Vec2::new(state.x, state.y);
+        self.show = state.show;
+    }
+    
     pub fn draw(&mut self, debugger: &mut Debugger) {
         if !self.show {
             return;
@@ -49,6 +99,7 @@ impl DebuggerUI {
             DebuggerState::Running => "State: Running",
             DebuggerState::Paused => "State: Paused", 
             DebuggerState::Stepping => "State: Stepping",
+            DebuggerState::StepFrame => "State: Stepping frame",
         };
         draw_text(state_text, x + PADDING, current_y, 16.0, YELLOW);
         current_y += 25.0;
@@ -56,6 +107,14 @@ impl DebuggerUI {
         // Step count
         let step_text = format!("Steps: {}", debugger.step_count);
         draw_text(&step_text, x + PADDING, current_y, 16.0, WHITE);
+        current_y += 20.0;
+        
+        // Last triggered watchpoint
+        let watch_text = match debugger.last_watchpoint_hit {
+            Some((address, value, access)) => format!("Watchpoint: ${:04X} = ${:02X} ({:?})", address, value, access),
+            None => "Watchpoint: none".to_string(),
+        };
+        draw_text(&watch_text, x + PADDING, current_y, 16.0, ORANGE);
         current_y += 30.0;
         
         // Control buttons
@@ -76,11 +135,30 @@ impl DebuggerUI {
             debugger.resume();
         }
         
+        // Export memory map button
+        if self.draw_button("Export Map", x + PADDING + (BUTTON_WIDTH + 5.0) * 3.0, button_y, BUTTON_WIDTH, BUTTON_HEIGHT) {
+            debugger.memory_map_export_requested = true;
+        }
+        
+        // Step frame button, on a second row
+        current_y += BUTTON_HEIGHT + 5.0;
+        if self.draw_button("Step Frame", x + PADDING, current_y, BUTTON_WIDTH, BUTTON_HEIGHT) {
+            debugger.step_frame();
+        }
+        
+        // Undo button, restoring memory from before the last step
+        let undo_label = format!("Undo ({})", debugger.undo_stack.len());
+        if self.draw_button(&undo_label, x + PADDING + BUTTON_WIDTH + 5.0, current_y, BUTTON_WIDTH, BUTTON_HEIGHT) && !debugger.undo_stack.is_empty() {
+            debugger.undo_requested = true;
+        }
+        
         current_y += BUTTON_HEIGHT + 20.0;
         
         // Step N instructions input
         draw_text("Step N instructions:", x + PADDING, current_y, 16.0, WHITE);
+        draw_text("Set PC / reg (A=3F):", x + PADDING + 170.0, current_y, 16.0, WHITE);
         current_y += 20.0;
+        self.draw_set_pc(debugger, x + PADDING + 170.0, current_y);
         
         // Input field for step count (simplified - in real implementation would use proper input handling)
         draw_rectangle(x + PADDING, current_y, 100.0, 25.0, DARKGRAY);
@@ -147,11 +225,7 @@ impl DebuggerUI {
         draw_text("Address (hex):", x + PADDING, current_y, 14.0, WHITE);
         current_y += 18.0;
         
-        draw_rectangle(x + PADDING, current_y, 80.0, 25.0, DARKGRAY);
-        draw_rectangle_lines(x + PADDING, current_y, 80.0, 25.0, 1.0, WHITE);
-        draw_text(&self.memory_address_input, x + PADDING + 5.0, current_y + 17.0, 14.0, WHITE);
-        
-        current_y += 35.0;
+        current_y = self.draw_change_watches(debugger, x + PADDING, current_y);
         
         // Breakpoints section
         draw_text("Breakpoints:", x + PADDING, current_y, 16.0, YELLOW);
@@ -181,6 +255,235 @@ impl DebuggerUI {
                 break;
             }
         }
+
+        self.draw_rom_info(debugger, x, y + DEBUGGER_WINDOW_HEIGHT + 5.0);
+        let history_height = self.draw_instruction_history(debugger, x + DEBUGGER_WINDOW_WIDTH + 5.0, y);
+        self.draw_access_log(debugger, x + DEBUGGER_WINDOW_WIDTH + 5.0, y + history_height + 5.0);
+    }
+
+    /// Scrollable list of the most recently executed instructions, newest at the
+    /// bottom. While paused, clicking an entry sets a temporary breakpoint there.
+    /// Returns the panel height.
+    pub fn draw_instruction_history(&mut self, debugger: &mut Debugger, x: f32, y: f32) -> f32 {
+        let header_height = 30.0;
+
+        // Shrink the panel to fit when the window is too short for every row
+        let available_rows = ((screen_height() - y - header_height - PADDING * 2.0) / HISTORY_LINE_HEIGHT).max(1.0) as usize;
+        let visible_rows = HISTORY_ROWS.min(available_rows);
+        let height = header_height + PADDING + visible_rows as f32 * HISTORY_LINE_HEIGHT;
+
+        draw_rectangle(x, y, HISTORY_WIDTH, height, Color::new(0.2, 0.2, 0.2, 0.9));
+        draw_rectangle_lines(x, y, HISTORY_WIDTH, height, 2.0, WHITE);
+        draw_text("Instruction History", x + PADDING, y + PADDING + 14.0, 18.0, WHITE);
+
+        let history = debugger.get_instruction_history();
+        let max_scroll = history.len().saturating_sub(visible_rows);
+
+        let mouse_pos = mouse_position();
+        let panel_hovered = mouse_pos.0 >= x && mouse_pos.0 <= x + HISTORY_WIDTH &&
+                            mouse_pos.1 >= y && mouse_pos.1 <= y + height;
+        if panel_hovered {
+            let wheel = mouse_wheel().1;
+            if wheel > 0.0 {
+                self.history_scroll += 1;
+            } else if wheel < 0.0 {
+                self.history_scroll = self.history_scroll.saturating_sub(1);
+            }
+        }
+        self.history_scroll = self.history_scroll.min(max_scroll);
+
+        let end = history.len() - self.history_scroll;
+        let start = end.saturating_sub(visible_rows);
+        let current_pc = debugger.current_snapshot.as_ref().map(|snapshot| snapshot.pc);
+        let paused = debugger.state == DebuggerState::Paused;
+
+        let mut clicked = None;
+        let mut current_y = y + header_height;
+        for &(pc, opcode) in &history[start..end] {
+            let line_top = current_y;
+            current_y += HISTORY_LINE_HEIGHT;
+
+            if current_pc == Some(pc) {
+                draw_rectangle(x + 2.0, line_top + 2.0, HISTORY_WIDTH - 4.0, HISTORY_LINE_HEIGHT, Color::new(0.3, 0.5, 0.3, 0.9));
+            }
+            let color = if debugger.check_breakpoint(pc) { RED } else { WHITE };
+            draw_text(format!("PC=0x{:04X} op=0x{:02X}", pc, opcode), x + PADDING, current_y, 14.0, color);
+
+            let line_hovered = panel_hovered && mouse_pos.1 >= line_top && mouse_pos.1 < current_y;
+            if paused && line_hovered && is_mouse_button_pressed(MouseButton::Left) {
+                clicked = Some(pc);
+            }
+        }
+
+        if let Some(pc) = clicked {
+            debugger.add_temporary_breakpoint(pc);
+        }
+        height
+    }
+
+    // Last memory accesses inside the filter range, shown while paused
+    fn draw_access_log(&self, debugger: &Debugger, x: f32, y: f32) {
+        if debugger.state != DebuggerState::Paused || debugger.recent_accesses.is_empty() {
+            return;
+        }
+
+        let accesses = debugger.filtered_accesses(ACCESS_LOG_ROWS);
+        let header_height = 30.0;
+        let height = header_height + PADDING + accesses.len().max(1) as f32 * HISTORY_LINE_HEIGHT;
+
+        draw_rectangle(x, y, HISTORY_WIDTH, height, Color::new(0.2, 0.2, 0.2, 0.9));
+        draw_rectangle_lines(x, y, HISTORY_WIDTH, height, 2.0, WHITE);
+        let (start, end) = debugger.access_filter;
+        draw_text(format!("Accesses ${:04X}-${:04X}", start, end), x + PADDING, y + PADDING + 14.0, 18.0, WHITE);
+
+        let mut current_y = y + header_height;
+        if accesses.is_empty() {
+            draw_text("No accesses in range", x + PADDING, current_y + HISTORY_LINE_HEIGHT, 14.0, LIGHTGRAY);
+        }
+        for access in accesses {
+            current_y += HISTORY_LINE_HEIGHT;
+            let (label, color) = match access.kind {
+                WatchKind::Write => ("W", ORANGE),
+                _ => ("R", SKYBLUE),
+            };
+            let text = format!("{} ${:04X} = ${:02X}  PC=${:04X}", label, access.addr, access.val, access.pc);
+            draw_text(&text, x + PADDING, current_y, 14.0, color);
+        }
+    }
+
+    // Collapsible cartridge header panel below the main debugger window
+    fn draw_rom_info(&mut self, debugger: &Debugger, x: f32, y: f32) {
+        let Some(rom_info) = &debugger.rom_info else {
+            return;
+        };
+
+        let header_height = 24.0;
+        let body_height = if self.rom_info_expanded {
+            PADDING + (rom_info.fields.len() + rom_info.warnings.len()) as f32 * 16.0
+        } else {
+            0.0
+        };
+
+        draw_rectangle(x, y, DEBUGGER_WINDOW_WIDTH, header_height + body_height, Color::new(0.2, 0.2, 0.2, 0.9));
+        draw_rectangle_lines(x, y, DEBUGGER_WINDOW_WIDTH, header_height + body_height, 2.0, WHITE);
+
+        let title = if self.rom_info_expanded { "[-] ROM Info" } else { "[+] ROM Info" };
+        let title_color = if rom_info.warnings.is_empty() { WHITE } else { ORANGE };
+        draw_text(title, x + PADDING, y + 17.0, 16.0, title_color);
+
+        let mouse_pos = mouse_position();
+        let header_hovered = mouse_pos.0 >= x && mouse_pos.0 <= x + DEBUGGER_WINDOW_WIDTH &&
+                             mouse_pos.1 >= y && mouse_pos.1 <= y + header_height;
+        if header_hovered && is_mouse_button_pressed(MouseButton::Left) {
+            self.rom_info_expanded = !self.rom_info_expanded;
+        }
+
+        if !self.rom_info_expanded {
+            return;
+        }
+
+        let mut current_y = y + header_height + PADDING;
+        for (label, value) in &rom_info.fields {
+            draw_text(format!("{}:", label), x + PADDING, current_y, 14.0, LIGHTGRAY);
+            draw_text(value, x + PADDING + 130.0, current_y, 14.0, WHITE);
+            current_y += 16.0;
+        }
+        for warning in &rom_info.warnings {
+            draw_text(format!("Warning: {}", warning), x + PADDING, current_y, 14.0, ORANGE);
+            current_y += 16.0;
+        }
+    }
+    
+    // Address box with a button that adds a change watch, then one row per watch showing
+    // its previous and current value and change count. Returns the y below the list.
+    fn draw_change_watches(&mut self, debugger: &mut Debugger, x: f32, y: f32) -> f32 {
+        let (mouse_x, mouse_y) = mouse_position();
+        if is_mouse_button_pressed(MouseButton::Left) {
+            self.memory_address_input_focused = mouse_x >= x && mouse_x <= x + 80.0 && mouse_y >= y && mouse_y <= y + 25.0;
+        }
+        
+        let border = if self.memory_address_input_focused { YELLOW } else { WHITE };
+        draw_rectangle(x, y, 80.0, 25.0, DARKGRAY);
+        draw_rectangle_lines(x, y, 80.0, 25.0, 1.0, border);
+        draw_text(&self.memory_address_input, x + 5.0, y + 17.0, 14.0, WHITE);
+        
+        if self.draw_button("Watch", x + 90.0, y - 2.0, 60.0, 25.0) {
+            if let Ok(address) = u16::from_str_radix(&self.memory_address_input, 16) {
+                debugger.add_change_watch(address, false);
+                self.memory_address_input.clear();
+            }
+        }
+        
+        let mut current_y = y + 35.0;
+        let mut toggled = None;
+        let mut removed = None;
+        for watch in &debugger.change_watches {
+            let previous = match watch.previous_value {
+                Some(value) => format!("${:02X}", value),
+                None => "--".to_string(),
+            };
+            let text = format!("${:04X}: {} -> ${:02X} ({} changes)", watch.address, previous, watch.last_value, watch.change_count);
+            draw_text(&text, x, current_y, 14.0, if watch.change_count > 0 { ORANGE } else { WHITE });
+            
+            // "B" is highlighted while the watch breaks on change
+            let break_label = if watch.break_on_change { "B*" } else { "B" };
+            if self.draw_button(break_label, x + 250.0, current_y - 12.0, 24.0, 15.0) {
+                toggled = Some(watch.address);
+            }
+            if self.draw_button("X", x + 280.0, current_y - 12.0, 20.0, 15.0) {
+                removed = Some(watch.address);
+            }
+            current_y += 18.0;
+        }
+        
+        if let Some(address) = toggled {
+            debugger.toggle_break_on_change(address);
+        }
+        if let Some(address) = removed {
+            debugger.remove_change_watch(address);
+        }
+        current_y + 5.0
+    }
+    
+    // Hex input with buttons that queue a PC or register change for the emulator. Setting PC
+    // into ROM is allowed but flagged, since it skips whatever set up the code's caller.
+    fn draw_set_pc(&mut self, debugger: &mut Debugger, x: f32, y: f32) {
+        let (mouse_x, mouse_y) = mouse_position();
+        if is_mouse_button_pressed(MouseButton::Left) {
+            self.set_pc_input_focused = mouse_x >= x && mouse_x <= x + 80.0 && mouse_y >= y && mouse_y <= y + 25.0;
+        }
+        
+        let border = if self.set_pc_input_focused { YELLOW } else { WHITE };
+        draw_rectangle(x, y, 80.0, 25.0, DARKGRAY);
+        draw_rectangle_lines(x, y, 80.0, 25.0, 1.0, border);
+        draw_text(&self.set_pc_input, x + 5.0, y + 17.0, 16.0, WHITE);
+        
+        if self.draw_button("Set PC", x + 85.0, y - 2.0, 60.0, 25.0) {
+            self.set_pc_message = match u16::from_str_radix(&self.set_pc_input, 16) {
+                Ok(address) => {
+                    debugger.set_pc(address);
+                    if address < 0x8000 {
+                        Some((format!("Warning: PC set into ROM at ${:04X}", address), ORANGE))
+                    } else {
+                        Some((format!("PC set to ${:04X}", address), LIME))
+                    }
+                }
+                Err(_) => Some(("Expected a hex address".to_string(), RED)),
+            };
+        }
+        if self.draw_button("Set Reg", x + 150.0, y - 2.0, 60.0, 25.0) {
+            self.set_pc_message = match parse_register_assignment(&self.set_pc_input) {
+                Some((register, value)) => {
+                    debugger.set_register(register, value);
+                    Some((format!("{:?} set to ${:02X}", register, value), LIME))
+                }
+                None => Some(("Expected e.g. A=3F".to_string(), RED)),
+            };
+        }
+        
+        if let Some((ref message, color)) = self.set_pc_message {
+            draw_text(message, x, y + 37.0, 12.0, color);
+        }
     }
     
     fn draw_button(&self, text: &str, x: f32, y: f32, width: f32, height: f32) -> bool {
@@ -199,6 +502,11 @@ impl DebuggerUI {
         is_hovered && is_mouse_button_pressed(MouseButton::Left)
     }
     
+    /// True while a text box has keyboard focus, so typed keys shouldn't act as hotkeys
+    pub fn is_typing(&self) -> bool {
+        self.set_pc_input_focused || self.memory_address_input_focused
+    }
+
     pub fn handle_input(&mut self) {
         // Handle keyboard input for text fields
         // This is a simplified implementation - in a real debugger you'd want proper input handling
@@ -208,6 +516,37 @@ impl DebuggerUI {
             self.show = !self.show;
         }
         
+        self.sprite_viewer.handle_input();
+        self.tile_viewer.handle_input();
+        self.bg_map_viewer.handle_input();
+        self.memory_editor.handle_input();
+        
+        // Typing goes to the Set PC box while it has focus
+        if self.set_pc_input_focused {
+            while let Some(c) = get_char_pressed() {
+                if (c.is_ascii_hexdigit() || c == '=') && self.set_pc_input.len() < 5 {
+                    self.set_pc_input.push(c.to_ascii_uppercase());
+                }
+            }
+            if is_key_pressed(KeyCode::Backspace) {
+                self.set_pc_input.pop();
+            }
+            return;
+        }
+        
+        // Likewise for the change watch address box
+        if self.memory_address_input_focused {
+            while let Some(c) = get_char_pressed() {
+                if c.is_ascii_hexdigit() && self.memory_address_input.len() < 4 {
+                    self.memory_address_input.push(c.to_ascii_uppercase());
+                }
+            }
+            if is_key_pressed(KeyCode::Backspace) {
+                self.memory_address_input.pop();
+            }
+            return;
+        }
+        
         // Handle number input for step count (simplified)
         for key in [KeyCode::Key0, KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4,
                    KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9] {
@@ -0,0 +1,169 @@
+use debugger::{parse_register_assignment, region_for, Debugger, DebuggerState, RegisterName, UNDO_DEPTH};
+use rgb::rgb::memory::{MemAccess, MemorySnapshot, WatchKind};
+
+#[test]
+fn test_temporary_breakpoint_is_consumed_when_hit() {
+    let mut debugger = Debugger::new();
+    
+    debugger.add_temporary_breakpoint(0x0150);
+    assert!(debugger.check_breakpoint(0x0150));
+    assert!(!debugger.hit_breakpoint(0x0100));
+    
+    assert!(debugger.hit_breakpoint(0x0150));
+    assert!(!debugger.hit_breakpoint(0x0150)); // Gone after the first hit
+}
+
+#[test]
+fn test_permanent_breakpoint_survives_hit() {
+    let mut debugger = Debugger::new();
+    
+    debugger.add_breakpoint(0x0150);
+    assert!(debugger.hit_breakpoint(0x0150));
+    assert!(debugger.hit_breakpoint(0x0150));
+}
+
+#[test]
+fn test_resume_clears_instruction_history() {
+    let mut debugger = Debugger::new();
+    
+    debugger.record_instruction(0x0100, 0x00);
+    debugger.record_instruction(0x0101, 0xC3);
+    assert_eq!(debugger.get_instruction_history(), &[(0x0100, 0x00), (0x0101, 0xC3)]);
+    
+    debugger.pause();
+    debugger.resume();
+    assert!(debugger.get_instruction_history().is_empty());
+}
+
+#[test]
+fn test_filtered_accesses_returns_newest_in_range() {
+    let mut debugger = Debugger::new();
+    let access = |addr, val| MemAccess { kind: WatchKind::Write, addr, val, pc: 0x0150 };
+    debugger.update_access_log(vec![access(0xC000, 1), access(0xFF40, 2), access(0xC001, 3), access(0xC002, 4)]);
+    
+    debugger.set_access_filter(0xDFFF, 0xC000); // Reversed bounds are accepted
+    assert_eq!(debugger.access_filter, (0xC000, 0xDFFF));
+    assert_eq!(debugger.filtered_accesses(2), vec![access(0xC001, 3), access(0xC002, 4)]);
+    assert_eq!(debugger.filtered_accesses(10).len(), 3);
+}
+
+#[test]
+fn test_region_for_covers_address_space_edges() {
+    assert_eq!(region_for(0x0000).name, "ROM bank 0");
+    assert_eq!(region_for(0x4000).name, "ROM bank N");
+    assert_eq!(region_for(0x9FFF).name, "VRAM");
+    assert_eq!(region_for(0xFEA0).name, "Not usable");
+    assert_eq!(region_for(0xFF80).name, "HRAM");
+    assert_eq!(region_for(0xFFFF).name, "IE");
+}
+
+#[test]
+fn test_export_memory_map_html() {
+    let debugger = Debugger::new();
+    let path = std::env::temp_dir().join(format!("rgb_memory_map_{}.html", std::process::id()));
+    
+    // Low byte of the address makes every cell's contents predictable
+    debugger.export_memory_map_html(&path, |addr| addr as u8).unwrap();
+    let html = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert_eq!(html.matches("<tr>").count(), 256);
+    for name in ["ROM bank 0", "VRAM", "External RAM", "WRAM", "Echo RAM", "OAM", "I/O registers", "HRAM", "IE"] {
+        assert!(html.contains(name), "missing region {}", name);
+    }
+    assert!(html.contains("title=\"$8000-$800F VRAM\" data-bytes=\"00 01 02 03 04 05 06 07 08 09 0A 0B 0C 0D 0E 0F\""));
+    assert!(html.contains("<span class=\"ie\">FF</span>")); // $FFFF sits in the last HRAM cell
+}
+
+#[test]
+fn test_set_pc_and_register_requests() {
+    let mut debugger = Debugger::new();
+    assert_eq!(debugger.set_pc_request, None);
+    
+    debugger.set_pc(0x0150);
+    debugger.set_register(RegisterName::A, 0x3F);
+    debugger.set_register(RegisterName::A, 0x40); // Latest value wins
+    debugger.set_register(RegisterName::H, 0xC0);
+    
+    assert_eq!(debugger.set_pc_request.take(), Some(0x0150));
+    assert_eq!(debugger.set_register_request.len(), 2);
+    assert_eq!(debugger.set_register_request[&RegisterName::A], 0x40);
+}
+
+#[test]
+fn test_parse_register_assignment() {
+    assert_eq!(parse_register_assignment("A=3F"), Some((RegisterName::A, 0x3F)));
+    assert_eq!(parse_register_assignment("l = ff"), Some((RegisterName::L, 0xFF)));
+    assert_eq!(parse_register_assignment("HL=1234"), None);
+    assert_eq!(parse_register_assignment("B=100"), None);
+    assert_eq!(parse_register_assignment("0150"), None);
+}
+
+#[test]
+fn test_step_frame_runs_until_vblank() {
+    let mut debugger = Debugger::new();
+    debugger.pause();
+    debugger.step_frame();
+    assert!(debugger.should_execute());
+    assert!(debugger.should_execute());
+
+    debugger.notify_vblank();
+    assert_eq!(debugger.state, DebuggerState::Paused);
+    assert!(!debugger.should_execute());
+
+    // VBlank doesn't stop a normal run
+    debugger.resume();
+    debugger.notify_vblank();
+    assert_eq!(debugger.state, DebuggerState::Running);
+}
+
+#[test]
+fn test_change_watch_counts_changes_and_breaks() {
+    let mut debugger = Debugger::new();
+    debugger.add_change_watch(0xC000, false);
+    debugger.add_change_watch(0xC001, true);
+
+    let mut memory = [0u8; 2];
+    debugger.update_memory_watches(|addr| memory[(addr - 0xC000) as usize]);
+    assert_eq!(debugger.change_watches[0].change_count, 0, "the first read only records the value");
+    assert_eq!(debugger.state, DebuggerState::Running);
+
+    memory[0] = 0x05;
+    debugger.update_memory_watches(|addr| memory[(addr - 0xC000) as usize]);
+    let watch = &debugger.change_watches[0];
+    assert_eq!((watch.previous_value, watch.last_value, watch.change_count), (Some(0x00), 0x05, 1));
+    assert_eq!(debugger.state, DebuggerState::Running, "a watch without break-on-change doesn't pause");
+
+    debugger.update_memory_watches(|addr| memory[(addr - 0xC000) as usize]);
+    assert_eq!(debugger.change_watches[0].change_count, 1, "an unchanged value isn't counted");
+
+    memory[1] = 0x10;
+    debugger.update_memory_watches(|addr| memory[(addr - 0xC000) as usize]);
+    assert_eq!(debugger.change_watches[1].change_count, 1);
+    assert_eq!(debugger.state, DebuggerState::Paused);
+
+    debugger.toggle_break_on_change(0xC001);
+    assert!(!debugger.change_watches[1].break_on_change);
+    debugger.remove_change_watch(0xC000);
+    assert_eq!(debugger.change_watches.len(), 1);
+}
+
+#[test]
+fn test_undo_stack_keeps_the_newest_snapshots() {
+    let snapshot = |marker: u8| MemorySnapshot { contents: Box::new([marker; 65536]), hram: Vec::new(), ppu_registers: Vec::new() };
+    let mut debugger = Debugger::new();
+    for marker in 0..UNDO_DEPTH as u8 + 5 {
+        debugger.push_undo(snapshot(marker));
+    }
+
+    assert_eq!(debugger.undo_stack.len(), UNDO_DEPTH);
+    assert_eq!(debugger.undo_stack[0].contents[0], 5, "the oldest snapshots are dropped");
+    assert_eq!(debugger.pop_undo().unwrap().contents[0], UNDO_DEPTH as u8 + 4);
+    assert_eq!(debugger.undo_stack.len(), UNDO_DEPTH - 1);
+
+    debugger.step_one();
+    assert!(debugger.is_stepping());
+    debugger.pause();
+    assert!(!debugger.is_stepping());
+}
@@ -0,0 +1,48 @@
+use debugger::assemble_line;
+use rgb::rgb::instructions::{decode_instruction, get_instruction_size, ArgKind, InstructionKind};
+
+#[test]
+fn test_assemble_common_instructions() {
+    let cases: [(&str, &[u8]); 22] = [
+        ("nop", &[0x00]),
+        ("ld a, 0x42", &[0x3E, 0x42]),
+        ("LD B, $FF", &[0x06, 0xFF]),
+        ("ld (hl), a", &[0x77]),
+        ("ld a, (hl+)", &[0x2A]),
+        ("ld hl, 0xC000", &[0x21, 0x00, 0xC0]),
+        ("ld (0xFF42), a", &[0xEA, 0x42, 0xFF]),
+        ("ldh a, (0xFF44)", &[0xF0, 0x44]),
+        ("inc a", &[0x3C]),
+        ("dec (hl)", &[0x35]),
+        ("inc de", &[0x13]),
+        ("add a, b", &[0x80]),
+        ("sub 5", &[0xD6, 0x05]),
+        ("xor a", &[0xAF]),
+        ("cp (hl)", &[0xBE]),
+        ("add hl, sp", &[0x39]),
+        ("push af", &[0xF5]),
+        ("jp nz, 0x0150", &[0xC2, 0x50, 0x01]),
+        ("jr -2", &[0x18, 0xFE]),
+        ("call c, 0x1234", &[0xDC, 0x34, 0x12]),
+        ("ret nc", &[0xD0]),
+        ("rst 0x38", &[0xFF]),
+    ];
+    for (line, bytes) in cases {
+        assert_eq!(assemble_line(line).as_deref(), Some(bytes), "{}", line);
+    }
+}
+
+#[test]
+fn test_assembled_bytes_decode_back() {
+    let bytes = assemble_line("ld a, 0x42").unwrap();
+    assert_eq!(bytes.len(), get_instruction_size(bytes[0]) as usize);
+    let kind = decode_instruction(bytes[0], Some(bytes[1]), None);
+    assert_eq!(kind, InstructionKind::LD(ArgKind::A, ArgKind::Immediate(0x42)));
+}
+
+#[test]
+fn test_assemble_rejects_unknown_input() {
+    for line in ["", "mov a, b", "ld (hl), (hl)", "ld a, 0x100", "jr 200", "rst 3", "ld q, 1", "nop a"] {
+        assert_eq!(assemble_line(line), None, "{}", line);
+    }
+}
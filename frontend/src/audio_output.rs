@@ -0,0 +1,51 @@
+// Live audio output
+// Plays the APU's samples on the default output device through cpal. The device pulls
+// from the AudioSyncBuffer the emulator pushes each frame's samples into.
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample, Stream, StreamConfig};
+use std::sync::{Arc, Mutex};
+
+use rgb::rgb::apu::SAMPLE_RATE;
+use rgb::rgb::audio_sync::AudioSyncBuffer;
+
+pub struct AudioOutput {
+    _stream: Stream, // Playback stops when the stream is dropped
+}
+
+impl AudioOutput {
+    /// Starts playing samples from `buffer` at the APU's 44100 Hz, with the mono signal
+    /// copied to every channel of the device
+    pub fn open(buffer: Arc<Mutex<AudioSyncBuffer>>) -> Result<Self, String> {
+        let device = cpal::default_host().default_output_device().ok_or("no output device")?;
+        let supported = device.supported_output_configs().map_err(|e| e.to_string())?
+            .find(|range| (range.min_sample_rate().0..=range.max_sample_rate().0).contains(&SAMPLE_RATE))
+            .ok_or_else(|| format!("device doesn't support {} Hz", SAMPLE_RATE))?
+            .with_sample_rate(cpal::SampleRate(SAMPLE_RATE));
+        let config = supported.config();
+        let stream = match supported.sample_format() {
+            SampleFormat::F32 => build_stream::<f32>(&device, &config, buffer),
+            SampleFormat::I16 => build_stream::<i16>(&device, &config, buffer),
+            SampleFormat::U16 => build_stream::<u16>(&device, &config, buffer),
+            format => return Err(format!("unsupported sample format {}", format)),
+        }?;
+        stream.play().map_err(|e| e.to_string())?;
+        Ok(Self { _stream: stream })
+    }
+}
+
+fn build_stream<T: SizedSample + FromSample<i16>>(device: &cpal::Device, config: &StreamConfig, buffer: Arc<Mutex<AudioSyncBuffer>>) -> Result<Stream, String> {
+    let channels = config.channels as usize;
+    device.build_output_stream(
+        config,
+        move |data: &mut [T], _| {
+            let mut buffer = buffer.lock().unwrap();
+            let samples = buffer.drain(data.len() / channels);
+            for (frame, &sample) in data.chunks_mut(channels).zip(samples) {
+                frame.fill(T::from_sample(sample));
+            }
+        },
+        |e| eprintln!("Warning: audio output error: {}", e),
+        None,
+    ).map_err(|e| e.to_string())
+}
@@ -0,0 +1,80 @@
+// Gamepad input
+// Reads controllers through gilrs and feeds their events into GamepadState, which picks
+// the active controller and holds its inputs for config.gamepad to map
+
+use gilrs::{Axis, Button, Event, EventType, Gilrs};
+
+use rgb::rgb::gamepad::{GamepadAxis, GamepadInput, GamepadState};
+
+pub struct GamepadInputReader {
+    gilrs: Gilrs,
+}
+
+impl GamepadInputReader {
+    /// Starts listening for controllers and registers the ones already connected
+    pub fn open(state: &mut GamepadState) -> Result<Self, String> {
+        let gilrs = Gilrs::new().map_err(|e| e.to_string())?;
+        for (id, gamepad) in gilrs.gamepads() {
+            state.connect(id.into(), gamepad.name());
+        }
+        Ok(Self { gilrs })
+    }
+
+    /// Applies every event since the last call; called once per frame
+    pub fn poll(&mut self, state: &mut GamepadState) {
+        while let Some(Event { id: gamepad_id, event, .. }) = self.gilrs.next_event() {
+            let id = usize::from(gamepad_id);
+            match event {
+                EventType::Connected => {
+                    let name = self.gilrs.gamepad(gamepad_id).name().to_string();
+                    state.connect(id, &name);
+                }
+                EventType::Disconnected => state.disconnect(id),
+                EventType::ButtonPressed(button, _) => {
+                    if let Some(input) = input_for_button(button) {
+                        state.set_button(id, input, true);
+                    }
+                }
+                EventType::ButtonReleased(button, _) => {
+                    if let Some(input) = input_for_button(button) {
+                        state.set_button(id, input, false);
+                    }
+                }
+                EventType::AxisChanged(axis, value, _) => {
+                    if let Some(axis) = stick_axis(axis) {
+                        state.set_axis(id, axis, value);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn input_for_button(button: Button) -> Option<GamepadInput> {
+    match button {
+        Button::South => Some(GamepadInput::South),
+        Button::East => Some(GamepadInput::East),
+        Button::North => Some(GamepadInput::North),
+        Button::West => Some(GamepadInput::West),
+        Button::LeftTrigger => Some(GamepadInput::LeftTrigger),
+        Button::RightTrigger => Some(GamepadInput::RightTrigger),
+        Button::Select => Some(GamepadInput::Select),
+        Button::Start => Some(GamepadInput::Start),
+        Button::DPadUp => Some(GamepadInput::DPadUp),
+        Button::DPadDown => Some(GamepadInput::DPadDown),
+        Button::DPadLeft => Some(GamepadInput::DPadLeft),
+        Button::DPadRight => Some(GamepadInput::DPadRight),
+        _ => None,
+    }
+}
+
+fn stick_axis(axis: Axis) -> Option<GamepadAxis> {
+    match axis {
+        Axis::LeftStickX => Some(GamepadAxis::LeftStickX),
+        Axis::LeftStickY => Some(GamepadAxis::LeftStickY),
+        Axis::RightStickX => Some(GamepadAxis::RightStickX),
+        Axis::RightStickY => Some(GamepadAxis::RightStickY),
+        _ => None,
+    }
+}
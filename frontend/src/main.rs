@@ -0,0 +1,1354 @@
+mod audio_output;
+mod gamepad_input;
+mod rom_browser;
+
+use macroquad::prelude::*;
+use rgb::rgb::cpu::{Cpu, FrameEnd};
+use rgb::rgb::memory::{MemoryMap, WatchKind};
+use rgb::rgb::HardwareModel;
+use rgb::rgb::ppu::Ppu;
+use rgb::rgb::printer::GameBoyPrinter;
+use rgb::rgb::apu;
+use rgb::rgb::audio_sync::{AudioSyncBuffer, SAMPLES_PER_FRAME};
+use rgb::rgb::gamepad::GamepadState;
+use rgb::rgb::joypad::SgbMode;
+use rgb::rgb::rom_db;
+use rgb::rgb::memory_dump::MemoryDump;
+use rgb::rgb::frame_timer::{FpsCounter, FrameTimer, TimingStats, FRAME_DURATION};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use audio_output::AudioOutput;
+use gamepad_input::GamepadInputReader;
+use std::cell::Cell;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use debugger::{assemble_line, Debugger, DebuggerUI, RegisterName, RomInfo, WindowState, MEMORY_PAGE_SIZE};
+use rgb::rgb::cart::CartridgeHeader;
+use rgb::rgb::config::{EmulatorConfig, CONFIG_PATH};
+use rgb::rgb::profiler::OpcodeProfile;
+use rgb::rgb::state_json::{self, StateJsonError};
+use rgb::rgb::trace_ring::{RingTraceBuffer, TraceEntry, DEFAULT_TRACE_RING_SIZE};
+
+const PROFILE_TOP_OPCODES: usize = 30; // Rows in the --profile report
+#[cfg(feature = "memory_profiling")]
+const PROFILE_TOP_ADDRESSES: usize = 20; // Rows in each --profile memory table
+const REPL_MAX_CYCLES: u64 = 70224; // One frame; injected code that jumps away stops here
+
+struct GameBoyEmulator {
+    cpu: Cpu,
+    #[cfg(debug_assertions)]
+    trace_writer: Option<BufWriter<File>>,
+    #[cfg(debug_assertions)]
+    trace_json: bool,
+    #[cfg(debug_assertions)]
+    instruction_count: u64,
+    debugger: Option<Debugger>,
+    debugger_ui: Option<DebuggerUI>,
+    frame_timer: FrameTimer,
+    benchmark_mode: bool,
+    config: EmulatorConfig,
+    // Where the config is saved on exit; None for emulators built from in-memory ROMs
+    config_path: Option<PathBuf>,
+    // Samples collected for --audio-dump, written as a WAV file when the emulator is dropped
+    audio_dump: Option<(PathBuf, Vec<i16>)>,
+    // Paces APU output for the audio device; its underrun/overrun counts go in the HUD.
+    // Shared with the device's callback, hence the Mutex
+    audio_sync: Arc<Mutex<AudioSyncBuffer>>,
+    // Live playback, opened with the window; None while headless or without a device
+    audio_output: Option<AudioOutput>,
+    // Inputs held on the active controller, pressed through config.gamepad
+    gamepad: GamepadState,
+    // Opcode counts for --profile, reported on exit to the path or stdout
+    opcode_profile: Option<(OpcodeProfile, Option<PathBuf>)>,
+    // Last instructions executed, printed by the panic hook; shared with it, hence the Mutex
+    trace_ring: Option<Arc<Mutex<RingTraceBuffer>>>,
+}
+
+// Creates a CPU with the cartridge loaded, ready to run from the boot ROM or the entry point
+fn build_cpu(rom_path: &str, config: &EmulatorConfig) -> Cpu {
+    build_cpu_with(config, |mmap| mmap.load_cartridge(std::path::Path::new(rom_path)))
+}
+
+// Like build_cpu, but with the cartridge ROM supplied as bytes instead of a file
+fn build_cpu_from_bytes(data: &[u8], config: &EmulatorConfig) -> Cpu {
+    build_cpu_with(config, |mmap| mmap.load_cartridge_from_bytes(data.to_vec()))
+}
+
+// A config.hardware_model of None picks the model from the cartridge header's CGB flag
+fn build_cpu_with(config: &EmulatorConfig, load_cartridge: impl FnOnce(&mut MemoryMap)) -> Cpu {
+    let mut cpu = Cpu::new_with_config(config);
+    
+    load_cartridge(&mut cpu.mmap);
+    if cpu.mmap.cart_header_checksum_valid() == Some(false) {
+        // Loaded anyway: ROM hacks often leave the checksum stale
+        eprintln!("Warning: header checksum mismatch; a real boot ROM would lock up at the logo");
+    }
+    cpu.hardware_model = config.hardware_model.unwrap_or_else(|| {
+        cpu.mmap.get_cart_header().map_or(HardwareModel::Dmg, |header| HardwareModel::from_cgb_flag(header.cgb_flag))
+    });
+    
+    if config.skip_boot {
+        // Post-boot registers for the detected model, starting at the cartridge entry point
+        cpu.reset_to_post_boot();
+    } else {
+        // Set initial state for Game Boy boot sequence
+        cpu.pc = 0x0000;  // Start at bootstrap ROM
+        cpu.sp = 0xFFFE;  // Initial stack pointer
+    }
+    cpu
+}
+
+impl GameBoyEmulator {
+    fn new(rom_path: &str, trace_file: Option<String>, trace_json: bool, enable_debugger: bool, watchpoints: &[(u16, WatchKind)], benchmark_mode: bool, config: EmulatorConfig) -> Self {
+        // Benchmark runs measure throughput, so silence the debug-build diagnostics
+        rgb::rgb::diagnostics::set_debug_output(!benchmark_mode);
+        
+        let cpu = build_cpu(rom_path, &config);
+        let mut emulator = Self::with_cpu(cpu, trace_file, trace_json, enable_debugger, watchpoints, benchmark_mode, config);
+        emulator.config_path = Some(PathBuf::from(CONFIG_PATH));
+        emulator
+    }
+
+    /// Builds an emulator around an in-memory ROM without touching the filesystem:
+    /// no trace, debugger or saved config. Without `skip_boot` the boot ROM is still
+    /// read from test-roms/dmg_boot.bin.
+    #[allow(dead_code)] // Public API method
+    fn from_rom_bytes(data: &[u8], skip_boot: bool) -> Self {
+        let config = EmulatorConfig { skip_boot, ..EmulatorConfig::default() };
+        let cpu = build_cpu_from_bytes(data, &config);
+        Self::with_cpu(cpu, None, false, false, &[], false, config)
+    }
+
+    fn with_cpu(mut cpu: Cpu, trace_file: Option<String>, trace_json: bool, enable_debugger: bool, watchpoints: &[(u16, WatchKind)], benchmark_mode: bool, config: EmulatorConfig) -> Self {
+        for &(address, kind) in watchpoints {
+            cpu.mmap.add_watchpoint(address, kind);
+        }
+        
+        #[cfg(debug_assertions)]
+        let trace_writer = if let Some(trace_path) = trace_file {
+            match File::create(&trace_path) {
+                Ok(file) => {
+                    let mut writer = BufWriter::new(file);
+                    if trace_json {
+                        // Write JSON array opening
+                        writeln!(writer, "[").unwrap();
+                    }
+                    Some(writer)
+                }
+                Err(e) => {
+                    eprintln!("Warning: Failed to create trace file '{}': {}", trace_path, e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        
+        // Identify the dump by hash, since the header title may be blank or corrupted
+        let rom_hash = cpu.mmap.get_cart_rom().map(rom_db::sha256_hex);
+        let known_rom = rom_hash.as_deref().and_then(rom_db::lookup_hash);
+        if let Some(ref hash) = rom_hash {
+            #[cfg(debug_assertions)]
+            eprintln!("ROM SHA-256: {}", hash);
+            match known_rom {
+                Some(info) => println!("Verified ROM: {} ({}, revision {})", info.title, info.region, info.revision),
+                None => println!("Unknown ROM (hash: {})", hash),
+            }
+        }
+        
+        let (debugger, debugger_ui) = if enable_debugger {
+            let mut debugger = Debugger::new();
+            debugger.rom_info = cpu.mmap.get_cart_header()
+                .map(|header| rom_info_from_header(&header, rom_hash.as_deref().unwrap_or_default(), known_rom));
+            let mut debugger_ui = DebuggerUI::new();
+            debugger_ui.set_window_state(WindowState {
+                x: config.debugger_window_x,
+                y: config.debugger_window_y,
+                show: config.debugger_show,
+            });
+            (Some(debugger), Some(debugger_ui))
+        } else {
+            (None, None)
+        };
+        
+        Self { 
+            cpu,
+            #[cfg(debug_assertions)]
+            trace_writer,
+            #[cfg(debug_assertions)]
+            trace_json,
+            #[cfg(debug_assertions)]
+            instruction_count: 0,
+            debugger,
+            debugger_ui,
+            frame_timer: FrameTimer::new(FRAME_DURATION),
+            benchmark_mode,
+            config,
+            config_path: None,
+            audio_dump: None,
+            audio_sync: Arc::new(Mutex::new(AudioSyncBuffer::default())),
+            audio_output: None,
+            gamepad: GamepadState::new(),
+            opcode_profile: None,
+            trace_ring: None,
+        }
+    }
+
+    /// Registers, the start of WRAM, the I/O registers and PPU state as JSON, compact for
+    /// logging or pretty-printed for reading
+    fn export_state_as_json(&self, pretty: bool) -> String {
+        state_json::export_state_as_json(&self.cpu, pretty)
+    }
+    
+    /// Restores the parts of an `export_state_as_json` state that can be written back
+    #[allow(dead_code)] // Public API method
+    fn import_state_from_json(&mut self, json: &str) -> Result<(), StateJsonError> {
+        state_json::import_state_from_json(&mut self.cpu, json)
+    }
+    
+    fn timing_stats(&self) -> TimingStats {
+        self.frame_timer.stats()
+    }
+    
+    #[cfg(debug_assertions)]
+    fn write_trace(&mut self) {
+        if let Some(ref mut writer) = self.trace_writer {
+            let pc = self.cpu.pc;
+            let sp = self.cpu.sp;
+            let regs = &self.cpu.registers;
+            
+            // Read the next few bytes for instruction context
+            let mem1 = self.cpu.mmap.peek(pc);
+            let mem2 = self.cpu.mmap.peek(pc.wrapping_add(1));
+            let mem3 = self.cpu.mmap.peek(pc.wrapping_add(2));
+            let mem4 = self.cpu.mmap.peek(pc.wrapping_add(3));
+            
+            if self.trace_json {
+                let comma = if self.instruction_count > 0 { "," } else { "" };
+                let trace_entry = format!(
+                    r#"{}{{
+    "instruction": {},
+    "A": "{:02X}",
+    "F": "{:02X}",
+    "B": "{:02X}",
+    "C": "{:02X}",
+    "D": "{:02X}",
+    "E": "{:02X}",
+    "H": "{:02X}",
+    "L": "{:02X}",
+    "SP": "{:04X}",
+    "PC": "{:04X}",
+    "memory": ["{:02X}", "{:02X}", "{:02X}", "{:02X}"]
+}}"#,
+                    comma,
+                    self.instruction_count,
+                    regs.a,
+                    u8::from(regs.f),
+                    regs.b,
+                    regs.c,
+                    regs.d,
+                    regs.e,
+                    regs.h,
+                    regs.l,
+                    sp,
+                    pc,
+                    mem1, mem2, mem3, mem4
+                );
+                writeln!(writer, "{}", trace_entry).unwrap();
+            } else {
+                writeln!(writer, "{}", trace_line(&self.cpu)).unwrap();
+            }
+            
+            self.instruction_count += 1;
+        }
+    }
+
+    fn get_frame_buffer(&self) -> &[u8] {
+        self.cpu.mmap.get_ppu().get_frame_buffer()
+    }
+}
+
+impl Drop for GameBoyEmulator {
+    fn drop(&mut self) {
+        #[cfg(debug_assertions)]
+        if let Some(ref mut writer) = self.trace_writer {
+            if self.trace_json {
+                // Close JSON array
+                writeln!(writer, "]").unwrap();
+            }
+            // Flush the writer
+            writer.flush().unwrap();
+        }
+        
+        if let Some((ref profile, ref path)) = self.opcode_profile {
+            #[allow(unused_mut)] // Only appended to with memory_profiling
+            let mut report = profile.report(PROFILE_TOP_OPCODES);
+            #[cfg(feature = "memory_profiling")]
+            report.push_str(&memory_profile_report(&self.cpu.mmap));
+            match path {
+                Some(path) => match std::fs::write(path, &report) {
+                    Ok(()) => println!("Profile: saved opcode counts to {}", path.display()),
+                    Err(e) => eprintln!("Warning: Failed to save profile '{}': {}", path.display(), e),
+                },
+                None => print!("{}", report),
+            }
+        }
+        
+        if let Some((ref path, ref samples)) = self.audio_dump {
+            match apu::write_wav(path, samples) {
+                Ok(()) => println!("Audio: saved {} samples to {}", samples.len(), path.display()),
+                Err(e) => eprintln!("Warning: Failed to save audio dump '{}': {}", path.display(), e),
+            }
+        }
+        
+        // Persist window placement for the next session
+        if let Some(ref debugger_ui) = self.debugger_ui {
+            let state = debugger_ui.get_window_state();
+            self.config.debugger_window_x = state.x;
+            self.config.debugger_window_y = state.y;
+            self.config.debugger_show = state.show;
+        }
+        if let Some(ref path) = self.config_path {
+            if let Err(e) = self.config.save(path) {
+                eprintln!("Warning: Failed to save config '{}': {}", path.display(), e);
+            }
+        }
+    }
+}
+
+// The most read and most written addresses, appended to the --profile opcode table
+#[cfg(feature = "memory_profiling")]
+fn memory_profile_report(mmap: &MemoryMap) -> String {
+    let mut report = String::new();
+    for (title, hot) in [("reads", mmap.hot_reads(PROFILE_TOP_ADDRESSES)), ("writes", mmap.hot_writes(PROFILE_TOP_ADDRESSES))] {
+        report.push_str(&format!("\nMemory profile: most frequent {}\nAddress  Count\n", title));
+        for (addr, count) in hot {
+            report.push_str(&format!("0x{:04X}  {:>12}\n", addr, count));
+        }
+    }
+    report
+}
+
+// Registers and the next four bytes at PC in the --trace text format
+fn trace_line(cpu: &Cpu) -> String {
+    TraceEntry::capture(cpu).to_string()
+}
+
+// Prints the trace ring before the usual panic message, so a crash shows what led up to it
+fn install_trace_ring_panic_hook(trace_ring: Arc<Mutex<RingTraceBuffer>>) {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // try_lock: the panic may have happened while the ring was being updated
+        if let Ok(trace_ring) = trace_ring.try_lock() {
+            eprint!("{}", trace_ring.report());
+        }
+        default_hook(info);
+    }));
+}
+
+// Reads --repl lines from stdin on a background thread so the window keeps running
+fn spawn_repl_reader() -> Receiver<String> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+// Assembles a REPL line, runs it from HRAM and prints the registers afterwards
+fn run_repl_line(cpu: &mut Cpu, line: &str) {
+    if line.trim().is_empty() {
+        return;
+    }
+    let Some(code) = assemble_line(line) else {
+        println!("Can't assemble '{}'", line.trim());
+        return;
+    };
+    match cpu.run_injected(&code, REPL_MAX_CYCLES) {
+        Ok(_) => println!("{}", trace_line(cpu)),
+        Err(e) => println!("Error: {}; {}", e, trace_line(cpu)),
+    }
+}
+
+// Format the cartridge header for the debugger's ROM info panel, preferring
+// the database title and region when the ROM hash is known
+fn rom_info_from_header(header: &CartridgeHeader, rom_hash: &str, known_rom: Option<&rom_db::RomInfo>) -> RomInfo {
+    let mut info = RomInfo::default();
+    let mut field = |label: &str, value: String| info.fields.push((label.to_string(), value));
+
+    match known_rom {
+        Some(known) => {
+            field("Title", known.title.to_string());
+            field("Region", known.region.to_string());
+            field("Revision", known.revision.to_string());
+        }
+        None => field("Title", header.title.clone()),
+    }
+    field("Database", if known_rom.is_some() { "verified".to_string() } else { "unknown ROM".to_string() });
+    field("SHA-256", rom_hash.to_string());
+    field("Manufacturer", header.manufacturer_code.clone());
+    field("CGB flag", format!("${:02X}", header.cgb_flag));
+    field("New licensee", header.new_licensee_code.clone());
+    field("SGB flag", format!("${:02X}", header.sgb_flag));
+    field("Cartridge type", format!("${:02X}", header.cartridge_type));
+    field("ROM size", format!("${:02X} ({} KB)", header.rom_size, header.rom_size_bytes() / 1024));
+    field("RAM size", format!("${:02X} ({} KB)", header.ram_size, header.ram_size_bytes() / 1024));
+    field("Destination", format!("${:02X} ({})", header.destination_code,
+                                 if header.destination_code == 0x00 { "Japan" } else { "Overseas" }));
+    field("Old licensee", format!("${:02X}", header.old_licensee_code));
+    field("Version", format!("${:02X}", header.version));
+    field("Header checksum", format!("${:02X} ({})", header.header_checksum,
+                                     if header.header_checksum_valid { "OK" } else { "BAD" }));
+    field("Global checksum", format!("${:04X} ({})", header.global_checksum,
+                                     if header.global_checksum_valid { "OK" } else { "BAD" }));
+
+    if !header.header_checksum_valid {
+        info.warnings.push("header checksum mismatch (boot ROM would lock up)".to_string());
+    }
+    if !header.global_checksum_valid {
+        info.warnings.push("global checksum mismatch".to_string());
+    }
+    info
+}
+
+// Writes the requested VRAM/OAM dumps and returns the process exit status
+fn write_memory_dumps(ppu: &Ppu, frame: u64, vram_path: Option<&str>, oam_path: Option<&str>) -> ExitCode {
+    let dumps = [(vram_path, MemoryDump::vram(ppu, frame as u16)), (oam_path, MemoryDump::oam(ppu, frame as u16))];
+    let mut status = ExitCode::SUCCESS;
+    for (path, dump) in dumps {
+        let Some(path) = path else {
+            continue;
+        };
+        match dump.save(std::path::Path::new(path)) {
+            Ok(()) => println!("Dumped {} bytes at frame {} to {}", dump.data.len(), frame, path),
+            Err(e) => {
+                eprintln!("Error: failed to write dump '{}': {}", path, e);
+                status = ExitCode::FAILURE;
+            }
+        }
+    }
+    status
+}
+
+// Returns the process exit status: success if both CPUs matched for every step
+#[cfg(paranoid_mode)]
+fn run_compare_trace(cpu: &mut Cpu, shadow: &mut Cpu, steps: u64) -> ExitCode {
+    for step in 0..steps {
+        let pc = cpu.pc;
+        if let Some(report) = cpu.step_parallel_check(shadow) {
+            eprintln!("Divergence at step {} (PC ${:04X}) {}", step, pc, report);
+            return ExitCode::FAILURE;
+        }
+    }
+    println!("Compare trace: {} steps matched", steps);
+    ExitCode::SUCCESS
+}
+
+fn window_conf() -> Conf {
+    // Uncapped runs also ask the driver not to wait for vsync
+    let uncapped = std::env::args().any(|arg| arg == "--no-fps-cap" || arg == "--benchmark-frames");
+    Conf {
+        window_title: "Game Boy Emulator".to_owned(),
+        platform: miniquad::conf::Platform {
+            swap_interval: if uncapped { Some(0) } else { None },
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+fn main() -> ExitCode {
+    // Initialize logger (only in debug builds)
+    #[cfg(debug_assertions)]
+    env_logger::init();
+    
+    // Parse command line arguments
+    let args: Vec<String> = std::env::args().collect();
+    let mut rom_path = "./test-roms/pkmn.gb"; // Default ROM if no argument provided
+    let mut skip_boot_rom = false;
+    let mut hardware_model = None; // Detected from the cartridge header unless forced
+    let mut accurate_unused_memory = false;
+    let mut sgb_mode = SgbMode::Disabled;
+    let mut trace_file: Option<String> = None;
+    let mut trace_json = false;
+    let mut trace_ring_size: Option<usize> = None;
+    let mut json_state_frame: Option<u64> = None;
+    let mut enable_debugger = false;
+    let mut watchpoints: Vec<(u16, WatchKind)> = Vec::new();
+    let mut test_exit_addr: Option<u16> = None;
+    let mut max_frames: Option<u64> = None;
+    let mut max_instructions: u32 = 0;
+    let mut rom_list_dir: Option<String> = None;
+    let mut no_fps_cap = false;
+    let mut benchmark_frames: Option<u64> = None;
+    let mut printer_dir: Option<String> = None;
+    let mut audio_dump_path: Option<String> = None;
+    let mut profile = false;
+    let mut profile_output: Option<String> = None;
+    let mut vram_dump_path: Option<String> = None;
+    let mut oam_dump_path: Option<String> = None;
+    let mut dump_at_frame: Option<u64> = None;
+    let mut access_log_size: Option<usize> = None;
+    let mut access_range: Option<(u16, u16)> = None;
+    let mut repl = false;
+    #[cfg(paranoid_mode)]
+    let mut compare_steps: Option<u64> = None;
+    
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--skip-boot" | "-s" => {
+                skip_boot_rom = true;
+                i += 1;
+            }
+            "--dmg" => {
+                hardware_model = Some(HardwareModel::Dmg);
+                i += 1;
+            }
+            "--cgb" => {
+                hardware_model = Some(HardwareModel::Cgb);
+                i += 1;
+            }
+            "--sgb" => {
+                sgb_mode = SgbMode::Enabled;
+                i += 1;
+            }
+            "--accurate-unused-mem" => {
+                accurate_unused_memory = true;
+                i += 1;
+            }
+            "--trace" | "-t" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --trace requires a file path");
+                    return ExitCode::FAILURE;
+                }
+                trace_file = Some(args[i + 1].clone());
+                i += 2;
+            }
+            "--trace-json" => {
+                trace_json = true;
+                i += 1;
+            }
+            "--json-state" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --json-state requires a frame number");
+                    return ExitCode::FAILURE;
+                }
+                match args[i + 1].parse::<u64>() {
+                    Ok(frame) => json_state_frame = Some(frame),
+                    Err(_) => {
+                        eprintln!("Error: invalid --json-state value '{}'", args[i + 1]);
+                        return ExitCode::FAILURE;
+                    }
+                }
+                i += 2;
+            }
+            "--trace-ring" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --trace-ring requires an instruction count");
+                    return ExitCode::FAILURE;
+                }
+                match args[i + 1].parse::<usize>() {
+                    Ok(size) => trace_ring_size = Some(size),
+                    Err(_) => {
+                        eprintln!("Error: invalid --trace-ring value '{}'", args[i + 1]);
+                        return ExitCode::FAILURE;
+                    }
+                }
+                i += 2;
+            }
+            "--repl" => {
+                repl = true;
+                i += 1;
+            }
+            "--debug" | "-d" => {
+                enable_debugger = true;
+                i += 1;
+            }
+            "--watch" | "-w" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --watch requires an address");
+                    return ExitCode::FAILURE;
+                }
+                // Format: ADDR[:r|:w] in hex, e.g. C000 or 0xC000:w
+                let spec = args[i + 1].as_str();
+                let (addr_str, kind) = match spec.split_once(':') {
+                    Some((addr, "r")) => (addr, WatchKind::Read),
+                    Some((addr, "w")) => (addr, WatchKind::Write),
+                    Some((addr, "rw")) => (addr, WatchKind::ReadWrite),
+                    Some(_) => {
+                        eprintln!("Error: invalid watchpoint kind in '{}' (expected r, w or rw)", spec);
+                        return ExitCode::FAILURE;
+                    }
+                    None => (spec, WatchKind::ReadWrite),
+                };
+                let addr_str = addr_str.trim_start_matches("0x").trim_start_matches("0X");
+                match u16::from_str_radix(addr_str, 16) {
+                    Ok(address) => watchpoints.push((address, kind)),
+                    Err(_) => {
+                        eprintln!("Error: invalid watchpoint address '{}'", spec);
+                        return ExitCode::FAILURE;
+                    }
+                }
+                i += 2;
+            }
+            "--access-log" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --access-log requires an entry count");
+                    return ExitCode::FAILURE;
+                }
+                match args[i + 1].parse::<usize>() {
+                    Ok(size) if size > 0 => access_log_size = Some(size),
+                    _ => {
+                        eprintln!("Error: invalid --access-log value '{}'", args[i + 1]);
+                        return ExitCode::FAILURE;
+                    }
+                }
+                i += 2;
+            }
+            "--access-range" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --access-range requires an address range");
+                    return ExitCode::FAILURE;
+                }
+                // Format: START-END in hex, e.g. C000-DFFF
+                let parse = |addr: &str| u16::from_str_radix(addr.trim_start_matches("0x").trim_start_matches("0X"), 16).ok();
+                match args[i + 1].split_once('-').and_then(|(start, end)| Some((parse(start)?, parse(end)?))) {
+                    Some(range) => access_range = Some(range),
+                    None => {
+                        eprintln!("Error: invalid --access-range '{}' (expected START-END in hex)", args[i + 1]);
+                        return ExitCode::FAILURE;
+                    }
+                }
+                i += 2;
+            }
+            "--test-exit" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --test-exit requires an address");
+                    return ExitCode::FAILURE;
+                }
+                let addr_str = args[i + 1].trim_start_matches("0x").trim_start_matches("0X");
+                match u16::from_str_radix(addr_str, 16) {
+                    Ok(address) => test_exit_addr = Some(address),
+                    Err(_) => {
+                        eprintln!("Error: invalid --test-exit address '{}'", args[i + 1]);
+                        return ExitCode::FAILURE;
+                    }
+                }
+                i += 2;
+            }
+            "--max-frames" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --max-frames requires a frame count");
+                    return ExitCode::FAILURE;
+                }
+                match args[i + 1].parse::<u64>() {
+                    Ok(frames) => max_frames = Some(frames),
+                    Err(_) => {
+                        eprintln!("Error: invalid --max-frames value '{}'", args[i + 1]);
+                        return ExitCode::FAILURE;
+                    }
+                }
+                i += 2;
+            }
+            "--max-instructions" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --max-instructions requires an instruction count");
+                    return ExitCode::FAILURE;
+                }
+                match args[i + 1].parse::<u32>() {
+                    Ok(count) => max_instructions = count,
+                    Err(_) => {
+                        eprintln!("Error: invalid --max-instructions value '{}'", args[i + 1]);
+                        return ExitCode::FAILURE;
+                    }
+                }
+                i += 2;
+            }
+            "--no-fps-cap" => {
+                no_fps_cap = true;
+                i += 1;
+            }
+            "--benchmark-frames" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --benchmark-frames requires a frame count");
+                    return ExitCode::FAILURE;
+                }
+                match args[i + 1].parse::<u64>() {
+                    Ok(frames) => benchmark_frames = Some(frames),
+                    Err(_) => {
+                        eprintln!("Error: invalid --benchmark-frames value '{}'", args[i + 1]);
+                        return ExitCode::FAILURE;
+                    }
+                }
+                i += 2;
+            }
+            "--printer" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --printer requires an output directory");
+                    return ExitCode::FAILURE;
+                }
+                printer_dir = Some(args[i + 1].clone());
+                i += 2;
+            }
+            "--compare-trace" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --compare-trace requires a step count");
+                    return ExitCode::FAILURE;
+                }
+                #[cfg(not(paranoid_mode))]
+                {
+                    eprintln!("Error: --compare-trace requires building with RUSTFLAGS=\"--cfg paranoid_mode\"");
+                    return ExitCode::FAILURE;
+                }
+                #[cfg(paranoid_mode)]
+                {
+                    match args[i + 1].parse::<u64>() {
+                        Ok(steps) => compare_steps = Some(steps),
+                        Err(_) => {
+                            eprintln!("Error: invalid --compare-trace value '{}'", args[i + 1]);
+                            return ExitCode::FAILURE;
+                        }
+                    }
+                    i += 2;
+                }
+            }
+            "--audio-dump" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --audio-dump requires a file path");
+                    return ExitCode::FAILURE;
+                }
+                audio_dump_path = Some(args[i + 1].clone());
+                i += 2;
+            }
+            "--profile" => {
+                profile = true;
+                i += 1;
+            }
+            "--profile-output" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --profile-output requires a file path");
+                    return ExitCode::FAILURE;
+                }
+                profile = true;
+                profile_output = Some(args[i + 1].clone());
+                i += 2;
+            }
+            "--dump-vram" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --dump-vram requires a file path");
+                    return ExitCode::FAILURE;
+                }
+                vram_dump_path = Some(args[i + 1].clone());
+                i += 2;
+            }
+            "--dump-oam" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --dump-oam requires a file path");
+                    return ExitCode::FAILURE;
+                }
+                oam_dump_path = Some(args[i + 1].clone());
+                i += 2;
+            }
+            "--dump-at-frame" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --dump-at-frame requires a frame count");
+                    return ExitCode::FAILURE;
+                }
+                match args[i + 1].parse::<u64>() {
+                    Ok(frames) if frames > 0 => dump_at_frame = Some(frames),
+                    _ => {
+                        eprintln!("Error: invalid --dump-at-frame value '{}'", args[i + 1]);
+                        return ExitCode::FAILURE;
+                    }
+                }
+                i += 2;
+            }
+            "--rom-list" => {
+                if i + 1 >= args.len() {
+                    eprintln!("Error: --rom-list requires a directory");
+                    return ExitCode::FAILURE;
+                }
+                rom_list_dir = Some(args[i + 1].clone());
+                i += 2;
+            }
+            "--help" | "-h" => {
+                println!("Game Boy Emulator");
+                println!("Usage: {} [options] [rom_path]", args[0]);
+                println!();
+                println!("Options:");
+                println!("  --skip-boot, -s      Skip the Game Boy boot sequence and start directly with the ROM");
+                println!("  --dmg, --cgb         Emulate that model instead of the one the cartridge header asks for");
+                println!("  --accurate-unused-mem  Make reads from 0xFEA0-0xFEFF depend on the PPU mode like a DMG");
+                println!("  --sgb                Answer Super Game Boy packets on the joypad port, so SGB-aware games detect an SGB");
+                println!("  --trace, -t <file>   Write execution trace to the specified file");
+                println!("  --trace-json         Format trace output as JSON (requires --trace)");
+                println!("  --json-state <n>     Print the machine state as JSON after frame n and keep running");
+                println!("  --trace-ring <n>     Keep the last n instructions and print them if the emulator panics");
+                println!("                       (on by default with {} in debug builds; 0 turns it off)", DEFAULT_TRACE_RING_SIZE);
+                println!("  --debug, -d          Enable interactive debugger");
+                println!("  --repl               Read instructions like `ld a, 0x42` from stdin, run each from HRAM and print the registers");
+                println!("  --watch, -w <addr>   Pause the debugger on access to a hex address (suffix :r or :w to limit)");
+                println!("  --access-log <n>     Keep the last n memory accesses and show them in the debugger when paused");
+                println!("  --access-range <a-b> Only show accesses to hex addresses a-b in the debugger (e.g. C000-DFFF)");
+                println!("  --test-exit <addr>   Run headless; exit with the first non-zero value written to a hex address");
+                println!("  --max-frames <n>     Exit with code 1 after n frames (test timeout)");
+                println!("  --max-instructions <n>  Cut each frame short after n instructions (0, the default, means no cap)");
+                println!("  --rom-list <dir>     Pick a ROM from a directory in an interactive list");
+                println!("  --printer <dir>      Attach a Game Boy Printer to the link port, saving prints as PNGs in dir");
+                println!("  --audio-dump <file>  Record the emulated audio to a WAV file (44100 Hz mono), saved on exit");
+                println!("  --dump-vram <file>   Save VRAM to a file at --dump-at-frame, then exit");
+                println!("  --dump-oam <file>    Save OAM to a file at --dump-at-frame, then exit");
+                println!("  --dump-at-frame <n>  Run n frames headless before writing the dumps");
+                println!("  --compare-trace <n>  Run n steps on two CPUs in lockstep and report the first divergence (paranoid_mode builds)");
+                println!("  --profile            Count executed opcodes and print the 30 most frequent on exit");
+                println!("  --profile-output <file>  Like --profile, but save the table to a file");
+                println!("  --no-fps-cap         Run as fast as possible and print the average FPS every 5 seconds");
+                println!("  --benchmark-frames <n>  Run n uncapped frames, print elapsed time and average FPS, then exit");
+                println!("  --help, -h           Show this help message");
+                println!();
+                println!("Debug tracing is only available in debug builds.");
+                println!("If no ROM path is provided, defaults to './test-roms/pkmn.gb'");
+                return ExitCode::SUCCESS;
+            }
+            arg if !arg.starts_with("--") => {
+                rom_path = arg;
+                i += 1;
+            }
+            _ => {
+                eprintln!("Unknown option: {}", args[i]);
+                eprintln!("Use --help for usage information");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    
+    // Validate trace options
+    if trace_json && trace_file.is_none() {
+        eprintln!("Error: --trace-json requires --trace <file>");
+        return ExitCode::FAILURE;
+    }
+    
+    if !watchpoints.is_empty() && !enable_debugger {
+        eprintln!("Error: --watch requires --debug");
+        return ExitCode::FAILURE;
+    }
+    
+    if (access_log_size.is_some() || access_range.is_some()) && !enable_debugger {
+        eprintln!("Error: --access-log and --access-range require --debug");
+        return ExitCode::FAILURE;
+    }
+    
+    let dump_requested = vram_dump_path.is_some() || oam_dump_path.is_some();
+    if dump_requested != dump_at_frame.is_some() {
+        eprintln!("Error: --dump-vram/--dump-oam and --dump-at-frame must be used together");
+        return ExitCode::FAILURE;
+    }
+    
+    // Let the user pick a ROM from the directory instead of the command line
+    let selected_rom: String;
+    if let Some(dir) = rom_list_dir {
+        let entries = match rom_browser::scan_directory(std::path::Path::new(&dir)) {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("Error: failed to read ROM directory '{}': {}", dir, e);
+                return ExitCode::FAILURE;
+            }
+        };
+        if entries.is_empty() {
+            eprintln!("Error: no .gb or .gbc files found in '{}'", dir);
+            return ExitCode::FAILURE;
+        }
+        match rom_browser::choose_rom(&entries) {
+            Some(path) => {
+                selected_rom = path.to_string_lossy().into_owned();
+                rom_path = &selected_rom;
+            }
+            None => return ExitCode::SUCCESS,
+        }
+    }
+    
+    let benchmark_mode = no_fps_cap || benchmark_frames.is_some();
+    let mut config = EmulatorConfig::load(std::path::Path::new(CONFIG_PATH));
+    config.skip_boot = skip_boot_rom;
+    config.hardware_model = hardware_model;
+    config.accurate_unused_memory = accurate_unused_memory;
+    config.sgb_mode = sgb_mode;
+    
+    let mut emulator = GameBoyEmulator::new(rom_path, trace_file, trace_json, enable_debugger, &watchpoints, benchmark_mode, config);
+    
+    emulator.cpu.max_instructions_per_frame = max_instructions;
+    if let Some(size) = access_log_size {
+        emulator.cpu.mmap.enable_access_log(size);
+    }
+    if let (Some((start, end)), Some(ref mut debugger)) = (access_range, &mut emulator.debugger) {
+        debugger.set_access_filter(start, end);
+    }
+    
+    if let Some(dir) = printer_dir {
+        emulator.cpu.mmap.attach_serial_device(Box::new(GameBoyPrinter::new(std::path::Path::new(&dir))));
+    }
+    
+    if let Some(path) = audio_dump_path {
+        emulator.audio_dump = Some((PathBuf::from(path), Vec::new()));
+    }
+    
+    // Debug builds always keep a trace ring; release builds only with --trace-ring
+    let trace_ring_size = if cfg!(debug_assertions) { trace_ring_size.or(Some(DEFAULT_TRACE_RING_SIZE)) } else { trace_ring_size };
+    if let Some(size) = trace_ring_size.filter(|&size| size > 0) {
+        let trace_ring = Arc::new(Mutex::new(RingTraceBuffer::new(size)));
+        install_trace_ring_panic_hook(Arc::clone(&trace_ring));
+        emulator.trace_ring = Some(trace_ring);
+    }
+    
+    if profile {
+        emulator.opcode_profile = Some((OpcodeProfile::new(), profile_output.map(PathBuf::from)));
+        #[cfg(feature = "memory_profiling")]
+        {
+            emulator.cpu.mmap.track_accesses = true;
+        }
+    }
+    
+    // Lockstep a second CPU against the emulator's and exit on the first divergence
+    #[cfg(paranoid_mode)]
+    if let Some(steps) = compare_steps {
+        let mut shadow = build_cpu(rom_path, &emulator.config);
+        return run_compare_trace(&mut emulator.cpu, &mut shadow, steps);
+    }
+    
+    let repl_lines = repl.then(|| {
+        println!("REPL: enter an instruction such as `ld a, 0x42` to run it between frames");
+        spawn_repl_reader()
+    });
+    
+    // Test mode: watch the magic address for the ROM's result and skip the window
+    let headless = test_exit_addr.is_some() || dump_at_frame.is_some();
+    if let Some(address) = test_exit_addr {
+        emulator.cpu.mmap.add_watchpoint(address, WatchKind::Write);
+    }
+    
+    let frame_loop = FrameLoop {
+        test_exit_addr,
+        max_frames,
+        json_state_frame,
+        dump_at_frame,
+        vram_dump_path,
+        oam_dump_path,
+        benchmark_frames,
+        repl_lines,
+        frames_run: 0,
+        benchmark_start: Instant::now(),
+        fps_counter: FpsCounter::new(60),
+        last_fps_report: Instant::now(),
+    };
+    
+    if headless {
+        return run_headless(emulator, frame_loop);
+    }
+    // The window only exists while the future runs, so the exit status is handed back
+    let exit_code = Rc::new(Cell::new(ExitCode::SUCCESS));
+    macroquad::Window::from_config(window_conf(), run_windowed(emulator, frame_loop, Rc::clone(&exit_code)));
+    exit_code.get()
+}
+
+// Per-run options and counters shared by the windowed and headless frame loops
+struct FrameLoop {
+    test_exit_addr: Option<u16>,
+    max_frames: Option<u64>,
+    json_state_frame: Option<u64>,
+    dump_at_frame: Option<u64>,
+    vram_dump_path: Option<String>,
+    oam_dump_path: Option<String>,
+    benchmark_frames: Option<u64>,
+    repl_lines: Option<Receiver<String>>,
+    frames_run: u64,
+    // Benchmark mode: rolling FPS over the last 60 frames, reported every 5 seconds
+    benchmark_start: Instant,
+    fps_counter: FpsCounter,
+    last_fps_report: Instant,
+}
+
+impl FrameLoop {
+    /// Runs one frame and the checks between frames, breaking with the exit status
+    /// once a test result, dump, timeout or benchmark ends the run
+    fn run_frame(&mut self, emulator: &mut GameBoyEmulator, joypad_buttons: rgb::rgb::joypad::JoypadButtons) -> ControlFlow<ExitCode> {
+        // Update joypad and check for button press interrupts
+        let button_pressed = emulator.cpu.mmap.update_joypad(joypad_buttons);
+        if button_pressed {
+            emulator.cpu.request_joypad_interrupt();
+        }
+        
+        // Run emulator until PPU completes a full frame (VBlank occurs)
+        // Game Boy runs at ~4.194 MHz, with ~69,905 cycles per frame at 59.7 FPS
+        let mut instructions_executed = 0;
+        let mut total_cycles = 0;
+        const CYCLES_PER_FRAME: u32 = 70224; // 4.194 MHz / 59.7 FPS = ~70,224 cycles per frame
+        // CGB double speed fits twice as many CPU cycles into each frame
+        let cycles_per_frame = CYCLES_PER_FRAME * emulator.cpu.speed_multiplier as u32;
+        
+        // Discard watchpoint hits caused by debugger/HUD reads between frames
+        emulator.cpu.mmap.take_watchpoint_hit();
+        
+        // Save memory before each debugger step so the UI's Undo can put it back
+        if let Some(ref mut debugger) = emulator.debugger {
+            if debugger.is_stepping() {
+                debugger.push_undo(emulator.cpu.mmap.snapshot());
+            }
+        }
+        
+        loop {
+            let debugger = &mut emulator.debugger;
+            let opcode_profile = &mut emulator.opcode_profile;
+            let trace_ring = &emulator.trace_ring;
+            let result = emulator.cpu.execute_frame_until_vblank_with(cycles_per_frame - total_cycles, |cpu| {
+                // Handle debugger logic (optimized for performance)
+                if let Some(debugger) = debugger.as_mut() {
+                    // Check for breakpoints first (fastest check)
+                    if debugger.hit_breakpoint(cpu.pc) {
+                        debugger.pause();
+                    }
+                    if !debugger.should_execute() {
+                        // Hardware keeps running so the frame still completes
+                        return false;
+                    }
+                    debugger.record_instruction(cpu.pc, cpu.mmap.peek(cpu.pc));
+                }
+                if let Some((profile, _)) = opcode_profile.as_mut() {
+                    profile.record_at(&cpu.mmap, cpu.pc);
+                }
+                if let Some(trace_ring) = trace_ring {
+                    trace_ring.lock().unwrap().push(TraceEntry::capture(cpu));
+                }
+                true
+            });
+            instructions_executed += result.instructions;
+            total_cycles += result.cycles;
+            if result.end == FrameEnd::VBlank {
+                if let Some(ref mut debugger) = emulator.debugger {
+                    debugger.notify_vblank();
+                }
+            }
+            
+            // Pause on watchpoint hits before the next instruction runs, then resume the frame
+            let FrameEnd::Watchpoint(address, value, access) = result.end else {
+                break;
+            };
+            let is_test_exit = self.test_exit_addr == Some(address) && access == WatchKind::Write;
+            if is_test_exit && value != 0 {
+                println!("Test exit: ${:02X} written to ${:04X}", value, address);
+                return ControlFlow::Break(ExitCode::from(value));
+            }
+            if !is_test_exit {
+                if let Some(ref mut debugger) = emulator.debugger {
+                    debugger.watchpoint_hit(address, value, access);
+                }
+            }
+            if total_cycles >= cycles_per_frame {
+                break;
+            }
+        }
+        
+        self.frames_run += 1;
+        let frames_run = self.frames_run;
+        
+        // Instructions typed into the REPL run between frames
+        if let Some(ref lines) = self.repl_lines {
+            while let Ok(line) = lines.try_recv() {
+                run_repl_line(&mut emulator.cpu, &line);
+            }
+        }
+        if self.json_state_frame == Some(frames_run) {
+            println!("{}", emulator.export_state_as_json(true));
+        }
+        if self.dump_at_frame == Some(frames_run) {
+            let status = write_memory_dumps(emulator.cpu.mmap.get_ppu(), frames_run, self.vram_dump_path.as_deref(), self.oam_dump_path.as_deref());
+            return ControlFlow::Break(status);
+        }
+        if self.max_frames.is_some_and(|limit| frames_run >= limit) {
+            println!("Test timeout: no result after {} frames", frames_run);
+            return ControlFlow::Break(ExitCode::FAILURE);
+        }
+        
+        if emulator.benchmark_mode {
+            self.fps_counter.tick();
+            if self.last_fps_report.elapsed() >= Duration::from_secs(5) {
+                println!("FPS: {:.1} (60-frame average)", self.fps_counter.fps());
+                self.last_fps_report = Instant::now();
+            }
+            if self.benchmark_frames.is_some_and(|limit| frames_run >= limit) {
+                let elapsed = self.benchmark_start.elapsed().as_secs_f64();
+                println!("Benchmark: {} frames in {:.3}s, average {:.1} FPS", frames_run, elapsed, frames_run as f64 / elapsed);
+                return ControlFlow::Break(ExitCode::SUCCESS);
+            }
+        }
+        
+        // Samples are drained every frame so the APU buffer never fills up
+        let samples = emulator.cpu.mmap.drain_audio_samples();
+        if let Some((_, ref mut recorded)) = emulator.audio_dump {
+            recorded.extend_from_slice(&samples);
+        }
+        let mut audio_sync = emulator.audio_sync.lock().unwrap();
+        audio_sync.push(&samples);
+        // Without a device, consume one frame's worth as playback would, so the
+        // underrun/overrun counts reflect how well output keeps pace
+        if emulator.audio_output.is_none() {
+            audio_sync.drain(SAMPLES_PER_FRAME);
+        }
+        drop(audio_sync);
+        
+        // Basic frame completion debug output
+        #[cfg(debug_assertions)]
+        if rgb::rgb::diagnostics::debug_output() {
+            let frame = emulator.cpu.mmap.get_ppu().frames_rendered();
+            if frame <= 3 || frame % 120 == 0 {
+                println!("Frame #{}: {} instructions, {} cycles", frame, instructions_executed, total_cycles);
+            }
+        }
+        
+        // Update debugger state once per frame (moved outside hot loop for performance)
+        if let Some(ref mut debugger) = emulator.debugger {
+            // PC and register edits queued by the debugger UI
+            if let Some(pc) = debugger.set_pc_request.take() {
+                emulator.cpu.pc = pc;
+            }
+            for (register, value) in debugger.set_register_request.drain() {
+                let registers = &mut emulator.cpu.registers;
+                match register {
+                    RegisterName::A => registers.a = value,
+                    RegisterName::B => registers.b = value,
+                    RegisterName::C => registers.c = value,
+                    RegisterName::D => registers.d = value,
+                    RegisterName::E => registers.e = value,
+                    RegisterName::F => registers.f = value.into(), // Low nibble is dropped
+                    RegisterName::H => registers.h = value,
+                    RegisterName::L => registers.l = value,
+                }
+            }
+            
+            if let Some(ref mut ui) = emulator.debugger_ui {
+                ui.update_cpu_snapshot(
+                    debugger,
+                    emulator.cpu.registers.a,
+                    emulator.cpu.registers.b,
+                    emulator.cpu.registers.c,
+                    emulator.cpu.registers.d,
+                    emulator.cpu.registers.e,
+                    u8::from(emulator.cpu.registers.f),
+                    emulator.cpu.registers.h,
+                    emulator.cpu.registers.l,
+                    emulator.cpu.pc,
+                    emulator.cpu.sp,
+                    emulator.cpu.ime,
+                    emulator.cpu.halted,
+                    emulator.cpu.registers.f.zero,
+                    emulator.cpu.registers.f.subtract,
+                    emulator.cpu.registers.f.half_carry,
+                    emulator.cpu.registers.f.carry,
+                );
+            }
+            
+            // Only refresh after the CPU ran, so HUD reads while paused don't replace the log
+            if instructions_executed > 0 {
+                debugger.update_access_log(emulator.cpu.mmap.get_access_log());
+            }
+            
+            // Update memory watches once per frame
+            debugger.update_memory_watches(|addr| emulator.cpu.mmap.peek(addr));
+            
+            if debugger.undo_requested {
+                debugger.undo_requested = false;
+                if let Some(snapshot) = debugger.pop_undo() {
+                    emulator.cpu.mmap.restore_from_snapshot(&snapshot);
+                }
+            }
+            
+            if debugger.memory_map_export_requested {
+                debugger.memory_map_export_requested = false;
+                let path = Path::new("memory_map.html");
+                match debugger.export_memory_map_html(path, |addr| emulator.cpu.mmap.peek(addr)) {
+                    Ok(()) => println!("Memory map exported to {}", path.display()),
+                    Err(e) => eprintln!("Failed to export memory map: {}", e),
+                }
+            }
+        }
+        
+        ControlFlow::Continue(())
+    }
+}
+
+// Runs frames back to back with no window or input until the run ends
+fn run_headless(mut emulator: GameBoyEmulator, mut frame_loop: FrameLoop) -> ExitCode {
+    loop {
+        if let ControlFlow::Break(status) = frame_loop.run_frame(&mut emulator, rgb::rgb::joypad::JoypadButtons::new()) {
+            return status;
+        }
+    }
+}
+
+async fn run_windowed(mut emulator: GameBoyEmulator, mut frame_loop: FrameLoop, exit_code: Rc<Cell<ExitCode>>) {
+    // Set target FPS to 60 (matching Game Boy refresh rate) with optimized screen size
+    request_new_screen_size(640.0, 576.0); // 160*4 x 144*4 scale
+    if let (Some(x), Some(y)) = (emulator.config.window_x, emulator.config.window_y) {
+        miniquad::window::set_window_position(x, y);
+    }
+    
+    // Uncapped runs produce samples faster than they can be played, so they stay silent
+    if !emulator.benchmark_mode {
+        match AudioOutput::open(Arc::clone(&emulator.audio_sync)) {
+            Ok(output) => emulator.audio_output = Some(output),
+            Err(e) => eprintln!("Warning: No audio output: {}", e),
+        }
+    }
+    
+    let mut gamepad_input = match GamepadInputReader::open(&mut emulator.gamepad) {
+        Ok(reader) => Some(reader),
+        Err(e) => {
+            eprintln!("Warning: Gamepads unavailable: {}", e);
+            None
+        }
+    };
+    
+    // Handle window close ourselves so the emulator is dropped and state saved
+    prevent_quit();
+    
+    loop {
+        if is_quit_requested() {
+            break;
+        }
+        
+        clear_background(GRAY);
+
+        // Poll keyboard and gamepad input and update joypad state
+        if let Some(ref mut reader) = gamepad_input {
+            reader.poll(&mut emulator.gamepad);
+        }
+        let mut joypad_buttons = rgb::rgb::joypad::JoypadButtons::from_keyboard_state(&emulator.config.keyboard, &is_key_down);
+        emulator.config.gamepad.apply(&emulator.gamepad, &mut joypad_buttons);
+        
+        if let ControlFlow::Break(status) = frame_loop.run_frame(&mut emulator, joypad_buttons) {
+            exit_code.set(status);
+            break;
+        }
+        
+        // C toggles color correction; holding C + Left Shift compares corrected (left half)
+        // against uncorrected (right half)
+        let typing = emulator.debugger_ui.as_ref().is_some_and(|ui| ui.is_typing());
+        let comparing = !typing && is_key_down(KeyCode::C) && is_key_down(KeyCode::LeftShift);
+        if !typing && is_key_pressed(KeyCode::C) && !is_key_down(KeyCode::LeftShift) {
+            let ppu = emulator.cpu.mmap.get_ppu_mut();
+            let enabled = !ppu.color_correction_enabled();
+            ppu.color_correction(enabled);
+        }
+        let color_correction = emulator.cpu.mmap.get_ppu().color_correction_enabled();
+
+        // Get frame buffer from PPU
+        let frame_buffer = emulator.get_frame_buffer();
+        
+        
+        
+
+        // Draw the Game Boy screen (160x144)
+        let scale = 4.0;
+        for y in 0..144 {
+            for x in 0..160 {
+                let pixel = frame_buffer[y * 160 + x];
+                // Original Game Boy green monochrome colors
+                let corrected = if comparing { x < 80 } else { color_correction };
+                let color = if pixel <= 3 {
+                    let [r, g, b, a] = rgb::rgb::ppu::shade_rgba(pixel, corrected);
+                    Color::new(r, g, b, a)
+                } else {
+                    MAGENTA // Error color
+                };
+                
+                draw_rectangle(
+                    x as f32 * scale,
+                    y as f32 * scale,
+                    scale,
+                    scale,
+                    color
+                );
+            }
+        }
+        
+
+        // Display FPS and timing info
+        let fps = get_fps();
+        let stats = emulator.timing_stats();
+        let fps_text = format!("Game Boy Emulator - FPS: {:.1} | Frame: {} | Frame time: {:.1}ms (max {:.1}ms) | Missed: {}", 
+            fps, emulator.cpu.mmap.get_ppu().frames_rendered(), stats.avg_frame_us as f32 / 1000.0, stats.max_frame_us as f32 / 1000.0, stats.missed_frames);
+        draw_text(&fps_text, 10.0, screen_height() - 20.0, 20.0, WHITE);
+        if emulator.cpu.mmap.cart_checksum_valid() == Some(false) {
+            draw_text("BAD ROM", screen_width() - 100.0, screen_height() - 20.0, 20.0, RED);
+        }
+        if emulator.cpu.mmap.cart_header_checksum_valid() == Some(false) {
+            draw_text("BAD HEADER", screen_width() - 220.0, screen_height() - 20.0, 20.0, RED);
+        }
+        
+        #[cfg(debug_assertions)]
+        {
+            let pc = emulator.cpu.pc;
+            let halted = emulator.cpu.halted;
+            let ly = emulator.cpu.mmap.peek(0xFF44); // Current scanline
+            let lcdc = emulator.cpu.mmap.peek(0xFF40); // LCD control
+            let ppu_ly = emulator.cpu.mmap.get_ppu().ly;
+            let lcd_enabled = (lcdc & 0x80) != 0;
+            let debug_text = format!("PC: 0x{:04X} | LY: {} | PPU_LY: {} | LCD: {} | Halted: {}", 
+                pc, ly, ppu_ly, lcd_enabled, halted);
+            draw_text(&debug_text, 10.0, screen_height() - 40.0, 16.0, WHITE);
+            
+            let audio_sync = emulator.audio_sync.lock().unwrap();
+            let audio_text = format!("Audio: {} buffered | Underruns: {} | Overruns: {}",
+                audio_sync.samples_available(), audio_sync.underruns(), audio_sync.overruns());
+            drop(audio_sync);
+            draw_text(&audio_text, 10.0, screen_height() - 56.0, 16.0, WHITE);
+            
+        }
+
+        // Handle debugger UI
+        if let (Some(ref mut debugger), Some(ref mut debugger_ui)) = (&mut emulator.debugger, &mut emulator.debugger_ui) {
+            debugger_ui.handle_input();
+            debugger_ui.draw(debugger);
+            
+            let ppu = emulator.cpu.mmap.get_ppu();
+            debugger_ui.sprite_viewer.draw(ppu.get_oam(), ppu.get_vram(), ppu.obp0, ppu.obp1, ppu.lcdc.sprite_size);
+            debugger_ui.tile_viewer.draw(ppu.get_vram(), ppu.bgp, ppu.lcdc.bg_window_tiles);
+            if debugger_ui.bg_map_viewer.show {
+                let map = Ppu::render_full_background_map(ppu.get_vram(), &ppu.lcdc, ppu.scx, ppu.scy, ppu.bgp);
+                debugger_ui.bg_map_viewer.draw(&map, ppu.lcdc.bg_tile_map, ppu.lcdc.bg_window_tiles, ppu.scx, ppu.scy);
+            }
+            if debugger_ui.memory_editor.show {
+                let page = emulator.cpu.mmap.read_range(debugger_ui.memory_editor.page_start, MEMORY_PAGE_SIZE);
+                debugger_ui.memory_editor.set_page(page);
+                debugger_ui.memory_editor.draw();
+            }
+        }
+
+        // Frame timing control - hold to 59.7 Hz regardless of vsync
+        if !emulator.benchmark_mode {
+            emulator.frame_timer.wait_for_next_frame();
+        }
+
+        next_frame().await
+    }
+    
+    // miniquad can only report the window position on Windows
+    #[cfg(target_os = "windows")]
+    {
+        let (x, y) = miniquad::window::get_window_position();
+        emulator.config.window_x = Some(x);
+        emulator.config.window_y = Some(y);
+    }
+}
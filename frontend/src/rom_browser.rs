@@ -0,0 +1,157 @@
+// Terminal ROM browser for --rom-list
+// Lists *.gb / *.gbc files in a directory with their header titles and lets the
+// user pick one with the arrow keys (or by number where raw input is unavailable)
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use rgb::rgb::cart::Cart;
+
+pub struct RomEntry {
+    pub path: PathBuf,
+    pub title: String,
+}
+
+/// Scans `dir` for Game Boy ROMs, sorted by file name
+pub fn scan_directory(dir: &Path) -> io::Result<Vec<RomEntry>> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_rom = path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("gb") || ext.eq_ignore_ascii_case("gbc"));
+        if !is_rom || !path.is_file() {
+            continue;
+        }
+
+        let title = Cart::read_title(&path).unwrap_or_else(|_| "Unreadable".to_string());
+        entries.push(RomEntry { path, title });
+    }
+    entries.sort_by(|a, b| a.path.file_name().cmp(&b.path.file_name()));
+    Ok(entries)
+}
+
+/// Shows the interactive list and returns the chosen ROM, or None if cancelled
+pub fn choose_rom(entries: &[RomEntry]) -> Option<PathBuf> {
+    if entries.is_empty() {
+        return None;
+    }
+
+    match RawTerminal::enable() {
+        Some(_raw) => choose_with_arrows(entries),
+        None => choose_by_number(entries),
+    }
+}
+
+fn draw_list(entries: &[RomEntry], selected: usize) {
+    let mut out = io::stdout();
+    // Clear screen and move the cursor home
+    write!(out, "\x1b[2J\x1b[H").ok();
+    write!(out, "Select a ROM (Up/Down to move, Enter to load, q to quit)\r\n\r\n").ok();
+    for (i, entry) in entries.iter().enumerate() {
+        let file_name = entry.path.file_name().unwrap_or_default().to_string_lossy();
+        let line = format!("{:>3}. {:<16} {}", i + 1, entry.title, file_name);
+        if i == selected {
+            // Reverse video for the highlighted entry
+            write!(out, "\x1b[7m{}\x1b[0m\r\n", line).ok();
+        } else {
+            write!(out, "{}\r\n", line).ok();
+        }
+    }
+    out.flush().ok();
+}
+
+fn choose_with_arrows(entries: &[RomEntry]) -> Option<PathBuf> {
+    let mut selected = 0;
+    let mut stdin = io::stdin();
+    let mut byte = [0u8; 1];
+
+    loop {
+        draw_list(entries, selected);
+        if stdin.read(&mut byte).ok()? == 0 {
+            return None;
+        }
+
+        match byte[0] {
+            b'\r' | b'\n' => return Some(entries[selected].path.clone()),
+            b'q' | b'Q' => return None,
+            0x1B => {
+                // Arrow keys arrive as ESC [ A (up) / ESC [ B (down)
+                let mut sequence = [0u8; 2];
+                if stdin.read_exact(&mut sequence).is_err() || sequence[0] != b'[' {
+                    continue;
+                }
+                match sequence[1] {
+                    b'A' => selected = selected.checked_sub(1).unwrap_or(entries.len() - 1),
+                    b'B' => selected = (selected + 1) % entries.len(),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn choose_by_number(entries: &[RomEntry]) -> Option<PathBuf> {
+    for (i, entry) in entries.iter().enumerate() {
+        let file_name = entry.path.file_name().unwrap_or_default().to_string_lossy();
+        println!("{:>3}. {:<16} {}", i + 1, entry.title, file_name);
+    }
+
+    loop {
+        print!("Enter ROM number (blank to quit): ");
+        io::stdout().flush().ok();
+
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line).ok()? == 0 || line.trim().is_empty() {
+            return None;
+        }
+        match line.trim().parse::<usize>() {
+            Ok(n) if n >= 1 && n <= entries.len() => return Some(entries[n - 1].path.clone()),
+            _ => println!("Invalid selection"),
+        }
+    }
+}
+
+// Puts the terminal into unbuffered, no-echo mode via `stty` and restores it on drop
+struct RawTerminal {
+    saved_mode: String,
+}
+
+impl RawTerminal {
+    #[cfg(unix)]
+    fn enable() -> Option<Self> {
+        use std::process::{Command, Stdio};
+
+        let output = Command::new("stty").arg("-g").stdin(Stdio::inherit()).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let saved_mode = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+        let status = Command::new("stty").args(["-icanon", "-echo", "min", "1"]).stdin(Stdio::inherit()).status().ok()?;
+        if !status.success() {
+            return None;
+        }
+        Some(Self { saved_mode })
+    }
+
+    #[cfg(not(unix))]
+    fn enable() -> Option<Self> {
+        None
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        {
+            use std::process::{Command, Stdio};
+            Command::new("stty").arg(&self.saved_mode).stdin(Stdio::inherit()).status().ok();
+        }
+        // Clear the browser before the emulator starts logging
+        print!("\x1b[2J\x1b[H");
+        io::stdout().flush().ok();
+    }
+}
@@ -0,0 +1,709 @@
+// Game Boy APU (Audio Processing Unit) Implementation
+// Covers Channel 1 (square wave with sweep and envelope), Channel 3 (wave RAM playback),
+// Channel 4 (LFSR noise) and the NR50/NR51/NR52 control registers, mixed down to a mono
+// 44100 Hz stream
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+pub const NR10_ADDR: u16 = 0xFF10; // Channel 1 sweep
+pub const NR11_ADDR: u16 = 0xFF11; // Channel 1 duty and length
+pub const NR12_ADDR: u16 = 0xFF12; // Channel 1 volume envelope
+pub const NR13_ADDR: u16 = 0xFF13; // Channel 1 frequency low
+pub const NR14_ADDR: u16 = 0xFF14; // Channel 1 trigger, length enable, frequency high
+pub const NR30_ADDR: u16 = 0xFF1A; // Channel 3 DAC enable
+pub const NR31_ADDR: u16 = 0xFF1B; // Channel 3 length
+pub const NR32_ADDR: u16 = 0xFF1C; // Channel 3 output level
+pub const NR33_ADDR: u16 = 0xFF1D; // Channel 3 frequency low
+pub const NR34_ADDR: u16 = 0xFF1E; // Channel 3 trigger, length enable, frequency high
+pub const NR41_ADDR: u16 = 0xFF20; // Channel 4 length
+pub const NR42_ADDR: u16 = 0xFF21; // Channel 4 volume envelope
+pub const NR43_ADDR: u16 = 0xFF22; // Channel 4 clock shift, LFSR width, divisor
+pub const NR44_ADDR: u16 = 0xFF23; // Channel 4 trigger, length enable
+pub const NR50_ADDR: u16 = 0xFF24; // Master volume
+pub const NR51_ADDR: u16 = 0xFF25; // Panning
+pub const NR52_ADDR: u16 = 0xFF26; // Power and channel status
+pub const WAVE_RAM_START: u16 = 0xFF30; // 32 4-bit samples, high nibble first
+pub const WAVE_RAM_END: u16 = 0xFF3F;
+
+pub const SAMPLE_RATE: u32 = 44100;
+const CPU_CLOCK: u32 = 4_194_304;
+const FRAME_SEQUENCER_PERIOD: u32 = 8192; // 512 Hz, the falling edge of DIV bit 4
+const MAX_BUFFERED_SAMPLES: usize = SAMPLE_RATE as usize; // Drop new samples if nobody drains
+const SAMPLE_AMPLITUDE: f32 = 8192.0; // Full volume on one channel, leaving headroom
+
+// Waveforms for 12.5%, 25%, 50% and 75% duty
+// NR32 output level 0-3 as a right shift of each sample: mute, 100%, 50%, 25%
+const WAVE_VOLUME_SHIFTS: [u8; 4] = [4, 0, 1, 2];
+// NR43 divisor code 0-7 in cycles; the noise period is the divisor shifted left by the clock shift
+const NOISE_DIVISORS: [u32; 8] = [8, 16, 32, 48, 64, 80, 96, 112];
+
+const DUTY_PATTERNS: [[u8; 8]; 4] = [
+    [0, 0, 0, 0, 0, 0, 0, 1],
+    [1, 0, 0, 0, 0, 0, 0, 1],
+    [1, 0, 0, 0, 0, 1, 1, 1],
+    [0, 1, 1, 1, 1, 1, 1, 0],
+];
+
+// Channel 1: a square wave whose frequency can sweep and whose volume follows an envelope
+struct SquareChannel {
+    enabled: bool,
+    dac_enabled: bool,
+    duty: u8,
+    duty_position: usize,
+    length_counter: u8,
+    length_enabled: bool,
+    frequency: u16, // 11-bit; the period is (2048 - frequency) * 4 cycles per duty step
+    frequency_timer: u32,
+    // Envelope (NR12)
+    initial_volume: u8,
+    envelope_increase: bool,
+    envelope_period: u8,
+    envelope_timer: u8,
+    volume: u8,
+    // Sweep (NR10)
+    sweep_period: u8,
+    sweep_negate: bool,
+    sweep_shift: u8,
+    sweep_timer: u8,
+    sweep_enabled: bool,
+    shadow_frequency: u16,
+}
+
+impl SquareChannel {
+    fn new() -> Self {
+        Self {
+            enabled: false,
+            dac_enabled: false,
+            duty: 0,
+            duty_position: 0,
+            length_counter: 0,
+            length_enabled: false,
+            frequency: 0,
+            frequency_timer: 0,
+            initial_volume: 0,
+            envelope_increase: false,
+            envelope_period: 0,
+            envelope_timer: 0,
+            volume: 0,
+            sweep_period: 0,
+            sweep_negate: false,
+            sweep_shift: 0,
+            sweep_timer: 0,
+            sweep_enabled: false,
+            shadow_frequency: 0,
+        }
+    }
+
+    fn period(&self) -> u32 {
+        (2048 - self.frequency as u32) * 4
+    }
+
+    fn trigger(&mut self) {
+        self.enabled = self.dac_enabled;
+        if self.length_counter == 0 {
+            self.length_counter = 64;
+        }
+        self.frequency_timer = self.period();
+        self.envelope_timer = self.envelope_period;
+        self.volume = self.initial_volume;
+
+        self.shadow_frequency = self.frequency;
+        self.sweep_timer = if self.sweep_period == 0 { 8 } else { self.sweep_period };
+        self.sweep_enabled = self.sweep_period != 0 || self.sweep_shift != 0;
+        if self.sweep_shift != 0 {
+            // Overflow check only; the result is discarded
+            self.calculate_sweep();
+        }
+    }
+
+    fn step(&mut self, cycles: u32) {
+        let mut remaining = cycles;
+        while remaining > 0 {
+            if self.frequency_timer > remaining {
+                self.frequency_timer -= remaining;
+                return;
+            }
+            remaining -= self.frequency_timer;
+            self.frequency_timer = self.period();
+            self.duty_position = (self.duty_position + 1) % 8;
+        }
+    }
+
+    // Next sweep frequency; disables the channel when it overflows 11 bits
+    fn calculate_sweep(&mut self) -> u16 {
+        let delta = self.shadow_frequency >> self.sweep_shift;
+        let frequency = if self.sweep_negate {
+            self.shadow_frequency.wrapping_sub(delta)
+        } else {
+            self.shadow_frequency + delta
+        };
+        if frequency > 2047 {
+            self.enabled = false;
+        }
+        frequency
+    }
+
+    fn clock_length(&mut self) {
+        if self.length_enabled && self.length_counter > 0 {
+            self.length_counter -= 1;
+            if self.length_counter == 0 {
+                self.enabled = false;
+            }
+        }
+    }
+
+    fn clock_sweep(&mut self) {
+        if self.sweep_timer > 0 {
+            self.sweep_timer -= 1;
+        }
+        if self.sweep_timer != 0 {
+            return;
+        }
+        self.sweep_timer = if self.sweep_period == 0 { 8 } else { self.sweep_period };
+
+        if self.sweep_enabled && self.sweep_period != 0 {
+            let frequency = self.calculate_sweep();
+            if frequency <= 2047 && self.sweep_shift != 0 {
+                self.shadow_frequency = frequency;
+                self.frequency = frequency;
+                // The new frequency is checked for overflow again straight away
+                self.calculate_sweep();
+            }
+        }
+    }
+
+    fn clock_envelope(&mut self) {
+        if self.envelope_period == 0 {
+            return;
+        }
+        if self.envelope_timer > 0 {
+            self.envelope_timer -= 1;
+        }
+        if self.envelope_timer == 0 {
+            self.envelope_timer = self.envelope_period;
+            if self.envelope_increase && self.volume < 15 {
+                self.volume += 1;
+            } else if !self.envelope_increase && self.volume > 0 {
+                self.volume -= 1;
+            }
+        }
+    }
+
+    // Current output from -1.0 to 1.0
+    fn output(&self) -> f32 {
+        if !self.enabled || !self.dac_enabled {
+            return 0.0;
+        }
+        let level = self.volume as f32 / 15.0;
+        if DUTY_PATTERNS[self.duty as usize][self.duty_position] != 0 { level } else { -level }
+    }
+}
+
+// Channel 3: plays back the 32 samples in wave RAM at a programmable rate
+struct WaveChannel {
+    enabled: bool,
+    dac_enabled: bool,
+    length_counter: u16,
+    length_enabled: bool,
+    volume_code: u8,
+    frequency: u16, // 11-bit; the period is (2048 - frequency) * 2 cycles per sample
+    frequency_timer: u32,
+    position: usize, // 0-31
+}
+
+impl WaveChannel {
+    fn new() -> Self {
+        Self {
+            enabled: false,
+            dac_enabled: false,
+            length_counter: 0,
+            length_enabled: false,
+            volume_code: 0,
+            frequency: 0,
+            frequency_timer: 0,
+            position: 0,
+        }
+    }
+
+    fn period(&self) -> u32 {
+        (2048 - self.frequency as u32) * 2
+    }
+
+    fn trigger(&mut self) {
+        self.enabled = self.dac_enabled;
+        if self.length_counter == 0 {
+            self.length_counter = 256;
+        }
+        self.frequency_timer = self.period();
+        self.position = 0;
+    }
+
+    fn step(&mut self, cycles: u32) {
+        if !self.enabled {
+            return;
+        }
+        let mut remaining = cycles;
+        while remaining > 0 {
+            if self.frequency_timer > remaining {
+                self.frequency_timer -= remaining;
+                return;
+            }
+            remaining -= self.frequency_timer;
+            self.frequency_timer = self.period();
+            self.position = (self.position + 1) % 32;
+        }
+    }
+
+    fn clock_length(&mut self) {
+        if self.length_enabled && self.length_counter > 0 {
+            self.length_counter -= 1;
+            if self.length_counter == 0 {
+                self.enabled = false;
+            }
+        }
+    }
+
+    // Current output from -1.0 to 1.0, centered on the shifted range so a muted channel is silent
+    fn output(&self, wave_ram: &[u8; 16]) -> f32 {
+        if !self.enabled || !self.dac_enabled {
+            return 0.0;
+        }
+        let byte = wave_ram[self.position / 2];
+        let sample = if self.position.is_multiple_of(2) { byte >> 4 } else { byte & 0x0F };
+        let shift = WAVE_VOLUME_SHIFTS[self.volume_code as usize];
+        let max = (15 >> shift) as f32;
+        (2.0 * (sample >> shift) as f32 - max) / 15.0
+    }
+}
+
+// Channel 4: pseudo-random noise from a 15-bit (or 7-bit) linear feedback shift register
+struct NoiseChannel {
+    enabled: bool,
+    dac_enabled: bool,
+    length_counter: u8,
+    length_enabled: bool,
+    // Noise parameters (NR43)
+    clock_shift: u8,
+    width_7bit: bool,
+    divisor_code: u8,
+    frequency_timer: u32,
+    lfsr: u16,
+    // Envelope (NR42)
+    initial_volume: u8,
+    envelope_increase: bool,
+    envelope_period: u8,
+    envelope_timer: u8,
+    volume: u8,
+}
+
+impl NoiseChannel {
+    fn new() -> Self {
+        Self {
+            enabled: false,
+            dac_enabled: false,
+            length_counter: 0,
+            length_enabled: false,
+            clock_shift: 0,
+            width_7bit: false,
+            divisor_code: 0,
+            frequency_timer: 0,
+            lfsr: 0,
+            initial_volume: 0,
+            envelope_increase: false,
+            envelope_period: 0,
+            envelope_timer: 0,
+            volume: 0,
+        }
+    }
+
+    fn period(&self) -> u32 {
+        NOISE_DIVISORS[self.divisor_code as usize] << self.clock_shift
+    }
+
+    fn read_nr43(&self) -> u8 {
+        (self.clock_shift << 4) | ((self.width_7bit as u8) << 3) | self.divisor_code
+    }
+
+    fn trigger(&mut self) {
+        self.enabled = self.dac_enabled;
+        if self.length_counter == 0 {
+            self.length_counter = 64;
+        }
+        self.frequency_timer = self.period();
+        self.envelope_timer = self.envelope_period;
+        self.volume = self.initial_volume;
+        self.lfsr = 0x7FFF;
+    }
+
+    fn step(&mut self, cycles: u32) {
+        // Clock shifts 14 and 15 leave the LFSR without a clock
+        if !self.enabled || self.clock_shift >= 14 {
+            return;
+        }
+        let mut remaining = cycles;
+        while remaining > 0 {
+            if self.frequency_timer > remaining {
+                self.frequency_timer -= remaining;
+                return;
+            }
+            remaining -= self.frequency_timer;
+            self.frequency_timer = self.period();
+            self.clock_lfsr();
+        }
+    }
+
+    // Shifts right, feeding bit 0 XOR bit 1 into bit 14 (and bit 6 in 7-bit mode)
+    fn clock_lfsr(&mut self) {
+        let feedback = (self.lfsr ^ (self.lfsr >> 1)) & 0x01;
+        self.lfsr = (self.lfsr >> 1) | (feedback << 14);
+        if self.width_7bit {
+            self.lfsr = (self.lfsr & !0x40) | (feedback << 6);
+        }
+    }
+
+    fn clock_length(&mut self) {
+        if self.length_enabled && self.length_counter > 0 {
+            self.length_counter -= 1;
+            if self.length_counter == 0 {
+                self.enabled = false;
+            }
+        }
+    }
+
+    fn clock_envelope(&mut self) {
+        if self.envelope_period == 0 {
+            return;
+        }
+        if self.envelope_timer > 0 {
+            self.envelope_timer -= 1;
+        }
+        if self.envelope_timer == 0 {
+            self.envelope_timer = self.envelope_period;
+            if self.envelope_increase && self.volume < 15 {
+                self.volume += 1;
+            } else if !self.envelope_increase && self.volume > 0 {
+                self.volume -= 1;
+            }
+        }
+    }
+
+    // Current output from -1.0 to 1.0: high while LFSR bit 0 is set
+    fn output(&self) -> f32 {
+        if !self.enabled || !self.dac_enabled {
+            return 0.0;
+        }
+        let level = self.volume as f32 / 15.0;
+        if self.lfsr & 0x01 != 0 { level } else { -level }
+    }
+}
+
+pub struct Apu {
+    power: bool,
+    channel1: SquareChannel,
+    channel3: WaveChannel,
+    channel4: NoiseChannel,
+    wave_ram: [u8; 16],
+    nr10: u8,
+    nr50: u8,
+    nr51: u8,
+    frame_sequencer_cycles: u32,
+    frame_sequencer_step: u8,
+    sample_cycles: u32, // Accumulates cycles * SAMPLE_RATE until a sample is due
+    samples: Vec<i16>,
+}
+
+impl Default for Apu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Apu {
+    pub fn new() -> Self {
+        Self {
+            power: false,
+            channel1: SquareChannel::new(),
+            channel3: WaveChannel::new(),
+            channel4: NoiseChannel::new(),
+            wave_ram: [0; 16],
+            nr10: 0,
+            nr50: 0,
+            nr51: 0,
+            frame_sequencer_cycles: 0,
+            frame_sequencer_step: 0,
+            sample_cycles: 0,
+            samples: Vec::new(),
+        }
+    }
+
+    /// Creates an APU in the state the boot ROM leaves it: powered, with Channels 1, 3 and 4 silent
+    pub fn new_post_boot() -> Self {
+        let mut apu = Self::new();
+        apu.write_register(NR52_ADDR, 0x80);
+        apu.write_register(NR50_ADDR, 0x77);
+        apu.write_register(NR51_ADDR, 0xF3);
+        apu.write_register(NR10_ADDR, 0x80);
+        apu.write_register(NR11_ADDR, 0x80);
+        apu.write_register(NR12_ADDR, 0xF3);
+        apu.write_register(NR30_ADDR, 0x7F);
+        apu.write_register(NR32_ADDR, 0x9F);
+        apu
+    }
+
+    pub fn read_register(&self, addr: u16) -> u8 {
+        let channel = &self.channel1;
+        let wave = &self.channel3;
+        let noise = &self.channel4;
+        match addr {
+            NR10_ADDR => self.nr10 | 0x80,
+            NR11_ADDR => (channel.duty << 6) | 0x3F, // Length is write-only
+            NR12_ADDR => (channel.initial_volume << 4) | ((channel.envelope_increase as u8) << 3) | channel.envelope_period,
+            NR13_ADDR => 0xFF, // Write-only
+            NR14_ADDR => ((channel.length_enabled as u8) << 6) | 0xBF,
+            NR30_ADDR => ((wave.dac_enabled as u8) << 7) | 0x7F,
+            NR31_ADDR => 0xFF, // Write-only
+            NR32_ADDR => (wave.volume_code << 5) | 0x9F,
+            NR33_ADDR => 0xFF, // Write-only
+            NR34_ADDR => ((wave.length_enabled as u8) << 6) | 0xBF,
+            NR41_ADDR => 0xFF, // Length is write-only
+            NR42_ADDR => (noise.initial_volume << 4) | ((noise.envelope_increase as u8) << 3) | noise.envelope_period,
+            NR43_ADDR => noise.read_nr43(),
+            NR44_ADDR => ((noise.length_enabled as u8) << 6) | 0xBF,
+            NR50_ADDR => self.nr50,
+            NR51_ADDR => self.nr51,
+            NR52_ADDR => ((self.power as u8) << 7) | 0x70 | ((noise.enabled as u8) << 3) | ((wave.enabled as u8) << 2) | (channel.enabled as u8),
+            // The CPU only sees wave RAM while Channel 3 isn't playing it
+            WAVE_RAM_START..=WAVE_RAM_END if wave.enabled => 0xFF,
+            WAVE_RAM_START..=WAVE_RAM_END => self.wave_ram[(addr - WAVE_RAM_START) as usize],
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write_register(&mut self, addr: u16, value: u8) {
+        // Wave RAM isn't a register: it keeps its contents and stays writable while powered off
+        if (WAVE_RAM_START..=WAVE_RAM_END).contains(&addr) {
+            if !self.channel3.enabled {
+                self.wave_ram[(addr - WAVE_RAM_START) as usize] = value;
+            }
+            return;
+        }
+        if addr == NR52_ADDR {
+            let power = value & 0x80 != 0;
+            if self.power && !power {
+                // Powering off clears every register
+                self.channel1 = SquareChannel::new();
+                self.channel3 = WaveChannel::new();
+                self.channel4 = NoiseChannel::new();
+                self.nr10 = 0;
+                self.nr50 = 0;
+                self.nr51 = 0;
+            } else if !self.power && power {
+                self.frame_sequencer_step = 0;
+            }
+            self.power = power;
+            return;
+        }
+        // The other registers ignore writes while the APU is off
+        if !self.power {
+            return;
+        }
+
+        let channel = &mut self.channel1;
+        let wave = &mut self.channel3;
+        let noise = &mut self.channel4;
+        match addr {
+            NR10_ADDR => {
+                self.nr10 = value & 0x7F;
+                channel.sweep_period = (value >> 4) & 0x07;
+                channel.sweep_negate = value & 0x08 != 0;
+                channel.sweep_shift = value & 0x07;
+            }
+            NR11_ADDR => {
+                channel.duty = value >> 6;
+                channel.length_counter = 64 - (value & 0x3F);
+            }
+            NR12_ADDR => {
+                channel.initial_volume = value >> 4;
+                channel.envelope_increase = value & 0x08 != 0;
+                channel.envelope_period = value & 0x07;
+                // The DAC is off when the upper five bits are clear, which also silences the channel
+                channel.dac_enabled = value & 0xF8 != 0;
+                if !channel.dac_enabled {
+                    channel.enabled = false;
+                }
+            }
+            NR13_ADDR => {
+                channel.frequency = (channel.frequency & 0x0700) | value as u16;
+            }
+            NR14_ADDR => {
+                channel.frequency = (channel.frequency & 0x00FF) | (((value & 0x07) as u16) << 8);
+                channel.length_enabled = value & 0x40 != 0;
+                if value & 0x80 != 0 {
+                    channel.trigger();
+                }
+            }
+            NR30_ADDR => {
+                wave.dac_enabled = value & 0x80 != 0;
+                if !wave.dac_enabled {
+                    wave.enabled = false;
+                }
+            }
+            NR31_ADDR => {
+                wave.length_counter = 256 - value as u16;
+            }
+            NR32_ADDR => {
+                wave.volume_code = (value >> 5) & 0x03;
+            }
+            NR33_ADDR => {
+                wave.frequency = (wave.frequency & 0x0700) | value as u16;
+            }
+            NR34_ADDR => {
+                wave.frequency = (wave.frequency & 0x00FF) | (((value & 0x07) as u16) << 8);
+                wave.length_enabled = value & 0x40 != 0;
+                if value & 0x80 != 0 {
+                    wave.trigger();
+                }
+            }
+            NR41_ADDR => {
+                noise.length_counter = 64 - (value & 0x3F);
+            }
+            NR42_ADDR => {
+                noise.initial_volume = value >> 4;
+                noise.envelope_increase = value & 0x08 != 0;
+                noise.envelope_period = value & 0x07;
+                noise.dac_enabled = value & 0xF8 != 0;
+                if !noise.dac_enabled {
+                    noise.enabled = false;
+                }
+            }
+            NR43_ADDR => {
+                noise.clock_shift = value >> 4;
+                noise.width_7bit = value & 0x08 != 0;
+                noise.divisor_code = value & 0x07;
+            }
+            NR44_ADDR => {
+                noise.length_enabled = value & 0x40 != 0;
+                if value & 0x80 != 0 {
+                    noise.trigger();
+                }
+            }
+            NR50_ADDR => self.nr50 = value,
+            NR51_ADDR => self.nr51 = value,
+            _ => {}
+        }
+    }
+
+    /// Restarts the frame sequencer's countdown; DIV writes reset the bit that clocks it
+    pub fn reset_frame_sequencer_timer(&mut self) {
+        self.frame_sequencer_cycles = 0;
+    }
+
+    pub fn step(&mut self, cycles: u16) {
+        let cycles = cycles as u32;
+
+        if self.power {
+            self.frame_sequencer_cycles += cycles;
+            while self.frame_sequencer_cycles >= FRAME_SEQUENCER_PERIOD {
+                self.frame_sequencer_cycles -= FRAME_SEQUENCER_PERIOD;
+                self.clock_frame_sequencer();
+            }
+            self.channel1.step(cycles);
+            self.channel3.step(cycles);
+            self.channel4.step(cycles);
+        }
+
+        self.sample_cycles += cycles * SAMPLE_RATE;
+        while self.sample_cycles >= CPU_CLOCK {
+            self.sample_cycles -= CPU_CLOCK;
+            if self.samples.len() < MAX_BUFFERED_SAMPLES {
+                let sample = self.mix();
+                self.samples.push(sample);
+            }
+        }
+    }
+
+    /// Channel 4's shift register, for inspecting the noise sequence
+    #[allow(dead_code)] // Public API method
+    pub fn noise_lfsr(&self) -> u16 {
+        self.channel4.lfsr
+    }
+
+    /// Returns the samples generated since the last call
+    pub fn drain_samples(&mut self) -> Vec<i16> {
+        std::mem::take(&mut self.samples)
+    }
+
+    // Length at 256 Hz, sweep at 128 Hz and envelope at 64 Hz
+    fn clock_frame_sequencer(&mut self) {
+        match self.frame_sequencer_step {
+            0 | 4 => {
+                self.channel1.clock_length();
+                self.channel3.clock_length();
+                self.channel4.clock_length();
+            }
+            2 | 6 => {
+                self.channel1.clock_length();
+                self.channel3.clock_length();
+                self.channel4.clock_length();
+                self.channel1.clock_sweep();
+            }
+            7 => {
+                self.channel1.clock_envelope();
+                self.channel4.clock_envelope();
+            }
+            _ => {}
+        }
+        self.frame_sequencer_step = (self.frame_sequencer_step + 1) % 8;
+    }
+
+    // Averages the left and right outputs, each scaled by NR50's master volume
+    fn mix(&self) -> i16 {
+        if !self.power {
+            return 0;
+        }
+        let channel1 = self.channel1.output();
+        let channel3 = self.channel3.output(&self.wave_ram);
+        let channel4 = self.channel4.output();
+        let left_volume = ((self.nr50 >> 4) & 0x07) as f32 + 1.0;
+        let right_volume = (self.nr50 & 0x07) as f32 + 1.0;
+        let mut left = 0.0;
+        let mut right = 0.0;
+        if self.nr51 & 0x10 != 0 { left += channel1; }
+        if self.nr51 & 0x40 != 0 { left += channel3; }
+        if self.nr51 & 0x80 != 0 { left += channel4; }
+        if self.nr51 & 0x01 != 0 { right += channel1; }
+        if self.nr51 & 0x04 != 0 { right += channel3; }
+        if self.nr51 & 0x08 != 0 { right += channel4; }
+        let left = left * left_volume / 8.0;
+        let right = right * right_volume / 8.0;
+        ((left + right) / 2.0 * SAMPLE_AMPLITUDE) as i16
+    }
+}
+
+/// Saves mono 16-bit samples at `SAMPLE_RATE` as a WAV file
+pub fn write_wav(path: &Path, samples: &[i16]) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    let data_size = (samples.len() * 2) as u32;
+
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(36 + data_size).to_le_bytes())?;
+    writer.write_all(b"WAVE")?;
+
+    writer.write_all(b"fmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?; // Chunk size
+    writer.write_all(&1u16.to_le_bytes())?; // PCM
+    writer.write_all(&1u16.to_le_bytes())?; // Mono
+    writer.write_all(&SAMPLE_RATE.to_le_bytes())?;
+    writer.write_all(&(SAMPLE_RATE * 2).to_le_bytes())?; // Byte rate
+    writer.write_all(&2u16.to_le_bytes())?; // Block align
+    writer.write_all(&16u16.to_le_bytes())?; // Bits per sample
+
+    writer.write_all(b"data")?;
+    writer.write_all(&data_size.to_le_bytes())?;
+    for sample in samples {
+        writer.write_all(&sample.to_le_bytes())?;
+    }
+    writer.flush()
+}
@@ -0,0 +1,81 @@
+// Audio sample synchronization
+// Buffers APU output between the emulator, which produces a frame's worth of samples
+// at a time, and the audio device, which consumes them at a steady rate
+
+use super::apu::SAMPLE_RATE;
+use std::collections::VecDeque;
+
+/// Whole samples produced per frame at 59.7 fps (44100 / 59.7 = 738.7)
+pub const SAMPLES_PER_FRAME: usize = (SAMPLE_RATE as f64 / 59.7) as usize;
+pub const DEFAULT_BUFFER_FRAMES: usize = 4; // ~67 ms of latency
+
+pub struct AudioSyncBuffer {
+    samples: VecDeque<i16>,
+    capacity: usize,
+    output: Vec<i16>, // Backing storage for the slice returned by drain
+    underruns: u64,
+    overruns: u64,
+}
+
+impl Default for AudioSyncBuffer {
+    fn default() -> Self {
+        Self::new(SAMPLES_PER_FRAME * DEFAULT_BUFFER_FRAMES)
+    }
+}
+
+impl AudioSyncBuffer {
+    /// Creates a buffer holding at most `capacity` samples
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+            output: Vec::new(),
+            underruns: 0,
+            overruns: 0,
+        }
+    }
+
+    /// Queues newly generated samples. When the emulator runs ahead of playback and
+    /// the buffer fills up, the oldest samples are dropped to make room.
+    pub fn push(&mut self, samples: &[i16]) {
+        let overflow = (self.samples.len() + samples.len()).saturating_sub(self.capacity);
+        if overflow > 0 {
+            self.overruns += 1;
+            let from_buffer = overflow.min(self.samples.len());
+            self.samples.drain(..from_buffer);
+        }
+        // A single push larger than the whole buffer keeps only its newest samples
+        let skip = samples.len().saturating_sub(self.capacity);
+        self.samples.extend(&samples[skip..]);
+    }
+
+    /// Takes `samples_needed` samples for the audio device. On underrun the missing
+    /// samples are filled with silence rather than waiting for the emulator.
+    pub fn drain(&mut self, samples_needed: usize) -> &[i16] {
+        let available = samples_needed.min(self.samples.len());
+        if available < samples_needed {
+            self.underruns += 1;
+        }
+        self.output.clear();
+        self.output.extend(self.samples.drain(..available));
+        self.output.resize(samples_needed, 0);
+        &self.output
+    }
+
+    #[allow(dead_code)] // Public API method
+    pub fn samples_available(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Number of drains that had to be padded with silence
+    #[allow(dead_code)] // Public API method
+    pub fn underruns(&self) -> u64 {
+        self.underruns
+    }
+
+    /// Number of pushes that dropped old samples
+    #[allow(dead_code)] // Public API method
+    pub fn overruns(&self) -> u64 {
+        self.overruns
+    }
+}
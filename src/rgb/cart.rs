@@ -1,7 +1,9 @@
-use std::fs;
+use std::fs::{self, File};
+use std::io::Read;
 use std::path::Path;
 #[cfg(debug_assertions)]
 use log::debug;
+use log::warn;
 
 #[derive(Debug, Clone, Copy)]
 pub enum CartridgeType {
@@ -14,6 +16,7 @@ pub enum CartridgeType {
     Mbc3 = 0x11,
     Mbc3Ram = 0x12,
     Mbc3RamBattery = 0x13,
+    Mbc1Multi, // MBC1M multicart - not a header value, detected from the ROM layout
 }
 
 impl CartridgeType {
@@ -45,6 +48,167 @@ impl CartridgeType {
             CartridgeType::Mbc3TimerBattery | CartridgeType::Mbc3TimerRamBattery
         )
     }
+
+    pub fn is_mbc1(&self) -> bool {
+        matches!(self, 
+            CartridgeType::Mbc1 | CartridgeType::Mbc1Ram | CartridgeType::Mbc1RamBattery
+        )
+    }
+}
+
+const MBC1M_ROM_SIZE: usize = 0x100000;    // Known MBC1M multicarts are 1 MiB
+const MBC1M_SUB_ROM_SIZE: usize = 0x40000; // Four 256 KiB games
+const LOGO_START: usize = 0x0104;
+const LOGO_END: usize = 0x0134;
+
+/// MBC1M multicarts can't be told apart by their header type, so look for the
+/// Nintendo logo repeated in the header of the second 256 KiB sub-ROM, which is
+/// where the multicart's second game starts.
+pub fn is_mbc1_multicart(rom: &[u8]) -> bool {
+    let cartridge_type = rom.get(0x0147).copied().and_then(CartridgeType::from_byte);
+    if !cartridge_type.is_some_and(|t| t.is_mbc1()) || rom.len() != MBC1M_ROM_SIZE {
+        return false;
+    }
+    rom[LOGO_START..LOGO_END] == rom[MBC1M_SUB_ROM_SIZE + LOGO_START..MBC1M_SUB_ROM_SIZE + LOGO_END]
+}
+
+pub const HEADER_END: usize = 0x0150; // First 336 bytes hold the entry point and header
+const ROM_BANK_SIZE: usize = 0x4000;
+
+/// ROM size in bytes for a header size code (0x0148): 32 KB << n for 0x00-0x08, plus
+/// the 72, 80 and 96 bank sizes some docs list for 0x52-0x54. None for unknown codes.
+pub fn rom_size_from_code(code: u8) -> Option<usize> {
+    match code {
+        0x00..=0x08 => Some(32768 << code),
+        0x52 => Some(72 * ROM_BANK_SIZE),
+        0x53 => Some(80 * ROM_BANK_SIZE),
+        0x54 => Some(96 * ROM_BANK_SIZE),
+        _ => None,
+    }
+}
+
+/// External RAM size in bytes for a header size code (0x0149). None for unknown codes.
+pub fn ram_size_from_code(code: u8) -> Option<usize> {
+    match code {
+        0x00 => Some(0),      // No RAM
+        0x02 => Some(8192),   // 8KB
+        0x03 => Some(32768),  // 32KB (4 banks of 8KB)
+        0x04 => Some(131072), // 128KB (16 banks of 8KB)
+        0x05 => Some(65536),  // 64KB (8 banks of 8KB)
+        _ => None,
+    }
+}
+
+/// A header field that disagrees with the ROM image or with another field
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeaderWarning {
+    UnknownRomSize(u8),
+    RomSizeMismatch { header: usize, actual: usize },
+    UnknownRamSize(u8),
+    HeaderChecksumMismatch,
+    UnsupportedCartridgeType(u8),
+    // The cartridge type has external RAM but the RAM size is zero, or the other way round
+    RamSizeMismatch { cartridge_type: u8, ram_size: usize },
+}
+
+impl std::fmt::Display for HeaderWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeaderWarning::UnknownRomSize(code) => write!(f, "unknown ROM size code ${:02X}", code),
+            HeaderWarning::RomSizeMismatch { header, actual } =>
+                write!(f, "header ROM size is {} bytes but the ROM is {} bytes", header, actual),
+            HeaderWarning::UnknownRamSize(code) => write!(f, "unknown RAM size code ${:02X}", code),
+            HeaderWarning::HeaderChecksumMismatch => write!(f, "header checksum mismatch"),
+            HeaderWarning::UnsupportedCartridgeType(code) => write!(f, "unsupported cartridge type ${:02X}", code),
+            HeaderWarning::RamSizeMismatch { cartridge_type, ram_size } =>
+                write!(f, "cartridge type ${:02X} doesn't match a RAM size of {} bytes", cartridge_type, ram_size),
+        }
+    }
+}
+
+/// Extracts the cartridge title from ROM bytes starting at 0x0000.
+/// The title is 0x0134-0x0142, except on CGB cartridges (CGB flag 0x80 or 0xC0 at
+/// 0x0143), where 0x013F-0x0142 hold the manufacturer code and the title ends at 0x013E.
+pub fn parse_title(rom: &[u8]) -> String {
+    let byte = |addr: usize| rom.get(addr).copied().unwrap_or(0);
+    let end = match byte(0x0143) {
+        0x80 | 0xC0 => 0x013E,
+        _ => 0x0142,
+    };
+
+    (0x0134..=end)
+        .map(byte)
+        .take_while(|&b| b != 0)
+        .map(|b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
+
+/// Parsed cartridge header (ROM bytes 0x0100-0x014F)
+#[derive(Debug, Clone)]
+pub struct CartridgeHeader {
+    pub title: String,
+    pub manufacturer_code: String,   // 0x013F-0x0142 (newer carts only)
+    pub cgb_flag: u8,                // 0x0143
+    pub new_licensee_code: String,   // 0x0144-0x0145
+    pub sgb_flag: u8,                // 0x0146
+    pub cartridge_type: u8,          // 0x0147
+    pub rom_size: u8,                // 0x0148
+    pub ram_size: u8,                // 0x0149
+    pub destination_code: u8,        // 0x014A: 0x00=Japan, 0x01=Overseas
+    pub old_licensee_code: u8,       // 0x014B: 0x33 means use new licensee code
+    pub version: u8,                 // 0x014C
+    pub header_checksum: u8,         // 0x014D
+    pub global_checksum: u16,        // 0x014E-0x014F (big-endian)
+    pub header_checksum_valid: bool,
+    pub global_checksum_valid: bool,
+}
+
+impl CartridgeHeader {
+    pub fn parse(rom: &[u8]) -> Self {
+        // Missing bytes in truncated ROMs read as zero
+        let byte = |addr: usize| rom.get(addr).copied().unwrap_or(0);
+        let ascii = |start: usize, end: usize| -> String {
+            (start..=end)
+                .map(byte)
+                .take_while(|&b| b != 0)
+                .map(|b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+                .collect()
+        };
+
+        let header_checksum = byte(0x014D);
+
+        let global_checksum = ((byte(0x014E) as u16) << 8) | byte(0x014F) as u16;
+
+        CartridgeHeader {
+            title: parse_title(rom),
+            manufacturer_code: ascii(0x013F, 0x0142),
+            cgb_flag: byte(0x0143),
+            new_licensee_code: ascii(0x0144, 0x0145),
+            sgb_flag: byte(0x0146),
+            cartridge_type: byte(0x0147),
+            rom_size: byte(0x0148),
+            ram_size: byte(0x0149),
+            destination_code: byte(0x014A),
+            old_licensee_code: byte(0x014B),
+            version: byte(0x014C),
+            header_checksum,
+            global_checksum,
+            header_checksum_valid: rom.len() > 0x014D && computed_header_checksum(rom) == header_checksum,
+            global_checksum_valid: rom.len() > 0x014F && computed_global_checksum(rom) == global_checksum,
+        }
+    }
+
+    /// ROM size in bytes, or 0 for an unknown size code
+    pub fn rom_size_bytes(&self) -> usize {
+        rom_size_from_code(self.rom_size).unwrap_or(0)
+    }
+
+    /// External RAM size in bytes, or 0 for an unknown size code
+    pub fn ram_size_bytes(&self) -> usize {
+        ram_size_from_code(self.ram_size).unwrap_or(0)
+    }
 }
 
 #[derive(Debug)]
@@ -55,11 +219,31 @@ pub struct Cart {
     
     // MBC3 state
     rom_bank: u8,     // Current ROM bank (1-127)
+    
+    // MBC1M state
+    upper_bank: u8,   // Secondary 2-bit register - selects the sub-ROM
+    banking_mode: bool, // Mode 1 also maps the selected sub-ROM's bank 0 at 0x0000
     ram_bank: u8,     // Current RAM bank (0-3) or RTC register (0x08-0x0C)
     ram_rtc_enable: bool, // RAM/RTC access enable
     
     // RTC state (simplified - no actual time tracking for now)
     rtc_registers: [u8; 5], // S, M, H, DL, DH
+
+    checksum_valid: bool, // Global checksum matched when the ROM was loaded
+    header_checksum_valid: bool, // Header checksum matched, so the boot ROM would accept it
+}
+
+// Header checksum the boot ROM computes: 0 - (sum of 0x0134..=0x014C + 0x19), truncated
+// to 8 bits. Bytes past the end of a short ROM count as 0.
+fn computed_header_checksum(rom: &[u8]) -> u8 {
+    (0x0134..=0x014C).fold(0u8, |x, addr| x.wrapping_sub(rom.get(addr).copied().unwrap_or(0)).wrapping_sub(1))
+}
+
+// Global checksum: 16-bit sum of every ROM byte except the checksum itself at 0x014E-0x014F
+fn computed_global_checksum(rom: &[u8]) -> u16 {
+    rom.iter().enumerate()
+        .filter(|(addr, _)| *addr != 0x014E && *addr != 0x014F)
+        .fold(0u16, |sum, (_, &b)| sum.wrapping_add(b as u16))
 }
 
 impl Cart {
@@ -70,43 +254,72 @@ impl Cart {
             eprintln!("Error: {}", e);
             panic!("expected valid file path: {}", e);
         });
-        
+        Self::from_bytes(buf)
+    }
+    
+    /// Creates a cartridge from ROM bytes already in memory
+    pub fn from_bytes(buf: Vec<u8>) -> Self {
         // Read cartridge type from header
-        let cartridge_type = if buf.len() > 0x0147 {
+        let cartridge_type = if is_mbc1_multicart(&buf) {
+            CartridgeType::Mbc1Multi
+        } else if buf.len() > 0x0147 {
             CartridgeType::from_byte(buf[0x0147]).unwrap_or(CartridgeType::RomOnly)
         } else {
             CartridgeType::RomOnly
         };
         
-        // Determine RAM size from header
-        let ram_size = if buf.len() > 0x0149 {
-            match buf[0x0149] {
-                0x00 => 0,      // No RAM
-                0x02 => 8192,   // 8KB
-                0x03 => 32768,  // 32KB (4 banks of 8KB)
-                0x04 => 131072, // 128KB (16 banks of 8KB)
-                0x05 => 65536,  // 64KB (8 banks of 8KB)
-                _ => 0,
-            }
-        } else {
-            0
-        };
-        
-        #[cfg(debug_assertions)]
-        {
-            debug!("Loaded ROM: {} bytes", buf.len());
-            debug!("Cartridge type: {:?}", cartridge_type);
-            debug!("RAM size: {} bytes", ram_size);
-        }
-        
-        Cart { 
+        let mut cart = Cart { 
             rom: buf,
-            ram: vec![0; ram_size],
+            ram: Vec::new(),
             cartridge_type,
             rom_bank: 1,           // MBC3 starts with ROM bank 1
+            upper_bank: 0,
+            banking_mode: false,
             ram_bank: 0,           // Start with RAM bank 0
             ram_rtc_enable: false, // RAM/RTC access disabled by default
             rtc_registers: [0; 5], // Initialize RTC registers to 0
+            checksum_valid: false,
+            header_checksum_valid: false,
+        };
+        cart.ram = vec![0; cart.ram_size_from_header()];
+        cart.checksum_valid = cart.verify_global_checksum();
+        cart.header_checksum_valid = cart.verify_header_checksum();
+        if !cart.header_checksum_valid {
+            // Loaded anyway: callers check header_checksum_valid() to report it
+            warn!("Header checksum mismatch; a real boot ROM would lock up at the logo");
+        }
+        
+        #[cfg(debug_assertions)]
+        {
+            debug!("Loaded ROM: {} bytes", cart.rom.len());
+            debug!("Cartridge type: {:?}", cart.cartridge_type);
+            debug!("RAM size: {} bytes", cart.ram.len());
+            for warning in cart.validate_header() {
+                debug!("Header warning: {}", warning);
+            }
+            if !cart.checksum_valid {
+                debug!("Global checksum mismatch: ROM may be corrupt");
+            }
+        }
+        cart
+    }
+    
+    /// Maps a CPU address in 0x0000-0x7FFF to an offset into the ROM image
+    fn rom_address(&self, addr: u16) -> usize {
+        match (self.cartridge_type, addr) {
+            // MBC1M: the upper bank selects a 256 KiB sub-ROM and only 4 bits of the
+            // primary register select a bank within it
+            (CartridgeType::Mbc1Multi, 0x0000..=0x3FFF) => {
+                let base = if self.banking_mode { self.upper_bank as usize * MBC1M_SUB_ROM_SIZE } else { 0 };
+                base + addr as usize
+            }
+            (CartridgeType::Mbc1Multi, _) => {
+                (self.upper_bank as usize * MBC1M_SUB_ROM_SIZE)
+                    + ((self.rom_bank & 0x0F) as usize * 0x4000)
+                    + (addr - 0x4000) as usize
+            }
+            (_, 0x0000..=0x3FFF) => addr as usize,
+            _ => (self.rom_bank as usize * 0x4000) + (addr - 0x4000) as usize,
         }
     }
     
@@ -114,19 +327,19 @@ impl Cart {
         match addr {
             // ROM Bank 0 (0x0000-0x3FFF) - always accessible
             0x0000..=0x3FFF => {
-                if (addr as usize) < self.rom.len() {
-                    self.rom[addr as usize]
+                let rom_addr = self.rom_address(addr);
+                if rom_addr < self.rom.len() {
+                    self.rom[rom_addr]
                 } else {
                     0xFF // Return 0xFF for reads beyond ROM size
                 }
             }
             // ROM Bank 1+ (0x4000-0x7FFF) - switchable bank
             0x4000..=0x7FFF => {
-                let bank_offset = addr - 0x4000;
-                let rom_addr = (self.rom_bank as usize * 0x4000) + bank_offset as usize;
+                let rom_addr = self.rom_address(addr);
                 
                 #[cfg(debug_assertions)]
-                {
+                if crate::rgb::diagnostics::debug_output() {
                     static mut ROM_READ_COUNT: u32 = 0;
                     unsafe {
                         ROM_READ_COUNT += 1;
@@ -141,8 +354,10 @@ impl Cart {
                     self.rom[rom_addr]
                 } else {
                     #[cfg(debug_assertions)]
-                    println!("ROM read beyond bounds: bank {} addr 0x{:04X} -> ROM addr 0x{:06X} (ROM size: 0x{:06X})", 
-                        self.rom_bank, addr, rom_addr, self.rom.len());
+                    if crate::rgb::diagnostics::debug_output() {
+                        println!("ROM read beyond bounds: bank {} addr 0x{:04X} -> ROM addr 0x{:06X} (ROM size: 0x{:06X})", 
+                            self.rom_bank, addr, rom_addr, self.rom.len());
+                    }
                     0xFF
                 }
             }
@@ -150,6 +365,18 @@ impl Cart {
         }
     }
     
+    /// Overwrites the ROM byte currently mapped at `addr` (0x0000-0x7FFF).
+    /// Unlike `write`, this bypasses the MBC registers; used for patching ROM in debug sessions.
+    pub fn patch_rom(&mut self, addr: u16, value: u8) {
+        if addr > 0x7FFF {
+            return;
+        }
+        let rom_addr = self.rom_address(addr);
+        if let Some(byte) = self.rom.get_mut(rom_addr) {
+            *byte = value;
+        }
+    }
+    
     pub fn read_ram(&self, addr: u16) -> u8 {
         if !self.ram_rtc_enable {
             return 0xFF; // RAM/RTC access disabled
@@ -189,6 +416,11 @@ impl Cart {
     }
     
     pub fn write(&mut self, addr: u16, value: u8) {
+        if let CartridgeType::Mbc1Multi = self.cartridge_type {
+            self.write_mbc1_multi(addr, value);
+            return;
+        }
+        
         // Handle Memory Bank Controller (MBC3) writes
         match addr {
             0x0000..=0x1FFF => {
@@ -206,7 +438,7 @@ impl Cart {
                 let bank = if value == 0 { 1 } else { value & 0x7F }; // Banks 1-127, 0 becomes 1
                 self.rom_bank = bank;
                 #[cfg(debug_assertions)]
-                {
+                if crate::rgb::diagnostics::debug_output() {
                     static mut BANK_SWITCH_COUNT: u32 = 0;
                     unsafe {
                         BANK_SWITCH_COUNT += 1;
@@ -246,6 +478,29 @@ impl Cart {
         }
     }
     
+    fn write_mbc1_multi(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x0000..=0x1FFF => {
+                // RAM Enable (low nibble 0xA enables)
+                self.ram_rtc_enable = value & 0x0F == 0x0A;
+            }
+            0x2000..=0x3FFF => {
+                // Primary 5-bit bank register; 0 becomes 1 before the multicart
+                // drops bit 4, so bank 0x10 maps to bank 0 of the sub-ROM
+                let bank = value & 0x1F;
+                self.rom_bank = if bank == 0 { 1 } else { bank };
+            }
+            0x4000..=0x5FFF => {
+                // Secondary 2-bit register - selects the sub-ROM
+                self.upper_bank = value & 0x03;
+            }
+            0x6000..=0x7FFF => {
+                self.banking_mode = value & 0x01 != 0;
+            }
+            _ => {}
+        }
+    }
+    
     pub fn write_ram(&mut self, addr: u16, value: u8) {
         if !self.ram_rtc_enable {
             return; // RAM/RTC access disabled
@@ -280,14 +535,124 @@ impl Cart {
         }
     }
     
+    /// ROM size in bytes declared by the header byte at 0x0148, which may differ from
+    /// the image actually loaded. 0 if the header is missing or the code is unknown.
+    #[allow(dead_code)] // Public API method
+    pub fn rom_size(&self) -> usize {
+        self.rom.get(0x0148).copied().and_then(rom_size_from_code).unwrap_or(0)
+    }
+    
+    /// External RAM size in bytes declared by the header byte at 0x0149. 0 if the header
+    /// is missing or the code is unknown.
+    pub fn ram_size_from_header(&self) -> usize {
+        self.rom.get(0x0149).copied().and_then(ram_size_from_code).unwrap_or(0)
+    }
+    
+    /// Checks the header's ROM size, RAM size, header checksum and cartridge type against
+    /// the loaded image and each other. An empty list means the header looks consistent.
+    #[allow(dead_code)] // Public API method
+    pub fn validate_header(&self) -> Vec<HeaderWarning> {
+        let header = self.get_header();
+        let mut warnings = Vec::new();
+        
+        match rom_size_from_code(header.rom_size) {
+            None => warnings.push(HeaderWarning::UnknownRomSize(header.rom_size)),
+            Some(size) if size != self.rom.len() => {
+                warnings.push(HeaderWarning::RomSizeMismatch { header: size, actual: self.rom.len() });
+            }
+            Some(_) => {}
+        }
+        let ram_size = ram_size_from_code(header.ram_size);
+        if ram_size.is_none() {
+            warnings.push(HeaderWarning::UnknownRamSize(header.ram_size));
+        }
+        if !header.header_checksum_valid {
+            warnings.push(HeaderWarning::HeaderChecksumMismatch);
+        }
+        match CartridgeType::from_byte(header.cartridge_type) {
+            None => warnings.push(HeaderWarning::UnsupportedCartridgeType(header.cartridge_type)),
+            Some(cartridge_type) => {
+                let ram_size = ram_size.unwrap_or(0);
+                if cartridge_type.has_ram() != (ram_size > 0) {
+                    warnings.push(HeaderWarning::RamSizeMismatch { cartridge_type: header.cartridge_type, ram_size });
+                }
+            }
+        }
+        warnings
+    }
+    
+    /// Raw ROM contents, including any patches applied since loading
+    pub fn rom(&self) -> &[u8] {
+        &self.rom
+    }
+    
+    /// Checks the global checksum at 0x014E-0x014F (big-endian): the 16-bit sum of every
+    /// other ROM byte. Real hardware never checks it, but a mismatch suggests a bad dump.
+    pub fn verify_global_checksum(&self) -> bool {
+        self.rom.len() > 0x014F
+            && computed_global_checksum(&self.rom) == ((self.rom[0x014E] as u16) << 8 | self.rom[0x014F] as u16)
+    }
+
+    /// Whether the global checksum matched when the ROM was loaded
+    pub fn checksum_valid(&self) -> bool {
+        self.checksum_valid
+    }
+
+    /// Checks the header checksum at 0x014D against 0x0134-0x014C, like the boot ROM does
+    /// before handing over to the cartridge
+    pub fn verify_header_checksum(&self) -> bool {
+        self.rom.len() > 0x014D && computed_header_checksum(&self.rom) == self.rom[0x014D]
+    }
+
+    /// Whether the header checksum matched when the ROM was loaded
+    pub fn header_checksum_valid(&self) -> bool {
+        self.header_checksum_valid
+    }
+
     pub fn get_title(&self) -> String {
         if self.rom.len() >= 0x0143 {
-            let title_bytes = &self.rom[0x0134..=0x0142];
-            String::from_utf8_lossy(title_bytes).trim_end_matches('\0').to_string()
+            parse_title(&self.rom)
         } else {
             "Unknown".to_string()
         }
     }
+
+    /// Reads just the header of a ROM file and returns its title,
+    /// without loading the whole ROM into memory
+    pub fn read_title(path: &Path) -> std::io::Result<String> {
+        let mut header = Vec::with_capacity(HEADER_END);
+        File::open(path)?.take(HEADER_END as u64).read_to_end(&mut header)?;
+        if header.len() < 0x0143 {
+            return Ok("Unknown".to_string());
+        }
+        Ok(parse_title(&header))
+    }
+
+    /// Reads just the cartridge type byte (0x0147) of a ROM file. None if the file can't be
+    /// read, is too short or has a type this emulator doesn't support.
+    #[allow(dead_code)] // Public API method
+    pub fn peek_cartridge_type(path: &Path) -> Option<CartridgeType> {
+        let header = read_header(path, 0x0148)?;
+        CartridgeType::from_byte(header[0x0147])
+    }
+
+    /// Like `read_title`, but None if the file can't be read or ends before the title does
+    #[allow(dead_code)] // Public API method
+    pub fn peek_title(path: &Path) -> Option<String> {
+        let title = Self::read_title(path).ok()?;
+        (fs::metadata(path).ok()?.len() >= 0x0143).then_some(title)
+    }
+
+    pub fn get_header(&self) -> CartridgeHeader {
+        CartridgeHeader::parse(&self.rom)
+    }
+}
+
+// The first `len` bytes of a ROM file, or None if it is shorter or can't be read
+fn read_header(path: &Path, len: usize) -> Option<Vec<u8>> {
+    let mut header = Vec::with_capacity(len);
+    File::open(path).ok()?.take(len as u64).read_to_end(&mut header).ok()?;
+    (header.len() == len).then_some(header)
 }
 
 #[cfg(test)]
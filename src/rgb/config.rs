@@ -0,0 +1,142 @@
+// Emulator configuration persisted between sessions
+// Stored as a `key = value` subset of TOML with optional [keyboard] and [gamepad] sections; unknown
+// keys are ignored and missing keys fall back to their defaults so older config files keep working
+
+use super::gamepad::GamepadMapping;
+use super::joypad::{key_name, KeyMapping, SgbMode};
+use super::HardwareModel;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const CONFIG_PATH: &str = "config.toml";
+pub const DEFAULT_BOOTSTRAP_PATH: &str = "test-roms/dmg_boot.bin";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmulatorConfig {
+    pub debugger_window_x: f32,
+    pub debugger_window_y: f32,
+    pub debugger_show: bool,
+    pub window_x: Option<u32>, // Main window position, None lets the OS decide
+    pub window_y: Option<u32>,
+    pub keyboard: KeyMapping, // [keyboard] section: Game Boy button = key name
+    pub gamepad: GamepadMapping, // [gamepad] section: Game Boy button = comma-separated gamepad inputs
+    // Hardware setup for MemoryMap::new_with_config and Cpu::new_with_config. These come
+    // from the command line and aren't saved to the config file.
+    pub skip_boot: bool, // Start at the cartridge entry point in the post-boot state
+    pub hardware_model: Option<HardwareModel>, // None picks the model from the cartridge header
+    pub echo_ram_mirrors: bool, // False makes ECHO RAM read 0xFF and ignore writes
+    pub accurate_unused_memory: bool, // 0xFEA0-0xFEFF reads follow the PPU mode instead of 0xFF
+    pub bootstrap_path: Option<PathBuf>, // Boot ROM mapped at 0x0000 unless skip_boot; None leaves it blank
+    pub sgb_mode: SgbMode, // Whether the joypad port answers Super Game Boy packets
+}
+
+impl Default for EmulatorConfig {
+    fn default() -> Self {
+        Self {
+            debugger_window_x: 650.0,
+            debugger_window_y: 50.0,
+            debugger_show: true,
+            window_x: None,
+            window_y: None,
+            keyboard: KeyMapping::default(),
+            gamepad: GamepadMapping::default(),
+            skip_boot: false,
+            hardware_model: None,
+            echo_ram_mirrors: true,
+            accurate_unused_memory: false,
+            bootstrap_path: Some(PathBuf::from(DEFAULT_BOOTSTRAP_PATH)),
+            sgb_mode: SgbMode::Disabled,
+        }
+    }
+}
+
+impl EmulatorConfig {
+    /// Loads the config file, returning defaults if it doesn't exist
+    pub fn load(path: &Path) -> Self {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let mut config = Self::default();
+        let mut section = String::new();
+
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') {
+                section = line.trim_matches(|c| c == '[' || c == ']').trim().to_string();
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                eprintln!("Warning: config line {}: expected 'key = value'", line_number + 1);
+                continue;
+            };
+            let key = key.trim();
+            let value = value.trim().trim_matches('"');
+
+            if section == "keyboard" {
+                if !config.keyboard.set_binding(key, value) {
+                    eprintln!("Warning: config line {}: invalid keyboard binding '{}'", line_number + 1, key);
+                }
+                continue;
+            }
+            if section == "gamepad" {
+                if !config.gamepad.set_binding(key, value) {
+                    eprintln!("Warning: config line {}: invalid gamepad binding '{}'", line_number + 1, key);
+                }
+                continue;
+            }
+            if !section.is_empty() {
+                continue; // Sections from newer versions
+            }
+
+            let parsed = match key {
+                "debugger_window_x" => value.parse().map(|v| config.debugger_window_x = v).is_ok(),
+                "debugger_window_y" => value.parse().map(|v| config.debugger_window_y = v).is_ok(),
+                "debugger_show" => value.parse().map(|v| config.debugger_show = v).is_ok(),
+                "window_x" => value.parse().map(|v| config.window_x = Some(v)).is_ok(),
+                "window_y" => value.parse().map(|v| config.window_y = Some(v)).is_ok(),
+                _ => true, // Unknown keys are left for newer versions
+            };
+            if !parsed {
+                eprintln!("Warning: config line {}: invalid value for '{}'", line_number + 1, key);
+            }
+        }
+
+        config
+    }
+
+    pub fn to_toml_string(&self) -> String {
+        let mut text = String::new();
+        text.push_str(&format!("debugger_window_x = {:.1}\n", self.debugger_window_x));
+        text.push_str(&format!("debugger_window_y = {:.1}\n", self.debugger_window_y));
+        text.push_str(&format!("debugger_show = {}\n", self.debugger_show));
+        if let Some(x) = self.window_x {
+            text.push_str(&format!("window_x = {}\n", x));
+        }
+        if let Some(y) = self.window_y {
+            text.push_str(&format!("window_y = {}\n", y));
+        }
+        text.push_str("\n[keyboard]\n");
+        for (button, key) in self.keyboard.bindings() {
+            // A key missing from the named-key table is saved as "?", which loads as the default
+            text.push_str(&format!("{} = \"{}\"\n", button, key_name(key).unwrap_or("?")));
+        }
+        text.push_str("\n[gamepad]\n");
+        for (key, inputs) in self.gamepad.bindings() {
+            let names: Vec<&str> = inputs.iter().map(|input| input.name()).collect();
+            text.push_str(&format!("{} = \"{}\"\n", key, names.join(", ")));
+        }
+        text
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        fs::write(path, self.to_toml_string())
+    }
+}
@@ -1,7 +1,11 @@
-use crate::rgb::instructions::{Instruction, InstructionKind, decode_instruction, get_instruction_size, decode_cb_instruction, get_cb_instruction_size, JumpCondition};
+use crate::rgb::instructions::{Instruction, InstructionKind, decode_instruction, is_illegal_opcode, get_instruction_size, decode_cb_instruction, get_cb_instruction_size, JumpCondition};
 use crate::rgb::instruction_timing::get_instruction_cycles;
-use crate::rgb::memory::MemoryMap;
+use crate::rgb::config::EmulatorConfig;
+use crate::rgb::memory::{MemoryMap, HRAM_END, HRAM_START};
+use crate::rgb::ppu::{SCREEN_HEIGHT, SCREEN_WIDTH};
 use crate::rgb::registers::Registers;
+use crate::rgb::HardwareModel;
+use super::memory::WatchKind;
 
 // Interrupt vector addresses
 const VBLANK_VECTOR: u16 = 0x0040;
@@ -14,6 +18,9 @@ const JOYPAD_VECTOR: u16 = 0x0060;
 const IE_REGISTER: u16 = 0xFFFF;  // Interrupt Enable
 const IF_REGISTER: u16 = 0xFF0F;  // Interrupt Flag
 
+const IDLE_STEP_CYCLES: u16 = 4;                // Hardware granularity while HALTed or paused
+const CYCLES_PER_FRAME: u32 = 70224;            // One frame at normal speed
+
 // Interrupt bit positions
 const VBLANK_BIT: u8 = 0;
 const LCD_STAT_BIT: u8 = 1;
@@ -21,6 +28,64 @@ const TIMER_BIT: u8 = 2;
 const SERIAL_BIT: u8 = 3;
 const JOYPAD_BIT: u8 = 4;
 
+/// Why `execute_frame_until_vblank` returned
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrameEnd {
+    VBlank,                          // PPU entered VBlank - the frame is complete
+    CycleLimit,                      // A full frame of cycles passed without VBlank (e.g. LCD off)
+    InstructionLimit,                // Cpu::max_instructions_per_frame reached
+    Watchpoint(u16, u8, WatchKind),  // A watchpoint fired (address, value, access); the frame can be resumed
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameResult {
+    pub end: FrameEnd,
+    pub cycles: u32,
+    pub instructions: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmulatorError {
+    Timeout,                // Cycle budget exhausted
+    IllegalOpcode(u8, u16), // (opcode, address)
+}
+
+impl std::fmt::Display for EmulatorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            EmulatorError::Timeout => write!(f, "cycle limit reached"),
+            EmulatorError::IllegalOpcode(opcode, address) => write!(f, "illegal opcode 0x{:02X} at 0x{:04X}", opcode, address),
+        }
+    }
+}
+
+impl std::error::Error for EmulatorError {}
+
+/// What differed after `execute_parallel_check` ran the same instruction on two CPUs
+#[cfg(paranoid_mode)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DivergenceReport {
+    pub instruction: Option<InstructionKind>, // None for a HALT idle step
+    pub differences: Vec<String>,
+}
+
+#[cfg(paranoid_mode)]
+impl std::fmt::Display for DivergenceReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.instruction {
+            Some(kind) => write!(f, "after {:?}: ", kind)?,
+            None => write!(f, "while halted: ")?,
+        }
+        write!(f, "{}", self.differences.join(", "))
+    }
+}
+
+#[cfg(paranoid_mode)]
+fn format_writes(writes: &[(u16, u8)]) -> String {
+    let writes: Vec<String> = writes.iter().map(|(address, value)| format!("{:04X}={:02X}", address, value)).collect();
+    format!("[{}]", writes.join(" "))
+}
+
 pub struct Cpu {
     pub registers: Registers,
     pub pc: u16,
@@ -31,97 +96,205 @@ pub struct Cpu {
     pub ime: bool,        // Interrupt Master Enable
     pub ei_delay: bool,   // EI instruction has 1-instruction delay
     pub halt_bug: bool,   // HALT bug state for next instruction
+    pub speed_multiplier: u8, // 1 = normal, 2 = CGB double speed
+    // Optional cap on instructions per execute_frame_until_vblank call (0 = none, --max-instructions)
+    pub max_instructions_per_frame: u32,
+    pub hardware_model: HardwareModel, // Survives reset, like the console itself
 }
 
-impl Cpu {
+/// Builds a `Cpu` with a fresh memory map and no bootstrap ROM loaded,
+/// so tests and tools can set up register state without touching fields directly
+pub struct CpuBuilder {
+    registers: Registers,
+    pc: u16,
+    sp: u16,
+}
+
+impl Default for CpuBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Decodes the instruction at `addr`, fetching its opcode and immediates through `fetch`.
+// Returns the kind and the instruction's size in bytes.
+fn decode_with(addr: u16, fetch: impl Fn(u16) -> u8) -> (InstructionKind, u16) {
+    let opcode = fetch(addr);
+    if opcode == 0xCB {
+        let cb_opcode = fetch(addr.wrapping_add(1));
+        return (decode_cb_instruction(cb_opcode), get_cb_instruction_size());
+    }
+
+    let size = get_instruction_size(opcode);
+    let immediate8 = (size >= 2).then(|| fetch(addr.wrapping_add(1)));
+    let immediate16 = (size >= 3).then(|| {
+        let low_byte = fetch(addr.wrapping_add(1));
+        let high_byte = fetch(addr.wrapping_add(2));
+        (high_byte as u16) << 8 | low_byte as u16
+    });
+    (decode_instruction(opcode, immediate8, immediate16), size)
+}
+
+impl CpuBuilder {
     #[allow(dead_code)] // Public API method
     pub fn new() -> Self {
-        let registers = Registers::new();
-        let mut mmap = MemoryMap::new();
-        mmap.load_bootstrap();
-
-        Cpu {
-            registers,
+        CpuBuilder {
+            registers: Registers::new(),
             pc: 0,
             sp: 0,
-            mmap,
-            halted: false,
-            ime: false,      // Interrupts disabled on startup
-            ei_delay: false, // No EI delay initially
-            halt_bug: false, // No HALT bug initially
         }
     }
 
+    #[allow(dead_code)] // Public API method
+    pub fn with_pc(mut self, pc: u16) -> Self {
+        self.pc = pc;
+        self
+    }
+
+    #[allow(dead_code)] // Public API method
+    pub fn with_sp(mut self, sp: u16) -> Self {
+        self.sp = sp;
+        self
+    }
+
+    #[allow(dead_code)] // Public API method
+    pub fn with_registers(mut self, registers: Registers) -> Self {
+        self.registers = registers;
+        self
+    }
+
+    #[allow(dead_code)] // Public API method
+    pub fn build(self) -> Cpu {
+        let mut cpu = Cpu::with_memory(MemoryMap::new());
+        cpu.registers = self.registers;
+        cpu.pc = self.pc;
+        cpu.sp = self.sp;
+        cpu
+    }
+}
+
+impl Cpu {
+    #[allow(dead_code)] // Public API method
+    pub fn new() -> Self {
+        Cpu::new_with_config(&EmulatorConfig::default())
+    }
+
     /// Creates a new CPU in the state that would exist after the boot ROM completes
     /// This allows skipping the boot sequence and starting directly with cartridge execution
     #[allow(dead_code)] // Public API method
     pub fn new_post_boot() -> Self {
-        let registers = Registers::new_post_boot();
-        let mmap = MemoryMap::new_post_boot();
+        Cpu::new_with_config(&EmulatorConfig { skip_boot: true, ..EmulatorConfig::default() })
+    }
 
-        Cpu {
-            registers,
-            pc: 0x0100,     // Cartridge entry point
-            sp: 0xFFFE,     // Stack pointer at top of RAM
+    /// Creates a CPU around `MemoryMap::new_with_config`, in the post-boot state for the
+    /// configured model when `skip_boot` is set. Without a configured model it starts as a DMG.
+    /// This is the only place the boot ROM is loaded, from `config.bootstrap_path`.
+    pub fn new_with_config(config: &EmulatorConfig) -> Self {
+        let mut cpu = Cpu::with_memory(MemoryMap::new_with_config(config));
+        cpu.hardware_model = config.hardware_model.unwrap_or_default();
+        if config.skip_boot {
+            cpu.reset_to_post_boot();
+        } else if let Some(path) = &config.bootstrap_path {
+            cpu.mmap.load_bootstrap(path);
+        }
+        cpu
+    }
+
+    // A CPU in the power-on state around the given memory map
+    fn with_memory(mmap: MemoryMap) -> Self {
+        let mut cpu = Cpu {
+            registers: Registers::new(),
+            pc: 0,
+            sp: 0,
             mmap,
             halted: false,
-            ime: false,     // Interrupts disabled after boot
+            ime: false,
             ei_delay: false,
             halt_bug: false,
-        }
+            speed_multiplier: 1,
+            max_instructions_per_frame: 0,
+            hardware_model: HardwareModel::Dmg,
+        };
+        cpu.reset();
+        cpu
+    }
+
+    /// Restores the CPU to its power-on state (PC 0, registers zero, interrupts off),
+    /// like the hardware reset pin. Memory and the other hardware are left untouched.
+    pub fn reset(&mut self) {
+        self.registers.reset();
+        self.pc = 0;
+        self.sp = 0;
+        self.halted = false;
+        self.ime = false;      // Interrupts disabled on startup
+        self.ei_delay = false; // No EI delay initially
+        self.halt_bug = false; // No HALT bug initially
+        self.speed_multiplier = 1;
+    }
+
+    /// Restores the CPU to the state the boot ROM hands over to the cartridge:
+    /// post-boot registers, PC at the entry point and the bootstrap ROM unmapped
+    pub fn reset_to_post_boot(&mut self) {
+        self.reset();
+        self.registers = Registers::new_post_boot_for(self.hardware_model);
+        self.pc = 0x0100; // Cartridge entry point
+        self.sp = 0xFFFE; // Stack pointer at top of RAM
+        self.mmap.disable_bootstrap();
     }
 
     pub fn decode(&mut self) -> Instruction {
-        let opcode = self.mmap.read(self.pc);
-        let mut instruction = Instruction::default();
-        instruction.instr = opcode;
-        
-        // Handle CB-prefixed instructions
-        if opcode == 0xCB {
-            let cb_opcode = self.mmap.read(self.pc + 1);
-            instruction.kind = decode_cb_instruction(cb_opcode);
-            self.pc += get_cb_instruction_size();
-            return instruction;
-        }
-        
-        // Get instruction size to determine how many bytes to read
-        let size = get_instruction_size(opcode);
-        
+        self.mmap.set_access_pc(self.pc);
+        let mmap = &self.mmap;
+        let (kind, size) = decode_with(self.pc, |addr| mmap.read(addr));
+        let instruction = Instruction { kind, instr: self.mmap.peek(self.pc), ..Instruction::default() };
+
         // Handle HALT bug: if in HALT bug state, don't advance PC
-        let pc_increment = if self.halt_bug {
+        if self.halt_bug && instruction.instr != 0xCB {
             #[cfg(debug_assertions)]
-            println!("HALT bug: Not advancing PC from 0x{:04X}, next instruction will execute twice", self.pc);
+            if crate::rgb::diagnostics::debug_output() {
+                println!("HALT bug: Not advancing PC from 0x{:04X}, next instruction will execute twice", self.pc);
+            }
             self.halt_bug = false; // Clear flag after handling
-            0 // Don't advance PC for HALT bug
-        } else {
-            size
-        };
-        
-        // Read immediate values based on instruction size
-        let immediate8 = if size >= 2 {
-            Some(self.mmap.read(self.pc + 1))
         } else {
-            None
-        };
-        
-        let immediate16 = if size >= 3 {
-            let low_byte = self.mmap.read(self.pc + 1);
-            let high_byte = self.mmap.read(self.pc + 2);
-            Some((high_byte as u16) << 8 | low_byte as u16)
-        } else {
-            None
-        };
-        
-        // Decode the instruction using the new modular system
-        instruction.kind = decode_instruction(opcode, immediate8, immediate16);
-        
-        // Advance PC by instruction size (or 0 for HALT bug)
-        self.pc += pc_increment;
-        
+            self.pc = self.pc.wrapping_add(size);
+        }
+
         instruction
     }
 
+    /// Decodes the instruction at `addr` and returns it with its size in bytes. Memory is
+    /// read with `peek`, so unlike `decode` this leaves PC, the HALT bug and watchpoints alone.
+    #[allow(dead_code)] // Public API method
+    pub fn decode_at(&self, addr: u16) -> (InstructionKind, u16) {
+        decode_with(addr, |addr| self.mmap.peek(addr))
+    }
+
+    /// Decodes `count` instructions from `start` without executing them, returning each
+    /// one's address and kind. Stops early near 0xFFFF rather than wrapping, since an
+    /// instruction there could run past the end of memory.
+    #[allow(dead_code)] // Public API method
+    pub fn disassemble_range(&self, start: u16, count: usize) -> Vec<(u16, InstructionKind)> {
+        let mut listing = Vec::with_capacity(count);
+        let mut address = start;
+        for _ in 0..count {
+            if address > 0xFFFC {
+                break;
+            }
+            let (kind, size) = self.decode_at(address);
+            listing.push((address, kind));
+            address += size;
+        }
+        listing
+    }
+
+    #[allow(dead_code)] // Public API method
     pub fn execute(&mut self, instruction: Instruction) -> u8 {
+        self.execute_and_step(instruction).0
+    }
+
+    /// Executes one instruction and steps hardware by its cycle count.
+    /// Returns the cycles taken and whether the PPU entered VBlank.
+    fn execute_and_step(&mut self, instruction: Instruction) -> (u8, bool) {
         
         // Calculate cycles for conditional instructions before moving the instruction
         let condition_taken = match &instruction.kind {
@@ -145,21 +318,283 @@ impl Cpu {
         // Execute the instruction using the modular execution system
         let _cycles = self.execute_instruction(instruction);
         
-        // Step timer and check for timer interrupt
-        if self.mmap.step_timer(actual_cycles as u16) {
+        let vblank = self.step_hardware(actual_cycles as u16);
+        
+        (actual_cycles, vblank)
+    }
+
+    /// Steps OAM DMA, the timer, serial port, APU and PPU, requesting any interrupts they raise.
+    /// Returns true if the PPU entered VBlank.
+    pub fn step_hardware(&mut self, cycles: u16) -> bool {
+        let interrupts = self.mmap.step(cycles, self.speed_multiplier);
+        if interrupts.timer {
             self.request_timer_interrupt();
         }
-        
-        // Step PPU and check for PPU interrupts
-        let (vblank_interrupt, stat_interrupt) = self.mmap.step_ppu(actual_cycles as u16);
-        if vblank_interrupt {
+        if interrupts.serial {
+            self.request_serial_interrupt();
+        }
+        if interrupts.vblank {
             self.request_vblank_interrupt();
         }
-        if stat_interrupt {
+        if interrupts.stat {
             self.request_lcd_stat_interrupt();
         }
+        interrupts.vblank
+    }
+
+    /// Runs until the PPU enters VBlank or `cycles_per_frame` cycles have elapsed,
+    /// including HALT periods, which step hardware in 4-cycle increments.
+    #[allow(dead_code)] // Public API method
+    pub fn execute_frame_until_vblank(&mut self, cycles_per_frame: u32) -> FrameResult {
+        self.execute_frame_until_vblank_with(cycles_per_frame, |_| true)
+    }
+
+    /// Like `execute_frame_until_vblank`, but calls `may_execute` before each
+    /// instruction. Returning false holds the CPU (e.g. debugger paused) while
+    /// hardware keeps running so the frame still completes.
+    pub fn execute_frame_until_vblank_with<F>(&mut self, cycles_per_frame: u32, mut may_execute: F) -> FrameResult
+    where
+        F: FnMut(&Cpu) -> bool,
+    {
+        let mut result = FrameResult { end: FrameEnd::CycleLimit, cycles: 0, instructions: 0 };
+        
+        // VBlank normally ends the frame. The cycle limit is what guarantees an end when it
+        // never comes (LCD off, or a ROM that spins with interrupts disabled): every pass
+        // advances at least IDLE_STEP_CYCLES, HALTed and paused ones included. The optional
+        // instruction cap only cuts frames shorter, e.g. to bound work per host frame.
+        while result.cycles < cycles_per_frame {
+            if self.max_instructions_per_frame != 0 && result.instructions >= self.max_instructions_per_frame {
+                #[cfg(debug_assertions)]
+                if crate::rgb::diagnostics::debug_output() {
+                    println!("INSTRUCTION LIMIT: {} instructions", result.instructions);
+                }
+                result.end = FrameEnd::InstructionLimit;
+                return result;
+            }
+            
+            let vblank = if self.halted {
+                result.cycles += IDLE_STEP_CYCLES as u32;
+                self.step_halted()
+            } else if may_execute(self) {
+                let (cycles, vblank) = self.step_instruction();
+                result.cycles += cycles;
+                result.instructions += 1;
+                
+                // Stop before the next instruction so the caller can react
+                if let Some((address, value, access)) = self.mmap.take_watchpoint_hit() {
+                    result.end = FrameEnd::Watchpoint(address, value, access);
+                    return result;
+                }
+                vblank
+            } else {
+                // Held by the caller - keep hardware running without executing
+                result.cycles += IDLE_STEP_CYCLES as u32;
+                self.step_hardware(IDLE_STEP_CYCLES)
+            };
+            
+            if vblank {
+                result.end = FrameEnd::VBlank;
+                return result;
+            }
+        }
+        
+        #[cfg(debug_assertions)]
+        if crate::rgb::diagnostics::debug_output() {
+            println!("CYCLE LIMIT: Completed frame with {} cycles", result.cycles);
+        }
+        result
+    }
+
+    /// Runs `n` frames and returns a copy of the frame buffer after each one. A frame ends at
+    /// VBlank, or after a frame's worth of cycles when VBlank never comes (e.g. LCD off);
+    /// watchpoint stops are resumed.
+    #[allow(dead_code)] // Public API method
+    pub fn run_frames(&mut self, n: u32) -> Vec<[u8; SCREEN_WIDTH * SCREEN_HEIGHT]> {
+        let mut frames = Vec::with_capacity(n as usize);
+        for _ in 0..n {
+            let cycles_per_frame = CYCLES_PER_FRAME * self.speed_multiplier as u32;
+            let mut cycles = 0;
+            while cycles < cycles_per_frame {
+                let result = self.execute_frame_until_vblank(cycles_per_frame - cycles);
+                cycles += result.cycles;
+                if !matches!(result.end, FrameEnd::Watchpoint(..)) {
+                    break;
+                }
+            }
+            frames.push(self.mmap.get_ppu().frame_buffer);
+        }
+        frames
+    }
+
+    /// Runs until `pc` reaches `target_pc`, returning the cycles consumed.
+    /// Fails with `Timeout` after `max_cycles`, or `IllegalOpcode` instead of
+    /// panicking when the CPU is about to execute an undefined opcode.
+    #[allow(dead_code)] // Public API method
+    pub fn run_until_pc(&mut self, target_pc: u16, max_cycles: u64) -> Result<u64, EmulatorError> {
+        let mut total_cycles: u64 = 0;
+        
+        while self.pc != target_pc {
+            if total_cycles >= max_cycles {
+                return Err(EmulatorError::Timeout);
+            }
+            
+            if self.halted {
+                total_cycles += IDLE_STEP_CYCLES as u64;
+                self.step_halted();
+            } else {
+                let opcode = self.mmap.peek(self.pc);
+                if is_illegal_opcode(opcode) {
+                    return Err(EmulatorError::IllegalOpcode(opcode, self.pc));
+                }
+                total_cycles += self.step_instruction().0 as u64;
+            }
+        }
+        
+        Ok(total_cycles)
+    }
+
+    /// Runs `code` from the start of HRAM until execution falls off its end, then puts
+    /// back PC and the HRAM bytes it overwrote. Registers, flags and memory keep whatever
+    /// the code did. Errors like `run_until_pc`, e.g. when the code jumps away.
+    #[allow(dead_code)] // Public API method
+    pub fn run_injected(&mut self, code: &[u8], max_cycles: u64) -> Result<u64, EmulatorError> {
+        assert!(code.len() <= (HRAM_END - HRAM_START) as usize, "injected code must fit in HRAM");
+        let end = HRAM_START + code.len() as u16;
+        let saved: Vec<u8> = (HRAM_START..end).map(|addr| self.mmap.peek(addr)).collect();
+        for (addr, &byte) in (HRAM_START..end).zip(code) {
+            self.mmap.write(addr, byte);
+        }
+        
+        let return_pc = self.pc;
+        self.pc = HRAM_START;
+        let result = self.run_until_pc(end, max_cycles);
+        self.pc = return_pc;
+        for (addr, &byte) in (HRAM_START..end).zip(&saved) {
+            self.mmap.write(addr, byte);
+        }
+        result
+    }
+
+    // Idles one hardware step while HALTed; returns true if the PPU entered VBlank
+    fn step_halted(&mut self) -> bool {
+        let vblank = self.step_hardware(IDLE_STEP_CYCLES);
+        
+        // HALT wakes on any pending interrupt, regardless of IME
+        let pending = self.mmap.read(IE_REGISTER) & self.mmap.read(IF_REGISTER) & 0x1F;
+        if pending != 0 {
+            self.halted = false;
+        }
+        vblank
+    }
+
+    // Executes one instruction plus any interrupt dispatch it leads to.
+    // Returns the cycles taken and whether the PPU entered VBlank.
+    fn step_instruction(&mut self) -> (u32, bool) {
+        let instruction = self.decode();
+        self.run_instruction(instruction)
+    }
+
+    // step_instruction for an already decoded instruction
+    fn run_instruction(&mut self, instruction: Instruction) -> (u32, bool) {
+        let (cycles, mut vblank) = self.execute_and_step(instruction);
+        let mut total_cycles = cycles as u32;
         
-        actual_cycles
+        self.handle_ei_delay();
+        
+        if self.check_interrupts() {
+            let interrupt_cycles = self.handle_interrupt();
+            vblank |= self.step_hardware(interrupt_cycles as u16);
+            total_cycles += interrupt_cycles as u32;
+        }
+        (total_cycles, vblank)
+    }
+
+    /// Executes `instruction` on this CPU and the instruction at `other`'s PC on
+    /// `other`, including any interrupt dispatch that follows, then compares
+    /// registers, flags and memory writes. Both CPUs are expected to start in
+    /// the same state with `instruction` just decoded from this one.
+    #[cfg(paranoid_mode)]
+    pub fn execute_parallel_check(&mut self, other: &mut Cpu, instruction: Instruction) -> Option<DivergenceReport> {
+        let kind = instruction.kind;
+        let other_instruction = other.decode();
+        let mut differences = Vec::new();
+        if other_instruction.kind != kind {
+            differences.push(format!("decoded {:?} vs {:?}", kind, other_instruction.kind));
+        }
+        
+        // Only writes made by this instruction are compared
+        self.mmap.enable_write_log();
+        other.mmap.enable_write_log();
+        self.run_instruction(instruction);
+        other.run_instruction(other_instruction);
+        
+        differences.extend(self.state_differences(other));
+        let writes = self.mmap.take_write_log();
+        let other_writes = other.mmap.take_write_log();
+        if writes != other_writes {
+            differences.push(format!("writes {} vs {}", format_writes(&writes), format_writes(&other_writes)));
+        }
+        
+        if differences.is_empty() {
+            None
+        } else {
+            Some(DivergenceReport { instruction: Some(kind), differences })
+        }
+    }
+
+    /// Steps both CPUs by one instruction, or one idle step while HALTed,
+    /// and reports any divergence
+    #[cfg(paranoid_mode)]
+    pub fn step_parallel_check(&mut self, other: &mut Cpu) -> Option<DivergenceReport> {
+        if !self.halted {
+            let instruction = self.decode();
+            return self.execute_parallel_check(other, instruction);
+        }
+        
+        // Halted state is compared after every step, so both CPUs are halted here
+        self.step_halted();
+        other.step_halted();
+        let differences = self.state_differences(other);
+        if differences.is_empty() {
+            None
+        } else {
+            Some(DivergenceReport { instruction: None, differences })
+        }
+    }
+
+    // Describes each CPU-visible field that differs between the two CPUs
+    #[cfg(paranoid_mode)]
+    fn state_differences(&self, other: &Cpu) -> Vec<String> {
+        let mut differences = Vec::new();
+        let (mine, theirs) = (&self.registers, &other.registers);
+        let register_pairs = [
+            ("A", mine.a, theirs.a),
+            ("B", mine.b, theirs.b),
+            ("C", mine.c, theirs.c),
+            ("D", mine.d, theirs.d),
+            ("E", mine.e, theirs.e),
+            ("F", u8::from(mine.f), u8::from(theirs.f)),
+            ("H", mine.h, theirs.h),
+            ("L", mine.l, theirs.l),
+        ];
+        for (name, a, b) in register_pairs {
+            if a != b {
+                differences.push(format!("{} {:02X} vs {:02X}", name, a, b));
+            }
+        }
+        if self.pc != other.pc {
+            differences.push(format!("PC {:04X} vs {:04X}", self.pc, other.pc));
+        }
+        if self.sp != other.sp {
+            differences.push(format!("SP {:04X} vs {:04X}", self.sp, other.sp));
+        }
+        if self.ime != other.ime {
+            differences.push(format!("IME {} vs {}", self.ime, other.ime));
+        }
+        if self.halted != other.halted {
+            differences.push(format!("halted {} vs {}", self.halted, other.halted));
+        }
+        differences
     }
 
     pub fn add(&mut self, value: u8) -> u8 {
@@ -217,15 +652,32 @@ impl Cpu {
         result
     }
     
+    pub fn add_with_carry(&mut self, value: u8) -> u8 {
+        // Widen to u16 so the carry out of bit 7 is kept
+        let carry: u16 = if self.registers.f.carry { 1 } else { 0 };
+        let a = self.registers.a as u16;
+        let value = value as u16;
+        let sum = a + value + carry;
+        let result = sum as u8;
+        
+        self.registers.f.zero = result == 0;
+        self.registers.f.subtract = false;
+        self.registers.f.half_carry = (a & 0x0F) + (value & 0x0F) + carry > 0x0F;
+        self.registers.f.carry = sum > 0xFF;
+        result
+    }
+    
     pub fn subtract_with_carry(&mut self, value: u8) -> u8 {
-        let carry = if self.registers.f.carry { 1 } else { 0 };
-        let temp = value.wrapping_add(carry);
-        let result = self.registers.a.wrapping_sub(temp);
+        // Widen to u16 so value + carry can't wrap (0xFF + 1 must stay 0x100)
+        let carry: u16 = if self.registers.f.carry { 1 } else { 0 };
+        let a = self.registers.a as u16;
+        let value = value as u16;
+        let result = a.wrapping_sub(value + carry) as u8;
         
         self.registers.f.zero = result == 0;
         self.registers.f.subtract = true;
-        self.registers.f.half_carry = (self.registers.a & 0x0F) < ((value & 0x0F) + carry);
-        self.registers.f.carry = (self.registers.a as u16) < (value as u16 + carry as u16);
+        self.registers.f.half_carry = (value & 0x0F) + carry > (a & 0x0F);
+        self.registers.f.carry = value + carry > a;
         result
     }
     
@@ -285,6 +737,11 @@ impl Cpu {
         pending != 0
     }
     
+    /// True when IF has the joypad interrupt flagged, i.e. a button was pressed
+    pub fn joypad_interrupt_flagged(&self) -> bool {
+        self.mmap.read(IF_REGISTER) & (1 << JOYPAD_BIT) != 0
+    }
+    
     pub fn handle_interrupt(&mut self) -> u8 {
         if !self.ime {
             return 0;
@@ -338,7 +795,7 @@ impl Cpu {
         };
         
         #[cfg(debug_assertions)]
-        {
+        if crate::rgb::diagnostics::debug_output() {
             static mut INTERRUPT_VECTOR_COUNT: u32 = 0;
             unsafe {
                 INTERRUPT_VECTOR_COUNT += 1;
@@ -0,0 +1,14 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Debug builds print diagnostics from hot paths (CPU, PPU, cartridge); benchmark
+// mode turns them off at runtime so they don't skew throughput measurements
+static DEBUG_OUTPUT: AtomicBool = AtomicBool::new(true);
+
+pub fn set_debug_output(enabled: bool) {
+    DEBUG_OUTPUT.store(enabled, Ordering::Relaxed);
+}
+
+#[cfg_attr(not(debug_assertions), allow(dead_code))] // Only read by debug-build diagnostics
+pub fn debug_output() -> bool {
+    DEBUG_OUTPUT.load(Ordering::Relaxed)
+}
@@ -139,29 +139,19 @@ fn execute_sub(cpu: &mut Cpu, dest: &ArgKind, src: &ArgKind) -> u8 {
 }
 
 fn execute_adc(cpu: &mut Cpu, dest: &ArgKind, src: &ArgKind) -> u8 {
-    let carry = if cpu.registers.f.carry { 1 } else { 0 };
-    let (a_val, src_val) = match (dest, src) {
-        (ArgKind::A, ArgKind::B) => (cpu.registers.a, cpu.registers.b),
-        (ArgKind::A, ArgKind::C) => (cpu.registers.a, cpu.registers.c),
-        (ArgKind::A, ArgKind::D) => (cpu.registers.a, cpu.registers.d),
-        (ArgKind::A, ArgKind::E) => (cpu.registers.a, cpu.registers.e),
-        (ArgKind::A, ArgKind::H) => (cpu.registers.a, cpu.registers.h),
-        (ArgKind::A, ArgKind::L) => (cpu.registers.a, cpu.registers.l),
-        (ArgKind::A, ArgKind::A) => (cpu.registers.a, cpu.registers.a),
-        (ArgKind::A, ArgKind::HL) => (cpu.registers.a, cpu.mmap.read(cpu.registers.get_hl())),
-        (ArgKind::A, ArgKind::Immediate(val)) => (cpu.registers.a, *val),
+    let value = match (dest, src) {
+        (ArgKind::A, ArgKind::A) => cpu.registers.a,
+        (ArgKind::A, ArgKind::B) => cpu.registers.b,
+        (ArgKind::A, ArgKind::C) => cpu.registers.c,
+        (ArgKind::A, ArgKind::D) => cpu.registers.d,
+        (ArgKind::A, ArgKind::E) => cpu.registers.e,
+        (ArgKind::A, ArgKind::H) => cpu.registers.h,
+        (ArgKind::A, ArgKind::L) => cpu.registers.l,
+        (ArgKind::A, ArgKind::HL) => cpu.mmap.read(cpu.registers.get_hl()),
+        (ArgKind::A, ArgKind::Immediate(value)) => *value,
         _ => panic!("Invalid ADC operands"),
     };
-    
-    let result = a_val.wrapping_add(src_val).wrapping_add(carry);
-    let half_carry = (a_val & 0x0F) + (src_val & 0x0F) + carry > 0x0F;
-    let full_carry = (a_val as u16) + (src_val as u16) + (carry as u16) > 0xFF;
-    
-    cpu.registers.a = result;
-    cpu.registers.f.zero = result == 0;
-    cpu.registers.f.subtract = false;
-    cpu.registers.f.half_carry = half_carry;
-    cpu.registers.f.carry = full_carry;
+    cpu.registers.a = cpu.add_with_carry(value);
     4
 }
 
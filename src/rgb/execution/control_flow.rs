@@ -91,8 +91,10 @@ fn execute_ret_cond(cpu: &mut Cpu, condition: JumpCondition) -> u8 {
 fn execute_reti(cpu: &mut Cpu) -> u8 {
     // Return from interrupt: pop PC from stack and enable interrupts
     cpu.pc = cpu.pop_stack();
-    // Enable interrupts immediately (no delay like EI)
+    // Enable interrupts immediately (no delay like EI). A pending EI delay is cancelled
+    // so it can't re-enable IME after a following DI.
     cpu.ime = true;
+    cpu.ei_delay = false;
     16
 }
 
@@ -53,6 +53,11 @@ pub fn execute(cpu: &mut Cpu, instruction: &InstructionKind) -> u8 {
 }
 
 fn execute_ld(cpu: &mut Cpu, dest: &ArgKind, src: &ArgKind) -> u8 {
+    // Self-loads (LD B,B / LD C,C / ... / LD A,A, opcodes 0x40-0x7F) leave everything unchanged
+    if std::mem::discriminant(dest) == std::mem::discriminant(src) {
+        return 4;
+    }
+    
     match (dest, src) {
         // 16-bit loads
         (ArgKind::BC, ArgKind::Immediate16(value)) => {
@@ -90,9 +95,6 @@ fn execute_ld(cpu: &mut Cpu, dest: &ArgKind, src: &ArgKind) -> u8 {
             cpu.registers.l = *value;
         }
         // Register-to-register loads
-        (ArgKind::A, ArgKind::A) => {
-            cpu.registers.a = cpu.registers.a;
-        }
         (ArgKind::A, ArgKind::B) => {
             cpu.registers.a = cpu.registers.b;
         }
@@ -114,9 +116,6 @@ fn execute_ld(cpu: &mut Cpu, dest: &ArgKind, src: &ArgKind) -> u8 {
         (ArgKind::B, ArgKind::A) => {
             cpu.registers.b = cpu.registers.a;
         }
-        (ArgKind::B, ArgKind::B) => {
-            cpu.registers.b = cpu.registers.b;
-        }
         (ArgKind::B, ArgKind::C) => {
             cpu.registers.b = cpu.registers.c;
         }
@@ -138,9 +137,6 @@ fn execute_ld(cpu: &mut Cpu, dest: &ArgKind, src: &ArgKind) -> u8 {
         (ArgKind::C, ArgKind::B) => {
             cpu.registers.c = cpu.registers.b;
         }
-        (ArgKind::C, ArgKind::C) => {
-            cpu.registers.c = cpu.registers.c;
-        }
         (ArgKind::C, ArgKind::D) => {
             cpu.registers.c = cpu.registers.d;
         }
@@ -162,9 +158,6 @@ fn execute_ld(cpu: &mut Cpu, dest: &ArgKind, src: &ArgKind) -> u8 {
         (ArgKind::D, ArgKind::C) => {
             cpu.registers.d = cpu.registers.c;
         }
-        (ArgKind::D, ArgKind::D) => {
-            cpu.registers.d = cpu.registers.d;
-        }
         (ArgKind::D, ArgKind::E) => {
             cpu.registers.d = cpu.registers.e;
         }
@@ -186,9 +179,6 @@ fn execute_ld(cpu: &mut Cpu, dest: &ArgKind, src: &ArgKind) -> u8 {
         (ArgKind::E, ArgKind::D) => {
             cpu.registers.e = cpu.registers.d;
         }
-        (ArgKind::E, ArgKind::E) => {
-            cpu.registers.e = cpu.registers.e;
-        }
         (ArgKind::E, ArgKind::H) => {
             cpu.registers.e = cpu.registers.h;
         }
@@ -210,9 +200,6 @@ fn execute_ld(cpu: &mut Cpu, dest: &ArgKind, src: &ArgKind) -> u8 {
         (ArgKind::H, ArgKind::E) => {
             cpu.registers.h = cpu.registers.e;
         }
-        (ArgKind::H, ArgKind::H) => {
-            cpu.registers.h = cpu.registers.h;
-        }
         (ArgKind::H, ArgKind::L) => {
             cpu.registers.h = cpu.registers.l;
         }
@@ -234,9 +221,6 @@ fn execute_ld(cpu: &mut Cpu, dest: &ArgKind, src: &ArgKind) -> u8 {
         (ArgKind::L, ArgKind::H) => {
             cpu.registers.l = cpu.registers.h;
         }
-        (ArgKind::L, ArgKind::L) => {
-            cpu.registers.l = cpu.registers.l;
-        }
         // Special case: LD SP,HL
         (ArgKind::SP, ArgKind::HL) => {
             cpu.sp = cpu.registers.get_hl();
@@ -297,7 +281,8 @@ fn execute_ld_mem(cpu: &mut Cpu, addr_reg: &ArgKind, src: &ArgKind) -> u8 {
         _ => panic!("Unsupported LD_MEM source"),
     };
     cpu.mmap.write(address, value);
-    8
+    // Cpu::execute takes timing from get_instruction_cycles; this is kept in line with it
+    if matches!(src, ArgKind::Immediate(_)) { 12 } else { 8 }
 }
 
 fn execute_ld_mem_dec(cpu: &mut Cpu, addr_reg: &ArgKind, src: &ArgKind) -> u8 {
@@ -32,7 +32,7 @@ fn execute_halt(cpu: &mut Cpu) -> u8 {
     // don't halt and cause next instruction to execute twice
     if !cpu.ime && cpu.check_pending_interrupts() {
         #[cfg(debug_assertions)]
-        {
+        if crate::rgb::diagnostics::debug_output() {
             static mut HALT_BUG_COUNT: u32 = 0;
             unsafe {
                 HALT_BUG_COUNT += 1;
@@ -45,7 +45,7 @@ fn execute_halt(cpu: &mut Cpu) -> u8 {
     } else {
         cpu.halted = true;
         #[cfg(debug_assertions)]
-        {
+        if crate::rgb::diagnostics::debug_output() {
             static mut HALT_COUNT: u32 = 0;
             unsafe {
                 HALT_COUNT += 1;
@@ -59,6 +59,22 @@ fn execute_halt(cpu: &mut Cpu) -> u8 {
 }
 
 fn execute_stop(cpu: &mut Cpu) -> u8 {
+    // CGB speed switch: with KEY1 bit 0 set, STOP toggles between normal and double
+    // speed. The CPU carries on afterwards instead of stopping, as games switch with
+    // interrupts disabled and would otherwise never wake up.
+    if cpu.mmap.speed_switch_armed() {
+        cpu.speed_multiplier = if cpu.speed_multiplier == 1 { 2 } else { 1 };
+        cpu.mmap.complete_speed_switch(cpu.speed_multiplier == 2);
+        return 4;
+    }
+    
+    // On DMG, STOP with a button press pending returns at once instead of entering
+    // low-power mode. Its 0x00 operand is consumed either way, as decode treats STOP
+    // as a 2-byte instruction.
+    if cpu.joypad_interrupt_flagged() {
+        return 4;
+    }
+    
     // STOP instruction - similar to HALT but stops CPU and LCD
     cpu.halted = true;
     4
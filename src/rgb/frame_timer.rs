@@ -0,0 +1,150 @@
+// Frame pacing for the main loop
+// Holds the emulator to the DMG refresh rate independently of the display's vsync
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+pub const TARGET_FPS: f64 = 59.7; // Game Boy's actual refresh rate is ~59.7 Hz
+pub const FRAME_DURATION: Duration = Duration::from_nanos((1_000_000_000.0 / TARGET_FPS) as u64);
+
+// Wake from sleep this early and spin the rest, since OS sleep granularity is ~1ms
+const SLEEP_SLOP: Duration = Duration::from_micros(1500);
+
+/// Time source for FrameTimer, so tests can pace frames without real sleeps
+pub trait Clock {
+    fn now(&self) -> Instant;
+    /// Blocks until `deadline`, which is after `now()`
+    fn sleep_until(&self, deadline: Instant);
+}
+
+/// The system's monotonic clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep_until(&self, deadline: Instant) {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining > SLEEP_SLOP {
+            std::thread::sleep(remaining - SLEEP_SLOP);
+        }
+        while Instant::now() < deadline {
+            std::hint::spin_loop();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TimingStats {
+    pub avg_frame_us: u64,
+    pub max_frame_us: u64,
+    pub missed_frames: u64,
+}
+
+pub struct FrameTimer<C: Clock = SystemClock> {
+    clock: C,
+    frame_duration: Duration,
+    next_deadline: Instant,
+    last_frame: Instant,
+    frame_count: u64,
+    total_frame_us: u64,
+    max_frame_us: u64,
+    missed_frames: u64,
+}
+
+impl FrameTimer {
+    pub fn new(frame_duration: Duration) -> Self {
+        Self::with_clock(frame_duration, SystemClock)
+    }
+}
+
+impl<C: Clock> FrameTimer<C> {
+    pub fn with_clock(frame_duration: Duration, clock: C) -> Self {
+        let now = clock.now();
+        Self {
+            clock,
+            frame_duration,
+            next_deadline: now + frame_duration,
+            last_frame: now,
+            frame_count: 0,
+            total_frame_us: 0,
+            max_frame_us: 0,
+            missed_frames: 0,
+        }
+    }
+
+    /// Blocks until the next frame deadline. Deadlines advance by a fixed
+    /// `frame_duration` from the previous deadline rather than from "now",
+    /// so oversleeping on one frame is paid back on the next.
+    pub fn wait_for_next_frame(&mut self) {
+        let now = self.clock.now();
+        if now < self.next_deadline {
+            self.clock.sleep_until(self.next_deadline);
+            self.next_deadline += self.frame_duration;
+        } else {
+            // Running behind: count the deadlines we blew through and resync
+            // instead of racing to catch up
+            let behind = now - self.next_deadline;
+            let missed = (behind.as_nanos() / self.frame_duration.as_nanos()) as u64 + 1;
+            self.missed_frames += missed;
+            self.next_deadline = now + self.frame_duration;
+        }
+
+        let frame_end = self.clock.now();
+        let frame_us = (frame_end - self.last_frame).as_micros() as u64;
+        self.last_frame = frame_end;
+        self.frame_count += 1;
+        self.total_frame_us += frame_us;
+        self.max_frame_us = self.max_frame_us.max(frame_us);
+    }
+
+    pub fn stats(&self) -> TimingStats {
+        TimingStats {
+            avg_frame_us: self.total_frame_us.checked_div(self.frame_count).unwrap_or(0),
+            max_frame_us: self.max_frame_us,
+            missed_frames: self.missed_frames,
+        }
+    }
+}
+
+/// Measures frames per second over a rolling window of recent frames
+pub struct FpsCounter {
+    window: usize,
+    frame_times: VecDeque<Instant>,
+}
+
+impl FpsCounter {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window,
+            frame_times: VecDeque::with_capacity(window + 1),
+        }
+    }
+
+    /// Records that a frame finished now
+    pub fn tick(&mut self) {
+        self.tick_at(Instant::now());
+    }
+
+    /// Records that a frame finished at `time`
+    pub fn tick_at(&mut self, time: Instant) {
+        self.frame_times.push_back(time);
+        if self.frame_times.len() > self.window + 1 {
+            self.frame_times.pop_front();
+        }
+    }
+
+    /// Average FPS across the window, or 0.0 until two frames have been recorded
+    pub fn fps(&self) -> f64 {
+        match (self.frame_times.front(), self.frame_times.back()) {
+            (Some(first), Some(last)) if self.frame_times.len() > 1 => {
+                let elapsed = (*last - *first).as_secs_f64();
+                if elapsed > 0.0 { (self.frame_times.len() - 1) as f64 / elapsed } else { 0.0 }
+            }
+            _ => 0.0,
+        }
+    }
+}
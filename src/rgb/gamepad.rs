@@ -0,0 +1,246 @@
+// Gamepad Input Mapping
+// Maps controller buttons and stick directions to Game Boy buttons and tracks which
+// connected controller is active. Inputs are named after gilrs' buttons and axes, which
+// the frontend reads and feeds into GamepadState
+
+use super::joypad::JoypadButtons;
+use log::debug;
+
+const STICK_THRESHOLD: f32 = 0.5; // How far a stick must be pushed to count as a press
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GamepadInput {
+    South,
+    East,
+    North,
+    West,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+    // Stick directions act as buttons once pushed past STICK_THRESHOLD
+    LeftStickUp,
+    LeftStickDown,
+    LeftStickLeft,
+    LeftStickRight,
+    RightStickUp,
+    RightStickDown,
+    RightStickLeft,
+    RightStickRight,
+}
+
+const ALL_INPUTS: [GamepadInput; 20] = [
+    GamepadInput::South, GamepadInput::East, GamepadInput::North, GamepadInput::West,
+    GamepadInput::LeftTrigger, GamepadInput::RightTrigger, GamepadInput::Select, GamepadInput::Start,
+    GamepadInput::DPadUp, GamepadInput::DPadDown, GamepadInput::DPadLeft, GamepadInput::DPadRight,
+    GamepadInput::LeftStickUp, GamepadInput::LeftStickDown, GamepadInput::LeftStickLeft, GamepadInput::LeftStickRight,
+    GamepadInput::RightStickUp, GamepadInput::RightStickDown, GamepadInput::RightStickLeft, GamepadInput::RightStickRight,
+];
+
+impl GamepadInput {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GamepadInput::South => "South",
+            GamepadInput::East => "East",
+            GamepadInput::North => "North",
+            GamepadInput::West => "West",
+            GamepadInput::LeftTrigger => "LeftTrigger",
+            GamepadInput::RightTrigger => "RightTrigger",
+            GamepadInput::Select => "Select",
+            GamepadInput::Start => "Start",
+            GamepadInput::DPadUp => "DPadUp",
+            GamepadInput::DPadDown => "DPadDown",
+            GamepadInput::DPadLeft => "DPadLeft",
+            GamepadInput::DPadRight => "DPadRight",
+            GamepadInput::LeftStickUp => "LeftStickUp",
+            GamepadInput::LeftStickDown => "LeftStickDown",
+            GamepadInput::LeftStickLeft => "LeftStickLeft",
+            GamepadInput::LeftStickRight => "LeftStickRight",
+            GamepadInput::RightStickUp => "RightStickUp",
+            GamepadInput::RightStickDown => "RightStickDown",
+            GamepadInput::RightStickLeft => "RightStickLeft",
+            GamepadInput::RightStickRight => "RightStickRight",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        ALL_INPUTS.iter().copied().find(|input| input.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+}
+
+impl GamepadAxis {
+    // The directions reached by pushing the axis negative and positive (Y is up-positive)
+    fn directions(&self) -> (GamepadInput, GamepadInput) {
+        match self {
+            GamepadAxis::LeftStickX => (GamepadInput::LeftStickLeft, GamepadInput::LeftStickRight),
+            GamepadAxis::LeftStickY => (GamepadInput::LeftStickDown, GamepadInput::LeftStickUp),
+            GamepadAxis::RightStickX => (GamepadInput::RightStickLeft, GamepadInput::RightStickRight),
+            GamepadAxis::RightStickY => (GamepadInput::RightStickDown, GamepadInput::RightStickUp),
+        }
+    }
+}
+
+/// Which gamepad inputs press each Game Boy button. Several inputs may share a button.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GamepadMapping {
+    pub a: Vec<GamepadInput>,
+    pub b: Vec<GamepadInput>,
+    pub start: Vec<GamepadInput>,
+    pub select: Vec<GamepadInput>,
+    pub up: Vec<GamepadInput>,
+    pub down: Vec<GamepadInput>,
+    pub left: Vec<GamepadInput>,
+    pub right: Vec<GamepadInput>,
+}
+
+impl Default for GamepadMapping {
+    // Sticks are left unmapped; they can be added in config.toml
+    fn default() -> Self {
+        Self {
+            a: vec![GamepadInput::South],
+            b: vec![GamepadInput::West],
+            start: vec![GamepadInput::Start],
+            select: vec![GamepadInput::Select],
+            up: vec![GamepadInput::DPadUp],
+            down: vec![GamepadInput::DPadDown],
+            left: vec![GamepadInput::DPadLeft],
+            right: vec![GamepadInput::DPadRight],
+        }
+    }
+}
+
+impl GamepadMapping {
+    /// (config key, bindings) for each Game Boy button, in config file order
+    pub fn bindings(&self) -> [(&'static str, &Vec<GamepadInput>); 8] {
+        [
+            ("a", &self.a), ("b", &self.b), ("start", &self.start), ("select", &self.select),
+            ("up", &self.up), ("down", &self.down), ("left", &self.left), ("right", &self.right),
+        ]
+    }
+
+    fn bindings_mut(&mut self, key: &str) -> Option<&mut Vec<GamepadInput>> {
+        match key {
+            "a" => Some(&mut self.a),
+            "b" => Some(&mut self.b),
+            "start" => Some(&mut self.start),
+            "select" => Some(&mut self.select),
+            "up" => Some(&mut self.up),
+            "down" => Some(&mut self.down),
+            "left" => Some(&mut self.left),
+            "right" => Some(&mut self.right),
+            _ => None,
+        }
+    }
+
+    /// Sets a button's bindings from a comma-separated list like `DPadUp, LeftStickUp`.
+    /// Returns false, leaving the mapping unchanged, for unknown buttons or input names.
+    pub fn set_binding(&mut self, key: &str, value: &str) -> bool {
+        let inputs: Option<Vec<GamepadInput>> = value.split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(GamepadInput::from_name)
+            .collect();
+        match (self.bindings_mut(key), inputs) {
+            (Some(bindings), Some(inputs)) => {
+                *bindings = inputs;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Presses every Game Boy button bound to an input the gamepad is holding. Buttons
+    /// already pressed (e.g. from the keyboard) stay pressed.
+    pub fn apply(&self, gamepad: &GamepadState, buttons: &mut JoypadButtons) {
+        let held = |bindings: &[GamepadInput]| bindings.iter().any(|&input| gamepad.is_pressed(input));
+        buttons.a |= held(&self.a);
+        buttons.b |= held(&self.b);
+        buttons.start |= held(&self.start);
+        buttons.select |= held(&self.select);
+        buttons.up |= held(&self.up);
+        buttons.down |= held(&self.down);
+        buttons.left |= held(&self.left);
+        buttons.right |= held(&self.right);
+    }
+}
+
+/// Connected controllers and the inputs held on the active one. The first controller
+/// connected becomes active; when it disconnects the next connected one takes over.
+pub struct GamepadState {
+    connected: Vec<usize>, // Backend gamepad ids, in connection order
+    active: Option<usize>,
+    pressed: Vec<GamepadInput>,
+}
+
+impl Default for GamepadState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GamepadState {
+    pub fn new() -> Self {
+        Self {
+            connected: Vec::new(),
+            active: None,
+            pressed: Vec::new(),
+        }
+    }
+
+    pub fn active(&self) -> Option<usize> {
+        self.active
+    }
+
+    pub fn connect(&mut self, id: usize, name: &str) {
+        if !self.connected.contains(&id) {
+            self.connected.push(id);
+        }
+        if self.active.is_none() {
+            self.active = Some(id);
+        }
+        debug!("Gamepad {} connected: {}{}", id, name, if self.active == Some(id) { " (active)" } else { "" });
+    }
+
+    pub fn disconnect(&mut self, id: usize) {
+        self.connected.retain(|&connected| connected != id);
+        if self.active == Some(id) {
+            self.active = self.connected.first().copied();
+            self.pressed.clear();
+        }
+        debug!("Gamepad {} disconnected", id);
+    }
+
+    /// Records a button press or release; events from inactive controllers are ignored
+    pub fn set_button(&mut self, id: usize, input: GamepadInput, pressed: bool) {
+        if self.active != Some(id) {
+            return;
+        }
+        self.pressed.retain(|&held| held != input);
+        if pressed {
+            self.pressed.push(input);
+        }
+    }
+
+    /// Records a stick position from -1.0 to 1.0 as presses of its two directions
+    pub fn set_axis(&mut self, id: usize, axis: GamepadAxis, value: f32) {
+        let (negative, positive) = axis.directions();
+        self.set_button(id, negative, value <= -STICK_THRESHOLD);
+        self.set_button(id, positive, value >= STICK_THRESHOLD);
+    }
+
+    pub fn is_pressed(&self, input: GamepadInput) -> bool {
+        self.pressed.contains(&input)
+    }
+}
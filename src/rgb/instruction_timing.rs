@@ -111,16 +111,27 @@ pub fn get_instruction_cycles(instruction: &InstructionKind, condition_taken: bo
         InstructionKind::DEC16(_) => 8,
         
         // Bit operations (CB-prefixed)
-        InstructionKind::BIT(_, _) => 8,
-        InstructionKind::RES(_, _) => 8,
-        InstructionKind::SET(_, _) => 8,
-        InstructionKind::RL(_) => 8,
-        InstructionKind::RR(_) => 8,
-        InstructionKind::RLC(_) => 8,
-        InstructionKind::RRC(_) => 8,
-        InstructionKind::SLA(_) => 8,
-        InstructionKind::SRA(_) => 8,
-        InstructionKind::SRL(_) => 8,
+        InstructionKind::BIT(_, src) => {
+            match src {
+                super::instructions::ArgKind::HL => 12, // BIT b,(HL): 12 cycles
+                _ => 8, // BIT b,r: 8 cycles
+            }
+        }
+        InstructionKind::RES(_, src)
+        | InstructionKind::SET(_, src)
+        | InstructionKind::RL(src)
+        | InstructionKind::RR(src)
+        | InstructionKind::RLC(src)
+        | InstructionKind::RRC(src)
+        | InstructionKind::SLA(src)
+        | InstructionKind::SRA(src)
+        | InstructionKind::SRL(src)
+        | InstructionKind::SWAP(src) => {
+            match src {
+                super::instructions::ArgKind::HL => 16, // Read-modify-write of (HL): 16 cycles
+                _ => 8, // Register operand: 8 cycles
+            }
+        }
         
         // Logical operations
         InstructionKind::XOR(_, src) => {
@@ -167,7 +178,6 @@ pub fn get_instruction_cycles(instruction: &InstructionKind, condition_taken: bo
         InstructionKind::RRA => 4,
         InstructionKind::RLCA => 4,
         InstructionKind::RRCA => 4,
-        InstructionKind::SWAP(_) => 8, // SWAP takes 8 cycles
         
         // Misc
         InstructionKind::NOP => 4,
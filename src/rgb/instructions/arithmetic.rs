@@ -52,6 +52,9 @@ pub fn decode_arithmetic_instruction(opcode: u8, immediate: Option<u8>) -> Optio
         0x9F => Some(InstructionKind::SBC(ArgKind::A, ArgKind::A)),
         0xDE => Some(InstructionKind::SBC(ArgKind::A, ArgKind::Immediate(immediate.unwrap_or(0)))), // SBC A,d8
         
+        // ADD SP,r8 - signed immediate added to SP
+        0xE8 => Some(InstructionKind::ADD_SP_R8(immediate.unwrap_or(0) as i8)),
+        
         _ => None,
     }
 }
@@ -67,6 +70,9 @@ pub fn get_arithmetic_instruction_size(opcode: u8) -> Option<u16> {
         
         // Immediate arithmetic instructions are 2 bytes
         0xC6 | 0xCE | 0xD6 | 0xDE => Some(2),
+        
+        // ADD SP,r8 - opcode + signed immediate
+        0xE8 => Some(2),
         _ => None,
     }
 }
\ No newline at end of file
@@ -1,4 +1,5 @@
 use super::InstructionKind;
+use std::fmt;
 
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum JumpCondition {
@@ -9,6 +10,19 @@ pub enum JumpCondition {
     NotCarry,
 }
 
+impl fmt::Display for JumpCondition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            JumpCondition::Always => "",
+            JumpCondition::Zero => "Z",
+            JumpCondition::NotZero => "NZ",
+            JumpCondition::Carry => "C",
+            JumpCondition::NotCarry => "NC",
+        };
+        f.write_str(name)
+    }
+}
+
 pub fn decode_control_flow_instruction(opcode: u8, immediate: Option<u8>, immediate16: Option<u16>) -> Option<InstructionKind> {
     match opcode {
         // Jump instructions
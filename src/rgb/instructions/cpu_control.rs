@@ -7,6 +7,9 @@ pub fn decode_cpu_control_instruction(opcode: u8) -> Option<InstructionKind> {
         0xFB => Some(InstructionKind::EI),   // Enable interrupts
         0x00 => Some(InstructionKind::NOP),  // No operation
         0x10 => Some(InstructionKind::STOP), // Stop
+        0x2F => Some(InstructionKind::CPL),  // Complement A
+        0x37 => Some(InstructionKind::SCF),  // Set carry flag
+        0x3F => Some(InstructionKind::CCF),  // Complement carry flag
         _ => None,
     }
 }
@@ -18,6 +21,9 @@ pub fn get_cpu_control_instruction_size(opcode: u8) -> Option<u16> {
         0xFB => Some(1), // EI
         0x00 => Some(1), // NOP
         0x10 => Some(2), // STOP (2 bytes: opcode + 0x00)
+        0x2F => Some(1), // CPL
+        0x37 => Some(1), // SCF
+        0x3F => Some(1), // CCF
         _ => None,
     }
 }
\ No newline at end of file
@@ -8,6 +8,8 @@ pub mod logical;
 pub mod memory;
 pub mod stack;
 
+use std::fmt;
+
 pub use load::{decode_load_instruction, get_load_instruction_size};
 pub use arithmetic::{decode_arithmetic_instruction, get_arithmetic_instruction_size};
 pub use increment::{decode_increment_instruction, get_increment_instruction_size};
@@ -18,7 +20,7 @@ pub use logical::{decode_logical_instruction, get_logical_instruction_size};
 pub use memory::{decode_memory_instruction, get_memory_instruction_size};
 pub use stack::{decode_stack_instruction, get_stack_instruction_size};
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum ArgKind {
     Immediate(u8),
     Immediate16(u16),
@@ -36,7 +38,7 @@ pub enum ArgKind {
     L,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 #[allow(non_camel_case_types)] // Allow descriptive instruction names
 pub enum InstructionKind {
     LD(ArgKind, ArgKind),
@@ -129,6 +131,119 @@ pub enum InstructionKind {
     SWAP(ArgKind), // Swap upper and lower nibbles
 }
 
+impl fmt::Display for ArgKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArgKind::Immediate(value) => write!(f, "0x{:02X}", value),
+            ArgKind::Immediate16(value) => write!(f, "0x{:04X}", value),
+            ArgKind::SP => f.write_str("SP"),
+            ArgKind::BC => f.write_str("BC"),
+            ArgKind::DE => f.write_str("DE"),
+            ArgKind::HL => f.write_str("HL"),
+            ArgKind::AF => f.write_str("AF"),
+            ArgKind::A => f.write_str("A"),
+            ArgKind::B => f.write_str("B"),
+            ArgKind::C => f.write_str("C"),
+            ArgKind::D => f.write_str("D"),
+            ArgKind::E => f.write_str("E"),
+            ArgKind::H => f.write_str("H"),
+            ArgKind::L => f.write_str("L"),
+        }
+    }
+}
+
+// 8-bit ALU, CB and INC/DEC instructions encode their (HL) operand as plain ArgKind::HL
+fn operand(arg: &ArgKind) -> String {
+    match arg {
+        ArgKind::HL => "(HL)".to_string(),
+        _ => arg.to_string(),
+    }
+}
+
+// "JP NZ, 0x1234" but "JP 0x1234" for unconditional jumps
+fn with_condition(mnemonic: &str, condition: &JumpCondition, target: &str) -> String {
+    match condition {
+        JumpCondition::Always => format!("{} {}", mnemonic, target),
+        _ => format!("{} {}, {}", mnemonic, condition, target),
+    }
+}
+
+impl fmt::Display for InstructionKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            // ADD HL,rr is the only 16-bit form; ADD A,HL means ADD A,(HL)
+            InstructionKind::ADD(ArgKind::HL, src) => write!(f, "ADD HL, {}", src),
+            InstructionKind::LD(dest, src) => write!(f, "LD {}, {}", dest, src),
+            InstructionKind::ADD(dest, src) => write!(f, "ADD {}, {}", dest, operand(src)),
+            InstructionKind::ADC(dest, src) => write!(f, "ADC {}, {}", dest, operand(src)),
+            InstructionKind::SUB(dest, src) => write!(f, "SUB {}, {}", dest, operand(src)),
+            InstructionKind::SBC(dest, src) => write!(f, "SBC {}, {}", dest, operand(src)),
+            InstructionKind::XOR(dest, src) => write!(f, "XOR {}, {}", dest, operand(src)),
+            InstructionKind::AND(dest, src) => write!(f, "AND {}, {}", dest, operand(src)),
+            InstructionKind::OR(dest, src) => write!(f, "OR {}, {}", dest, operand(src)),
+            InstructionKind::CP(dest, src) => write!(f, "CP {}, {}", dest, operand(src)),
+            InstructionKind::CP_MEM(dest, src) => write!(f, "CP {}, ({})", dest, src),
+            InstructionKind::NOP => f.write_str("NOP"),
+            InstructionKind::INC(reg) => write!(f, "INC {}", reg),
+            InstructionKind::DEC(reg) => write!(f, "DEC {}", reg),
+            InstructionKind::INC_MEM(addr) => write!(f, "INC ({})", addr),
+            InstructionKind::DEC_MEM(addr) => write!(f, "DEC ({})", addr),
+            InstructionKind::INC16(reg) => write!(f, "INC {}", reg),
+            InstructionKind::DEC16(reg) => write!(f, "DEC {}", reg),
+            InstructionKind::BIT(bit, reg) => write!(f, "BIT {}, {}", bit, operand(reg)),
+            InstructionKind::RES(bit, reg) => write!(f, "RES {}, {}", bit, operand(reg)),
+            InstructionKind::SET(bit, reg) => write!(f, "SET {}, {}", bit, operand(reg)),
+            InstructionKind::JP(condition, addr) => f.write_str(&with_condition("JP", condition, &format!("0x{:04X}", addr))),
+            InstructionKind::JP_HL => f.write_str("JP HL"),
+            InstructionKind::JR(condition, offset) => f.write_str(&with_condition("JR", condition, &format!("{:+}", offset))),
+            InstructionKind::CALL(addr) => write!(f, "CALL 0x{:04X}", addr),
+            InstructionKind::CALL_COND(condition, addr) => f.write_str(&with_condition("CALL", condition, &format!("0x{:04X}", addr))),
+            InstructionKind::RET => f.write_str("RET"),
+            InstructionKind::RET_COND(condition) => write!(f, "RET {}", condition),
+            InstructionKind::RETI => f.write_str("RETI"),
+            InstructionKind::RST(vector) => write!(f, "RST 0x{:02X}", vector),
+            InstructionKind::HALT => f.write_str("HALT"),
+            InstructionKind::STOP => f.write_str("STOP"),
+            InstructionKind::LD_MEM(addr, src) => write!(f, "LD ({}), {}", addr, src),
+            InstructionKind::LD_MEM_INC(addr, src) => write!(f, "LD ({}+), {}", addr, src),
+            InstructionKind::LD_MEM_DEC(addr, src) => write!(f, "LD ({}-), {}", addr, src),
+            InstructionKind::LD_FROM_MEM(dest, addr) => write!(f, "LD {}, ({})", dest, addr),
+            InstructionKind::LD_FROM_MEM_INC(dest, addr) => write!(f, "LD {}, ({}+)", dest, addr),
+            InstructionKind::LD_FROM_MEM_DEC(dest, addr) => write!(f, "LD {}, ({}-)", dest, addr),
+            InstructionKind::LD_MEM_16(addr, src) => write!(f, "LD ({}), {}", addr, src),
+            InstructionKind::LD_FROM_MEM_16(dest, addr) => write!(f, "LD {}, ({})", dest, addr),
+            InstructionKind::LDH_TO_C(src) => write!(f, "LD (C), {}", src),
+            InstructionKind::LDH_FROM_C(dest) => write!(f, "LD {}, (C)", dest),
+            InstructionKind::LDH_TO_N(src, offset) => write!(f, "LDH (0x{:02X}), {}", offset, src),
+            InstructionKind::LDH_FROM_N(dest, offset) => write!(f, "LDH {}, (0x{:02X})", dest, offset),
+            InstructionKind::LDHL_SP_R8(offset) => write!(f, "LD HL, SP{:+}", offset),
+            InstructionKind::LD_SP_TO_MEM(addr) => write!(f, "LD (0x{:04X}), SP", addr),
+            InstructionKind::EI => f.write_str("EI"),
+            InstructionKind::DI => f.write_str("DI"),
+            InstructionKind::DAA => f.write_str("DAA"),
+            InstructionKind::CPL => f.write_str("CPL"),
+            InstructionKind::SCF => f.write_str("SCF"),
+            InstructionKind::CCF => f.write_str("CCF"),
+            InstructionKind::ADD_SP_R8(offset) => write!(f, "ADD SP, {:+}", offset),
+            InstructionKind::PUSH(reg) => write!(f, "PUSH {}", reg),
+            InstructionKind::POP(reg) => write!(f, "POP {}", reg),
+            InstructionKind::RL(reg) => write!(f, "RL {}", operand(reg)),
+            InstructionKind::RR(reg) => write!(f, "RR {}", operand(reg)),
+            InstructionKind::RLA => f.write_str("RLA"),
+            InstructionKind::RRA => f.write_str("RRA"),
+            InstructionKind::RLCA => f.write_str("RLCA"),
+            InstructionKind::RRCA => f.write_str("RRCA"),
+            InstructionKind::RLC(reg) => write!(f, "RLC {}", operand(reg)),
+            InstructionKind::RRC(reg) => write!(f, "RRC {}", operand(reg)),
+            InstructionKind::SLA(reg) => write!(f, "SLA {}", operand(reg)),
+            InstructionKind::SRA(reg) => write!(f, "SRA {}", operand(reg)),
+            InstructionKind::SRL(reg) => write!(f, "SRL {}", operand(reg)),
+            InstructionKind::SWAP(reg) => write!(f, "SWAP {}", operand(reg)),
+        }
+    }
+}
+
+#[derive(Debug)]
 #[allow(dead_code)] // Instruction format fields for future use
 pub struct Instruction {
     pub kind: InstructionKind,
@@ -149,6 +264,8 @@ impl Default for Instruction {
 }
 
 
+/// The decoder families cover disjoint opcodes (e.g. every LD r,(HL) is in the memory family),
+/// so the order they're tried in doesn't change the result
 pub fn decode_instruction(opcode: u8, immediate: Option<u8>, immediate16: Option<u16>) -> InstructionKind {
     // Try load instructions first
     if let Some(load_instr) = decode_load_instruction(opcode, immediate, immediate16) {
@@ -192,22 +309,22 @@ pub fn decode_instruction(opcode: u8, immediate: Option<u8>, immediate16: Option
     
     // Handle other instructions
     match opcode {
-        0x00 => InstructionKind::NOP,
         0x07 => InstructionKind::RLCA,
         0x08 => InstructionKind::LD_SP_TO_MEM(immediate16.unwrap_or(0)),
         0x0F => InstructionKind::RRCA,
         0x17 => InstructionKind::RLA,
         0x1F => InstructionKind::RRA,
         0x27 => InstructionKind::DAA,
-        0x2F => InstructionKind::CPL,
-        0x37 => InstructionKind::SCF,
-        0x3F => InstructionKind::CCF,
         0xD9 => InstructionKind::RETI,
-        0xE8 => InstructionKind::ADD_SP_R8(immediate.unwrap_or(0) as i8),
         _ => panic!("Unknown opcode: 0x{:02X}", opcode),
     }
 }
 
+/// The 11 opcodes with no defined instruction; real hardware locks up on them
+pub fn is_illegal_opcode(opcode: u8) -> bool {
+    matches!(opcode, 0xD3 | 0xDB | 0xDD | 0xE3 | 0xE4 | 0xEB | 0xEC | 0xED | 0xF4 | 0xFC | 0xFD)
+}
+
 pub fn get_instruction_size(opcode: u8) -> u16 {
     // Try load instructions first
     if let Some(size) = get_load_instruction_size(opcode) {
@@ -251,18 +368,13 @@ pub fn get_instruction_size(opcode: u8) -> u16 {
     
     // Handle other instructions
     match opcode {
-        0x00 => 1, // NOP
         0x07 => 1, // RLCA
         0x08 => 3, // LD (nn),SP - 3 bytes: opcode + 16-bit address
         0x0F => 1, // RRCA
         0x17 => 1, // RLA
         0x1F => 1, // RRA
         0x27 => 1, // DAA
-        0x2F => 1, // CPL
-        0x37 => 1, // SCF
-        0x3F => 1, // CCF
         0xD9 => 1, // RETI
-        0xE8 => 2, // ADD SP,r8 - 2 bytes: opcode + signed immediate
         _ => panic!("Unknown opcode: 0x{:02X}", opcode),
     }
 }
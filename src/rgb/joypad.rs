@@ -1,6 +1,8 @@
 // Game Boy Joypad Implementation
 // Handles the joypad register (0xFF00) and button state management
 
+use macroquad::input::KeyCode;
+
 #[derive(Debug, Clone, Copy)]
 pub struct JoypadButtons {
     pub a: bool,
@@ -26,12 +28,205 @@ impl JoypadButtons {
             right: false,
         }
     }
+
+    /// The buttons whose mapped keys are held
+    pub fn from_keyboard_state(mapping: &KeyMapping, keys: &impl KeyboardState) -> Self {
+        Self {
+            a: keys.is_key_down(mapping.a_key),
+            b: keys.is_key_down(mapping.b_key),
+            start: keys.is_key_down(mapping.start_key),
+            select: keys.is_key_down(mapping.select_key),
+            up: keys.is_key_down(mapping.up_key),
+            down: keys.is_key_down(mapping.down_key),
+            left: keys.is_key_down(mapping.left_key),
+            right: keys.is_key_down(mapping.right_key),
+        }
+    }
+}
+
+/// Which keys are held. Any `Fn(KeyCode) -> bool` works, so macroquad's `is_key_down`
+/// can be passed directly and tests can use a closure.
+pub trait KeyboardState {
+    fn is_key_down(&self, key: KeyCode) -> bool;
+}
+
+impl<F: Fn(KeyCode) -> bool> KeyboardState for F {
+    fn is_key_down(&self, key: KeyCode) -> bool {
+        self(key)
+    }
+}
+
+// Keys that can be named in the [keyboard] config section, by their KeyCode variant name
+const KEY_NAMES: [(KeyCode, &str); 53] = [
+    (KeyCode::A, "A"), (KeyCode::B, "B"), (KeyCode::C, "C"), (KeyCode::D, "D"), (KeyCode::E, "E"),
+    (KeyCode::F, "F"), (KeyCode::G, "G"), (KeyCode::H, "H"), (KeyCode::I, "I"), (KeyCode::J, "J"),
+    (KeyCode::K, "K"), (KeyCode::L, "L"), (KeyCode::M, "M"), (KeyCode::N, "N"), (KeyCode::O, "O"),
+    (KeyCode::P, "P"), (KeyCode::Q, "Q"), (KeyCode::R, "R"), (KeyCode::S, "S"), (KeyCode::T, "T"),
+    (KeyCode::U, "U"), (KeyCode::V, "V"), (KeyCode::W, "W"), (KeyCode::X, "X"), (KeyCode::Y, "Y"),
+    (KeyCode::Z, "Z"),
+    (KeyCode::Key0, "Key0"), (KeyCode::Key1, "Key1"), (KeyCode::Key2, "Key2"), (KeyCode::Key3, "Key3"),
+    (KeyCode::Key4, "Key4"), (KeyCode::Key5, "Key5"), (KeyCode::Key6, "Key6"), (KeyCode::Key7, "Key7"),
+    (KeyCode::Key8, "Key8"), (KeyCode::Key9, "Key9"),
+    (KeyCode::Up, "Up"), (KeyCode::Down, "Down"), (KeyCode::Left, "Left"), (KeyCode::Right, "Right"),
+    (KeyCode::Enter, "Enter"), (KeyCode::Space, "Space"), (KeyCode::Backspace, "Backspace"), (KeyCode::Tab, "Tab"),
+    (KeyCode::LeftShift, "LeftShift"), (KeyCode::RightShift, "RightShift"),
+    (KeyCode::LeftControl, "LeftControl"), (KeyCode::RightControl, "RightControl"),
+    (KeyCode::LeftAlt, "LeftAlt"), (KeyCode::RightAlt, "RightAlt"),
+    (KeyCode::Comma, "Comma"), (KeyCode::Period, "Period"), (KeyCode::Slash, "Slash"),
+];
+
+pub fn key_name(key: KeyCode) -> Option<&'static str> {
+    KEY_NAMES.iter().find(|(code, _)| *code == key).map(|(_, name)| *name)
+}
+
+pub fn key_from_name(name: &str) -> Option<KeyCode> {
+    KEY_NAMES.iter().find(|(_, key_name)| *key_name == name).map(|(code, _)| *code)
+}
+
+/// Which keyboard key presses each Game Boy button
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyMapping {
+    pub a_key: KeyCode,
+    pub b_key: KeyCode,
+    pub start_key: KeyCode,
+    pub select_key: KeyCode,
+    pub up_key: KeyCode,
+    pub down_key: KeyCode,
+    pub left_key: KeyCode,
+    pub right_key: KeyCode,
+}
+
+impl Default for KeyMapping {
+    fn default() -> Self {
+        Self {
+            a_key: KeyCode::Z,
+            b_key: KeyCode::X,
+            start_key: KeyCode::Enter,
+            select_key: KeyCode::RightShift,
+            up_key: KeyCode::Up,
+            down_key: KeyCode::Down,
+            left_key: KeyCode::Left,
+            right_key: KeyCode::Right,
+        }
+    }
+}
+
+impl KeyMapping {
+    /// (config key, key) for each Game Boy button, in config file order
+    pub fn bindings(&self) -> [(&'static str, KeyCode); 8] {
+        [
+            ("a", self.a_key), ("b", self.b_key), ("start", self.start_key), ("select", self.select_key),
+            ("up", self.up_key), ("down", self.down_key), ("left", self.left_key), ("right", self.right_key),
+        ]
+    }
+
+    /// Binds a button to a key named like `Z` or `RightShift`. Returns false, leaving the
+    /// mapping unchanged, for unknown buttons or key names.
+    pub fn set_binding(&mut self, button: &str, key_name: &str) -> bool {
+        let Some(key) = key_from_name(key_name) else {
+            return false;
+        };
+        let slot = match button {
+            "a" => &mut self.a_key,
+            "b" => &mut self.b_key,
+            "start" => &mut self.start_key,
+            "select" => &mut self.select_key,
+            "up" => &mut self.up_key,
+            "down" => &mut self.down_key,
+            "left" => &mut self.left_key,
+            "right" => &mut self.right_key,
+            _ => return false,
+        };
+        *slot = key;
+        true
+    }
+}
+
+/// Whether the joypad port answers Super Game Boy command packets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SgbMode {
+    /// A plain Game Boy: packets are ignored, so P1 never reports a second controller
+    /// and SGB-aware games fall back to their DMG behavior
+    #[default]
+    Disabled,
+    /// Packets are decoded and MLT_REQ is honored, which is what games check to detect an SGB
+    Enabled,
+}
+
+const SGB_PACKET_BYTES: usize = 16;
+const SGB_MLT_REQ: u8 = 0x11; // Multiplayer request: the command games use to detect an SGB
+
+// Bit-level receiver for SGB packets sent through P1. Pulling both select lines low
+// resets it; then P14 low sends a 0 and P15 low sends a 1, each followed by both lines
+// high. 128 bits and a 0 stop bit make a packet.
+#[derive(Debug, Default)]
+struct SgbReceiver {
+    receiving: bool,
+    bits_received: usize,
+    packet: [u8; SGB_PACKET_BYTES],
+    line_released: bool, // Both lines went high since the last bit, so the next pulse is a new bit
+    p15_low: bool, // P15 as of the last write; its rising edge selects the next controller
+    player_count: u8, // Set by MLT_REQ: 1, 2 or 4
+    current_player: u8,
+}
+
+impl SgbReceiver {
+    // Handles a P1 write; `select` is bits 5-4 as written (0 = line pulled low)
+    fn write(&mut self, select: u8) {
+        match select {
+            0x00 => {
+                self.receiving = true;
+                self.bits_received = 0;
+                self.packet = [0; SGB_PACKET_BYTES];
+                self.line_released = false;
+            }
+            0x30 => {
+                if self.p15_low && !self.receiving && self.player_count > 1 {
+                    self.current_player = (self.current_player + 1) % self.player_count;
+                }
+                self.line_released = true;
+            }
+            _ => {
+                if self.receiving && self.line_released {
+                    self.receive_bit(select == 0x10);
+                }
+                self.line_released = false;
+            }
+        }
+        self.p15_low = select & 0x20 == 0;
+    }
+
+    fn receive_bit(&mut self, bit: bool) {
+        if self.bits_received == SGB_PACKET_BYTES * 8 {
+            // Stop bit ends the packet
+            self.receiving = false;
+            self.handle_packet();
+            return;
+        }
+        if bit {
+            self.packet[self.bits_received / 8] |= 1 << (self.bits_received % 8);
+        }
+        self.bits_received += 1;
+    }
+
+    fn handle_packet(&mut self) {
+        // The first byte is the command in bits 7-3 and the packet count in bits 2-0
+        if self.packet[0] >> 3 == SGB_MLT_REQ {
+            self.player_count = match self.packet[1] & 0x03 {
+                1 => 2,
+                3 => 4,
+                _ => 1,
+            };
+            self.current_player = 0;
+        }
+    }
 }
 
 pub struct Joypad {
     buttons: JoypadButtons,
     direction_selected: bool,
     button_selected: bool,
+    sgb: Option<SgbReceiver>, // None with SgbMode::Disabled
 }
 
 impl Joypad {
@@ -40,9 +235,22 @@ impl Joypad {
             buttons: JoypadButtons::new(),
             direction_selected: false,
             button_selected: false,
+            sgb: None,
         }
     }
 
+    pub fn set_sgb_mode(&mut self, mode: SgbMode) {
+        self.sgb = match mode {
+            SgbMode::Enabled => Some(SgbReceiver::default()),
+            SgbMode::Disabled => None,
+        };
+    }
+
+    /// Controllers the SGB reports after an MLT_REQ; always 1 without SGB support
+    pub fn sgb_player_count(&self) -> u8 {
+        self.sgb.as_ref().map_or(1, |sgb| sgb.player_count.max(1))
+    }
+
     pub fn update_buttons(&mut self, buttons: JoypadButtons) -> bool {
         // Check if any new button was pressed (edge detection)
         let old_buttons = self.buttons;
@@ -90,6 +298,14 @@ impl Joypad {
             if self.buttons.a { result &= !0x01; }
         }
 
+        // With neither group selected an SGB in multiplayer mode reports the current
+        // controller as 0xF minus its number; a DMG always reads 0xF
+        if !self.button_selected && !self.direction_selected {
+            if let Some(sgb) = self.sgb.as_ref() {
+                result = (result & 0xF0) | (0x0F - sgb.current_player);
+            }
+        }
+
         result
     }
 
@@ -98,6 +314,9 @@ impl Joypad {
         // Only bits 5 and 4 are writable (select which button group to read)
         self.button_selected = (value & 0x20) == 0;
         self.direction_selected = (value & 0x10) == 0;
+        if let Some(sgb) = self.sgb.as_mut() {
+            sgb.write(value & 0x30);
+        }
     }
 
     // Check if any button is currently pressed (for interrupt generation)
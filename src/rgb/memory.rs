@@ -1,43 +1,253 @@
 use super::ppu::Ppu;
-use super::cart::Cart;
+use super::cart::{Cart, CartridgeHeader};
 use super::timer::Timer;
 use super::joypad::Joypad;
+use super::serial::{Serial, SerialDevice};
+use super::apu::Apu;
+use super::config::EmulatorConfig;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
 use std::fs;
 use std::path::Path;
 #[cfg(debug_assertions)]
 use log::debug;
 
+pub const KEY1_ADDR: u16 = 0xFF4D; // CGB speed switch
+pub const KEY1_PREPARE: u8 = 0x01; // Bit 0: switch speed on the next STOP
+pub const KEY1_DOUBLE_SPEED: u8 = 0x80; // Bit 7: current speed (read-only)
+pub const HRAM_START: u16 = 0xFF80;
+pub const HRAM_END: u16 = 0xFFFE;
+const HRAM_SIZE: usize = (HRAM_END - HRAM_START + 1) as usize; // 127 bytes
+pub const DMA_ADDR: u16 = 0xFF46;
+const DMA_LENGTH: u16 = 160; // Bytes copied to OAM per transfer
+const DMA_CYCLES_PER_BYTE: u16 = 4;
+// PPU registers captured by snapshot: 0xFF40-0xFF4B without DMA
+const SNAPSHOT_PPU_REGISTERS: [u16; 11] = [0xFF40, 0xFF41, 0xFF42, 0xFF43, 0xFF44, 0xFF45, 0xFF47, 0xFF48, 0xFF49, 0xFF4A, 0xFF4B];
+
+// Compressed Nintendo logo every cartridge carries at 0x0104-0x0133
+const NINTENDO_LOGO: [u8; 48] = [
+    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+    0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+    0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E
+];
+const REGISTERED_MARK_TILE: [u8; 8] = [0x3C, 0x42, 0xB9, 0xA5, 0xB9, 0xA5, 0x42, 0x3C]; // One bitplane of the (R) tile
+const LOGO_TILE_MAP_ROWS: [usize; 2] = [0x1904, 0x1924]; // VRAM offsets of 0x9904 and 0x9924
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WatchKind {
+    Write,
+    Read,
+    ReadWrite,
+}
+
+impl WatchKind {
+    pub fn matches(&self, access: WatchKind) -> bool {
+        match self {
+            WatchKind::ReadWrite => true,
+            _ => *self == access,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Watchpoint {
+    pub address: u16,
+    pub kind: WatchKind,
+}
+
+/// One entry in the memory map's access log
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemAccess {
+    pub kind: WatchKind, // Read or Write
+    pub addr: u16,
+    pub val: u8,
+    pub pc: u16, // Instruction that made the access
+}
+
+/// The memory map's RAM and PPU registers at one point in time, for undoing a debugger
+/// step. VRAM and OAM aren't included to keep snapshots small.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemorySnapshot {
+    pub contents: Box<[u8; 65536]>,
+    pub hram: Vec<u8>,
+    pub ppu_registers: Vec<(u16, u8)>, // (address, value), restored through the PPU's register writes
+}
+
+/// Interrupts raised by the hardware during one `MemoryMap::step`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct StepInterrupts {
+    pub timer: bool,
+    pub serial: bool,
+    pub vblank: bool,
+    pub stat: bool,
+}
+
 pub struct MemoryMap {
     contents: [u8; 65536],
+    hram: [u8; HRAM_SIZE],
     ppu: Ppu,
     timer: Timer,
     joypad: Joypad,
+    serial: Serial,
+    apu: Apu,
     cart: Option<Cart>,
     pub bootstrap_enabled: bool,
+    bootstrap_loaded: bool, // Set by load_bootstrap; the map itself never loads one
+    // ECHO RAM (0xE000-0xFDFF) reads 0xFF and ignores writes instead of mirroring WRAM,
+    // to catch games touching the prohibited area
+    pub prohibit_echo_ram: bool,
+    // 0xFEA0-0xFEFF reads like a DMG instead of always 0xFF: during OAM scan it returns the
+    // OAM row being fetched, while drawing 0xFF and otherwise 0x00 (Ppu::read_unused_oam_area)
+    pub accurate_unused_memory: bool,
+    watchpoints: Vec<Watchpoint>,
+    // Set by read/write when a watchpoint matches; read takes &self so this needs a Cell
+    watchpoint_hit: Cell<Option<(u16, u8, WatchKind)>>,
+    // Inclusive ROM-area ranges served from contents instead of the cartridge
+    patched_ranges: Vec<(u16, u16)>,
+    key1: u8,
+    // Last accesses for post-mortem debugging (None = disabled); RefCell for the same reason
+    access_log: RefCell<Option<VecDeque<MemAccess>>>,
+    access_log_size: usize,
+    access_pc: u16, // PC of the current instruction, set by the CPU
+    // OAM DMA: source of the running transfer and bytes copied so far (None = idle)
+    dma_source: u16,
+    dma_progress: Option<u16>,
+    dma_cycles: u16,
+    dma_current_byte: u8, // Last byte transferred; the CPU sees it on the bus during DMA
+    // Every CPU write since the last take_write_log (None = disabled), for tests and --compare-trace
+    write_log: Option<Vec<(u16, u8)>>,
+    // Counts every read and write per address while set; read takes &self, hence the RefCell
+    #[cfg(feature = "memory_profiling")]
+    pub track_accesses: bool,
+    #[cfg(feature = "memory_profiling")]
+    read_counts: RefCell<Box<[u64; 65536]>>,
+    #[cfg(feature = "memory_profiling")]
+    write_counts: Box<[u64; 65536]>,
+}
+
+// Allocated on the heap directly; a 512 KiB array built on the stack first could overflow it
+#[cfg(feature = "memory_profiling")]
+fn zeroed_counts() -> Box<[u64; 65536]> {
+    vec![0; 65536].into_boxed_slice().try_into().unwrap()
+}
+
+// The `n` highest counts as (address, count), highest first, skipping addresses never accessed
+#[cfg(feature = "memory_profiling")]
+fn hottest(counts: &[u64; 65536], n: usize) -> Vec<(u16, u64)> {
+    let mut hot: Vec<(u16, u64)> = counts.iter().enumerate()
+        .filter(|&(_, &count)| count > 0)
+        .map(|(addr, &count)| (addr as u16, count))
+        .collect();
+    hot.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    hot.truncate(n);
+    hot
+}
+
+// Up to 8 bytes from the first non-zero one in start..=end, e.g. "C3 50 01 ... @0x0101"
+fn preview_bytes(start: u16, end: u16, read: &dyn Fn(u16) -> u8) -> String {
+    let Some(first) = (start..=end).find(|&addr| read(addr) != 0) else {
+        return "all zero".to_string();
+    };
+    let bytes: Vec<String> = (first..=end).take(8).map(|addr| format!("{:02X}", read(addr))).collect();
+    let mut preview = bytes.join(" ");
+    if end - first >= 8 {
+        preview.push_str(" ...");
+    }
+    if first != start {
+        preview.push_str(&format!(" @0x{:04X}", first));
+    }
+    preview
+}
+
+impl Default for MemoryMap {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl MemoryMap {
+    /// Creates a MemoryMap in the power-on or post-boot state the config asks for, with
+    /// ECHO RAM behavior set. Like `new`, it never loads the boot ROM; `Cpu::new_with_config`
+    /// does that from `config.bootstrap_path`.
+    pub fn new_with_config(config: &EmulatorConfig) -> Self {
+        let mut mmap = if config.skip_boot { Self::new_post_boot() } else { Self::new() };
+        mmap.prohibit_echo_ram = !config.echo_ram_mirrors;
+        mmap.accurate_unused_memory = config.accurate_unused_memory;
+        mmap.joypad.set_sgb_mode(config.sgb_mode);
+        mmap
+    }
+
+    /// Power-on state with every byte zeroed. The bootstrap area is mapped but stays
+    /// blank until `load_bootstrap` is called, so tests can place their own code there.
     pub fn new() -> Self {
         MemoryMap {
             contents: [0; 65536],
+            hram: [0; HRAM_SIZE],
             ppu: Ppu::new(),
             timer: Timer::new(),
             joypad: Joypad::new(),
+            serial: Serial::new(),
+            apu: Apu::new(),
             cart: None,
             bootstrap_enabled: true,
+            bootstrap_loaded: false,
+            prohibit_echo_ram: false,
+            accurate_unused_memory: false,
+            watchpoints: Vec::new(),
+            watchpoint_hit: Cell::new(None),
+            patched_ranges: Vec::new(),
+            key1: 0,
+            access_log: RefCell::new(None),
+            access_log_size: 0,
+            access_pc: 0,
+            dma_source: 0,
+            dma_progress: None,
+            dma_cycles: 0,
+            dma_current_byte: 0xFF,
+            write_log: None,
+            #[cfg(feature = "memory_profiling")]
+            track_accesses: false,
+            #[cfg(feature = "memory_profiling")]
+            read_counts: RefCell::new(zeroed_counts()),
+            #[cfg(feature = "memory_profiling")]
+            write_counts: zeroed_counts(),
         }
     }
     
-    /// Creates a new MemoryMap in the post-boot state for skipping boot sequence
-    /// This initializes hardware registers to their expected post-boot values
-    pub fn new_post_boot() -> Self {
+    // Post-boot state for skipping the boot sequence, with hardware registers
+    // initialized to the values the boot ROM leaves behind
+    fn new_post_boot() -> Self {
         let mut mmap = MemoryMap {
             contents: [0; 65536],
+            hram: [0; HRAM_SIZE],
             ppu: Ppu::new_post_boot(),
             timer: Timer::new_post_boot(),
             joypad: Joypad::new(),
+            serial: Serial::new(),
+            apu: Apu::new_post_boot(),
             cart: None,
             bootstrap_enabled: false, // Bootstrap ROM already disabled
+            bootstrap_loaded: false,
+            prohibit_echo_ram: false,
+            accurate_unused_memory: false,
+            watchpoints: Vec::new(),
+            watchpoint_hit: Cell::new(None),
+            patched_ranges: Vec::new(),
+            key1: 0,
+            access_log: RefCell::new(None),
+            access_log_size: 0,
+            access_pc: 0,
+            dma_source: 0,
+            dma_progress: None,
+            dma_cycles: 0,
+            dma_current_byte: 0xFF,
+            write_log: None,
+            #[cfg(feature = "memory_profiling")]
+            track_accesses: false,
+            #[cfg(feature = "memory_profiling")]
+            read_counts: RefCell::new(zeroed_counts()),
+            #[cfg(feature = "memory_profiling")]
+            write_counts: zeroed_counts(),
         };
         
         // Set post-boot hardware register values
@@ -52,10 +262,7 @@ impl MemoryMap {
     /// Initialize hardware registers to their post-boot state
     fn init_post_boot_registers(&mut self) {
         // Sound registers (all disabled after boot)
-        self.contents[0xFF10] = 0x80; // NR10
-        self.contents[0xFF11] = 0xBF; // NR11
-        self.contents[0xFF12] = 0xF3; // NR12
-        self.contents[0xFF14] = 0xBF; // NR14
+        // NR10-NR14, NR41-NR44 and NR50-NR52 are set up by Apu::new_post_boot
         self.contents[0xFF16] = 0x3F; // NR21
         self.contents[0xFF17] = 0x00; // NR22
         self.contents[0xFF19] = 0xBF; // NR24
@@ -63,13 +270,6 @@ impl MemoryMap {
         self.contents[0xFF1B] = 0xFF; // NR31
         self.contents[0xFF1C] = 0x9F; // NR32
         self.contents[0xFF1E] = 0xBF; // NR34
-        self.contents[0xFF20] = 0xFF; // NR41
-        self.contents[0xFF21] = 0x00; // NR42
-        self.contents[0xFF22] = 0x00; // NR43
-        self.contents[0xFF23] = 0xBF; // NR44
-        self.contents[0xFF24] = 0x77; // NR50
-        self.contents[0xFF25] = 0xF3; // NR51
-        self.contents[0xFF26] = 0xF1; // NR52
         
         // Interrupt registers (disabled after boot)
         self.contents[0xFF0F] = 0xE0; // IF - no interrupts pending
@@ -79,11 +279,32 @@ impl MemoryMap {
         self.contents[0xFF50] = 0x01; // Bootstrap ROM disabled
     }
     
-    /// Initialize VRAM for post-boot state
-    /// The boot ROM would normally initialize some tile data, but games are expected
-    /// to handle their own graphics loading
+    /// Initialize VRAM for post-boot state: the logo the boot ROM scrolled in is left
+    /// behind in tiles 1-25 of the 0x8000 area, which the post-boot LCDC selects
     fn init_post_boot_vram(&mut self) {
-        // VRAM is initialized to zero by default - games handle their own tile loading
+        // The header logo is decompressed two bytes per tile: each nibble becomes two rows
+        // with every bit doubled, on the low bitplane only
+        let vram = &mut self.ppu.vram;
+        for (i, &byte) in NINTENDO_LOGO.iter().enumerate() {
+            for (half, nibble) in [byte >> 4, byte & 0x0F].into_iter().enumerate() {
+                let row = (0..4).fold(0u8, |row, bit| if nibble & (1 << bit) != 0 { row | (0b11 << (bit * 2)) } else { row });
+                let offset = 0x0010 + i * 8 + half * 4;
+                vram[offset] = row;
+                vram[offset + 2] = row;
+            }
+        }
+        // The (R) follows as tile 25
+        for (i, &row) in REGISTERED_MARK_TILE.iter().enumerate() {
+            vram[0x0190 + i * 2] = row;
+        }
+        
+        // Two rows of 12 logo tiles, with the (R) at the end of the first
+        for (row, &start) in LOGO_TILE_MAP_ROWS.iter().enumerate() {
+            for column in 0..12 {
+                vram[start + column] = (row * 12 + column + 1) as u8;
+            }
+        }
+        vram[0x1910] = 25;
     }
     
     /// Disables the bootstrap ROM, allowing cartridge access to 0x0000-0x00FF
@@ -91,14 +312,275 @@ impl MemoryMap {
         self.bootstrap_enabled = false;
     }
     
+    /// Copies `bytes` into memory starting at `address`, bypassing MBC routing.
+    /// The patch lands in the bootstrap area while it is enabled and in the
+    /// fake cartridge ROM when no cartridge is loaded, so tests can place code
+    /// without a ROM file, e.g. `install_rom_patch(0x0000, &[0x3E, 0x42, 0x76])`.
+    #[allow(dead_code)] // Public API method
+    pub fn install_rom_patch(&mut self, address: u16, bytes: &[u8]) {
+        let mut addr = address;
+        for &value in bytes {
+            self.contents[addr as usize] = value;
+            addr = addr.wrapping_add(1);
+        }
+    }
+    
+    /// Like `install_rom_patch`, but reads in the patched range keep returning the
+    /// patch after the bootstrap ROM is disabled or a cartridge is loaded. The patch is
+    /// read-only: writes there still reach the cartridge's MBC registers.
+    #[allow(dead_code)] // Public API method
+    pub fn install_bootstrap_patch(&mut self, address: u16, bytes: &[u8]) {
+        if bytes.is_empty() {
+            return;
+        }
+        self.install_rom_patch(address, bytes);
+        
+        let end = address.wrapping_add((bytes.len() - 1) as u16);
+        if end < address {
+            // Wrapped past 0xFFFF
+            self.patched_ranges.push((address, 0xFFFF));
+            self.patched_ranges.push((0x0000, end));
+        } else {
+            self.patched_ranges.push((address, end));
+        }
+    }
+    
+    fn is_patched(&self, addr: u16) -> bool {
+        self.patched_ranges.iter().any(|&(start, end)| (start..=end).contains(&addr))
+    }
+    
     pub fn load_cartridge(&mut self, path: &Path) {
-        let cart = Cart::new(path);
+        self.insert_cartridge(Cart::new(path));
+    }
+
+    /// Loads a cartridge from ROM bytes already in memory, e.g. a synthetic test ROM
+    pub fn load_cartridge_from_bytes(&mut self, data: Vec<u8>) {
+        self.insert_cartridge(Cart::from_bytes(data));
+    }
+
+    fn insert_cartridge(&mut self, cart: Cart) {
         #[cfg(debug_assertions)]
-        println!("Cartridge loaded: {}", cart.get_title());
+        if crate::rgb::diagnostics::debug_output() {
+            println!("Cartridge loaded: {}", cart.get_title());
+        }
         self.cart = Some(cart);
+        #[cfg(debug_assertions)]
+        if crate::rgb::diagnostics::debug_output() {
+            print!("{}", self.memory_map_report());
+        }
+    }
+
+    /// One line per memory region: its address range, up to 8 bytes from the first
+    /// non-zero one and a short summary, for debugging ROM loading. VRAM and OAM are
+    /// read directly so the PPU mode can't hide them.
+    #[allow(dead_code)] // Public API method
+    pub fn memory_map_report(&self) -> String {
+        let bus = |addr: u16| self.peek(addr);
+        let vram = |addr: u16| self.ppu.vram[(addr - 0x8000) as usize];
+        let oam = |addr: u16| self.ppu.oam[(addr - 0xFE00) as usize];
+        
+        let (rom_summary, ram_summary) = match &self.cart {
+            Some(cart) => (
+                format!("{} bytes, title: \"{}\"", cart.rom().len(), cart.get_title()),
+                format!("{} bytes", cart.ram_size_from_header()),
+            ),
+            None => ("no cartridge".to_string(), "no cartridge".to_string()),
+        };
+        let tiles_used = self.ppu.vram[..0x1800].chunks(16).filter(|tile| tile.iter().any(|&b| b != 0)).count();
+        let sprites_used = self.ppu.oam.chunks(4).filter(|sprite| sprite.iter().any(|&b| b != 0)).count();
+        let io_summary = format!("LCDC={:02X} STAT={:02X} LY={:02X} IF={:02X} IE={:02X}",
+            self.peek(0xFF40), self.peek(0xFF41), self.peek(0xFF44), self.peek(0xFF0F), self.peek(0xFFFF));
+        
+        let mut report = String::new();
+        let mut line = |start: u16, end: u16, name: &str, read: &dyn Fn(u16) -> u8, summary: String| {
+            report.push_str(&format!("[0x{:04X}-0x{:04X}] {}: {} ({})\n", start, end, name, preview_bytes(start, end, read), summary));
+        };
+        line(0x0000, 0x3FFF, "ROM Bank 0", &bus, rom_summary);
+        line(0x4000, 0x7FFF, "ROM Bank N", &bus, "switchable".to_string());
+        line(0x8000, 0x97FF, "VRAM Tiles", &vram, format!("{} of 384 tiles used", tiles_used));
+        line(0x9800, 0x9FFF, "VRAM Tile Maps", &vram, "two 32x32 maps".to_string());
+        line(0xA000, 0xBFFF, "External RAM", &bus, ram_summary);
+        line(0xC000, 0xDFFF, "WRAM", &bus, "8192 bytes".to_string());
+        line(0xFE00, 0xFE9F, "OAM", &oam, format!("{} of 40 sprites set", sprites_used));
+        line(0xFF00, 0xFF7F, "I/O", &bus, io_summary);
+        line(HRAM_START, HRAM_END, "HRAM", &bus, format!("{} bytes", HRAM_SIZE));
+        report
+    }
+
+    /// The loaded cartridge's ROM, for hashing and identification
+    pub fn get_cart_rom(&self) -> Option<&[u8]> {
+        self.cart.as_ref().map(|cart| cart.rom())
+    }
+
+    pub fn get_cart_header(&self) -> Option<CartridgeHeader> {
+        self.cart.as_ref().map(|cart| cart.get_header())
+    }
+
+    /// Whether the loaded cartridge's global checksum matched, or None without a cartridge
+    pub fn cart_checksum_valid(&self) -> Option<bool> {
+        self.cart.as_ref().map(|cart| cart.checksum_valid())
+    }
+
+    /// Whether the loaded cartridge's header checksum matched, or None without a cartridge
+    pub fn cart_header_checksum_valid(&self) -> Option<bool> {
+        self.cart.as_ref().map(|cart| cart.header_checksum_valid())
+    }
+
+    pub fn add_watchpoint(&mut self, address: u16, kind: WatchKind) {
+        self.watchpoints.retain(|wp| wp.address != address);
+        self.watchpoints.push(Watchpoint { address, kind });
+    }
+    
+    #[allow(dead_code)] // Public API method
+    pub fn remove_watchpoint(&mut self, address: u16) {
+        self.watchpoints.retain(|wp| wp.address != address);
+    }
+    
+    #[allow(dead_code)] // Public API method
+    pub fn get_watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+    
+    /// Starts recording the last `size` reads and writes, discarding any earlier log
+    pub fn enable_access_log(&mut self, size: usize) {
+        self.access_log_size = size;
+        *self.access_log.get_mut() = Some(VecDeque::with_capacity(size));
+    }
+
+    /// Tags subsequent accesses in the log with the given instruction address
+    pub fn set_access_pc(&mut self, pc: u16) {
+        self.access_pc = pc;
+    }
+
+    /// (address, value) for each HRAM byte, read without side effects
+    #[allow(dead_code)] // Public API method
+    pub fn iter_hram(&self) -> impl Iterator<Item = (u16, u8)> + '_ {
+        self.hram.iter().enumerate().map(|(offset, &value)| (HRAM_START + offset as u16, value))
+    }
+
+    /// The logged accesses, oldest first (empty when the log is disabled). Returns a
+    /// copy because the log lives behind a RefCell.
+    pub fn get_access_log(&self) -> Vec<MemAccess> {
+        match self.access_log.borrow().as_ref() {
+            Some(log) => log.iter().copied().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    fn log_access(&self, kind: WatchKind, addr: u16, val: u8) {
+        let mut access_log = self.access_log.borrow_mut();
+        let Some(log) = access_log.as_mut() else {
+            return;
+        };
+        if log.len() >= self.access_log_size {
+            log.pop_front();
+        }
+        if self.access_log_size > 0 {
+            log.push_back(MemAccess { kind, addr, val, pc: self.access_pc });
+        }
+    }
+
+    /// True when KEY1 bit 0 requests a speed switch on the next STOP
+    pub fn speed_switch_armed(&self) -> bool {
+        self.key1 & KEY1_PREPARE != 0
+    }
+
+    /// Records the speed a STOP switched to in KEY1 bit 7 and clears the request
+    pub fn complete_speed_switch(&mut self, double_speed: bool) {
+        self.key1 = if double_speed { KEY1_DOUBLE_SPEED } else { 0 };
+    }
+
+    /// Returns the first watchpoint hit since the last call and clears it
+    pub fn take_watchpoint_hit(&mut self) -> Option<(u16, u8, WatchKind)> {
+        self.watchpoint_hit.take()
+    }
+    
+    /// Captures RAM and the PPU registers for the debugger's undo stack
+    pub fn snapshot(&self) -> MemorySnapshot {
+        MemorySnapshot {
+            contents: Box::new(self.contents),
+            hram: self.hram.to_vec(),
+            ppu_registers: SNAPSHOT_PPU_REGISTERS.iter().map(|&addr| (addr, self.ppu.read_register(addr))).collect(),
+        }
+    }
+    
+    /// Puts back a snapshot's RAM. The PPU registers go through the PPU's register writes,
+    /// so read-only state like LY and the STAT mode and LYC flags follows the PPU, not the snapshot.
+    pub fn restore_from_snapshot(&mut self, snapshot: &MemorySnapshot) {
+        self.contents = *snapshot.contents;
+        self.hram.copy_from_slice(&snapshot.hram);
+        for &(addr, value) in &snapshot.ppu_registers {
+            self.ppu.write_register(addr, value);
+        }
+    }
+    
+    /// Starts recording every write as (address, value), discarding any earlier log
+    #[allow(dead_code)] // Public API method
+    pub fn enable_write_log(&mut self) {
+        self.write_log = Some(Vec::new());
+    }
+    
+    /// Returns every (address, value) written since the last call and clears the log.
+    /// Empty when the log was never enabled.
+    #[allow(dead_code)] // Public API method
+    pub fn take_write_log(&mut self) -> Vec<(u16, u8)> {
+        match self.write_log.as_mut() {
+            Some(log) => std::mem::take(log),
+            None => Vec::new(),
+        }
+    }
+    
+    /// How often `addr` has been read while `track_accesses` was set
+    #[cfg(feature = "memory_profiling")]
+    #[allow(dead_code)] // Public API method
+    pub fn read_count(&self, addr: u16) -> u64 {
+        self.read_counts.borrow()[addr as usize]
+    }
+
+    /// How often `addr` has been written while `track_accesses` was set
+    #[cfg(feature = "memory_profiling")]
+    #[allow(dead_code)] // Public API method
+    pub fn write_count(&self, addr: u16) -> u64 {
+        self.write_counts[addr as usize]
+    }
+
+    /// The `n` most read addresses as (address, count), most read first
+    #[cfg(feature = "memory_profiling")]
+    pub fn hot_reads(&self, n: usize) -> Vec<(u16, u64)> {
+        hottest(&self.read_counts.borrow(), n)
+    }
+
+    /// The `n` most written addresses as (address, count), most written first
+    #[cfg(feature = "memory_profiling")]
+    pub fn hot_writes(&self, n: usize) -> Vec<(u16, u64)> {
+        hottest(&self.write_counts, n)
+    }
+
+    fn check_watchpoint(&self, addr: u16, val: u8, access: WatchKind) {
+        // Fast path - this runs on every memory access
+        if self.watchpoints.is_empty() {
+            return;
+        }
+        
+        let matched = self.watchpoints.iter()
+            .any(|wp| wp.address == addr && wp.kind.matches(access));
+        
+        // Keep the first hit so the earliest access in an instruction is reported
+        if matched && self.watchpoint_hit.get().is_none() {
+            self.watchpoint_hit.set(Some((addr, val, access)));
+        }
     }
 
     pub fn write(&mut self, addr: u16, val: u8) {
+        self.check_watchpoint(addr, val, WatchKind::Write);
+        self.log_access(WatchKind::Write, addr, val);
+        if let Some(log) = self.write_log.as_mut() {
+            log.push((addr, val));
+        }
+        #[cfg(feature = "memory_profiling")]
+        if self.track_accesses {
+            self.write_counts[addr as usize] += 1;
+        }
         
         match addr {
             // Cartridge ROM area (0x0000-0x7FFF) - handle MBC writes
@@ -111,14 +593,30 @@ impl MemoryMap {
             0xFF00 => {
                 self.joypad.write_register(val);
             }
+            // Serial Registers (0xFF01-0xFF02)
+            0xFF01..=0xFF02 => {
+                self.serial.write_register(addr, val);
+            }
             // Timer Registers (0xFF04-0xFF07)
             0xFF04..=0xFF07 => {
+                if addr == 0xFF04 {
+                    // Resetting DIV also resets the bit that clocks the APU frame sequencer
+                    self.apu.reset_frame_sequencer_timer();
+                }
                 self.timer.write_register(addr, val);
             }
+            // Sound Channels 1, 3 and 4, control registers and wave RAM
+            0xFF10..=0xFF14 | 0xFF1A..=0xFF1E | 0xFF20..=0xFF23 | 0xFF24..=0xFF26 | 0xFF30..=0xFF3F => {
+                self.apu.write_register(addr, val);
+            }
+            // Speed switch: only the prepare bit is writable
+            KEY1_ADDR => {
+                self.key1 = (self.key1 & KEY1_DOUBLE_SPEED) | (val & KEY1_PREPARE);
+            }
             // DMA register (0xFF46) - OAM DMA transfer
-            0xFF46 => {
+            DMA_ADDR => {
                 #[cfg(debug_assertions)]
-                {
+                if crate::rgb::diagnostics::debug_output() {
                     static mut DMA_COUNT: u32 = 0;
                     unsafe {
                         DMA_COUNT += 1;
@@ -128,40 +626,10 @@ impl MemoryMap {
                     }
                 }
                 
-                // Perform DMA transfer: copy 160 bytes from (val * 0x100) to OAM
-                let source_addr = (val as u16) << 8; // val * 0x100
-                for i in 0..160 {
-                    let source_byte = self.read(source_addr + i);
-                    self.ppu.write_oam(0xFE00 + i, source_byte);
-                }
-                
-                // Debug first few sprites after DMA transfer and source data
-                #[cfg(debug_assertions)]
-                {
-                    static mut DMA_SPRITE_DEBUG_COUNT: u32 = 0;
-                    unsafe {
-                        DMA_SPRITE_DEBUG_COUNT += 1;
-                        if DMA_SPRITE_DEBUG_COUNT <= 5 {
-                            eprintln!("DMA #{}: Source data at 0x{:04X}:", DMA_SPRITE_DEBUG_COUNT, source_addr);
-                            for i in 0..12 { // First 12 bytes (3 sprites worth)
-                                let source_byte = self.read(source_addr + i);
-                                eprint!(" {:02X}", source_byte);
-                                if (i + 1) % 4 == 0 { eprintln!(); }
-                            }
-                            eprintln!("DMA #{}: First 3 sprites after transfer:", DMA_SPRITE_DEBUG_COUNT);
-                            for sprite_idx in 0..3 {
-                                let sprite_addr = sprite_idx * 4;
-                                let y = self.ppu.read_oam(0xFE00 + sprite_addr);
-                                let x = self.ppu.read_oam(0xFE00 + sprite_addr + 1);
-                                let tile = self.ppu.read_oam(0xFE00 + sprite_addr + 2);
-                                let flags = self.ppu.read_oam(0xFE00 + sprite_addr + 3);
-                                eprintln!("  Sprite {}: pos=({},{}), tile=0x{:02X}, flags=0x{:02X}", 
-                                    sprite_idx, x, y, tile, flags);
-                            }
-                        }
-                    }
-                }
-                
+                // Start copying 160 bytes from (val * 0x100) to OAM, one byte every 4 cycles
+                self.dma_source = (val as u16) << 8; // val * 0x100
+                self.dma_progress = Some(0);
+                self.dma_cycles = 0;
                 self.contents[addr as usize] = val;
             }
             // PPU Registers (0xFF40-0xFF4B) excluding DMA
@@ -178,10 +646,18 @@ impl MemoryMap {
                     cart.write_ram(addr, val);
                 }
             }
+            // ECHO RAM (0xE000-0xFDFF) - mirror of 0xC000-0xDDFF
+            0xE000..=0xFDFF => {
+                if !self.prohibit_echo_ram {
+                    self.contents[(addr - 0x2000) as usize] = val;
+                }
+            }
             // OAM (0xFE00-0xFE9F)
             0xFE00..=0xFE9F => {
                 self.ppu.write_oam(addr, val);
             }
+            // Unusable area (0xFEA0-0xFEFF) - writes are ignored
+            0xFEA0..=0xFEFF => {}
             // Bootstrap disable register
             0xFF50 => {
                 if val != 0 {
@@ -196,8 +672,13 @@ impl MemoryMap {
                 self.contents[addr as usize] = val | 0xE0; // Upper 3 bits always set
             }
             0xFFFF => {
-                // IE register - Interrupt Enable
-                self.contents[addr as usize] = val;
+                // IE register - Interrupt Enable; only the 5 interrupt bits exist, so the
+                // unused upper 3 bits are dropped and read back as 0
+                self.contents[addr as usize] = val & 0x1F;
+            }
+            // High RAM (0xFF80-0xFFFE)
+            HRAM_START..=HRAM_END => {
+                self.hram[(addr - HRAM_START) as usize] = val;
             }
             // Regular memory
             _ => {
@@ -207,7 +688,89 @@ impl MemoryMap {
     }
 
     pub fn read(&self, addr: u16) -> u8 {
-        let result = match addr {
+        let result = self.peek(addr);
+        
+        self.check_watchpoint(addr, result, WatchKind::Read);
+        self.log_access(WatchKind::Read, addr, result);
+        #[cfg(feature = "memory_profiling")]
+        if self.track_accesses {
+            self.read_counts.borrow_mut()[addr as usize] += 1;
+        }
+        
+        result
+    }
+
+    /// Reads like the CPU would, but without triggering watchpoints or logging the access
+    pub fn peek(&self, addr: u16) -> u8 {
+        if self.dma_blocks(addr) { self.dma_current_byte } else { self.bus_read(addr) }
+    }
+
+    /// True while an OAM DMA transfer is copying bytes
+    pub fn dma_in_progress(&self) -> bool {
+        self.dma_progress.is_some()
+    }
+
+    // During DMA the CPU only reaches HRAM and the I/O registers; everything below 0xFE00
+    // reads the byte DMA is moving. The PPU reads VRAM and OAM directly, so it's unaffected.
+    fn dma_blocks(&self, addr: u16) -> bool {
+        self.dma_in_progress() && addr < 0xFE00
+    }
+
+    /// Advances a running OAM DMA transfer
+    #[allow(dead_code)] // Public API method
+    pub fn step_dma(&mut self, cycles: u16) {
+        let Some(mut copied) = self.dma_progress else {
+            return;
+        };
+        self.dma_cycles += cycles;
+        while self.dma_cycles >= DMA_CYCLES_PER_BYTE && copied < DMA_LENGTH {
+            self.dma_cycles -= DMA_CYCLES_PER_BYTE;
+            let byte = self.bus_read(self.dma_source + copied);
+            self.ppu.write_oam(0xFE00 + copied, byte);
+            self.dma_current_byte = byte;
+            copied += 1;
+        }
+        if copied < DMA_LENGTH {
+            self.dma_progress = Some(copied);
+            return;
+        }
+        self.dma_progress = None;
+        
+        // Debug first few sprites after DMA transfer and source data
+        #[cfg(debug_assertions)]
+        if crate::rgb::diagnostics::debug_output() {
+            static mut DMA_SPRITE_DEBUG_COUNT: u32 = 0;
+            unsafe {
+                DMA_SPRITE_DEBUG_COUNT += 1;
+                if DMA_SPRITE_DEBUG_COUNT <= 5 {
+                    eprintln!("DMA #{}: Source data at 0x{:04X}:", DMA_SPRITE_DEBUG_COUNT, self.dma_source);
+                    for i in 0..12 { // First 12 bytes (3 sprites worth)
+                        let source_byte = self.bus_read(self.dma_source + i);
+                        eprint!(" {:02X}", source_byte);
+                        if (i + 1) % 4 == 0 { eprintln!(); }
+                    }
+                    eprintln!("DMA #{}: First 3 sprites after transfer:", DMA_SPRITE_DEBUG_COUNT);
+                    for sprite_idx in 0..3 {
+                        let sprite_addr = sprite_idx * 4;
+                        let y = self.ppu.read_oam(0xFE00 + sprite_addr);
+                        let x = self.ppu.read_oam(0xFE00 + sprite_addr + 1);
+                        let tile = self.ppu.read_oam(0xFE00 + sprite_addr + 2);
+                        let flags = self.ppu.read_oam(0xFE00 + sprite_addr + 3);
+                        eprintln!("  Sprite {}: pos=({},{}), tile=0x{:02X}, flags=0x{:02X}", 
+                            sprite_idx, x, y, tile, flags);
+                    }
+                }
+            }
+        }
+    }
+
+    // The value at an address as seen by the memory bus, without watchpoints or logging
+    fn bus_read(&self, addr: u16) -> u8 {
+        match addr {
+            // Patches installed with install_bootstrap_patch take precedence over ROM
+            0x0000..=0x7FFF if self.is_patched(addr) => {
+                self.contents[addr as usize]
+            }
             // Bootstrap ROM area (0x0000-0x00FF) - only accessible when bootstrap is enabled
             0x0000..=0x00FF if self.bootstrap_enabled => {
                 self.contents[addr as usize]
@@ -223,10 +786,16 @@ impl MemoryMap {
             }
             // Joypad Register (0xFF00)
             0xFF00 => self.joypad.read_register(),
+            // Serial Registers (0xFF01-0xFF02)
+            0xFF01..=0xFF02 => self.serial.read_register(addr),
+            // Sound Channels 1, 3 and 4, control registers and wave RAM
+            0xFF10..=0xFF14 | 0xFF1A..=0xFF1E | 0xFF20..=0xFF23 | 0xFF24..=0xFF26 | 0xFF30..=0xFF3F => self.apu.read_register(addr),
             // Timer Registers (0xFF04-0xFF07)
             0xFF04..=0xFF07 => self.timer.read_register(addr),
             // PPU Registers (0xFF40-0xFF4B)
             0xFF40..=0xFF4B => self.ppu.read_register(addr),
+            // Speed switch (unused bits read as 1)
+            KEY1_ADDR => self.key1 | 0x7E,
             // VRAM (0x8000-0x9FFF)
             0x8000..=0x9FFF => self.ppu.read_vram(addr),
             // Cartridge RAM area (0xA000-0xBFFF)
@@ -237,8 +806,14 @@ impl MemoryMap {
                     0xFF
                 }
             }
+            // ECHO RAM (0xE000-0xFDFF) - mirror of 0xC000-0xDDFF
+            0xE000..=0xFDFF if self.prohibit_echo_ram => 0xFF,
+            0xE000..=0xFDFF => self.contents[(addr - 0x2000) as usize],
             // OAM (0xFE00-0xFE9F)
             0xFE00..=0xFE9F => self.ppu.read_oam(addr),
+            // Unusable area (0xFEA0-0xFEFF)
+            0xFEA0..=0xFEFF if self.accurate_unused_memory => self.ppu.read_unused_oam_area(addr),
+            0xFEA0..=0xFEFF => 0xFF,
             // Interrupt registers
             0xFF0F => {
                 // IF register - return with upper 3 bits set
@@ -248,18 +823,65 @@ impl MemoryMap {
                 // IE register
                 self.contents[addr as usize]
             }
+            // High RAM (0xFF80-0xFFFE)
+            HRAM_START..=HRAM_END => self.hram[(addr - HRAM_START) as usize],
             // Regular memory
             _ => {
                 self.contents[addr as usize]
             }
-        };
+        }
+    }
+
+    /// Reads `len` bytes starting at `start`, wrapping past 0xFFFF. Like `peek`, this
+    /// doesn't trigger watchpoints or show up in the access log, so debugger views can use it.
+    /// WRAM runs are copied as a slice.
+    pub fn read_range(&self, start: u16, len: u16) -> Vec<u8> {
+        let mut data = Vec::with_capacity(len as usize);
+        let mut addr = start;
+        let mut remaining = len as usize;
         
+        while remaining > 0 {
+            if (0xC000..=0xDFFF).contains(&addr) && !self.dma_in_progress() {
+                // WRAM has no side effects, so the contiguous part can be copied directly
+                let run = remaining.min(0xE000 - addr as usize);
+                data.extend_from_slice(&self.contents[addr as usize..addr as usize + run]);
+                addr = addr.wrapping_add(run as u16);
+                remaining -= run;
+            } else {
+                data.push(self.peek(addr));
+                addr = addr.wrapping_add(1);
+                remaining -= 1;
+            }
+        }
         
-        result
+        data
+    }
+    
+    /// Writes `data` starting at `start`, wrapping past 0xFFFF.
+    /// With a cartridge loaded, writes to 0x0000-0x7FFF patch the mapped ROM instead of
+    /// reaching the MBC registers, so this can be used for bulk ROM patching. Without one
+    /// they are dropped like any ROM write; use `install_rom_patch` to patch that case.
+    #[allow(dead_code)] // Public API method
+    pub fn write_range(&mut self, start: u16, data: &[u8]) {
+        let mut addr = start;
+        for &value in data {
+            match (addr, self.cart.as_mut()) {
+                (0x0000..=0x00FF, _) if self.bootstrap_enabled => self.contents[addr as usize] = value,
+                (0x0000..=0x7FFF, Some(cart)) => cart.patch_rom(addr, value),
+                _ => self.write(addr, value),
+            }
+            addr = addr.wrapping_add(1);
+        }
     }
 
-    pub fn load_bootstrap(&mut self) {
-        let buf = fs::read("test-roms/dmg_boot.bin")
+    /// True once `load_bootstrap` has filled the bootstrap area
+    #[allow(dead_code)] // Public API method
+    pub fn is_bootstrap_loaded(&self) -> bool {
+        self.bootstrap_loaded
+    }
+    
+    pub fn load_bootstrap(&mut self, path: &Path) {
+        let buf = fs::read(path)
             .expect("expected valid file path for bootstrap rom");
         assert_eq!(
             buf.len(),
@@ -268,6 +890,7 @@ impl MemoryMap {
         );
 
         self.contents[0..(255 + 1)].copy_from_slice(&buf);
+        self.bootstrap_loaded = true;
         
         // Load fake cartridge header with Nintendo logo so bootstrap ROM has something to display
         self.load_fake_cartridge_header();
@@ -276,16 +899,8 @@ impl MemoryMap {
     fn load_fake_cartridge_header(&mut self) {
         // Only load fake header if no cartridge is loaded
         if self.cart.is_none() {
-            // Nintendo logo data that the bootstrap ROM expects at 0x0104-0x0133
-            // This is the compressed logo data from a real Game Boy cartridge
-            let nintendo_logo: [u8; 48] = [
-                0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
-                0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
-                0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E
-            ];
-            
-            // Copy logo data to cartridge header location
-            for (i, &byte) in nintendo_logo.iter().enumerate() {
+            // Copy the logo the bootstrap ROM expects to the cartridge header location
+            for (i, &byte) in NINTENDO_LOGO.iter().enumerate() {
                 self.contents[0x0104 + i] = byte;
             }
             
@@ -305,7 +920,22 @@ impl MemoryMap {
         &mut self.ppu
     }
 
-    pub fn step_ppu(&mut self, cycles: u16) -> (bool, bool) {
+    /// Steps OAM DMA, the timer, serial port, APU and PPU by `cycles` CPU cycles and returns
+    /// the interrupts they raised. In CGB double speed (`speed_multiplier` 2) the APU and PPU
+    /// only advance half as far, since they don't speed up with the CPU.
+    pub fn step(&mut self, cycles: u16, speed_multiplier: u8) -> StepInterrupts {
+        self.step_dma(cycles);
+        let timer = self.step_timer(cycles);
+        let serial = self.step_serial(cycles);
+        
+        let normal_speed_cycles = cycles / speed_multiplier as u16;
+        self.step_apu(normal_speed_cycles);
+        let (vblank, stat) = self.step_ppu(normal_speed_cycles);
+        
+        StepInterrupts { timer, serial, vblank, stat }
+    }
+
+    fn step_ppu(&mut self, cycles: u16) -> (bool, bool) {
         self.ppu.step(cycles);
         
         // Check for PPU interrupt flags and return them
@@ -320,14 +950,39 @@ impl MemoryMap {
         (vblank_interrupt, stat_interrupt)
     }
 
-    pub fn step_timer(&mut self, cycles: u16) -> bool {
+    fn step_timer(&mut self, cycles: u16) -> bool {
         self.timer.step(cycles)
     }
     
+    #[allow(dead_code)] // Public API method
+    pub fn step_serial(&mut self, cycles: u16) -> bool {
+        self.serial.step(cycles)
+    }
+    
+    fn step_apu(&mut self, cycles: u16) {
+        self.apu.step(cycles);
+    }
+    
+    /// Returns the audio samples generated since the last call (mono, 44100 Hz)
+    pub fn drain_audio_samples(&mut self) -> Vec<i16> {
+        self.apu.drain_samples()
+    }
+    
+    /// Connects a device (e.g. the Game Boy Printer) to the link port
+    pub fn attach_serial_device(&mut self, device: Box<dyn SerialDevice>) {
+        self.serial.attach_device(device);
+    }
+    
     pub fn update_joypad(&mut self, buttons: super::joypad::JoypadButtons) -> bool {
         self.joypad.update_buttons(buttons)
     }
     
+    /// Controllers the SGB reports after an MLT_REQ; always 1 without SGB support
+    #[allow(dead_code)] // Public API method
+    pub fn sgb_player_count(&self) -> u8 {
+        self.joypad.sgb_player_count()
+    }
+
     pub fn joypad_interrupt_requested(&self) -> bool {
         self.joypad.any_button_pressed()
     }
@@ -0,0 +1,83 @@
+// VRAM/OAM dump files
+// Raw snapshots of PPU memory with a small header so offline tools can interpret them
+
+use super::ppu::Ppu;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub const VRAM_MAGIC: [u8; 4] = *b"VRAM";
+pub const OAM_MAGIC: [u8; 4] = *b"OAM\0";
+pub const HEADER_SIZE: usize = 8;
+
+/// A dump file: 4-byte magic, frame number (u16 little-endian), LCDC and BGP,
+/// followed by the raw memory contents
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryDump {
+    pub magic: [u8; 4],
+    pub frame: u16, // Wraps after 65535 frames
+    pub lcdc: u8,
+    pub bgp: u8,
+    pub data: Vec<u8>,
+}
+
+impl MemoryDump {
+    /// Snapshots the 8 KB of VRAM
+    pub fn vram(ppu: &Ppu, frame: u16) -> Self {
+        Self::from_ppu(VRAM_MAGIC, ppu, frame, ppu.get_vram())
+    }
+
+    /// Snapshots the 160-byte OAM
+    pub fn oam(ppu: &Ppu, frame: u16) -> Self {
+        Self::from_ppu(OAM_MAGIC, ppu, frame, ppu.get_oam())
+    }
+
+    fn from_ppu(magic: [u8; 4], ppu: &Ppu, frame: u16, data: &[u8]) -> Self {
+        Self {
+            magic,
+            frame,
+            lcdc: ppu.lcdc.to_byte(),
+            bgp: ppu.bgp,
+            data: data.to_vec(),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + self.data.len());
+        bytes.extend_from_slice(&self.magic);
+        bytes.extend_from_slice(&self.frame.to_le_bytes());
+        bytes.push(self.lcdc);
+        bytes.push(self.bgp);
+        bytes.extend_from_slice(&self.data);
+        bytes
+    }
+
+    /// Parses a dump, returning None if the header is truncated or the magic unknown
+    #[allow(dead_code)] // Public API method
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < HEADER_SIZE {
+            return None;
+        }
+        let magic: [u8; 4] = bytes[0..4].try_into().ok()?;
+        if magic != VRAM_MAGIC && magic != OAM_MAGIC {
+            return None;
+        }
+        Some(Self {
+            magic,
+            frame: u16::from_le_bytes([bytes[4], bytes[5]]),
+            lcdc: bytes[6],
+            bgp: bytes[7],
+            data: bytes[HEADER_SIZE..].to_vec(),
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    #[allow(dead_code)] // Public API method
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        Self::from_bytes(&bytes).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "not a VRAM or OAM dump"))
+    }
+}
@@ -7,4 +7,33 @@ pub mod timer;
 pub mod joypad;
 pub mod instructions;
 pub mod instruction_timing;
-pub mod execution;
\ No newline at end of file
+pub mod execution;
+pub mod frame_timer;
+pub mod config;
+pub mod diagnostics;
+pub mod serial;
+pub mod printer;
+pub mod apu;
+pub mod rom_db;
+pub mod memory_dump;
+pub mod audio_sync;
+pub mod gamepad;
+pub mod profiler;
+pub mod trace_ring;
+pub mod state_json;
+
+/// Which Game Boy is being emulated. CGB-specific hardware should check this.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HardwareModel {
+    #[default]
+    Dmg,
+    Cgb,
+}
+
+impl HardwareModel {
+    /// The model a cartridge asks for through its CGB flag at 0x0143: bit 7 set
+    /// (0x80 = CGB enhanced, 0xC0 = CGB only) selects CGB
+    pub fn from_cgb_flag(cgb_flag: u8) -> Self {
+        if cgb_flag & 0x80 != 0 { HardwareModel::Cgb } else { HardwareModel::Dmg }
+    }
+}
\ No newline at end of file
@@ -3,6 +3,9 @@
 
 #[cfg(debug_assertions)]
 use log::debug;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
 
 // PPU Constants
 pub const SCREEN_WIDTH: usize = 160;
@@ -13,6 +16,47 @@ pub const VRAM_SIZE: usize = 0x2000; // 8KB
 pub const OAM_SIZE: usize = 160; // 160 bytes
 pub const MAX_SPRITES: usize = 40;
 pub const MAX_SPRITES_PER_LINE: usize = 10;
+pub const BG_MAP_SIZE: usize = 256; // Full background map is 256x256 pixels
+pub const BG_MAP_VIEWPORT_MARKER: u8 = 4; // Outline value used by render_full_background_map
+
+// Display colors for shades 0-3 (lightest to darkest) as RGBA, in the original green tint
+pub const DMG_SHADES: [[f32; 4]; 4] = [
+    [0.616, 0.733, 0.059, 1.0],
+    [0.541, 0.675, 0.059, 1.0],
+    [0.188, 0.384, 0.188, 1.0],
+    [0.063, 0.247, 0.063, 1.0],
+];
+// The same shades through the LCD's ~2.2 gamma: the middle two are spaced evenly in emitted
+// light between the lightest and darkest, rather than evenly in color value
+pub const DMG_SHADES_CORRECTED: [[f32; 4]; 4] = [
+    [0.616, 0.733, 0.059, 1.0],
+    [0.513, 0.622, 0.060, 1.0],
+    [0.376, 0.480, 0.062, 1.0],
+    [0.063, 0.247, 0.063, 1.0],
+];
+
+/// RGBA display color of a shade, with or without gamma correction
+pub fn shade_rgba(shade: u8, corrected: bool) -> [f32; 4] {
+    let shades = if corrected { &DMG_SHADES_CORRECTED } else { &DMG_SHADES };
+    shades[(shade & 0x03) as usize]
+}
+
+// Decode one 8-pixel row of a 2bpp tile into color indices (0-3)
+pub fn decode_tile_row(vram: &[u8], tile_addr: usize, row: usize) -> [u8; 8] {
+    let mut pixels = [0u8; 8];
+    let byte_offset = tile_addr + row * 2;
+    if byte_offset + 1 >= vram.len() {
+        return pixels;
+    }
+
+    let low_byte = vram[byte_offset];
+    let high_byte = vram[byte_offset + 1];
+    for (x, pixel) in pixels.iter_mut().enumerate() {
+        let bit = 7 - x;
+        *pixel = (((high_byte >> bit) & 1) << 1) | ((low_byte >> bit) & 1);
+    }
+    pixels
+}
 
 // PPU Register Addresses
 pub const LCDC_ADDR: u16 = 0xFF40; // LCD Control
@@ -38,6 +82,8 @@ pub enum PpuMode {
 
 // PPU Timing (in CPU cycles) - Game Boy DMG specs
 pub const OAM_SCAN_CYCLES: u16 = 80;   // Mode 2: OAM scan
+const OAM_ROW_SCAN_CYCLES: u16 = 4;    // OAM scan reads one row every 4 dots
+const OAM_ROW_BYTES: usize = 8;        // Two 4-byte objects per OAM row
 pub const DRAWING_CYCLES: u16 = 172;   // Mode 3: Drawing (variable, but avg 172)
 pub const HBLANK_CYCLES: u16 = 204;    // Mode 0: H-Blank  
 pub const SCANLINE_CYCLES: u16 = 456;  // Total cycles per scanline (80+172+204)
@@ -121,6 +167,14 @@ impl StatFlags {
     }
 }
 
+// Which layer drew a screen pixel, with the background/window tile index or the sprite's OAM index
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PpuLayer {
+    Background(u8),
+    Window(u8),
+    Sprite(u8),
+}
+
 // Sprite Attributes (OAM Entry)
 #[derive(Debug, Clone, Copy)]
 pub struct Sprite {
@@ -128,6 +182,7 @@ pub struct Sprite {
     pub x: u8,          // X position
     pub tile: u8,       // Tile number
     pub flags: u8,      // Attributes
+    pub oam_index: u8,  // Position in OAM (0-39)
 }
 
 impl Sprite {
@@ -137,6 +192,7 @@ impl Sprite {
             x: bytes[1],
             tile: bytes[2],
             flags: bytes[3],
+            oam_index: 0,
         }
     }
 
@@ -157,10 +213,14 @@ pub struct Ppu {
     pub stat: StatFlags,
     pub scy: u8,  // Background scroll Y
     pub scx: u8,  // Background scroll X
+    // SCX written during Mode 3 as (new value, first screen column that uses it); the
+    // line being drawn keeps the old value to the left of that column
+    pub scx_pending_change: Option<(u8, u16)>,
     pub ly: u8,   // Current scanline
     pub lyc: u8,  // Scanline compare
     pub wy: u8,   // Window Y position
     pub wx: u8,   // Window X position
+    pub window_line_counter: u8, // Internal window line, advances only on lines the window is drawn
     pub bgp: u8,  // Background palette
     pub obp0: u8, // Object palette 0
     pub obp1: u8, // Object palette 1
@@ -170,6 +230,8 @@ pub struct Ppu {
     pub cycles: u16,
     pub frame_buffer: [u8; SCREEN_WIDTH * SCREEN_HEIGHT],
     pub scanline_sprites: Vec<Sprite>,
+    pub pixel_source: [PpuLayer; SCREEN_WIDTH], // Layer behind each pixel of the line being drawn
+    frame_pixel_sources: Vec<PpuLayer>, // pixel_source of every finished line, for get_color_at_screen_position
     
     // Interrupts
     pub vblank_interrupt: bool,
@@ -177,6 +239,9 @@ pub struct Ppu {
     
     // STAT interrupt edge detection
     prev_stat_line: bool,
+
+    color_correction: bool, // Display shades through DMG_SHADES_CORRECTED
+    frames_rendered: u64, // Times LY wrapped from 153 back to 0
 }
 
 impl Ppu {
@@ -188,10 +253,12 @@ impl Ppu {
             stat: StatFlags::from_byte(0x00),
             scy: 0,
             scx: 0,
+            scx_pending_change: None,
             ly: 0,
             lyc: 0,
             wy: 0,
             wx: 0,
+            window_line_counter: 0,
             bgp: 0xFC, // Default palette
             obp0: 0xFF,
             obp1: 0xFF,
@@ -199,9 +266,13 @@ impl Ppu {
             cycles: 0,
             frame_buffer: [0; SCREEN_WIDTH * SCREEN_HEIGHT],
             scanline_sprites: Vec::with_capacity(MAX_SPRITES_PER_LINE),
+            pixel_source: [PpuLayer::Background(0); SCREEN_WIDTH],
+            frame_pixel_sources: vec![PpuLayer::Background(0); SCREEN_WIDTH * SCREEN_HEIGHT],
             vblank_interrupt: false,
             stat_interrupt: false,
             prev_stat_line: false,
+            color_correction: false,
+            frames_rendered: 0,
         }
     }
     
@@ -215,10 +286,12 @@ impl Ppu {
             stat: StatFlags::from_byte(0x00), // Mode 0 (H-Blank)
             scy: 0,
             scx: 0,
+            scx_pending_change: None,
             ly: 0,
             lyc: 0,
             wy: 0,
             wx: 0,
+            window_line_counter: 0,
             bgp: 0xFC, // Background palette (11-11-11-00)
             obp0: 0xFF, // Object palette 0 (all black)
             obp1: 0xFF, // Object palette 1 (all black)
@@ -226,9 +299,13 @@ impl Ppu {
             cycles: 0,
             frame_buffer: [0; SCREEN_WIDTH * SCREEN_HEIGHT],
             scanline_sprites: Vec::with_capacity(MAX_SPRITES_PER_LINE),
+            pixel_source: [PpuLayer::Background(0); SCREEN_WIDTH],
+            frame_pixel_sources: vec![PpuLayer::Background(0); SCREEN_WIDTH * SCREEN_HEIGHT],
             vblank_interrupt: false,
             stat_interrupt: false,
             prev_stat_line: false,
+            color_correction: false,
+            frames_rendered: 0,
         }
     }
 
@@ -258,6 +335,7 @@ impl Ppu {
                     if self.ly < SCREEN_HEIGHT as u8 {
                         self.render_scanline();
                     }
+                    self.mid_scanline_scroll_tick();
                     
                     self.set_mode(PpuMode::HBlank);
                     
@@ -280,6 +358,8 @@ impl Ppu {
                     // Reset to line 0 after line 153
                     if self.ly >= 154 {
                         self.ly = 0;
+                        self.frames_rendered += 1;
+                        self.window_line_counter = 0;
                         self.set_mode(PpuMode::OamScan);
                     }
                 }
@@ -287,14 +367,10 @@ impl Ppu {
                 _ => {
                     // Reset if something goes wrong
                     self.ly = 0;
+                    self.window_line_counter = 0;
                     self.set_mode(PpuMode::OamScan);
                 }
             }
-            
-            // Only check STAT interrupts when necessary (not every cycle)
-            if self.ly % 4 == 0 {
-                self.check_stat_interrupts();
-            }
         }
     }
 
@@ -341,6 +417,25 @@ impl Ppu {
         }
     }
 
+    /// Commits an SCX write that landed during Mode 3 once its line has been drawn, so the
+    /// following lines use the new value throughout
+    fn mid_scanline_scroll_tick(&mut self) {
+        if let Some((value, _)) = self.scx_pending_change.take() {
+            self.scx = value;
+        }
+    }
+
+    /// Frames completed since power-on or the last `reset_frame_counter`, counted each
+    /// time LY returns to 0 after VBlank. Frames with the LCD off aren't counted.
+    pub fn frames_rendered(&self) -> u64 {
+        self.frames_rendered
+    }
+
+    #[allow(dead_code)] // Public API method
+    pub fn reset_frame_counter(&mut self) {
+        self.frames_rendered = 0;
+    }
+
     fn handle_vblank(&mut self) -> bool {
         if self.cycles >= SCANLINE_CYCLES {
             self.cycles -= SCANLINE_CYCLES;
@@ -349,6 +444,7 @@ impl Ppu {
 
             if self.ly >= (SCREEN_HEIGHT as u8 + VBLANK_LINES) {
                 self.ly = 0;
+                self.window_line_counter = 0;
                 self.set_mode(PpuMode::OamScan);
             }
             true
@@ -359,12 +455,8 @@ impl Ppu {
 
 
     fn update_lyc_flag(&mut self) {
-        let lyc_match = self.ly == self.lyc;
-        self.stat.lyc_flag = lyc_match;
-        
-        if lyc_match && self.stat.lyc_interrupt {
-            self.stat_interrupt = true;
-        }
+        self.stat.lyc_flag = self.ly == self.lyc;
+        self.check_stat_interrupts();
     }
 
     fn scan_oam(&mut self) {
@@ -372,7 +464,7 @@ impl Ppu {
         
         if !self.lcdc.sprite_enable {
             #[cfg(debug_assertions)]
-            {
+            if crate::rgb::diagnostics::debug_output() {
                 static mut SPRITE_DISABLED_COUNT: u32 = 0;
                 unsafe {
                     SPRITE_DISABLED_COUNT += 1;
@@ -388,7 +480,7 @@ impl Ppu {
         
         // Debug all sprites in OAM to find invisible ones
         #[cfg(debug_assertions)]
-        {
+        if crate::rgb::diagnostics::debug_output() {
             static mut OAM_SCAN_DEBUG_COUNT: u32 = 0;
             static mut LAST_DEBUG_LY: u8 = 255;
             unsafe {
@@ -398,7 +490,7 @@ impl Ppu {
                     if OAM_SCAN_DEBUG_COUNT <= 20 && (self.ly == 20 || self.ly == 80 || self.ly == 120) { // Check multiple lines
                         let mut visible_sprites = 0;
                         let mut total_sprites = 0;
-                        for i in 0..10 { // Check first 10 sprites
+                        for i in 0..10 { // Only log the first 10 OAM entries
                             let sprite_addr = i * 4;
                             let sprite_data = [
                                 self.oam[sprite_addr],
@@ -421,6 +513,8 @@ impl Ppu {
             }
         }
         
+        // DMG picks the first MAX_SPRITES_PER_LINE sprites on the line in OAM order, whatever
+        // their X; X only decides which of the picked ones draws on top (see render_sprites_line)
         for i in 0..MAX_SPRITES {
             let sprite_addr = i * 4;
             let sprite_data = [
@@ -430,13 +524,13 @@ impl Ppu {
                 self.oam[sprite_addr + 3],
             ];
             
-            let sprite = Sprite::from_oam_bytes(&sprite_data);
+            let sprite = Sprite { oam_index: i as u8, ..Sprite::from_oam_bytes(&sprite_data) };
             
             // Check if sprite is on current scanline
             let sprite_y = sprite.y.wrapping_sub(16);
             if self.ly >= sprite_y && self.ly < sprite_y + sprite_height {
                 #[cfg(debug_assertions)]
-                {
+                if crate::rgb::diagnostics::debug_output() {
                     static mut SPRITE_FOUND_COUNT: u32 = 0;
                     unsafe {
                         SPRITE_FOUND_COUNT += 1;
@@ -470,7 +564,7 @@ impl Ppu {
 
         // Debug window enable/disable changes
         #[cfg(debug_assertions)]
-        {
+        if crate::rgb::diagnostics::debug_output() {
             static mut LAST_WINDOW_ENABLE: Option<bool> = None;
             static mut LAST_WY: Option<u8> = None;
             static mut LAST_WX: Option<u8> = None;
@@ -490,6 +584,9 @@ impl Ppu {
             }
         }
 
+        // Pixels no layer draws keep this source (color 0 from a disabled background)
+        self.pixel_source = [PpuLayer::Background(0); SCREEN_WIDTH];
+
         // Render background
         if self.lcdc.bg_enable {
             self.render_background_line(y);
@@ -507,6 +604,8 @@ impl Ppu {
         if self.lcdc.sprite_enable {
             self.render_sprites_line(y);
         }
+
+        self.frame_pixel_sources[y * SCREEN_WIDTH..(y + 1) * SCREEN_WIDTH].copy_from_slice(&self.pixel_source);
     }
 
     fn render_background_line(&mut self, y: usize) {
@@ -515,8 +614,15 @@ impl Ppu {
         let tile_y = scroll_y / TILE_SIZE;
         let pixel_y = scroll_y % TILE_SIZE;
 
+        // A mid-line SCX write takes effect from its column on
+        let (new_scx, change_column) = match self.scx_pending_change {
+            Some((value, column)) => (value, column as usize),
+            None => (self.scx, SCREEN_WIDTH),
+        };
+
         for x in 0..SCREEN_WIDTH {
-            let scroll_x = self.scx.wrapping_add(x as u8) as usize;
+            let scx = if x < change_column { self.scx } else { new_scx };
+            let scroll_x = scx.wrapping_add(x as u8) as usize;
             let tile_x = scroll_x / TILE_SIZE;
             let pixel_x = scroll_x % TILE_SIZE;
 
@@ -527,28 +633,13 @@ impl Ppu {
             let tile_index = (tile_y % 32) * TILES_PER_ROW + (tile_x % 32);
             let tile_id = self.vram[tile_map_addr + tile_index];
             
-            let tile_data_addr = if self.lcdc.bg_window_tiles {
-                // Unsigned addressing (0x8000-0x8FFF)
-                tile_id as usize * 16
-            } else {
-                // Signed addressing (0x8800-0x97FF) with base at 0x9000
-                // In signed mode, tile IDs 0x80-0xFF are treated as -128 to -1
-                let signed_tile_id = if tile_id >= 0x80 {
-                    (tile_id as i16) - 256
-                } else {
-                    tile_id as i16
-                };
-                let addr = (0x1000_i16 + signed_tile_id * 16) as usize;
-                
-                
-                addr
-            };
-            
+            let tile_data_addr = Self::bg_tile_data_addr(tile_id, self.lcdc.bg_window_tiles);
 
             let pixel_color = self.get_tile_pixel(tile_data_addr, pixel_x, pixel_y);
             let final_color = self.apply_palette(pixel_color, self.bgp);
             
             self.frame_buffer[y * SCREEN_WIDTH + x] = final_color;
+            self.pixel_source[x] = PpuLayer::Background(tile_id);
         }
     }
 
@@ -557,7 +648,7 @@ impl Ppu {
             return;
         }
 
-        let window_y = y - self.wy as usize;
+        let window_y = self.window_line_counter as usize;
         let tile_y = window_y / TILE_SIZE;
         let pixel_y = window_y % TILE_SIZE;
 
@@ -572,24 +663,23 @@ impl Ppu {
             let tile_index = tile_y * TILES_PER_ROW + tile_x;
             let tile_id = self.vram[tile_map_addr + tile_index];
 
-            let tile_data_addr = if self.lcdc.bg_window_tiles {
-                tile_id as usize * 16
-            } else {
-                let signed_tile_id = tile_id as i8 as i16;
-                (0x1000_i16 + signed_tile_id * 16) as usize
-            };
+            let tile_data_addr = Self::bg_tile_data_addr(tile_id, self.lcdc.bg_window_tiles);
 
             let pixel_color = self.get_tile_pixel(tile_data_addr, pixel_x, pixel_y);
             let final_color = self.apply_palette(pixel_color, self.bgp);
             
             self.frame_buffer[y * SCREEN_WIDTH + x] = final_color;
+            self.pixel_source[x] = PpuLayer::Window(tile_id);
         }
+
+        // Window was drawn on this line - advance to its next row
+        self.window_line_counter = self.window_line_counter.wrapping_add(1);
     }
 
     fn render_sprites_line(&mut self, y: usize) {
         // Debug sprite rendering for falling blocks
         #[cfg(debug_assertions)]
-        if !self.scanline_sprites.is_empty() {
+        if crate::rgb::diagnostics::debug_output() && !self.scanline_sprites.is_empty() {
             static mut SPRITE_DEBUG_COUNT: u32 = 0;
             unsafe {
                 SPRITE_DEBUG_COUNT += 1;
@@ -606,33 +696,36 @@ impl Ppu {
             }
         }
         
-        // Render sprites in reverse order for proper priority
+        // DMG priority: lower X wins, OAM order breaks ties. The stable sort keeps
+        // OAM order within equal X, and drawing lowest priority first lets the
+        // winner overwrite the rest.
+        self.scanline_sprites.sort_by_key(|sprite| sprite.x);
         for sprite in self.scanline_sprites.iter().rev() {
             let sprite_y = sprite.y.wrapping_sub(16) as usize;
-            let sprite_x = sprite.x.wrapping_sub(8) as usize;
+            // Left edge in screen space; X < 8 is partially (or, at 0, fully) offscreen
+            let sprite_x = sprite.x as isize - 8;
             
-            if sprite_x >= SCREEN_WIDTH {
+            if sprite_x >= SCREEN_WIDTH as isize {
                 continue;
             }
 
             let line_in_sprite = y - sprite_y;
-            let actual_line = if sprite.flip_y() {
-                if self.lcdc.sprite_size { 15 - line_in_sprite } else { 7 - line_in_sprite }
-            } else {
-                line_in_sprite
-            };
-
+            
+            // 8x16 sprites stack an even top tile over the odd tile after it;
+            // Y-flip swaps the tiles as well as the rows within them
             let tile_id = if self.lcdc.sprite_size {
-                sprite.tile & 0xFE // 8x16 sprites use even tile numbers
+                if (line_in_sprite >= 8) != sprite.flip_y() { sprite.tile | 0x01 } else { sprite.tile & 0xFE }
             } else {
                 sprite.tile
             };
+            let row_in_tile = line_in_sprite % 8;
+            let actual_line = if sprite.flip_y() { 7 - row_in_tile } else { row_in_tile };
 
             let tile_data_addr = tile_id as usize * 16 + actual_line * 2;
 
             // Debug sprite tile data
             #[cfg(debug_assertions)]
-            {
+            if crate::rgb::diagnostics::debug_output() {
                 static mut SPRITE_TILE_DEBUG_COUNT: u32 = 0;
                 unsafe {
                     SPRITE_TILE_DEBUG_COUNT += 1;
@@ -649,7 +742,11 @@ impl Ppu {
             }
 
             for pixel_x in 0..TILE_SIZE {
-                let screen_x = sprite_x + pixel_x;
+                let screen_x = sprite_x + pixel_x as isize;
+                if screen_x < 0 {
+                    continue;
+                }
+                let screen_x = screen_x as usize;
                 if screen_x >= SCREEN_WIDTH {
                     break;
                 }
@@ -666,11 +763,23 @@ impl Ppu {
                     let palette = if sprite.palette() { self.obp1 } else { self.obp0 };
                     let final_color = self.apply_palette(pixel_color, palette);
                     self.frame_buffer[y * SCREEN_WIDTH + screen_x] = final_color;
+                    self.pixel_source[screen_x] = PpuLayer::Sprite(sprite.oam_index);
                 }
             }
         }
     }
 
+    // VRAM offset of a background/window tile's data. Unsigned addressing (0x8000-0x8FFF),
+    // or signed addressing (0x8800-0x97FF) with base at 0x9000, where tile IDs 0x80-0xFF
+    // are treated as -128 to -1
+    fn bg_tile_data_addr(tile_id: u8, unsigned_tiles: bool) -> usize {
+        if unsigned_tiles {
+            tile_id as usize * 16
+        } else {
+            (0x1000 + tile_id as i8 as isize * 16) as usize
+        }
+    }
+
     fn get_tile_pixel(&self, tile_data_addr: usize, pixel_x: usize, pixel_y: usize) -> u8 {
         let byte_offset = tile_data_addr + pixel_y * 2;
         
@@ -711,7 +820,7 @@ impl Ppu {
             LCDC_ADDR => self.lcdc.to_byte(),
             STAT_ADDR => self.stat.to_byte(),
             SCY_ADDR => self.scy,
-            SCX_ADDR => self.scx,
+            SCX_ADDR => self.scx_pending_change.map_or(self.scx, |(value, _)| value),
             LY_ADDR => self.ly,
             LYC_ADDR => self.lyc,
             WY_ADDR => self.wy,
@@ -727,13 +836,12 @@ impl Ppu {
         match addr {
             LCDC_ADDR => {
                 let old_enable = self.lcdc.lcd_enable;
-                let old_bg_window_tiles = self.lcdc.bg_window_tiles;
                 let old_sprite_enable = self.lcdc.sprite_enable;
                 self.lcdc = LcdcFlags::from_byte(value);
                 
                 // Debug sprite enable/disable changes
                 #[cfg(debug_assertions)]
-                if old_sprite_enable != self.lcdc.sprite_enable {
+                if crate::rgb::diagnostics::debug_output() && old_sprite_enable != self.lcdc.sprite_enable {
                     static mut SPRITE_TOGGLE_COUNT: u32 = 0;
                     unsafe {
                         SPRITE_TOGGLE_COUNT += 1;
@@ -745,28 +853,15 @@ impl Ppu {
                     }
                 }
                 
-                // When switching from unsigned to signed addressing during bootstrap,
-                // copy tile data from unsigned area to signed area for compatibility
-                if old_bg_window_tiles && !self.lcdc.bg_window_tiles {
-                    // Copy tiles 0x00-0x7F from unsigned area to signed area
-                    for tile_id in 0..0x80 {
-                        let src_addr = tile_id * 16; // Unsigned area offset
-                        let dst_addr = 0x1000 + tile_id * 16; // Signed area offset
-                        
-                        if dst_addr + 16 <= VRAM_SIZE {
-                            for i in 0..16 {
-                                self.vram[dst_addr + i] = self.vram[src_addr + i];
-                            }
-                        }
-                    }
-                }
-                
                 // Handle LCD disable
                 if old_enable && !self.lcdc.lcd_enable {
+                    self.mid_scanline_scroll_tick();
                     self.ly = 0;
+                    self.window_line_counter = 0;
                     self.cycles = 0;
                     self.mode = PpuMode::HBlank;
                     self.stat.mode = PpuMode::HBlank;
+                    self.prev_stat_line = false; // STAT line is held low while the LCD is off
                 }
             },
             STAT_ADDR => {
@@ -774,10 +869,11 @@ impl Ppu {
                 let old_stat = self.stat.to_byte();
                 let new_stat = (value & 0x78) | (old_stat & 0x87);
                 self.stat = StatFlags::from_byte(new_stat);
+                self.check_stat_interrupts();
             },
             SCY_ADDR => {
                 #[cfg(debug_assertions)]
-                if value != self.scy {
+                if crate::rgb::diagnostics::debug_output() && value != self.scy {
                     static mut SCY_CHANGE_COUNT: u32 = 0;
                     unsafe {
                         SCY_CHANGE_COUNT += 1;
@@ -790,7 +886,7 @@ impl Ppu {
             },
             SCX_ADDR => {
                 #[cfg(debug_assertions)]
-                if value != self.scx {
+                if crate::rgb::diagnostics::debug_output() && value != self.scx {
                     static mut SCX_CHANGE_COUNT: u32 = 0;
                     unsafe {
                         SCX_CHANGE_COUNT += 1;
@@ -799,7 +895,16 @@ impl Ppu {
                         }
                     }
                 }
-                self.scx = value;
+                // Lines are drawn all at once when they end, so the dot within the line
+                // tells whether this write lands in Mode 3. Each Mode 3 dot is taken as
+                // one pixel, ignoring the fetcher's startup delay; the last write in a line wins.
+                let drawing_dots = OAM_SCAN_CYCLES..OAM_SCAN_CYCLES + DRAWING_CYCLES;
+                if self.lcdc.lcd_enable && (self.ly as usize) < SCREEN_HEIGHT && drawing_dots.contains(&self.cycles) {
+                    self.scx_pending_change = Some((value, self.cycles - OAM_SCAN_CYCLES));
+                } else {
+                    self.scx_pending_change = None;
+                    self.scx = value;
+                }
             },
             LYC_ADDR => {
                 self.lyc = value;
@@ -810,7 +915,7 @@ impl Ppu {
             BGP_ADDR => self.bgp = value,
             OBP0_ADDR => {
                 #[cfg(debug_assertions)]
-                if value != self.obp0 {
+                if crate::rgb::diagnostics::debug_output() && value != self.obp0 {
                     static mut OBP0_CHANGE_COUNT: u32 = 0;
                     unsafe {
                         OBP0_CHANGE_COUNT += 1;
@@ -823,7 +928,7 @@ impl Ppu {
             },
             OBP1_ADDR => {
                 #[cfg(debug_assertions)]
-                if value != self.obp1 {
+                if crate::rgb::diagnostics::debug_output() && value != self.obp1 {
                     static mut OBP1_CHANGE_COUNT: u32 = 0;
                     unsafe {
                         OBP1_CHANGE_COUNT += 1;
@@ -863,7 +968,7 @@ impl Ppu {
         
         // Debug VRAM writes to tile maps and tiles
         #[cfg(debug_assertions)]
-        {
+        if crate::rgb::diagnostics::debug_output() {
             static mut VRAM_WRITE_COUNT: u32 = 0;
             unsafe {
                 VRAM_WRITE_COUNT += 1;
@@ -905,6 +1010,26 @@ impl Ppu {
         self.oam[(addr - 0xFE00) as usize]
     }
 
+    /// What a DMG reads from the unusable 0xFEA0-0xFEFF area at `addr`. During OAM scan
+    /// the PPU fetches one 8-byte OAM row (two objects) every 4 dots, and the read returns
+    /// that row's bytes, repeating every 8 addresses. While drawing it returns 0xFF, and
+    /// 0x00 in HBlank, VBlank or with the LCD off. The OAM corruption these reads also
+    /// cause during OAM scan isn't emulated.
+    pub fn read_unused_oam_area(&self, addr: u16) -> u8 {
+        // Lines are drawn all at once when they end, so the mode comes from the dot within the line
+        if !self.lcdc.lcd_enable || self.ly as usize >= SCREEN_HEIGHT {
+            return 0x00;
+        }
+        if self.cycles < OAM_SCAN_CYCLES {
+            let row = (self.cycles / OAM_ROW_SCAN_CYCLES) as usize;
+            self.oam[row * OAM_ROW_BYTES + (addr as usize % OAM_ROW_BYTES)]
+        } else if self.cycles < OAM_SCAN_CYCLES + DRAWING_CYCLES {
+            0xFF
+        } else {
+            0x00
+        }
+    }
+
     pub fn write_oam(&mut self, addr: u16, value: u8) {
         // TEMPORARILY DISABLE OAM BLOCKING - NEEDED FOR GRAPHICS TO WORK
         // if self.mode == PpuMode::Drawing || self.mode == PpuMode::OamScan {
@@ -913,7 +1038,7 @@ impl Ppu {
         
         // Debug OAM writes to see sprite data
         #[cfg(debug_assertions)]
-        {
+        if crate::rgb::diagnostics::debug_output() {
             static mut OAM_WRITE_COUNT: u32 = 0;
             unsafe {
                 OAM_WRITE_COUNT += 1;
@@ -942,6 +1067,106 @@ impl Ppu {
         &self.frame_buffer
     }
 
+    /// Enables or disables gamma-corrected display colors
+    pub fn color_correction(&mut self, enabled: bool) {
+        self.color_correction = enabled;
+    }
+
+    pub fn color_correction_enabled(&self) -> bool {
+        self.color_correction
+    }
+
+    /// RGBA display color of a shade under the current color correction setting
+    #[allow(dead_code)] // Public API method
+    pub fn display_color(&self, shade: u8) -> [f32; 4] {
+        shade_rgba(shade, self.color_correction)
+    }
+
+    /// Writes the current frame as 24-bit RGB, row by row, mapping shades 0-3 through `palette`.
+    /// Pixel (x, y) starts at `output[(y * 160 + x) * 3]`.
+    #[allow(dead_code)] // Public API method
+    pub fn render_to_rgb_buffer(&self, output: &mut [u8; SCREEN_WIDTH * SCREEN_HEIGHT * 3], palette: &[(u8, u8, u8); 4]) {
+        for (pixel, &shade) in output.chunks_exact_mut(3).zip(self.frame_buffer.iter()) {
+            let (r, g, b) = palette[(shade & 0x03) as usize];
+            pixel.copy_from_slice(&[r, g, b]);
+        }
+    }
+
+    /// Like `render_to_rgb_buffer`, but opaque RGBA for PNG encoding or texture upload
+    #[allow(dead_code)] // Public API method
+    pub fn render_to_rgba_buffer(&self, output: &mut [u8; SCREEN_WIDTH * SCREEN_HEIGHT * 4], palette: &[(u8, u8, u8); 4]) {
+        for (pixel, &shade) in output.chunks_exact_mut(4).zip(self.frame_buffer.iter()) {
+            let (r, g, b) = palette[(shade & 0x03) as usize];
+            pixel.copy_from_slice(&[r, g, b, 0xFF]);
+        }
+    }
+
+    // Save the current frame buffer as a 160x144 RGBA PNG, mapping shades 0-3 through `palette`
+    #[allow(dead_code)] // Public API method
+    pub fn dump_frame_as_png(&self, path: &Path, palette: &[(u8, u8, u8); 4]) -> std::io::Result<()> {
+        let file = File::create(path)?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), SCREEN_WIDTH as u32, SCREEN_HEIGHT as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut pixels = Box::new([0u8; SCREEN_WIDTH * SCREEN_HEIGHT * 4]);
+        self.render_to_rgba_buffer(&mut pixels, palette);
+
+        let mut writer = encoder.write_header().map_err(std::io::Error::other)?;
+        writer.write_image_data(&pixels[..]).map_err(std::io::Error::other)?;
+        writer.finish().map_err(std::io::Error::other)
+    }
+
+    // Raw OAM and VRAM access for debug viewers (bypasses mode-based access blocking)
+    pub fn get_oam(&self) -> &[u8] {
+        &self.oam
+    }
+
+    pub fn get_vram(&self) -> &[u8] {
+        &self.vram
+    }
+
+    /// Shade of a screen pixel (x < 160, y < 144) in the last frame, and the layer that drew it
+    #[allow(dead_code)] // Public API method
+    pub fn get_color_at_screen_position(&self, x: u8, y: u8) -> (u8, PpuLayer) {
+        let index = y as usize * SCREEN_WIDTH + x as usize;
+        (self.frame_buffer[index], self.frame_pixel_sources[index])
+    }
+
+    // Render the whole 32x32 tile background map to shade values (0-3), using the
+    // map and tile addressing selected by LCDC bits 3 and 4. The 160x144 viewport at
+    // SCX/SCY is outlined with BG_MAP_VIEWPORT_MARKER, wrapping like the hardware does.
+    pub fn render_full_background_map(vram: &[u8], lcdc: &LcdcFlags, scx: u8, scy: u8, bgp: u8) -> [u8; BG_MAP_SIZE * BG_MAP_SIZE] {
+        let mut map = [0u8; BG_MAP_SIZE * BG_MAP_SIZE];
+        let tile_map_addr = if lcdc.bg_tile_map { 0x1C00 } else { 0x1800 };
+
+        for y in 0..BG_MAP_SIZE {
+            let tile_y = y / TILE_SIZE;
+            let pixel_y = y % TILE_SIZE;
+            for tile_x in 0..TILES_PER_ROW {
+                let tile_id = vram.get(tile_map_addr + tile_y * TILES_PER_ROW + tile_x).copied().unwrap_or(0);
+                let pixels = decode_tile_row(vram, Self::bg_tile_data_addr(tile_id, lcdc.bg_window_tiles), pixel_y);
+                for (pixel_x, color) in pixels.into_iter().enumerate() {
+                    map[y * BG_MAP_SIZE + tile_x * TILE_SIZE + pixel_x] = (bgp >> (color * 2)) & 0x03;
+                }
+            }
+        }
+
+        // Viewport outline
+        for dx in 0..SCREEN_WIDTH {
+            let x = scx.wrapping_add(dx as u8) as usize;
+            map[scy as usize * BG_MAP_SIZE + x] = BG_MAP_VIEWPORT_MARKER;
+            map[scy.wrapping_add((SCREEN_HEIGHT - 1) as u8) as usize * BG_MAP_SIZE + x] = BG_MAP_VIEWPORT_MARKER;
+        }
+        for dy in 0..SCREEN_HEIGHT {
+            let y = scy.wrapping_add(dy as u8) as usize;
+            map[y * BG_MAP_SIZE + scx as usize] = BG_MAP_VIEWPORT_MARKER;
+            map[y * BG_MAP_SIZE + scx.wrapping_add((SCREEN_WIDTH - 1) as u8) as usize] = BG_MAP_VIEWPORT_MARKER;
+        }
+
+        map
+    }
+
     // Check and clear interrupt flags
     #[allow(dead_code)] // Public API method
     pub fn take_vblank_interrupt(&mut self) -> bool {
@@ -962,12 +1187,20 @@ impl Ppu {
         if self.mode != new_mode {
             self.mode = new_mode;
             self.stat.mode = new_mode;
-            
+            self.check_stat_interrupts();
         }
     }
     
-    // Check for STAT interrupts with edge-triggered behavior
+    // Check for STAT interrupts with edge-triggered behavior.
+    // The STAT line is the OR of all enabled sources, so sources that overlap
+    // (e.g. LYC=LY during OAM scan) only raise one interrupt ("STAT blocking").
+    // Every change to the mode, LYC flag or enable bits must come through here.
     fn check_stat_interrupts(&mut self) {
+        if !self.lcdc.lcd_enable {
+            self.prev_stat_line = false;
+            return;
+        }
+        
         let stat_line = self.should_trigger_stat_interrupt();
         
         // Edge-triggered: interrupt only on 0->1 transition
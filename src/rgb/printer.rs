@@ -0,0 +1,248 @@
+// Game Boy Printer Emulation
+// Speaks the printer's packet protocol over the serial port and saves each print as a PNG
+
+use super::serial::SerialDevice;
+use super::ppu::decode_tile_row;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use log::debug;
+
+const MAGIC_1: u8 = 0x88;
+const MAGIC_2: u8 = 0x33;
+const ALIVE: u8 = 0x81; // Response to the first keep-alive byte
+
+// Packet commands
+const COMMAND_INIT: u8 = 0x01;
+const COMMAND_PRINT: u8 = 0x02;
+const COMMAND_DATA: u8 = 0x04;
+const COMMAND_INQUIRY: u8 = 0x0F;
+
+// Status byte bits
+pub const STATUS_CHECKSUM_ERROR: u8 = 0x01;
+pub const STATUS_UNPROCESSED_DATA: u8 = 0x08;
+pub const STATUS_PACKET_ERROR: u8 = 0x10;
+
+const TILES_PER_ROW: usize = 20; // Prints are 160 pixels wide
+const TILE_BYTES: usize = 16;
+const DEFAULT_PALETTE: u8 = 0xE4; // Palette 0x00 prints like 0xE4
+const GRAY_LEVELS: [u8; 4] = [0xFF, 0xAA, 0x55, 0x00];
+
+// Position within a packet: magic, header, data, checksum, then two bytes where
+// the printer answers with ALIVE and its status
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PacketState {
+    Magic1,
+    Magic2,
+    Command,
+    Compression,
+    LengthLow,
+    LengthHigh,
+    Data,
+    ChecksumLow,
+    ChecksumHigh,
+    KeepAlive,
+    Status,
+}
+
+pub struct GameBoyPrinter {
+    state: PacketState,
+    command: u8,
+    compressed: bool,
+    length: u16,
+    packet_data: Vec<u8>,
+    checksum: u16,
+    received_checksum: u16,
+    status: u8,
+    image_data: Vec<u8>, // 2bpp tile data accumulated from DATA packets
+    output_dir: PathBuf,
+    print_count: u32,
+    last_print: Option<PathBuf>,
+}
+
+impl GameBoyPrinter {
+    /// Creates a printer that saves prints to `output_dir` as print_NNN.png
+    pub fn new(output_dir: &Path) -> Self {
+        Self {
+            state: PacketState::Magic1,
+            command: 0,
+            compressed: false,
+            length: 0,
+            packet_data: Vec::new(),
+            checksum: 0,
+            received_checksum: 0,
+            status: 0,
+            image_data: Vec::new(),
+            output_dir: output_dir.to_path_buf(),
+            print_count: 0,
+            last_print: None,
+        }
+    }
+
+    /// Path of the most recently saved print, if any
+    #[allow(dead_code)] // Public API method
+    pub fn last_print(&self) -> Option<&Path> {
+        self.last_print.as_deref()
+    }
+
+    fn add_to_checksum(&mut self, byte: u8) {
+        self.checksum = self.checksum.wrapping_add(byte as u16);
+    }
+
+    // Runs the completed packet's command and updates the status reported back
+    fn process_packet(&mut self) {
+        if self.checksum != self.received_checksum {
+            self.status |= STATUS_CHECKSUM_ERROR;
+            return;
+        }
+        self.status &= !STATUS_CHECKSUM_ERROR;
+
+        match self.command {
+            COMMAND_INIT => {
+                self.image_data.clear();
+                self.status = 0;
+            }
+            COMMAND_DATA => {
+                if self.compressed {
+                    decompress_rle(&self.packet_data, &mut self.image_data);
+                } else {
+                    self.image_data.extend_from_slice(&self.packet_data);
+                }
+                if !self.image_data.is_empty() {
+                    self.status |= STATUS_UNPROCESSED_DATA;
+                }
+            }
+            COMMAND_PRINT => {
+                // Data: sheet count, margins, palette, exposure
+                let palette = match self.packet_data.get(2) {
+                    Some(0) | None => DEFAULT_PALETTE,
+                    Some(&palette) => palette,
+                };
+                if let Err(e) = self.print(palette) {
+                    eprintln!("Warning: failed to save printer output: {}", e);
+                }
+                self.image_data.clear();
+                self.status &= !STATUS_UNPROCESSED_DATA;
+            }
+            COMMAND_INQUIRY => {}
+            _ => self.status |= STATUS_PACKET_ERROR,
+        }
+    }
+
+    // Saves the accumulated image as a grayscale PNG
+    fn print(&mut self, palette: u8) -> std::io::Result<()> {
+        let tile_rows = self.image_data.len() / (TILES_PER_ROW * TILE_BYTES);
+        if tile_rows == 0 {
+            return Ok(());
+        }
+        let width = TILES_PER_ROW * 8;
+        let height = tile_rows * 8;
+
+        let mut pixels = vec![0u8; width * height];
+        for y in 0..height {
+            for tile_x in 0..TILES_PER_ROW {
+                let tile_addr = ((y / 8) * TILES_PER_ROW + tile_x) * TILE_BYTES;
+                let row = decode_tile_row(&self.image_data, tile_addr, y % 8);
+                for (x, &color) in row.iter().enumerate() {
+                    let shade = (palette >> (color * 2)) & 0x03;
+                    pixels[y * width + tile_x * 8 + x] = GRAY_LEVELS[shade as usize];
+                }
+            }
+        }
+
+        self.print_count += 1;
+        let path = self.output_dir.join(format!("print_{:03}.png", self.print_count));
+        let file = File::create(&path)?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header().map_err(std::io::Error::other)?;
+        writer.write_image_data(&pixels).map_err(std::io::Error::other)?;
+        writer.finish().map_err(std::io::Error::other)?;
+
+        debug!("Printer: saved {}", path.display());
+        self.last_print = Some(path);
+        Ok(())
+    }
+}
+
+impl SerialDevice for GameBoyPrinter {
+    fn exchange(&mut self, byte: u8) -> u8 {
+        let mut response = 0x00;
+
+        self.state = match self.state {
+            PacketState::Magic1 => {
+                if byte == MAGIC_1 { PacketState::Magic2 } else { PacketState::Magic1 }
+            }
+            PacketState::Magic2 => {
+                if byte == MAGIC_2 { PacketState::Command } else { PacketState::Magic1 }
+            }
+            PacketState::Command => {
+                self.command = byte;
+                self.checksum = byte as u16;
+                PacketState::Compression
+            }
+            PacketState::Compression => {
+                self.compressed = byte & 0x01 != 0;
+                self.add_to_checksum(byte);
+                PacketState::LengthLow
+            }
+            PacketState::LengthLow => {
+                self.length = byte as u16;
+                self.add_to_checksum(byte);
+                PacketState::LengthHigh
+            }
+            PacketState::LengthHigh => {
+                self.length |= (byte as u16) << 8;
+                self.add_to_checksum(byte);
+                self.packet_data.clear();
+                if self.length == 0 { PacketState::ChecksumLow } else { PacketState::Data }
+            }
+            PacketState::Data => {
+                self.packet_data.push(byte);
+                self.add_to_checksum(byte);
+                if self.packet_data.len() >= self.length as usize { PacketState::ChecksumLow } else { PacketState::Data }
+            }
+            PacketState::ChecksumLow => {
+                self.received_checksum = byte as u16;
+                PacketState::ChecksumHigh
+            }
+            PacketState::ChecksumHigh => {
+                self.received_checksum |= (byte as u16) << 8;
+                PacketState::KeepAlive
+            }
+            PacketState::KeepAlive => {
+                response = ALIVE;
+                self.process_packet();
+                PacketState::Status
+            }
+            PacketState::Status => {
+                response = self.status;
+                PacketState::Magic1
+            }
+        };
+
+        response
+    }
+}
+
+// Printer RLE: a control byte with bit 7 set repeats the next byte (n & 0x7F) + 2
+// times, otherwise the next n + 1 bytes are copied literally
+fn decompress_rle(data: &[u8], output: &mut Vec<u8>) {
+    let mut i = 0;
+    while i < data.len() {
+        let control = data[i];
+        i += 1;
+        if control & 0x80 != 0 {
+            if let Some(&value) = data.get(i) {
+                output.extend(std::iter::repeat_n(value, (control & 0x7F) as usize + 2));
+            }
+            i += 1;
+        } else {
+            let end = (i + control as usize + 1).min(data.len());
+            output.extend_from_slice(&data[i..end]);
+            i = end;
+        }
+    }
+}
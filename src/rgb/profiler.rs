@@ -0,0 +1,85 @@
+// Opcode Profiler
+// Counts how often each opcode executes, for finding the hottest instructions to optimize
+
+use super::instructions::{decode_cb_instruction, decode_instruction, is_illegal_opcode};
+use super::memory::MemoryMap;
+
+const CB_OFFSET: usize = 256; // CB-prefixed opcodes are counted after the 256 regular ones
+
+pub struct OpcodeProfile {
+    opcode_counts: Box<[u64; 512]>,
+}
+
+impl Default for OpcodeProfile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OpcodeProfile {
+    pub fn new() -> Self {
+        Self {
+            opcode_counts: Box::new([0; 512]),
+        }
+    }
+
+    /// Counts one execution; `cb_opcode` is the byte after a 0xCB prefix
+    pub fn record(&mut self, opcode: u8, cb_opcode: u8) {
+        let index = if opcode == 0xCB { CB_OFFSET + cb_opcode as usize } else { opcode as usize };
+        self.opcode_counts[index] += 1;
+    }
+
+    /// Counts the instruction about to execute at `pc`, read without triggering watchpoints
+    pub fn record_at(&mut self, mmap: &MemoryMap, pc: u16) {
+        self.record(mmap.peek(pc), mmap.peek(pc.wrapping_add(1)));
+    }
+
+    /// How often an opcode ran; `cb` selects the CB-prefixed table
+    #[allow(dead_code)] // Public API method
+    pub fn count(&self, opcode: u8, cb: bool) -> u64 {
+        self.opcode_counts[if cb { CB_OFFSET } else { 0 } + opcode as usize]
+    }
+
+    /// The `n` most executed opcodes as (index, count), highest first. Indices 256 and up
+    /// are CB-prefixed. Opcodes that never ran are left out.
+    pub fn top(&self, n: usize) -> Vec<(usize, u64)> {
+        let mut counts: Vec<(usize, u64)> = self.opcode_counts.iter().copied().enumerate()
+            .filter(|&(_, count)| count > 0)
+            .collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        counts.truncate(n);
+        counts
+    }
+
+    /// Table of the `n` most executed opcodes with their share of all instructions
+    pub fn report(&self, n: usize) -> String {
+        let total: u64 = self.opcode_counts.iter().sum();
+        let mut report = format!("Opcode profile: {} instructions (immediate operands shown as 0)\n", total);
+        report.push_str("Opcode  Count           %  Instruction\n");
+        for (index, count) in self.top(n) {
+            let percent = count as f64 * 100.0 / total as f64;
+            report.push_str(&format!("{:<6}  {:>12}  {:>5.1}  {}\n", opcode_label(index), count, percent, mnemonic(index)));
+        }
+        report
+    }
+}
+
+// "3E" for regular opcodes, "CB 37" for CB-prefixed ones
+fn opcode_label(index: usize) -> String {
+    if index >= CB_OFFSET {
+        format!("CB {:02X}", index - CB_OFFSET)
+    } else {
+        format!("{:02X}", index)
+    }
+}
+
+fn mnemonic(index: usize) -> String {
+    if index >= CB_OFFSET {
+        return decode_cb_instruction((index - CB_OFFSET) as u8).to_string();
+    }
+    let opcode = index as u8;
+    if is_illegal_opcode(opcode) {
+        return "(illegal)".to_string();
+    }
+    decode_instruction(opcode, Some(0), Some(0)).to_string()
+}
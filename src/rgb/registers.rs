@@ -1,4 +1,6 @@
-#[derive(Clone, Copy)]
+use super::HardwareModel;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub struct FlagsRegister {
     pub zero: bool,
     pub subtract: bool,
@@ -67,9 +69,32 @@ impl Registers {
         }
     }
 
+    /// Restores every register to its power-on value of zero
+    pub fn reset(&mut self) {
+        *self = Registers::new();
+    }
+
     /// Creates registers in the post-boot state for DMG (original Game Boy)
     /// These values match what the boot ROM sets before handing control to the cartridge
+    #[allow(dead_code)] // Public API method
     pub fn new_post_boot() -> Self {
+        Self::new_post_boot_for(HardwareModel::Dmg)
+    }
+
+    /// Registers as the given model's boot ROM leaves them; games check A to tell them apart
+    pub fn new_post_boot_for(model: HardwareModel) -> Self {
+        if model == HardwareModel::Cgb {
+            return Registers {
+                a: 0x11, // Indicates CGB hardware
+                b: 0x00,
+                c: 0x00,
+                d: 0xFF,
+                e: 0x56,
+                f: FlagsRegister { zero: true, subtract: false, half_carry: false, carry: false }, // 0x80
+                h: 0x00,
+                l: 0x0D,
+            };
+        }
         let flags = FlagsRegister {
             zero: true,        // Z=1
             subtract: false,   // N=0  
@@ -0,0 +1,86 @@
+// Game Boy Serial Port Implementation
+// Handles SB (0xFF01) / SC (0xFF02) and exchanges bytes with an attached link-cable device
+
+pub const SB_ADDR: u16 = 0xFF01; // Serial transfer data
+pub const SC_ADDR: u16 = 0xFF02; // Serial transfer control
+
+const TRANSFER_START: u8 = 0x80; // SC bit 7
+const INTERNAL_CLOCK: u8 = 0x01; // SC bit 0
+const TRANSFER_CYCLES: u32 = 4096; // 8 bits at 8192 Hz
+
+/// A device on the other end of the link cable. The Game Boy shifts out one
+/// byte while the device shifts one back in, so every transfer is an exchange.
+pub trait SerialDevice {
+    fn exchange(&mut self, byte: u8) -> u8;
+}
+
+pub struct Serial {
+    sb: u8,
+    sc: u8,
+    transfer_cycles: u32,
+    device: Option<Box<dyn SerialDevice>>,
+}
+
+impl Default for Serial {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Serial {
+    pub fn new() -> Self {
+        Self {
+            sb: 0x00,
+            sc: 0x00,
+            transfer_cycles: 0,
+            device: None,
+        }
+    }
+
+    pub fn attach_device(&mut self, device: Box<dyn SerialDevice>) {
+        self.device = Some(device);
+    }
+
+    pub fn read_register(&self, addr: u16) -> u8 {
+        match addr {
+            SB_ADDR => self.sb,
+            SC_ADDR => self.sc | 0x7E, // Unused bits read as 1
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write_register(&mut self, addr: u16, value: u8) {
+        match addr {
+            SB_ADDR => self.sb = value,
+            SC_ADDR => {
+                self.sc = value & (TRANSFER_START | INTERNAL_CLOCK);
+                self.transfer_cycles = 0;
+            }
+            _ => {}
+        }
+    }
+
+    /// Advances an in-progress transfer. Returns true when a transfer completes
+    /// and the serial interrupt should be requested.
+    pub fn step(&mut self, cycles: u16) -> bool {
+        // Only internally clocked transfers progress; with an external clock the
+        // Game Boy waits for the other side, which never clocks without a device
+        if self.sc & (TRANSFER_START | INTERNAL_CLOCK) != (TRANSFER_START | INTERNAL_CLOCK) {
+            return false;
+        }
+
+        self.transfer_cycles += cycles as u32;
+        if self.transfer_cycles < TRANSFER_CYCLES {
+            return false;
+        }
+
+        // No device connected: the line floats high
+        self.sb = match self.device.as_mut() {
+            Some(device) => device.exchange(self.sb),
+            None => 0xFF,
+        };
+        self.sc &= !TRANSFER_START;
+        self.transfer_cycles = 0;
+        true
+    }
+}
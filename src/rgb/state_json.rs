@@ -0,0 +1,155 @@
+// JSON State Export
+// A human-readable view of the machine state for sharing with other tools: registers,
+// the start of WRAM, the I/O registers and PPU state. Importing it restores registers,
+// WRAM and the I/O registers that are safe to write back (see import_state_from_json).
+
+use super::cpu::Cpu;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const WRAM_START: u16 = 0xC000;
+const WRAM_EXPORT_BYTES: u16 = 256;
+const IO_START: u16 = 0xFF00;
+const IO_BYTES: u16 = 0x80;
+const IE_ADDR: u16 = 0xFFFF;
+// I/O registers written back on import: IF and the PPU registers other than LY and DMA.
+// The rest are skipped since writing them has side effects, like starting a sound
+// channel or disabling the boot ROM.
+const IMPORTED_IO_REGISTERS: [u16; 11] = [0xFF0F, 0xFF40, 0xFF41, 0xFF42, 0xFF43, 0xFF45, 0xFF47, 0xFF48, 0xFF49, 0xFF4A, 0xFF4B];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StateJsonError {
+    Syntax(usize, usize), // Line and column where parsing failed
+    Invalid(String),      // Well-formed JSON that isn't a state, e.g. "missing field `sp`"
+}
+
+impl fmt::Display for StateJsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StateJsonError::Syntax(line, column) => write!(f, "invalid JSON at line {} column {}", line, column),
+            StateJsonError::Invalid(message) => write!(f, "invalid state: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for StateJsonError {}
+
+impl From<serde_json::Error> for StateJsonError {
+    fn from(e: serde_json::Error) -> Self {
+        if e.is_syntax() || e.is_eof() {
+            StateJsonError::Syntax(e.line(), e.column())
+        } else {
+            StateJsonError::Invalid(e.to_string())
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RegisterState {
+    a: u8,
+    f: u8,
+    b: u8,
+    c: u8,
+    d: u8,
+    e: u8,
+    h: u8,
+    l: u8,
+    sp: u16,
+    pc: u16,
+    ime: bool,
+    halted: bool,
+}
+
+// Exported for reading only; import ignores it
+#[derive(Debug, Default, Serialize)]
+struct PpuState {
+    mode: String,
+    ly: u8,
+    bgp: u8,
+    obp0: u8,
+    obp1: u8,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MachineState {
+    registers: RegisterState,
+    wram: Vec<u8>,
+    io: Vec<u8>,
+    ie: u8,
+    #[serde(skip_deserializing)]
+    ppu: PpuState,
+}
+
+fn bytes(cpu: &Cpu, start: u16, count: u16) -> Vec<u8> {
+    (start..start + count).map(|addr| cpu.mmap.peek(addr)).collect()
+}
+
+/// The CPU registers, WRAM 0xC000-0xC0FF, the I/O registers 0xFF00-0xFF7F and IE, and
+/// the PPU's mode, LY and palettes. `pretty` adds line breaks and indentation.
+pub fn export_state_as_json(cpu: &Cpu, pretty: bool) -> String {
+    let regs = &cpu.registers;
+    let ppu = cpu.mmap.get_ppu();
+    let state = MachineState {
+        registers: RegisterState {
+            a: regs.a,
+            f: u8::from(regs.f),
+            b: regs.b,
+            c: regs.c,
+            d: regs.d,
+            e: regs.e,
+            h: regs.h,
+            l: regs.l,
+            sp: cpu.sp,
+            pc: cpu.pc,
+            ime: cpu.ime,
+            halted: cpu.halted,
+        },
+        wram: bytes(cpu, WRAM_START, WRAM_EXPORT_BYTES),
+        io: bytes(cpu, IO_START, IO_BYTES),
+        ie: cpu.mmap.peek(IE_ADDR),
+        ppu: PpuState {
+            mode: format!("{:?}", ppu.mode),
+            ly: ppu.ly,
+            bgp: ppu.read_register(0xFF47),
+            obp0: ppu.read_register(0xFF48),
+            obp1: ppu.read_register(0xFF49),
+        },
+    };
+    let text = if pretty { serde_json::to_string_pretty(&state) } else { serde_json::to_string(&state) };
+    text.expect("machine state always serializes")
+}
+
+/// Loads a state written by `export_state_as_json`. Registers and WRAM are restored, and of
+/// the I/O registers only IF and the PPU registers (not LY or DMA), through normal writes.
+/// Nothing is changed if the JSON is invalid or incomplete.
+pub fn import_state_from_json(cpu: &mut Cpu, json: &str) -> Result<(), StateJsonError> {
+    let state: MachineState = serde_json::from_str(json)?;
+    for (name, values, len) in [("wram", &state.wram, WRAM_EXPORT_BYTES), ("io", &state.io, IO_BYTES)] {
+        if values.len() != len as usize {
+            return Err(StateJsonError::Invalid(format!("{} holds {} bytes, expected {}", name, values.len(), len)));
+        }
+    }
+
+    let saved = &state.registers;
+    let regs = &mut cpu.registers;
+    regs.a = saved.a;
+    regs.f = saved.f.into();
+    regs.b = saved.b;
+    regs.c = saved.c;
+    regs.d = saved.d;
+    regs.e = saved.e;
+    regs.h = saved.h;
+    regs.l = saved.l;
+    cpu.sp = saved.sp;
+    cpu.pc = saved.pc;
+    cpu.ime = saved.ime;
+    cpu.halted = saved.halted;
+    for (addr, &value) in (WRAM_START..).zip(&state.wram) {
+        cpu.mmap.write(addr, value);
+    }
+    for addr in IMPORTED_IO_REGISTERS {
+        cpu.mmap.write(addr, state.io[(addr - IO_START) as usize]);
+    }
+    cpu.mmap.write(IE_ADDR, state.ie);
+    Ok(())
+}
@@ -3,7 +3,7 @@ pub struct Timer {
     tima: u8,           // Timer counter (0xFF05)
     tma: u8,            // Timer modulo (0xFF06)
     tac: u8,            // Timer control (0xFF07)
-    timer_cycles: u16,  // Cycle accumulator for TIMA register
+    pending_interrupt: bool, // TIMA overflow caused by a register write, reported on next step
 }
 
 impl Timer {
@@ -13,7 +13,7 @@ impl Timer {
             tima: 0,
             tma: 0,
             tac: 0,       // Timer disabled on reset
-            timer_cycles: 0,
+            pending_interrupt: false,
         }
     }
     
@@ -25,33 +25,22 @@ impl Timer {
             tima: 0,
             tma: 0,
             tac: 0,       // Timer disabled after boot
-            timer_cycles: 0,
+            pending_interrupt: false,
         }
     }
 
     pub fn step(&mut self, cycles: u16) -> bool {
-        let mut timer_interrupt = false;
+        let mut timer_interrupt = self.pending_interrupt;
+        self.pending_interrupt = false;
 
-        // Update internal DIV counter - increments every T-cycle
-        // Game Boy cycles are passed as T-cycles, so increment directly
-        self.div_counter = self.div_counter.wrapping_add(cycles);
-
-        // Update TIMA register if timer is enabled
-        if self.is_timer_enabled() {
-            self.timer_cycles += cycles;
-            let timer_frequency = self.get_timer_frequency();
-            
-            while self.timer_cycles >= timer_frequency {
-                self.timer_cycles -= timer_frequency;
-                
-                // Increment TIMA
-                if self.tima == 0xFF {
-                    // TIMA overflow - reload with TMA and request timer interrupt
-                    self.tima = self.tma;
-                    timer_interrupt = true;
-                } else {
-                    self.tima = self.tima.wrapping_add(1);
-                }
+        // The internal counter increments every T-cycle. TIMA ticks on the falling
+        // edge of the counter bit selected by TAC (ANDed with the enable bit), so
+        // step one cycle at a time to catch every edge.
+        for _ in 0..cycles {
+            let old_input = self.timer_input();
+            self.div_counter = self.div_counter.wrapping_add(1);
+            if old_input && !self.timer_input() {
+                timer_interrupt |= self.increment_tima();
             }
         }
 
@@ -83,7 +72,11 @@ impl Timer {
         
         match addr {
             0xFF04 => {
-                // Writing to DIV resets the internal counter to 0
+                // Writing to DIV resets the internal counter to 0. If the selected
+                // bit was set, the reset is a falling edge and TIMA ticks immediately.
+                if self.timer_input() {
+                    self.pending_interrupt |= self.increment_tima();
+                }
                 self.div_counter = 0;
             }
             0xFF05 => {
@@ -93,25 +86,47 @@ impl Timer {
                 self.tma = value;
             }
             0xFF07 => {
+                // Changing the enable bit or clock select can also drop the timer input
+                // from 1 to 0, which ticks TIMA just like a counter edge
+                let old_input = self.timer_input();
                 self.tac = value & 0x07; // Only lower 3 bits are used
-                // Note: Real Game Boy behavior on TAC write is complex and can affect timer state
-                // For now, keep timer_cycles to maintain current timing
+                if old_input && !self.timer_input() {
+                    self.pending_interrupt |= self.increment_tima();
+                }
             }
             _ => {}
         }
     }
 
+    /// Increments TIMA, returning true on overflow (TIMA reloaded from TMA)
+    fn increment_tima(&mut self) -> bool {
+        if self.tima == 0xFF {
+            // TIMA overflow - reload with TMA and request timer interrupt
+            self.tima = self.tma;
+            true
+        } else {
+            self.tima = self.tima.wrapping_add(1);
+            false
+        }
+    }
+
+    // Multiplexer output feeding the falling-edge detector
+    fn timer_input(&self) -> bool {
+        self.is_timer_enabled() && (self.div_counter & (1 << self.get_timer_bit())) != 0
+    }
+
     fn is_timer_enabled(&self) -> bool {
         (self.tac & 0x04) != 0
     }
 
-    fn get_timer_frequency(&self) -> u16 {
+    // Internal counter bit watched for each TAC clock select
+    fn get_timer_bit(&self) -> u16 {
         match self.tac & 0x03 {
-            0 => 1024, // 4096 Hz (CPU_FREQ / 4096 = ~1024 cycles)
-            1 => 16,   // 262144 Hz (CPU_FREQ / 262144 = ~16 cycles)
-            2 => 64,   // 65536 Hz (CPU_FREQ / 65536 = ~64 cycles)  
-            3 => 256,  // 16384 Hz (CPU_FREQ / 16384 = ~256 cycles)
+            0 => 9, // 4096 Hz (every 1024 cycles)
+            1 => 3, // 262144 Hz (every 16 cycles)
+            2 => 5, // 65536 Hz (every 64 cycles)
+            3 => 7, // 16384 Hz (every 256 cycles)
             _ => unreachable!(),
         }
     }
-}
\ No newline at end of file
+}
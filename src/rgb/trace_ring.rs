@@ -0,0 +1,96 @@
+// Trace Ring Buffer
+// Keeps the last few executed instructions in memory so a crash can show what led up to it
+
+use super::cpu::Cpu;
+use std::collections::VecDeque;
+use std::fmt;
+
+pub const DEFAULT_TRACE_RING_SIZE: usize = 64; // Entries kept in debug builds without --trace-ring
+
+/// CPU state before one instruction, printed in the --trace text format
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TraceEntry {
+    pub a: u8,
+    pub f: u8,
+    pub b: u8,
+    pub c: u8,
+    pub d: u8,
+    pub e: u8,
+    pub h: u8,
+    pub l: u8,
+    pub sp: u16,
+    pub pc: u16,
+    pub mem: [u8; 4], // The bytes at PC, read without triggering watchpoints
+}
+
+impl TraceEntry {
+    pub fn capture(cpu: &Cpu) -> Self {
+        let regs = &cpu.registers;
+        let pc = cpu.pc;
+        Self {
+            a: regs.a,
+            f: u8::from(regs.f),
+            b: regs.b,
+            c: regs.c,
+            d: regs.d,
+            e: regs.e,
+            h: regs.h,
+            l: regs.l,
+            sp: cpu.sp,
+            pc,
+            mem: [0, 1, 2, 3].map(|offset| cpu.mmap.peek(pc.wrapping_add(offset))),
+        }
+    }
+}
+
+// "A: 01 F: B0 B: 00 C: 13 D: 00 E: D8 H: 01 L: 4D SP: FFFE PC: 00:0101 (C3 13 02 CE)"
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "A: {:02X} F: {:02X} B: {:02X} C: {:02X} D: {:02X} E: {:02X} H: {:02X} L: {:02X} SP: {:04X} PC: 00:{:04X} ({:02X} {:02X} {:02X} {:02X})",
+            self.a, self.f, self.b, self.c, self.d, self.e, self.h, self.l, self.sp, self.pc,
+            self.mem[0], self.mem[1], self.mem[2], self.mem[3]
+        )
+    }
+}
+
+/// The most recent `capacity` trace entries, oldest first
+pub struct RingTraceBuffer {
+    entries: VecDeque<TraceEntry>,
+    capacity: usize,
+}
+
+impl RingTraceBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Adds an entry, dropping the oldest once the buffer is full
+    pub fn push(&mut self, entry: TraceEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    #[allow(dead_code)] // Public API method
+    pub fn entries(&self) -> impl Iterator<Item = &TraceEntry> {
+        self.entries.iter()
+    }
+
+    /// The buffered entries one per line under a heading, for printing after a crash
+    pub fn report(&self) -> String {
+        let mut report = format!("Last {} instructions (oldest first):\n", self.entries.len());
+        for entry in &self.entries {
+            report.push_str(&format!("{}\n", entry));
+        }
+        report
+    }
+}
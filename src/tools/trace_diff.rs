@@ -0,0 +1,198 @@
+// Trace comparison tool
+// Diffs two text-format traces (as written by `rgb --trace`) and reports the first divergence
+//
+// Usage: trace_diff [--skip N] <reference_trace> <actual_trace>
+// Line format: "A: 01 F: B0 B: 00 C: 13 D: 00 E: D8 H: 01 L: 4D SP: FFFE PC: 00:0101 (C3 13 02 CE)"
+
+use std::env;
+use std::fs;
+use std::process;
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TraceEntry {
+    a: u8,
+    f: u8,
+    b: u8,
+    c: u8,
+    d: u8,
+    e: u8,
+    h: u8,
+    l: u8,
+    sp: u16,
+    pc: u16,
+    mem: [u8; 4],
+}
+
+impl TraceEntry {
+    fn parse(line: &str) -> Option<Self> {
+        let (registers, memory) = line.split_once('(')?;
+
+        let mut entry = TraceEntry { a: 0, f: 0, b: 0, c: 0, d: 0, e: 0, h: 0, l: 0, sp: 0, pc: 0, mem: [0; 4] };
+        let mut tokens = registers.split_whitespace();
+        while let Some(label) = tokens.next() {
+            let value = tokens.next()?;
+            match label {
+                "A:" => entry.a = u8::from_str_radix(value, 16).ok()?,
+                "F:" => entry.f = u8::from_str_radix(value, 16).ok()?,
+                "B:" => entry.b = u8::from_str_radix(value, 16).ok()?,
+                "C:" => entry.c = u8::from_str_radix(value, 16).ok()?,
+                "D:" => entry.d = u8::from_str_radix(value, 16).ok()?,
+                "E:" => entry.e = u8::from_str_radix(value, 16).ok()?,
+                "H:" => entry.h = u8::from_str_radix(value, 16).ok()?,
+                "L:" => entry.l = u8::from_str_radix(value, 16).ok()?,
+                "SP:" => entry.sp = u16::from_str_radix(value, 16).ok()?,
+                // PC is written as "bank:address"; only the address is compared
+                "PC:" => entry.pc = u16::from_str_radix(value.rsplit(':').next()?, 16).ok()?,
+                _ => return None,
+            }
+        }
+
+        let bytes: Vec<u8> = memory
+            .trim_end()
+            .trim_end_matches(')')
+            .split_whitespace()
+            .map(|byte| u8::from_str_radix(byte, 16).ok())
+            .collect::<Option<_>>()?;
+        if bytes.len() != 4 {
+            return None;
+        }
+        entry.mem.copy_from_slice(&bytes);
+
+        Some(entry)
+    }
+
+    // (name, formatted value) pairs in trace order, used for the side-by-side diff
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("A", format!("{:02X}", self.a)),
+            ("F", format!("{:02X}", self.f)),
+            ("B", format!("{:02X}", self.b)),
+            ("C", format!("{:02X}", self.c)),
+            ("D", format!("{:02X}", self.d)),
+            ("E", format!("{:02X}", self.e)),
+            ("H", format!("{:02X}", self.h)),
+            ("L", format!("{:02X}", self.l)),
+            ("SP", format!("{:04X}", self.sp)),
+            ("PC", format!("{:04X}", self.pc)),
+            ("MEM", format!("{:02X} {:02X} {:02X} {:02X}", self.mem[0], self.mem[1], self.mem[2], self.mem[3])),
+        ]
+    }
+}
+
+fn load_trace(path: &str) -> Vec<String> {
+    let contents = fs::read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("Error: failed to read trace file '{}': {}", path, e);
+        process::exit(2);
+    });
+    contents.lines().map(|line| line.to_string()).collect()
+}
+
+fn parse_line(line: &str, path: &str, line_number: usize) -> TraceEntry {
+    TraceEntry::parse(line).unwrap_or_else(|| {
+        eprintln!("Error: {}:{}: malformed trace line: {}", path, line_number, line);
+        process::exit(2);
+    })
+}
+
+fn print_diff(line_number: usize, reference: &TraceEntry, actual: &TraceEntry) {
+    println!("First divergence at line {}:", line_number);
+    println!();
+
+    let reference_fields = reference.fields();
+    let actual_fields = actual.fields();
+
+    let mut header = format!("{:<10}", "");
+    let mut reference_row = format!("{:<10}", "reference");
+    let mut actual_row = format!("{:<10}", "actual");
+    for ((name, reference_value), (_, actual_value)) in reference_fields.iter().zip(actual_fields.iter()) {
+        let width = reference_value.len().max(name.len()) + 2;
+        header.push_str(&format!("{:<width$}", name, width = width));
+        if reference_value == actual_value {
+            reference_row.push_str(&format!("{:<width$}", reference_value, width = width));
+            actual_row.push_str(&format!("{:<width$}", actual_value, width = width));
+        } else {
+            reference_row.push_str(&format!("{}{:<width$}{}", GREEN, reference_value, RESET, width = width));
+            actual_row.push_str(&format!("{}{:<width$}{}", RED, actual_value, RESET, width = width));
+        }
+    }
+
+    println!("{}", header);
+    println!("{}", reference_row);
+    println!("{}", actual_row);
+}
+
+fn print_usage(program: &str) {
+    println!("Usage: {} [--skip N] <reference_trace> <actual_trace>", program);
+    println!();
+    println!("Options:");
+    println!("  --skip N, -n N       Skip the first N lines of both traces");
+    println!("  --help, -h           Show this help message");
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    let mut skip = 0usize;
+    let mut paths = Vec::new();
+
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--skip" | "-n" => {
+                if i + 1 < args.len() {
+                    skip = args[i + 1].parse().unwrap_or_else(|_| {
+                        eprintln!("Error: invalid --skip value '{}'", args[i + 1]);
+                        process::exit(2);
+                    });
+                    i += 2;
+                } else {
+                    eprintln!("Error: --skip requires a line count");
+                    process::exit(2);
+                }
+            }
+            "--help" | "-h" => {
+                print_usage(&args[0]);
+                return;
+            }
+            arg => {
+                paths.push(arg.to_string());
+                i += 1;
+            }
+        }
+    }
+
+    if paths.len() != 2 {
+        print_usage(&args[0]);
+        process::exit(2);
+    }
+
+    let reference_lines = load_trace(&paths[0]);
+    let actual_lines = load_trace(&paths[1]);
+
+    for (index, (reference_line, actual_line)) in reference_lines.iter().zip(actual_lines.iter()).enumerate().skip(skip) {
+        let line_number = index + 1;
+        let reference = parse_line(reference_line, &paths[0], line_number);
+        let actual = parse_line(actual_line, &paths[1], line_number);
+
+        if reference != actual {
+            print_diff(line_number, &reference, &actual);
+            process::exit(1);
+        }
+    }
+
+    if reference_lines.len() != actual_lines.len() {
+        println!(
+            "Traces match for {} lines, but lengths differ (reference: {}, actual: {})",
+            reference_lines.len().min(actual_lines.len()),
+            reference_lines.len(),
+            actual_lines.len()
+        );
+        process::exit(1);
+    }
+
+    println!("Traces match ({} lines compared)", reference_lines.len().saturating_sub(skip));
+}
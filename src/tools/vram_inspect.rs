@@ -0,0 +1,76 @@
+// VRAM dump inspector
+// Renders the tiles in a dump written by `rgb --dump-vram` to the terminal, or lists
+// the sprites in a `--dump-oam` dump
+//
+// Usage: vram_inspect <dump_file>
+
+use rgb::rgb::ppu::decode_tile_row;
+use rgb::rgb::memory_dump::{MemoryDump, OAM_MAGIC, VRAM_MAGIC};
+use std::env;
+use std::path::Path;
+use std::process;
+
+const TILE_DATA_SIZE: usize = 0x1800; // 384 tiles at 0x8000-0x97FF
+const TILE_BYTES: usize = 16;
+const TILES_PER_ROW: usize = 16;
+const SHADE_CHARS: [char; 4] = [' ', '░', '▒', '█']; // Lightest to darkest
+
+fn print_tiles(dump: &MemoryDump) {
+    if dump.data.len() < TILE_DATA_SIZE {
+        eprintln!("Error: VRAM dump holds {} bytes, expected at least {}", dump.data.len(), TILE_DATA_SIZE);
+        process::exit(2);
+    }
+
+    let tile_count = TILE_DATA_SIZE / TILE_BYTES;
+    for first_tile in (0..tile_count).step_by(TILES_PER_ROW) {
+        for row in 0..8 {
+            let mut line = if row == 0 {
+                format!("${:04X} ", 0x8000 + first_tile * TILE_BYTES)
+            } else {
+                " ".repeat(6)
+            };
+            for tile in first_tile..first_tile + TILES_PER_ROW {
+                for color in decode_tile_row(&dump.data, tile * TILE_BYTES, row) {
+                    let shade = (dump.bgp >> (color * 2)) & 0x03;
+                    line.push(SHADE_CHARS[shade as usize]);
+                }
+            }
+            println!("{}", line);
+        }
+    }
+}
+
+fn print_sprites(dump: &MemoryDump) {
+    println!("{:<6} {:>4} {:>4} {:>5} {:>6}", "Sprite", "Y", "X", "Tile", "Flags");
+    for (index, entry) in dump.data.chunks_exact(4).enumerate() {
+        println!("{:<6} {:>4} {:>4}   ${:02X}    ${:02X}", index, entry[0], entry[1], entry[2], entry[3]);
+    }
+}
+
+fn print_usage(program: &str) {
+    println!("Usage: {} <dump_file>", program);
+    println!();
+    println!("Reads a dump written by --dump-vram or --dump-oam.");
+    println!("VRAM dumps are drawn as tiles shaded through the dumped BGP; OAM dumps are listed as a sprite table.");
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() != 2 || args[1] == "--help" || args[1] == "-h" {
+        print_usage(&args[0]);
+        process::exit(if args.len() == 2 { 0 } else { 2 });
+    }
+
+    let dump = MemoryDump::load(Path::new(&args[1])).unwrap_or_else(|e| {
+        eprintln!("Error: failed to read dump '{}': {}", args[1], e);
+        process::exit(2);
+    });
+
+    println!("Frame {}, LCDC ${:02X}, BGP ${:02X}", dump.frame, dump.lcdc, dump.bgp);
+    match dump.magic {
+        VRAM_MAGIC => print_tiles(&dump),
+        OAM_MAGIC => print_sprites(&dump),
+        _ => unreachable!("MemoryDump::load only accepts known magics"),
+    }
+}
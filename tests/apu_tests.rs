@@ -0,0 +1,218 @@
+use rgb::rgb::apu::{Apu, NR10_ADDR, NR11_ADDR, NR12_ADDR, NR13_ADDR, NR14_ADDR, NR30_ADDR, NR32_ADDR, NR33_ADDR, NR34_ADDR,
+                    NR42_ADDR, NR43_ADDR, NR44_ADDR, NR50_ADDR, NR51_ADDR, NR52_ADDR, SAMPLE_RATE, WAVE_RAM_START};
+
+// Powered APU with Channel 1 panned to both sides at full master volume
+fn powered_apu() -> Apu {
+    let mut apu = Apu::new();
+    apu.write_register(NR52_ADDR, 0x80);
+    apu.write_register(NR50_ADDR, 0x77);
+    apu.write_register(NR51_ADDR, 0x11);
+    apu
+}
+
+fn run(apu: &mut Apu, cycles: u32) {
+    for _ in 0..cycles / 4 {
+        apu.step(4);
+    }
+}
+
+fn channel1_on(apu: &Apu) -> bool {
+    apu.read_register(NR52_ADDR) & 0x01 != 0
+}
+
+// Sawtooth rising from 0 to 15 twice, played by Channel 3 at full volume
+fn start_wave(apu: &mut Apu) {
+    apu.write_register(NR51_ADDR, 0x44);
+    for i in 0..16u16 {
+        let high = (i * 2) % 16;
+        apu.write_register(WAVE_RAM_START + i, ((high << 4) | (high + 1)) as u8);
+    }
+    apu.write_register(NR30_ADDR, 0x80); // DAC on
+    apu.write_register(NR32_ADDR, 0x20); // 100% volume
+    apu.write_register(NR33_ADDR, 0x00);
+    apu.write_register(NR34_ADDR, 0x87); // Trigger, frequency 0x700: 512 cycles per 32 samples
+}
+
+#[test]
+fn test_square_wave_samples() {
+    let mut apu = powered_apu();
+    apu.write_register(NR11_ADDR, 0x80); // 50% duty
+    apu.write_register(NR12_ADDR, 0xF0); // Volume 15, no envelope
+    apu.write_register(NR13_ADDR, 0x00);
+    apu.write_register(NR14_ADDR, 0x87); // Trigger, frequency 0x700 (512 Hz)
+
+    run(&mut apu, 4_194_304 / 10);
+    let samples = apu.drain_samples();
+
+    // A tenth of a second at 44100 Hz, alternating between +/- full volume
+    assert!((samples.len() as i64 - SAMPLE_RATE as i64 / 10).abs() <= 1);
+    let max = *samples.iter().max().unwrap();
+    let min = *samples.iter().min().unwrap();
+    assert!(max > 0);
+    assert_eq!(min, -max);
+    assert!(apu.drain_samples().is_empty());
+}
+
+#[test]
+fn test_envelope_fades_out() {
+    let mut apu = powered_apu();
+    apu.write_register(NR11_ADDR, 0x80);
+    apu.write_register(NR12_ADDR, 0xF1); // Volume 15, decreasing every 64 Hz tick
+    apu.write_register(NR14_ADDR, 0x87);
+
+    // 15 envelope ticks take 15/64 of a second
+    run(&mut apu, 4_194_304 / 4);
+    apu.drain_samples();
+    run(&mut apu, 8192);
+    assert!(apu.drain_samples().iter().all(|&sample| sample == 0));
+    assert!(channel1_on(&apu)); // Silent, but still running
+}
+
+#[test]
+fn test_sweep_overflow_disables_channel() {
+    let mut apu = powered_apu();
+    apu.write_register(NR10_ADDR, 0x11); // Period 1, increasing, shift 1
+    apu.write_register(NR12_ADDR, 0xF0);
+    apu.write_register(NR13_ADDR, 0x00);
+    apu.write_register(NR14_ADDR, 0x84); // Frequency 0x400
+    assert!(channel1_on(&apu));
+
+    // The first sweep tick (frame sequencer step 2) moves to 0x600, whose next step overflows
+    run(&mut apu, 8192 * 3);
+    assert!(!channel1_on(&apu));
+}
+
+#[test]
+fn test_length_counter_disables_channel() {
+    let mut apu = powered_apu();
+    apu.write_register(NR11_ADDR, 0x3F); // Length 1
+    apu.write_register(NR12_ADDR, 0xF0);
+    apu.write_register(NR14_ADDR, 0xC7); // Trigger with length enabled
+
+    assert!(channel1_on(&apu));
+    run(&mut apu, 8192);
+    assert!(!channel1_on(&apu));
+}
+
+#[test]
+fn test_power_off_clears_registers() {
+    let mut apu = powered_apu();
+    apu.write_register(NR12_ADDR, 0xF0);
+    apu.write_register(NR14_ADDR, 0x80);
+
+    apu.write_register(NR52_ADDR, 0x00);
+    assert_eq!(apu.read_register(NR52_ADDR), 0x70);
+    assert_eq!(apu.read_register(NR12_ADDR), 0x00);
+    assert_eq!(apu.read_register(NR50_ADDR), 0x00);
+
+    // Writes are ignored until the APU is powered back on
+    apu.write_register(NR50_ADDR, 0x77);
+    assert_eq!(apu.read_register(NR50_ADDR), 0x00);
+}
+
+#[test]
+fn test_wave_channel_plays_wave_ram() {
+    let mut apu = powered_apu();
+    start_wave(&mut apu);
+    assert_eq!(apu.read_register(NR52_ADDR) & 0x04, 0x04);
+
+    run(&mut apu, 4_194_304 / 10);
+    let samples = apu.drain_samples();
+
+    // Every output level of the sawtooth shows up, from the lowest to the highest sample
+    let max = *samples.iter().max().unwrap();
+    let min = *samples.iter().min().unwrap();
+    assert!(max > 0);
+    assert_eq!(min, -max);
+    let mut levels = samples.clone();
+    levels.sort();
+    levels.dedup();
+    assert_eq!(levels.len(), 16);
+}
+
+#[test]
+fn test_wave_volume_shift() {
+    let mut full = powered_apu();
+    start_wave(&mut full);
+    let mut quarter = powered_apu();
+    start_wave(&mut quarter);
+    quarter.write_register(NR32_ADDR, 0x60); // 25%: samples shifted right by 2
+
+    run(&mut full, 8192);
+    run(&mut quarter, 8192);
+    let full_max = *full.drain_samples().iter().max().unwrap();
+    let quarter_max = *quarter.drain_samples().iter().max().unwrap();
+    assert_eq!(quarter_max, full_max * 3 / 15); // Peak sample 15 >> 2 = 3, centered on 3/2
+
+    let mut muted = powered_apu();
+    start_wave(&mut muted);
+    muted.write_register(NR32_ADDR, 0x00);
+    run(&mut muted, 8192);
+    assert!(muted.drain_samples().iter().all(|&sample| sample == 0));
+}
+
+#[test]
+fn test_wave_ram_locked_while_channel3_plays() {
+    let mut apu = powered_apu();
+    apu.write_register(WAVE_RAM_START, 0x12);
+    assert_eq!(apu.read_register(WAVE_RAM_START), 0x12);
+
+    start_wave(&mut apu);
+    assert_eq!(apu.read_register(WAVE_RAM_START), 0xFF);
+    apu.write_register(WAVE_RAM_START + 1, 0x00); // Ignored
+
+    // Turning the DAC off stops the channel and hands wave RAM back to the CPU
+    apu.write_register(NR30_ADDR, 0x00);
+    assert_eq!(apu.read_register(NR52_ADDR) & 0x04, 0x00);
+    assert_eq!(apu.read_register(WAVE_RAM_START), 0x01);
+    assert_eq!(apu.read_register(WAVE_RAM_START + 1), 0x23);
+}
+
+// Triggers Channel 4 at full volume with the given NR43 noise parameters
+fn start_noise(apu: &mut Apu, nr43: u8) {
+    apu.write_register(NR51_ADDR, 0x88);
+    apu.write_register(NR42_ADDR, 0xF0);
+    apu.write_register(NR43_ADDR, nr43);
+    apu.write_register(NR44_ADDR, 0x80);
+}
+
+#[test]
+fn test_noise_lfsr_15bit_sequence() {
+    let mut apu = powered_apu();
+    start_noise(&mut apu, 0x21); // Divisor 16 << shift 2: one LFSR step every 64 cycles
+    assert_eq!(apu.read_register(NR43_ADDR), 0x21);
+    assert_eq!(apu.read_register(NR52_ADDR) & 0x08, 0x08);
+    assert_eq!(apu.noise_lfsr(), 0x7FFF);
+
+    let expected = [
+        0x3FFF, 0x1FFF, 0x0FFF, 0x07FF, 0x03FF, 0x01FF, 0x00FF, 0x007F,
+        0x003F, 0x001F, 0x000F, 0x0007, 0x0003, 0x0001, 0x4000, 0x2000,
+    ];
+    for value in expected {
+        run(&mut apu, 60);
+        assert_ne!(apu.noise_lfsr(), value); // Not clocked until the full period has passed
+        run(&mut apu, 4);
+        assert_eq!(apu.noise_lfsr(), value);
+    }
+}
+
+#[test]
+fn test_noise_lfsr_7bit_sequence() {
+    let mut apu = powered_apu();
+    start_noise(&mut apu, 0x08); // 7-bit width, divisor 8: one LFSR step every 8 cycles
+
+    let expected = [
+        0x3FBF, 0x1F9F, 0x0F8F, 0x0787, 0x0383, 0x0181, 0x40C0, 0x2020,
+        0x1010, 0x0808, 0x0404, 0x0202, 0x4141, 0x60E0, 0x3030, 0x1818,
+    ];
+    for value in expected {
+        run(&mut apu, 8);
+        assert_eq!(apu.noise_lfsr(), value);
+    }
+
+    // The output follows bit 0, so the channel produces both levels
+    run(&mut apu, 4_194_304 / 10);
+    let samples = apu.drain_samples();
+    assert!(samples.iter().any(|&sample| sample > 0));
+    assert!(samples.iter().any(|&sample| sample < 0));
+}
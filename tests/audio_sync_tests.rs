@@ -0,0 +1,43 @@
+use rgb::rgb::audio_sync::{AudioSyncBuffer, SAMPLES_PER_FRAME};
+
+#[test]
+fn test_samples_per_frame() {
+    assert_eq!(SAMPLES_PER_FRAME, 738); // 44100 / 59.7 = 738.7
+}
+
+#[test]
+fn test_drain_returns_samples_in_order() {
+    let mut buffer = AudioSyncBuffer::new(8);
+    buffer.push(&[1, 2, 3]);
+    buffer.push(&[4, 5]);
+    
+    assert_eq!(buffer.samples_available(), 5);
+    assert_eq!(buffer.drain(4), &[1, 2, 3, 4]);
+    assert_eq!(buffer.samples_available(), 1);
+    assert_eq!(buffer.underruns(), 0);
+}
+
+#[test]
+fn test_underrun_pads_with_silence() {
+    let mut buffer = AudioSyncBuffer::new(8);
+    buffer.push(&[7, 8]);
+    
+    assert_eq!(buffer.drain(4), &[7, 8, 0, 0]);
+    assert_eq!(buffer.drain(2), &[0, 0]);
+    assert_eq!(buffer.underruns(), 2);
+}
+
+#[test]
+fn test_overrun_drops_oldest_samples() {
+    let mut buffer = AudioSyncBuffer::new(4);
+    buffer.push(&[1, 2, 3]);
+    buffer.push(&[4, 5, 6]);
+    
+    assert_eq!(buffer.overruns(), 1);
+    assert_eq!(buffer.drain(4), &[3, 4, 5, 6]);
+    
+    // A push bigger than the buffer keeps only its newest samples
+    buffer.push(&[10, 11, 12, 13, 14, 15]);
+    assert_eq!(buffer.overruns(), 2);
+    assert_eq!(buffer.drain(4), &[12, 13, 14, 15]);
+}
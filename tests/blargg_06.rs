@@ -0,0 +1,63 @@
+// Blargg cpu_instrs sub-tests, run headlessly. Each ROM prints its name and then
+// "Passed" or "Failed" plus details to the serial port.
+//
+// The ROMs aren't part of the repo: set BLARGG_ROMS to the directory holding
+// cpu_instrs/individual/ to run these tests; without it they are skipped.
+use rgb::rgb::cpu::{Cpu, FrameEnd};
+use rgb::rgb::serial::{SB_ADDR, SC_ADDR};
+use rgb::rgb::memory::WatchKind;
+use std::path::{Path, PathBuf};
+
+const MAX_CYCLES: u64 = 5_000_000;
+const CYCLES_PER_FRAME: u32 = 70224;
+
+// Path of a cpu_instrs sub-test ROM, or None when BLARGG_ROMS isn't set
+fn rom_path(name: &str) -> Option<PathBuf> {
+    let dir = std::env::var_os("BLARGG_ROMS")?;
+    Some(PathBuf::from(dir).join("cpu_instrs").join("individual").join(name))
+}
+
+// Runs a ROM from the cartridge entry point, collecting each byte it sends over serial,
+// until it reports a pass or MAX_CYCLES run out (so a failure's details are all printed).
+// A write watchpoint on SC catches every transfer as it starts.
+fn run_serial_output(path: &Path) -> String {
+    let mut cpu = Cpu::new_post_boot();
+    cpu.mmap.load_cartridge(path);
+    cpu.mmap.add_watchpoint(SC_ADDR, WatchKind::Write);
+
+    let mut output = String::new();
+    let mut cycles: u64 = 0;
+    while cycles < MAX_CYCLES && !output.ends_with("Passed\n") {
+        let frame = cpu.execute_frame_until_vblank(CYCLES_PER_FRAME);
+        cycles += frame.cycles as u64;
+        if let FrameEnd::Watchpoint(SC_ADDR, value, WatchKind::Write) = frame.end {
+            if value & 0x80 != 0 {
+                output.push(cpu.mmap.read(SB_ADDR) as char);
+            }
+        }
+    }
+    output
+}
+
+fn assert_cpu_instrs_passes(name: &str) {
+    let Some(path) = rom_path(name) else {
+        eprintln!("Skipping {}: BLARGG_ROMS is not set", name);
+        return;
+    };
+    let output = run_serial_output(&path);
+    assert!(output.ends_with("Passed\n"), "{} did not pass; serial output:\n{}", name, output);
+}
+
+// One test per sub-test ROM. The others in cpu_instrs/individual can be added the same way:
+// 01-special, 02-interrupts, 03-op sp,hl, 04-op r,imm, 05-op rp, 07-jr,jp,call,ret,rst,
+// 08-misc instrs, 09-op r,r, 10-bit ops and 11-op a,(hl)
+macro_rules! cpu_instrs_test {
+    ($test:ident, $rom:expr) => {
+        #[test]
+        fn $test() {
+            assert_cpu_instrs_passes($rom);
+        }
+    };
+}
+
+cpu_instrs_test!(test_blargg_06_ld_r_r, "06-ld r,r.gb");
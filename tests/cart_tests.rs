@@ -0,0 +1,279 @@
+use rgb::rgb::cart::{is_mbc1_multicart, parse_title, ram_size_from_code, rom_size_from_code, Cart, CartridgeHeader, CartridgeType, HeaderWarning, HEADER_END};
+
+// Build a minimal 32KB ROM with a valid header and global checksum
+fn build_rom() -> Vec<u8> {
+    let mut rom = vec![0u8; 0x8000];
+    rom[0x0134..0x0134 + 4].copy_from_slice(b"TEST");
+    rom[0x0143] = 0x80; // CGB compatible
+    rom[0x0144] = b'0';
+    rom[0x0145] = b'1';
+    rom[0x0146] = 0x03; // SGB support
+    rom[0x0147] = 0x13; // MBC3+RAM+BATTERY
+    rom[0x0148] = 0x00; // 32KB
+    rom[0x0149] = 0x03; // 32KB RAM
+    rom[0x014A] = 0x01; // Overseas
+    rom[0x014B] = 0x33; // Use new licensee code
+    rom[0x014C] = 0x02;
+
+    let header_sum = rom[0x0134..=0x014C].iter().fold(0u8, |sum, &b| sum.wrapping_add(b));
+    rom[0x014D] = 0u8.wrapping_sub(header_sum.wrapping_add(0x19));
+
+    let global: u16 = rom.iter().enumerate()
+        .filter(|(addr, _)| *addr != 0x014E && *addr != 0x014F)
+        .fold(0u16, |sum, (_, &b)| sum.wrapping_add(b as u16));
+    rom[0x014E] = (global >> 8) as u8;
+    rom[0x014F] = global as u8;
+    rom
+}
+
+#[test]
+fn test_header_fields_parsed() {
+    let header = CartridgeHeader::parse(&build_rom());
+
+    assert_eq!(header.title, "TEST");
+    assert_eq!(header.cgb_flag, 0x80);
+    assert_eq!(header.new_licensee_code, "01");
+    assert_eq!(header.sgb_flag, 0x03);
+    assert_eq!(header.cartridge_type, 0x13);
+    assert_eq!(header.rom_size_bytes(), 32768);
+    assert_eq!(header.ram_size_bytes(), 32768);
+    assert_eq!(header.destination_code, 0x01);
+    assert_eq!(header.old_licensee_code, 0x33);
+    assert_eq!(header.version, 0x02);
+}
+
+#[test]
+fn test_header_checksums_valid() {
+    let header = CartridgeHeader::parse(&build_rom());
+
+    assert!(header.header_checksum_valid);
+    assert!(header.global_checksum_valid);
+}
+
+#[test]
+fn test_header_checksum_mismatch_detected() {
+    let mut rom = build_rom();
+    rom[0x014D] = rom[0x014D].wrapping_add(1);
+    let header = CartridgeHeader::parse(&rom);
+    assert!(!header.header_checksum_valid);
+
+    // Corrupting a byte outside the header only breaks the global checksum
+    let mut rom = build_rom();
+    rom[0x4000] ^= 0xFF;
+    let header = CartridgeHeader::parse(&rom);
+    assert!(header.header_checksum_valid);
+    assert!(!header.global_checksum_valid);
+}
+
+#[test]
+fn test_verify_header_checksum() {
+    let cart = Cart::from_bytes(build_rom());
+    assert!(cart.verify_header_checksum());
+    assert!(cart.header_checksum_valid());
+
+    // A bad header checksum is reported but the cartridge still loads and runs
+    let mut rom = build_rom();
+    rom[0x0134] = b'X';
+    let cart = Cart::from_bytes(rom);
+    assert!(!cart.verify_header_checksum());
+    assert!(!cart.header_checksum_valid());
+    assert_eq!(cart.get_title(), "XEST");
+
+    assert!(!Cart::from_bytes(vec![0; 0x014D]).verify_header_checksum());
+}
+
+#[test]
+fn test_verify_global_checksum() {
+    let cart = Cart::from_bytes(build_rom());
+    assert!(cart.verify_global_checksum());
+    assert!(cart.checksum_valid());
+
+    let mut rom = build_rom();
+    rom[0x014F] = rom[0x014F].wrapping_add(1);
+    let cart = Cart::from_bytes(rom);
+    assert!(!cart.verify_global_checksum());
+    assert!(!cart.checksum_valid());
+
+    // Too short to hold the checksum at all
+    assert!(!Cart::from_bytes(vec![0; 0x100]).verify_global_checksum());
+}
+
+#[test]
+fn test_parse_title_old_format_uses_15_bytes() {
+    let mut rom = vec![0u8; 0x0150];
+    rom[0x0134..=0x0143].copy_from_slice(b"FIFTEEN BYTE TTL");
+
+    // 0x0143 is never part of the title
+    assert_eq!(parse_title(&rom), "FIFTEEN BYTE TT");
+}
+
+#[test]
+fn test_parse_title_new_format_stops_before_manufacturer_code() {
+    let mut rom = vec![0u8; 0x0150];
+    rom[0x0134..0x0134 + 5].copy_from_slice(b"ZELDA");
+    rom[0x013F..=0x0142].copy_from_slice(b"AZ7E"); // Manufacturer code
+    rom[0x0143] = 0x80;
+    rom[0x014B] = 0x33;
+
+    assert_eq!(parse_title(&rom), "ZELDA");
+}
+
+#[test]
+fn test_parse_title_ignores_new_licensee_code_without_cgb_flag() {
+    let mut rom = vec![0u8; 0x0150];
+    rom[0x0134..=0x0142].copy_from_slice(b"TETRIS DX ROM00");
+    rom[0x014B] = 0x33; // New licensee code, but a DMG-only cartridge
+
+    assert_eq!(parse_title(&rom), "TETRIS DX ROM00");
+}
+
+#[test]
+fn test_parse_title_trims_nulls_and_cgb_flag() {
+    let mut rom = vec![0u8; 0x0150];
+    rom[0x0134..=0x0142].copy_from_slice(b"FIFTEEN BYTES  ");
+    rom[0x0143] = 0x01; // Not a CGB flag, and not part of the title
+
+    assert_eq!(parse_title(&rom), "FIFTEEN BYTES");
+
+    rom[0x013B..=0x0142].fill(0);
+    assert_eq!(parse_title(&rom), "FIFTEEN");
+}
+
+#[test]
+fn test_parse_title_cgb_flag_stops_before_manufacturer_code() {
+    let mut rom = vec![0u8; 0x0150];
+    rom[0x0134..=0x013E].copy_from_slice(b"POKEMON YEL");
+    rom[0x013F..=0x0142].copy_from_slice(b"APSE"); // Manufacturer code
+    rom[0x0143] = 0x80; // CGB compatible, with an old licensee code
+
+    assert_eq!(parse_title(&rom), "POKEMON YEL");
+
+    rom[0x0143] = 0xC0; // CGB only
+    assert_eq!(parse_title(&rom), "POKEMON YEL");
+    rom[0x0143] = 0x80;
+
+    let cart = Cart::from_bytes(rom);
+    assert_eq!(cart.get_title(), "POKEMON YEL");
+}
+
+// Build a 1 MiB MBC1 ROM; each 16 KiB bank is tagged with its global bank number.
+// With `multicart` set, the logo is repeated in the second sub-ROM's header.
+fn build_mbc1m_rom(multicart: bool) -> Vec<u8> {
+    let mut rom = vec![0u8; 0x100000];
+    for bank in 0..64 {
+        rom[bank * 0x4000 + 0x2000] = bank as u8;
+    }
+    let logo: Vec<u8> = (0..48).map(|i| 0xA0 ^ i as u8).collect();
+    rom[0x0104..0x0134].copy_from_slice(&logo);
+    if multicart {
+        rom[0x40104..0x40134].copy_from_slice(&logo);
+    }
+    rom[0x0147] = 0x01; // MBC1
+    rom[0x0148] = 0x05; // 1 MiB
+    rom
+}
+
+#[test]
+fn test_mbc1m_detection() {
+    assert!(is_mbc1_multicart(&build_mbc1m_rom(true)));
+    assert!(!is_mbc1_multicart(&build_mbc1m_rom(false)));
+}
+
+#[test]
+fn test_mbc1m_bank_registers_select_sub_rom() {
+    let mut cart = Cart::from_bytes(build_mbc1m_rom(true));
+
+    // Sub-ROM 1, bank 2 -> global bank 0x10 + 2
+    cart.write(0x4000, 0x01);
+    cart.write(0x2000, 0x02);
+    assert_eq!(cart.read(0x6000), 0x12);
+
+    // Bit 4 of the primary register is ignored, so 0x10 selects the sub-ROM's bank 0
+    cart.write(0x2000, 0x10);
+    assert_eq!(cart.read(0x6000), 0x10);
+
+    // Bank 0 region follows the selected sub-ROM only in mode 1
+    assert_eq!(cart.read(0x2000), 0x00);
+    cart.write(0x6000, 0x01);
+    assert_eq!(cart.read(0x2000), 0x10);
+
+    cart.write(0x4000, 0x03);
+    assert_eq!(cart.read(0x2000), 0x30);
+}
+
+#[test]
+fn test_peek_header_fields_from_file() {
+    let dir = std::env::temp_dir();
+    let header_only = dir.join(format!("rgb_peek_header_{}.gb", std::process::id()));
+    let truncated = dir.join(format!("rgb_peek_truncated_{}.gb", std::process::id()));
+    std::fs::write(&header_only, &build_rom()[..HEADER_END]).unwrap();
+    std::fs::write(&truncated, &build_rom()[..0x0100]).unwrap();
+
+    let cartridge_type = Cart::peek_cartridge_type(&header_only);
+    let title = Cart::peek_title(&header_only);
+    let truncated_type = Cart::peek_cartridge_type(&truncated);
+    let truncated_title = Cart::peek_title(&truncated);
+    std::fs::remove_file(&header_only).ok();
+    std::fs::remove_file(&truncated).ok();
+
+    assert!(matches!(cartridge_type, Some(CartridgeType::Mbc3RamBattery)));
+    assert_eq!(title.as_deref(), Some("TEST"));
+    assert!(truncated_type.is_none());
+    assert_eq!(truncated_title, None);
+    assert_eq!(Cart::peek_title(&dir.join("rgb_missing_rom.gb")), None);
+}
+
+#[test]
+fn test_rom_size_codes() {
+    let expected = [
+        (0x00, 32 * 1024), (0x01, 64 * 1024), (0x02, 128 * 1024), (0x03, 256 * 1024), (0x04, 512 * 1024),
+        (0x05, 1024 * 1024), (0x06, 2048 * 1024), (0x07, 4096 * 1024), (0x08, 8192 * 1024),
+        (0x52, 72 * 0x4000), (0x53, 80 * 0x4000), (0x54, 96 * 0x4000),
+    ];
+    for (code, size) in expected {
+        assert_eq!(rom_size_from_code(code), Some(size), "code ${:02X}", code);
+    }
+    assert_eq!(rom_size_from_code(0x09), None);
+
+    let mut rom = build_rom();
+    rom[0x0148] = 0x01;
+    assert_eq!(Cart::from_bytes(rom).rom_size(), 64 * 1024);
+}
+
+#[test]
+fn test_ram_size_codes() {
+    let expected = [(0x00, 0), (0x02, 8 * 1024), (0x03, 32 * 1024), (0x04, 128 * 1024), (0x05, 64 * 1024)];
+    for (code, size) in expected {
+        assert_eq!(ram_size_from_code(code), Some(size), "code ${:02X}", code);
+    }
+    assert_eq!(ram_size_from_code(0x01), None);
+
+    assert_eq!(Cart::from_bytes(build_rom()).ram_size_from_header(), 32 * 1024);
+}
+
+#[test]
+fn test_validate_header() {
+    assert!(Cart::from_bytes(build_rom()).validate_header().is_empty());
+
+    // A 64 KB header on a 32 KB image, and MBC3+RAM with no RAM size. Changing the
+    // header without fixing its checksum is caught too.
+    let mut rom = build_rom();
+    rom[0x0148] = 0x01;
+    rom[0x0149] = 0x00;
+    assert_eq!(Cart::from_bytes(rom).validate_header(), vec![
+        HeaderWarning::RomSizeMismatch { header: 64 * 1024, actual: 32 * 1024 },
+        HeaderWarning::HeaderChecksumMismatch,
+        HeaderWarning::RamSizeMismatch { cartridge_type: 0x13, ram_size: 0 },
+    ]);
+
+    let mut rom = build_rom();
+    rom[0x0147] = 0xFC; // Pocket Camera
+    rom[0x0148] = 0x60;
+    rom[0x0149] = 0x07;
+    assert_eq!(Cart::from_bytes(rom).validate_header(), vec![
+        HeaderWarning::UnknownRomSize(0x60),
+        HeaderWarning::UnknownRamSize(0x07),
+        HeaderWarning::HeaderChecksumMismatch,
+        HeaderWarning::UnsupportedCartridgeType(0xFC),
+    ]);
+}
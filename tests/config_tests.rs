@@ -0,0 +1,64 @@
+use rgb::rgb::config::EmulatorConfig;
+use macroquad::input::KeyCode;
+use rgb::rgb::gamepad::{GamepadInput, GamepadMapping};
+use rgb::rgb::joypad::KeyMapping;
+
+#[test]
+fn test_config_missing_fields_use_defaults() {
+    let config = EmulatorConfig::parse("debugger_window_x = 120.0\n");
+    let defaults = EmulatorConfig::default();
+
+    assert_eq!(config.debugger_window_x, 120.0);
+    assert_eq!(config.debugger_window_y, defaults.debugger_window_y);
+    assert_eq!(config.debugger_show, defaults.debugger_show);
+    assert_eq!(config.window_x, None);
+}
+
+#[test]
+fn test_config_ignores_comments_unknown_keys_and_bad_values() {
+    let text = "# saved by rgb\nfuture_option = 3\ndebugger_show = maybe\ndebugger_window_y = 75 # inline\n";
+    let config = EmulatorConfig::parse(text);
+
+    assert_eq!(config.debugger_window_y, 75.0);
+    assert!(config.debugger_show); // Invalid value keeps the default
+}
+
+#[test]
+fn test_config_round_trip() {
+    let config = EmulatorConfig {
+        debugger_window_x: 10.5,
+        debugger_window_y: 300.0,
+        debugger_show: false,
+        window_x: Some(40),
+        window_y: Some(80),
+        gamepad: GamepadMapping::default(),
+        ..EmulatorConfig::default()
+    };
+
+    assert_eq!(EmulatorConfig::parse(&config.to_toml_string()), config);
+}
+
+#[test]
+fn test_config_gamepad_section() {
+    let text = "debugger_show = false\n[gamepad]\na = \"East\"\nup = \"DPadUp, LeftStickUp\"\nb = \"Triangle\"\n";
+    let config = EmulatorConfig::parse(text);
+
+    assert!(!config.debugger_show);
+    assert_eq!(config.gamepad.a, vec![GamepadInput::East]);
+    assert_eq!(config.gamepad.up, vec![GamepadInput::DPadUp, GamepadInput::LeftStickUp]);
+    assert_eq!(config.gamepad.b, GamepadMapping::default().b); // Unknown input keeps the default
+
+    assert_eq!(EmulatorConfig::parse(&config.to_toml_string()), config);
+}
+
+#[test]
+fn test_config_keyboard_section() {
+    let text = "[keyboard]\na = \"Space\"\nselect = \"Tab\"\nb = \"NoSuchKey\"\n";
+    let config = EmulatorConfig::parse(text);
+
+    assert_eq!(config.keyboard.a_key, KeyCode::Space);
+    assert_eq!(config.keyboard.select_key, KeyCode::Tab);
+    assert_eq!(config.keyboard.b_key, KeyMapping::default().b_key); // Unknown key keeps the default
+
+    assert_eq!(EmulatorConfig::parse(&config.to_toml_string()), config);
+}
@@ -1,23 +1,17 @@
-use rgb::rgb::{cpu::Cpu, registers::Registers, memory::MemoryMap};
+use rgb::rgb::{cpu::{Cpu, CpuBuilder, EmulatorError}, registers::{FlagsRegister, Registers}, HardwareModel};
+use rgb::rgb::instructions::{ArgKind, InstructionKind};
 
 #[test]
 fn test_ld_bc_d16() {
-    let mut cpu = Cpu {
-        registers: Registers::new(),
-        pc: 0,
-        sp: 0,
-        mmap: MemoryMap::new(),
-        halted: false,
-    };
+    let mut cpu = CpuBuilder::new().build();
     
     // Write the LD BC, d16 instruction to memory
     // Opcode 0x01, followed by low byte 0x34, high byte 0x12
-    cpu.mmap.write(0x0000, 0x01);
-    cpu.mmap.write(0x0001, 0x34);
-    cpu.mmap.write(0x0002, 0x12);
+    cpu.mmap.install_rom_patch(0x0000, &[0x01, 0x34, 0x12]);
     
     // Decode and execute the instruction
     let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::LD(ArgKind::BC, ArgKind::Immediate16(0x1234)));
     cpu.execute(instruction);
     
     // Verify BC register contains 0x1234
@@ -30,19 +24,12 @@ fn test_ld_bc_d16() {
 
 #[test]
 fn test_ld_de_d16() {
-    let mut cpu = Cpu {
-        registers: Registers::new(),
-        pc: 0,
-        sp: 0,
-        mmap: MemoryMap::new(),
-        halted: false,
-    };
-    
-    cpu.mmap.write(0x0000, 0x11);
-    cpu.mmap.write(0x0001, 0xAB);
-    cpu.mmap.write(0x0002, 0xCD);
+    let mut cpu = CpuBuilder::new().build();
+    
+    cpu.mmap.install_rom_patch(0x0000, &[0x11, 0xAB, 0xCD]);
     
     let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::LD(ArgKind::DE, ArgKind::Immediate16(0xCDAB)));
     cpu.execute(instruction);
     
     assert_eq!(cpu.registers.d, 0xCD);
@@ -52,19 +39,12 @@ fn test_ld_de_d16() {
 
 #[test]
 fn test_ld_hl_d16() {
-    let mut cpu = Cpu {
-        registers: Registers::new(),
-        pc: 0,
-        sp: 0,
-        mmap: MemoryMap::new(),
-        halted: false,
-    };
-    
-    cpu.mmap.write(0x0000, 0x21);
-    cpu.mmap.write(0x0001, 0x56);
-    cpu.mmap.write(0x0002, 0x78);
+    let mut cpu = CpuBuilder::new().build();
+    
+    cpu.mmap.install_rom_patch(0x0000, &[0x21, 0x56, 0x78]);
     
     let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::LD(ArgKind::HL, ArgKind::Immediate16(0x7856)));
     cpu.execute(instruction);
     
     assert_eq!(cpu.registers.h, 0x78);
@@ -74,19 +54,12 @@ fn test_ld_hl_d16() {
 
 #[test]
 fn test_ld_sp_d16() {
-    let mut cpu = Cpu {
-        registers: Registers::new(),
-        pc: 0,
-        sp: 0,
-        mmap: MemoryMap::new(),
-        halted: false,
-    };
-    
-    cpu.mmap.write(0x0000, 0x31);
-    cpu.mmap.write(0x0001, 0xFF);
-    cpu.mmap.write(0x0002, 0xFE);
+    let mut cpu = CpuBuilder::new().build();
+    
+    cpu.mmap.install_rom_patch(0x0000, &[0x31, 0xFF, 0xFE]);
     
     let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::LD(ArgKind::SP, ArgKind::Immediate16(0xFEFF)));
     cpu.execute(instruction);
     
     assert_eq!(cpu.sp, 0xFEFF);
@@ -95,18 +68,12 @@ fn test_ld_sp_d16() {
 
 #[test]
 fn test_ld_b_d8() {
-    let mut cpu = Cpu {
-        registers: Registers::new(),
-        pc: 0,
-        sp: 0,
-        mmap: MemoryMap::new(),
-        halted: false,
-    };
+    let mut cpu = CpuBuilder::new().build();
     
-    cpu.mmap.write(0x0000, 0x06);
-    cpu.mmap.write(0x0001, 0x42);
+    cpu.mmap.install_rom_patch(0x0000, &[0x06, 0x42]);
     
     let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::LD(ArgKind::B, ArgKind::Immediate(0x42)));
     cpu.execute(instruction);
     
     assert_eq!(cpu.registers.b, 0x42);
@@ -115,18 +82,12 @@ fn test_ld_b_d8() {
 
 #[test]
 fn test_ld_a_d8() {
-    let mut cpu = Cpu {
-        registers: Registers::new(),
-        pc: 0,
-        sp: 0,
-        mmap: MemoryMap::new(),
-        halted: false,
-    };
+    let mut cpu = CpuBuilder::new().build();
     
-    cpu.mmap.write(0x0000, 0x3E);
-    cpu.mmap.write(0x0001, 0xFF);
+    cpu.mmap.install_rom_patch(0x0000, &[0x3E, 0xFF]);
     
     let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::LD(ArgKind::A, ArgKind::Immediate(0xFF)));
     cpu.execute(instruction);
     
     assert_eq!(cpu.registers.a, 0xFF);
@@ -135,18 +96,13 @@ fn test_ld_a_d8() {
 
 #[test]
 fn test_ld_b_c() {
-    let mut cpu = Cpu {
-        registers: Registers::new(),
-        pc: 0,
-        sp: 0,
-        mmap: MemoryMap::new(),
-        halted: false,
-    };
+    let mut cpu = CpuBuilder::new().build();
     
     cpu.registers.c = 0x35;
-    cpu.mmap.write(0x0000, 0x41);
+    cpu.mmap.install_rom_patch(0x0000, &[0x41]);
     
     let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::LD(ArgKind::B, ArgKind::C));
     cpu.execute(instruction);
     
     assert_eq!(cpu.registers.b, 0x35);
@@ -155,18 +111,13 @@ fn test_ld_b_c() {
 
 #[test]
 fn test_ld_a_h() {
-    let mut cpu = Cpu {
-        registers: Registers::new(),
-        pc: 0,
-        sp: 0,
-        mmap: MemoryMap::new(),
-        halted: false,
-    };
+    let mut cpu = CpuBuilder::new().build();
     
     cpu.registers.h = 0x99;
-    cpu.mmap.write(0x0000, 0x7C);
+    cpu.mmap.install_rom_patch(0x0000, &[0x7C]);
     
     let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::LD(ArgKind::A, ArgKind::H));
     cpu.execute(instruction);
     
     assert_eq!(cpu.registers.a, 0x99);
@@ -175,20 +126,263 @@ fn test_ld_a_h() {
 
 #[test]
 fn test_ld_a_a() {
-    let mut cpu = Cpu {
-        registers: Registers::new(),
-        pc: 0,
-        sp: 0,
-        mmap: MemoryMap::new(),
-        halted: false,
-    };
+    let mut cpu = CpuBuilder::new().build();
     
     cpu.registers.a = 0x77;
-    cpu.mmap.write(0x0000, 0x7F);
+    cpu.mmap.install_rom_patch(0x0000, &[0x7F]);
     
     let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::LD(ArgKind::A, ArgKind::A));
     cpu.execute(instruction);
     
     assert_eq!(cpu.registers.a, 0x77);
     assert_eq!(cpu.pc, 1);
-}
\ No newline at end of file
+}
+#[test]
+fn test_cpu_builder_sets_state() {
+    let cpu = CpuBuilder::new()
+        .with_pc(0x0150)
+        .with_sp(0xDFFF)
+        .with_registers(Registers::new_post_boot())
+        .build();
+
+    assert_eq!(cpu.pc, 0x0150);
+    assert_eq!(cpu.sp, 0xDFFF);
+    assert_eq!(cpu.registers.a, 0x01);
+    assert_eq!(cpu.registers.c, 0x13);
+    assert!(!cpu.halted);
+    assert!(!cpu.ime);
+    assert!(!cpu.ei_delay);
+    assert!(!cpu.halt_bug);
+}
+
+#[test]
+fn test_cpu_new_post_boot_state() {
+    let cpu = Cpu::new_post_boot();
+
+    assert_eq!(cpu.pc, 0x0100);
+    assert_eq!(cpu.sp, 0xFFFE);
+    assert_eq!(u8::from(cpu.registers.f), 0xB0);
+    assert_eq!(cpu.registers.f, FlagsRegister { zero: true, subtract: false, half_carry: true, carry: true });
+    assert!(!cpu.ime);
+    assert!(!cpu.ei_delay);
+    assert!(!cpu.halt_bug);
+}
+
+#[test]
+fn test_run_until_pc_returns_cycles() -> Result<(), EmulatorError> {
+    let mut cpu = CpuBuilder::new().build();
+    
+    cpu.mmap.install_rom_patch(0x0000, &[0x3E, 0x01, 0xC3, 0x50, 0x01]); // LD A, 0x01; JP 0x0150
+    
+    let cycles = cpu.run_until_pc(0x0150, 1_000_000)?;
+    assert_eq!(cycles, 8 + 16);
+    assert_eq!(cpu.registers.a, 0x01);
+    Ok(())
+}
+
+#[test]
+fn test_run_until_pc_timeout() {
+    let mut cpu = CpuBuilder::new().build();
+    
+    cpu.mmap.install_rom_patch(0x0000, &[0x18, 0xFE]); // JR -2 (spin forever)
+    
+    assert_eq!(cpu.run_until_pc(0x0150, 10_000), Err(EmulatorError::Timeout));
+}
+
+#[test]
+fn test_run_until_pc_illegal_opcode() {
+    let mut cpu = CpuBuilder::new().build();
+    
+    cpu.mmap.install_rom_patch(0x0000, &[0x00, 0xD3]); // NOP; undefined opcode
+    
+    assert_eq!(cpu.run_until_pc(0x0150, 10_000), Err(EmulatorError::IllegalOpcode(0xD3, 0x0001)));
+}
+
+#[test]
+fn test_run_until_pc_opcode_check_is_not_logged() -> Result<(), EmulatorError> {
+    let mut cpu = CpuBuilder::new().build();
+    cpu.mmap.enable_access_log(16);
+    
+    cpu.mmap.install_rom_patch(0x0000, &[0x00, 0x00]); // NOP; NOP
+    
+    cpu.run_until_pc(0x0002, 1_000)?;
+    // Only the two opcode fetches, not the illegal-opcode checks before them
+    assert_eq!(cpu.mmap.get_access_log().len(), 2);
+    Ok(())
+}
+
+#[test]
+fn test_cpu_reset_restores_power_on_state() -> Result<(), EmulatorError> {
+    let mut cpu = CpuBuilder::new().with_sp(0xFFFE).build();
+    
+    cpu.mmap.install_rom_patch(0x0000, &[0x3E, 0x42, 0x06, 0x07, 0x37, 0xFB, 0x00]); // LD A, 0x42; LD B, 0x07; SCF; EI; NOP
+    
+    cpu.run_until_pc(0x0007, 1_000)?;
+    assert!(cpu.ime);
+    
+    cpu.reset();
+    
+    assert_eq!(cpu.pc, 0x0000);
+    assert_eq!(cpu.sp, 0x0000);
+    assert_eq!((cpu.registers.a, cpu.registers.b, cpu.registers.c, cpu.registers.d), (0, 0, 0, 0));
+    assert_eq!((cpu.registers.e, cpu.registers.h, cpu.registers.l), (0, 0, 0));
+    assert_eq!(u8::from(cpu.registers.f), 0x00);
+    assert!(!cpu.ime);
+    assert!(!cpu.ei_delay);
+    assert!(!cpu.halted);
+    assert!(!cpu.halt_bug);
+    Ok(())
+}
+
+#[test]
+fn test_cpu_reset_to_post_boot_state() {
+    let mut cpu = CpuBuilder::new().with_pc(0x1234).with_sp(0xC000).build();
+    
+    cpu.reset_to_post_boot();
+    
+    assert_eq!(cpu.pc, 0x0100);
+    assert_eq!(cpu.sp, 0xFFFE);
+    assert_eq!(cpu.registers.a, 0x01);
+    assert_eq!(u8::from(cpu.registers.f), 0xB0);
+    assert_eq!(cpu.registers.get_hl(), 0x014D);
+}
+
+// Write logging only exists in paranoid_mode builds: RUSTFLAGS="--cfg paranoid_mode" cargo test
+#[cfg(paranoid_mode)]
+fn parallel_cpus(program: &[u8]) -> (Cpu, Cpu) {
+    let mut cpus = (CpuBuilder::new().with_sp(0xFFFE).build(), CpuBuilder::new().with_sp(0xFFFE).build());
+    cpus.0.mmap.install_rom_patch(0x0000, program);
+    cpus.1.mmap.install_rom_patch(0x0000, program);
+    cpus
+}
+
+#[cfg(paranoid_mode)]
+#[test]
+fn test_parallel_check_matching_cpus() {
+    let (mut cpu, mut other) = parallel_cpus(&[0x3E, 0x42, 0xEA, 0x00, 0xC0]); // LD A, 0x42; LD (0xC000), A
+    
+    for _ in 0..2 {
+        let instruction = cpu.decode();
+        assert_eq!(cpu.execute_parallel_check(&mut other, instruction), None);
+    }
+    assert_eq!(other.mmap.read(0xC000), 0x42);
+}
+
+#[cfg(paranoid_mode)]
+#[test]
+fn test_parallel_check_reports_divergence() {
+    let (mut cpu, mut other) = parallel_cpus(&[0xEA, 0x00, 0xC0]); // LD (0xC000), A
+    other.registers.a = 0x01;
+    
+    let instruction = cpu.decode();
+    let report = cpu.execute_parallel_check(&mut other, instruction).expect("CPUs should diverge");
+    assert_eq!(report.instruction, Some(InstructionKind::LD_MEM_16(ArgKind::Immediate16(0xC000), ArgKind::A)));
+    assert_eq!(report.differences, vec!["A 00 vs 01".to_string(), "writes [C000=00] vs [C000=01]".to_string()]);
+}
+
+#[test]
+fn test_reti_cancels_pending_ei_delay() {
+    let mut cpu = CpuBuilder::new().with_sp(0xFFFE).build();
+    cpu.mmap.install_rom_patch(0x0000, &[0xFB, 0xCD, 0x10, 0x00, 0xF3]); // EI; CALL 0x0010; DI
+    cpu.mmap.install_rom_patch(0x0010, &[0xD9]); // RETI
+
+    // Executed one at a time, without the step loop applying the EI delay in between
+    let instruction = cpu.decode();
+    cpu.execute(instruction);
+    assert!(cpu.ei_delay);
+    let instruction = cpu.decode();
+    cpu.execute(instruction);
+    assert_eq!(cpu.pc, 0x0010);
+
+    let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::RETI);
+    cpu.execute(instruction);
+    assert_eq!(cpu.pc, 0x0004);
+    assert!(cpu.ime);
+    assert!(!cpu.ei_delay);
+
+    // With the delay gone, the DI after RETI sticks
+    let instruction = cpu.decode();
+    cpu.execute(instruction);
+    cpu.handle_ei_delay();
+    assert!(!cpu.ime);
+}
+
+#[test]
+fn test_hardware_model_post_boot_registers() {
+    assert_eq!(HardwareModel::from_cgb_flag(0x00), HardwareModel::Dmg);
+    assert_eq!(HardwareModel::from_cgb_flag(0x80), HardwareModel::Cgb);
+    assert_eq!(HardwareModel::from_cgb_flag(0xC0), HardwareModel::Cgb);
+
+    assert_eq!(Registers::new_post_boot_for(HardwareModel::Dmg).a, 0x01);
+    let cgb = Registers::new_post_boot_for(HardwareModel::Cgb);
+    assert_eq!(cgb.a, 0x11);
+    assert_eq!(u8::from(cgb.f), 0x80);
+
+    // The model survives a reset, so resetting to post-boot keeps the CGB registers
+    let mut cpu = Cpu::new_post_boot();
+    assert_eq!(cpu.hardware_model, HardwareModel::Dmg);
+    cpu.hardware_model = HardwareModel::Cgb;
+    cpu.reset_to_post_boot();
+    assert_eq!(cpu.registers.a, 0x11);
+}
+
+#[test]
+fn test_run_frames_captures_each_frame() {
+    // Each VBlank wakes the HALT; the loop then bumps a WRAM counter and shows it through BGP
+    let mut rom = vec![0x00; 0x8000];
+    rom[0x0040] = 0xD9; // RETI
+    rom[0x0100..0x0110].copy_from_slice(&[
+        0x3E, 0x01, 0xE0, 0xFF, // LD A, 0x01; LDH (IE), A
+        0xFB,                   // EI
+        0x76,                   // loop: HALT
+        0x21, 0x00, 0xC0,       // LD HL, 0xC000
+        0x34,                   // INC (HL)
+        0x7E,                   // LD A, (HL)
+        0xE0, 0x47,             // LDH (BGP), A
+        0x18, 0xF6,             // JR loop
+        0x00,
+    ]);
+    let mut cpu = Cpu::new_post_boot();
+    cpu.mmap.load_cartridge_from_bytes(rom);
+
+    let frames = cpu.run_frames(2);
+    assert_eq!(frames.len(), 2);
+    assert_ne!(frames[0], frames[1]);
+    // The second frame is drawn with BGP = 1, so the blank top row (above the boot
+    // logo left in VRAM) is shade 1
+    assert!(frames[1][..160].iter().all(|&shade| shade == 1));
+    assert_eq!(cpu.mmap.read(0xC000), 1);
+}
+
+#[test]
+fn test_push_stack_is_little_endian_and_pops_back() {
+    let mut cpu = CpuBuilder::new().build();
+    cpu.sp = 0xFFFE;
+
+    cpu.push_stack(0x1234);
+    assert_eq!(cpu.sp, 0xFFFC);
+    assert_eq!(cpu.mmap.read(0xFFFC), 0x34, "low byte at the lower address");
+    assert_eq!(cpu.mmap.read(0xFFFD), 0x12, "high byte at the higher address");
+
+    assert_eq!(cpu.pop_stack(), 0x1234);
+    assert_eq!(cpu.sp, 0xFFFE);
+}
+
+#[test]
+fn test_run_injected_keeps_registers_and_restores_pc_and_hram() {
+    let mut cpu = CpuBuilder::new().with_pc(0x0150).build();
+    cpu.mmap.write(0xFF80, 0x12);
+
+    let cycles = cpu.run_injected(&[0x3E, 0x42, 0x3C], 1000).unwrap(); // LD A, 0x42; INC A
+    assert_eq!(cycles, 12);
+    assert_eq!(cpu.registers.a, 0x43);
+    assert_eq!(cpu.pc, 0x0150);
+    assert_eq!(cpu.mmap.read(0xFF80), 0x12);
+
+    // Code that jumps away never reaches its end
+    let result = cpu.run_injected(&[0x18, 0xFE], 100); // JR -2
+    assert_eq!(result, Err(EmulatorError::Timeout));
+    assert_eq!(cpu.pc, 0x0150);
+}
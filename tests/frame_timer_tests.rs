@@ -0,0 +1,95 @@
+use rgb::rgb::frame_timer::{Clock, FpsCounter, FrameTimer};
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+// A clock that only moves when sleeping or when the test advances it to simulate work
+#[derive(Clone)]
+struct FakeClock(Rc<Cell<Instant>>);
+
+impl FakeClock {
+    fn new() -> Self {
+        FakeClock(Rc::new(Cell::new(Instant::now())))
+    }
+
+    fn advance(&self, by: Duration) {
+        self.0.set(self.0.get() + by);
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Instant {
+        self.0.get()
+    }
+
+    fn sleep_until(&self, deadline: Instant) {
+        self.0.set(deadline);
+    }
+}
+
+#[test]
+fn test_frame_timer_no_drift_over_600_frames() {
+    let frame_duration = Duration::from_micros(16_750);
+    let clock = FakeClock::new();
+    let start = clock.now();
+    let mut timer = FrameTimer::with_clock(frame_duration, clock.clone());
+
+    // Uneven work per frame, all of it within budget
+    for frame in 0..600u64 {
+        clock.advance(Duration::from_micros(1_000 + frame % 7 * 2_000));
+        timer.wait_for_next_frame();
+    }
+
+    assert_eq!(clock.now() - start, frame_duration * 600);
+    assert_eq!(timer.stats().missed_frames, 0);
+}
+
+#[test]
+fn test_frame_timer_stats() {
+    let frame_duration = Duration::from_millis(2);
+    let clock = FakeClock::new();
+    let mut timer = FrameTimer::with_clock(frame_duration, clock.clone());
+
+    for _ in 0..9 {
+        timer.wait_for_next_frame();
+    }
+    // One frame overruns by 1ms
+    clock.advance(Duration::from_millis(3));
+    timer.wait_for_next_frame();
+
+    let stats = timer.stats();
+    assert_eq!(stats.avg_frame_us, 2_100);
+    assert_eq!(stats.max_frame_us, 3_000);
+    assert_eq!(stats.missed_frames, 1);
+}
+
+#[test]
+fn test_frame_timer_counts_missed_frames() {
+    let frame_duration = Duration::from_millis(2);
+    let clock = FakeClock::new();
+    let mut timer = FrameTimer::with_clock(frame_duration, clock.clone());
+
+    // Stall for several frames before the first wait
+    clock.advance(Duration::from_millis(9));
+    timer.wait_for_next_frame();
+    assert_eq!(timer.stats().missed_frames, 4);
+
+    // The schedule restarts from the stall instead of racing to catch up
+    timer.wait_for_next_frame();
+    assert_eq!(timer.stats().missed_frames, 4);
+    assert_eq!(timer.stats().max_frame_us, 9_000);
+}
+
+#[test]
+fn test_fps_counter_rolling_window() {
+    let mut counter = FpsCounter::new(60);
+    assert_eq!(counter.fps(), 0.0);
+
+    let start = Instant::now();
+    for frame in 0..100u32 {
+        counter.tick_at(start + Duration::from_millis(2) * frame);
+    }
+
+    // Only the last 60 intervals count, all 2ms apart
+    assert!((counter.fps() - 500.0).abs() < 1e-6, "unexpected fps {}", counter.fps());
+}
@@ -0,0 +1,66 @@
+use rgb::rgb::gamepad::{GamepadAxis, GamepadInput, GamepadMapping, GamepadState};
+use rgb::rgb::joypad::JoypadButtons;
+
+fn mapped_buttons(mapping: &GamepadMapping, gamepad: &GamepadState) -> JoypadButtons {
+    let mut buttons = JoypadButtons::new();
+    mapping.apply(gamepad, &mut buttons);
+    buttons
+}
+
+#[test]
+fn test_default_mapping() {
+    let mapping = GamepadMapping::default();
+    let mut gamepad = GamepadState::new();
+    gamepad.connect(0, "Test Pad");
+    gamepad.set_button(0, GamepadInput::South, true);
+    gamepad.set_button(0, GamepadInput::DPadLeft, true);
+    gamepad.set_axis(0, GamepadAxis::LeftStickY, 1.0); // Sticks are unmapped by default
+
+    let buttons = mapped_buttons(&mapping, &gamepad);
+    assert!(buttons.a && buttons.left);
+    assert!(!buttons.b && !buttons.up && !buttons.start);
+
+    gamepad.set_button(0, GamepadInput::South, false);
+    assert!(!mapped_buttons(&mapping, &gamepad).a);
+}
+
+#[test]
+fn test_stick_mapping_uses_threshold() {
+    let mut mapping = GamepadMapping::default();
+    assert!(mapping.set_binding("up", "DPadUp, LeftStickUp"));
+    assert!(!mapping.set_binding("turbo", "South"));
+    let mut gamepad = GamepadState::new();
+    gamepad.connect(0, "Test Pad");
+
+    gamepad.set_axis(0, GamepadAxis::LeftStickY, 0.3);
+    assert!(!mapped_buttons(&mapping, &gamepad).up);
+    gamepad.set_axis(0, GamepadAxis::LeftStickY, 0.8);
+    assert!(mapped_buttons(&mapping, &gamepad).up);
+    gamepad.set_axis(0, GamepadAxis::LeftStickY, -0.8);
+    assert!(!mapped_buttons(&mapping, &gamepad).up);
+    assert!(gamepad.is_pressed(GamepadInput::LeftStickDown));
+}
+
+#[test]
+fn test_first_connected_controller_is_active() {
+    let mut gamepad = GamepadState::new();
+    assert_eq!(gamepad.active(), None);
+
+    gamepad.connect(3, "First");
+    gamepad.connect(5, "Second");
+    assert_eq!(gamepad.active(), Some(3));
+
+    // Only the active controller's input counts
+    gamepad.set_button(5, GamepadInput::Start, true);
+    assert!(!gamepad.is_pressed(GamepadInput::Start));
+
+    gamepad.set_button(3, GamepadInput::Start, true);
+    gamepad.disconnect(3);
+    assert_eq!(gamepad.active(), Some(5));
+    assert!(!gamepad.is_pressed(GamepadInput::Start)); // Held inputs are released
+
+    gamepad.disconnect(5);
+    assert_eq!(gamepad.active(), None);
+    gamepad.connect(7, "Hot-plugged");
+    assert_eq!(gamepad.active(), Some(7));
+}
@@ -1,19 +1,21 @@
-use rgb::rgb::{cpu::Cpu, registers::Registers, memory::MemoryMap};
+use rgb::rgb::cpu::{CpuBuilder, FrameEnd};
+use rgb::rgb::instructions::{decode_cb_instruction, decode_instruction, get_instruction_size, is_illegal_opcode, ArgKind, InstructionKind, JumpCondition};
+use rgb::rgb::instructions::{decode_arithmetic_instruction, decode_control_flow_instruction, decode_cpu_control_instruction, decode_increment_instruction,
+                             decode_load_instruction, decode_logical_instruction, decode_memory_instruction, decode_stack_instruction};
+use rgb::rgb::instructions::{get_arithmetic_instruction_size, get_control_flow_instruction_size, get_cpu_control_instruction_size, get_increment_instruction_size,
+                             get_load_instruction_size, get_logical_instruction_size, get_memory_instruction_size, get_stack_instruction_size};
+use rgb::rgb::instruction_timing::get_instruction_cycles;
+use rgb::rgb::memory::KEY1_ADDR;
 
 #[test]
 fn test_inc_a() {
-    let mut cpu = Cpu {
-        registers: Registers::new(),
-        pc: 0,
-        sp: 0,
-        mmap: MemoryMap::new(),
-        halted: false,
-    };
+    let mut cpu = CpuBuilder::new().build();
     
     cpu.registers.a = 0x0F;
-    cpu.mmap.write(0x0000, 0x3C); // INC A
+    cpu.mmap.install_rom_patch(0x0000, &[0x3C]); // INC A
     
     let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::INC(ArgKind::A));
     cpu.execute(instruction);
     
     assert_eq!(cpu.registers.a, 0x10);
@@ -25,18 +27,13 @@ fn test_inc_a() {
 
 #[test]
 fn test_inc_b_zero_flag() {
-    let mut cpu = Cpu {
-        registers: Registers::new(),
-        pc: 0,
-        sp: 0,
-        mmap: MemoryMap::new(),
-        halted: false,
-    };
+    let mut cpu = CpuBuilder::new().build();
     
     cpu.registers.b = 0xFF;
-    cpu.mmap.write(0x0000, 0x04); // INC B
+    cpu.mmap.install_rom_patch(0x0000, &[0x04]); // INC B
     
     let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::INC(ArgKind::B));
     cpu.execute(instruction);
     
     assert_eq!(cpu.registers.b, 0x00);
@@ -48,19 +45,13 @@ fn test_inc_b_zero_flag() {
 
 #[test]
 fn test_bit_0_b_set() {
-    let mut cpu = Cpu {
-        registers: Registers::new(),
-        pc: 0,
-        sp: 0,
-        mmap: MemoryMap::new(),
-        halted: false,
-    };
+    let mut cpu = CpuBuilder::new().build();
     
     cpu.registers.b = 0x01; // Bit 0 is set
-    cpu.mmap.write(0x0000, 0xCB); // CB prefix
-    cpu.mmap.write(0x0001, 0x40); // BIT 0, B
+    cpu.mmap.install_rom_patch(0x0000, &[0xCB, 0x40]); // BIT 0, B
     
     let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::BIT(0, ArgKind::B));
     cpu.execute(instruction);
     
     assert!(!cpu.registers.f.zero); // Bit is set, so Z=0
@@ -71,17 +62,10 @@ fn test_bit_0_b_set() {
 
 #[test]
 fn test_bit_0_b_clear() {
-    let mut cpu = Cpu {
-        registers: Registers::new(),
-        pc: 0,
-        sp: 0,
-        mmap: MemoryMap::new(),
-        halted: false,
-    };
+    let mut cpu = CpuBuilder::new().build();
     
     cpu.registers.b = 0xFE; // Bit 0 is clear
-    cpu.mmap.write(0x0000, 0xCB); // CB prefix
-    cpu.mmap.write(0x0001, 0x40); // BIT 0, B
+    cpu.mmap.install_rom_patch(0x0000, &[0xCB, 0x40]); // BIT 0, B
     
     let instruction = cpu.decode();
     cpu.execute(instruction);
@@ -92,21 +76,150 @@ fn test_bit_0_b_clear() {
     assert_eq!(cpu.pc, 2);
 }
 
+#[test]
+fn test_bit_hl_takes_12_cycles() {
+    let mut cpu = CpuBuilder::new().build();
+    
+    cpu.registers.set_hl(0xC000);
+    cpu.mmap.write(0xC000, 0x80);
+    cpu.mmap.install_rom_patch(0x0000, &[0xCB, 0x7E]); // BIT 7, (HL)
+    
+    let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::BIT(7, ArgKind::HL));
+    assert_eq!(cpu.execute(instruction), 12);
+    assert!(!cpu.registers.f.zero);
+}
+
+#[test]
+fn test_adc_carry_in_wraps_to_zero() {
+    let mut cpu = CpuBuilder::new().build();
+    
+    cpu.registers.a = 0xFF;
+    cpu.registers.b = 0x00;
+    cpu.registers.f.carry = true;
+    cpu.mmap.install_rom_patch(0x0000, &[0x88]); // ADC A, B
+    
+    let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::ADC(ArgKind::A, ArgKind::B));
+    cpu.execute(instruction);
+    
+    assert_eq!(cpu.registers.a, 0x00);
+    assert!(cpu.registers.f.zero);
+    assert!(!cpu.registers.f.subtract);
+    assert!(cpu.registers.f.half_carry);
+    assert!(cpu.registers.f.carry);
+}
+
+#[test]
+fn test_inc_hl_mem_takes_12_cycles_and_sets_half_carry() {
+    let mut cpu = CpuBuilder::new().build();
+    
+    cpu.registers.set_hl(0xC000);
+    cpu.mmap.write(0xC000, 0x0F);
+    cpu.mmap.install_rom_patch(0x0000, &[0x34]); // INC (HL)
+    
+    let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::INC_MEM(ArgKind::HL));
+    assert_eq!(cpu.execute(instruction), 12);
+    assert_eq!(cpu.mmap.read(0xC000), 0x10);
+    assert!(cpu.registers.f.half_carry);
+}
+
+#[test]
+fn test_dec_hl_mem_takes_12_cycles_and_sets_half_carry() {
+    let mut cpu = CpuBuilder::new().build();
+    
+    cpu.registers.set_hl(0xC000);
+    cpu.mmap.write(0xC000, 0x10);
+    cpu.mmap.install_rom_patch(0x0000, &[0x35]); // DEC (HL)
+    
+    let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::DEC_MEM(ArgKind::HL));
+    assert_eq!(cpu.execute(instruction), 12);
+    assert_eq!(cpu.mmap.read(0xC000), 0x0F);
+    assert!(cpu.registers.f.half_carry); // Borrow from bit 4
+    assert!(cpu.registers.f.subtract);
+}
+
+#[test]
+fn test_set_hl_takes_16_cycles() {
+    let mut cpu = CpuBuilder::new().build();
+    
+    cpu.registers.set_hl(0xC000);
+    cpu.mmap.write(0xC000, 0x00);
+    cpu.mmap.install_rom_patch(0x0000, &[0xCB, 0xDE]); // SET 3, (HL)
+    
+    let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::SET(3, ArgKind::HL));
+    assert_eq!(cpu.execute(instruction), 16);
+    assert_eq!(cpu.mmap.read(0xC000), 0x08);
+}
+
+#[test]
+fn test_res_hl_takes_16_cycles() {
+    let mut cpu = CpuBuilder::new().build();
+    
+    cpu.registers.set_hl(0xC000);
+    cpu.mmap.write(0xC000, 0xFF);
+    cpu.mmap.install_rom_patch(0x0000, &[0xCB, 0x86]); // RES 0, (HL)
+    
+    let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::RES(0, ArgKind::HL));
+    assert_eq!(cpu.execute(instruction), 16);
+    assert_eq!(cpu.mmap.read(0xC000), 0xFE);
+}
+
+#[test]
+fn test_bit_set_res_hl_every_bit() {
+    for bit in 0..8u8 {
+        // BIT b, (HL) is 0x46 + 8b; RES and SET follow at 0x86 and 0xC6
+        for (cb_opcode, start, expected_value, zero) in [
+            (0x46 + bit * 8, !(1u8 << bit), !(1u8 << bit), Some(true)),
+            (0x86 + bit * 8, 0xFF, !(1u8 << bit), None),
+            (0xC6 + bit * 8, 0x00, 1u8 << bit, None),
+        ] {
+            let mut cpu = CpuBuilder::new().build();
+            cpu.registers.set_hl(0xC000);
+            cpu.mmap.write(0xC000, start);
+            cpu.mmap.install_rom_patch(0x0000, &[0xCB, cb_opcode]);
+
+            let instruction = cpu.decode();
+            let expected_cycles = get_instruction_cycles(&instruction.kind, false);
+            assert_eq!(cpu.execute(instruction), expected_cycles, "CB {:02X}", cb_opcode);
+            assert_eq!(expected_cycles, if zero.is_some() { 12 } else { 16 }, "CB {:02X}", cb_opcode);
+            assert_eq!(cpu.mmap.read(0xC000), expected_value, "CB {:02X}", cb_opcode);
+            if let Some(zero) = zero {
+                assert_eq!(cpu.registers.f.zero, zero, "CB {:02X}", cb_opcode);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_ld_hl_immediate_takes_12_cycles() {
+    let mut cpu = CpuBuilder::new().build();
+    cpu.registers.set_hl(0xC000);
+    cpu.registers.b = 0x17;
+    cpu.mmap.install_rom_patch(0x0000, &[0x36, 0x42, 0x70]); // LD (HL), 0x42; LD (HL), B
+
+    let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::LD_MEM(ArgKind::HL, ArgKind::Immediate(0x42)));
+    assert_eq!(cpu.execute(instruction), 12);
+    assert_eq!(cpu.mmap.read(0xC000), 0x42);
+
+    let instruction = cpu.decode();
+    assert_eq!(cpu.execute(instruction), 8);
+    assert_eq!(cpu.mmap.read(0xC000), 0x17);
+}
+
 #[test]
 fn test_jp_always() {
-    let mut cpu = Cpu {
-        registers: Registers::new(),
-        pc: 0,
-        sp: 0,
-        mmap: MemoryMap::new(),
-        halted: false,
-    };
+    let mut cpu = CpuBuilder::new().build();
     
-    cpu.mmap.write(0x0000, 0xC3); // JP a16
-    cpu.mmap.write(0x0001, 0x34); // Low byte
-    cpu.mmap.write(0x0002, 0x12); // High byte
+    cpu.mmap.install_rom_patch(0x0000, &[0xC3, 0x34, 0x12]); // JP a16
     
     let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::JP(JumpCondition::Always, 0x1234));
     cpu.execute(instruction);
     
     assert_eq!(cpu.pc, 0x1234);
@@ -114,20 +227,13 @@ fn test_jp_always() {
 
 #[test]
 fn test_jp_zero_condition_true() {
-    let mut cpu = Cpu {
-        registers: Registers::new(),
-        pc: 0,
-        sp: 0,
-        mmap: MemoryMap::new(),
-        halted: false,
-    };
+    let mut cpu = CpuBuilder::new().build();
     
     cpu.registers.f.zero = true;
-    cpu.mmap.write(0x0000, 0xCA); // JP Z, a16
-    cpu.mmap.write(0x0001, 0x78); // Low byte
-    cpu.mmap.write(0x0002, 0x56); // High byte
+    cpu.mmap.install_rom_patch(0x0000, &[0xCA, 0x78, 0x56]); // JP Z, a16
     
     let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::JP(JumpCondition::Zero, 0x5678));
     cpu.execute(instruction);
     
     assert_eq!(cpu.pc, 0x5678);
@@ -135,18 +241,10 @@ fn test_jp_zero_condition_true() {
 
 #[test]
 fn test_jp_zero_condition_false() {
-    let mut cpu = Cpu {
-        registers: Registers::new(),
-        pc: 0,
-        sp: 0,
-        mmap: MemoryMap::new(),
-        halted: false,
-    };
+    let mut cpu = CpuBuilder::new().build();
     
     cpu.registers.f.zero = false;
-    cpu.mmap.write(0x0000, 0xCA); // JP Z, a16
-    cpu.mmap.write(0x0001, 0x78); // Low byte
-    cpu.mmap.write(0x0002, 0x56); // High byte
+    cpu.mmap.install_rom_patch(0x0000, &[0xCA, 0x78, 0x56]); // JP Z, a16
     
     let instruction = cpu.decode();
     cpu.execute(instruction);
@@ -156,29 +254,23 @@ fn test_jp_zero_condition_false() {
 
 #[test]
 fn test_call_and_ret() {
-    let mut cpu = Cpu {
-        registers: Registers::new(),
-        pc: 0x100,
-        sp: 0xFFFE,
-        mmap: MemoryMap::new(),
-        halted: false,
-    };
+    let mut cpu = CpuBuilder::new().with_pc(0x100).with_sp(0xFFFE).build();
     
     // Test CALL
-    cpu.mmap.write(0x0100, 0xCD); // CALL a16
-    cpu.mmap.write(0x0101, 0x00); // Low byte
-    cpu.mmap.write(0x0102, 0x02); // High byte
+    cpu.mmap.install_rom_patch(0x0100, &[0xCD, 0x00, 0x02]); // CALL 0x0200
     
     let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::CALL(0x0200));
     cpu.execute(instruction);
     
     assert_eq!(cpu.pc, 0x0200); // Should jump to called address
     assert_eq!(cpu.sp, 0xFFFC); // Stack pointer should decrease by 2
     
     // Test RET
-    cpu.mmap.write(0x0200, 0xC9); // RET
+    cpu.mmap.install_rom_patch(0x0200, &[0xC9]); // RET
     
     let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::RET);
     cpu.execute(instruction);
     
     assert_eq!(cpu.pc, 0x103); // Should return to address after CALL
@@ -187,19 +279,605 @@ fn test_call_and_ret() {
 
 #[test]
 fn test_halt() {
-    let mut cpu = Cpu {
-        registers: Registers::new(),
-        pc: 0,
-        sp: 0,
-        mmap: MemoryMap::new(),
-        halted: false,
-    };
+    let mut cpu = CpuBuilder::new().build();
     
-    cpu.mmap.write(0x0000, 0x76); // HALT
+    cpu.mmap.install_rom_patch(0x0000, &[0x76]); // HALT
     
     let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::HALT);
     cpu.execute(instruction);
     
     assert!(cpu.halted);
     assert_eq!(cpu.pc, 1);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_stop_without_speed_switch_halts() {
+    let mut cpu = CpuBuilder::new().build();
+    
+    cpu.mmap.install_rom_patch(0x0000, &[0x10, 0x00]); // STOP
+    
+    let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::STOP);
+    cpu.execute(instruction);
+    
+    assert!(cpu.halted);
+    assert_eq!(cpu.speed_multiplier, 1);
+    assert_eq!(cpu.pc, 2);
+}
+
+#[test]
+fn test_write_log_records_ld_a16_a() {
+    let mut cpu = CpuBuilder::new().build();
+    
+    cpu.mmap.install_rom_patch(0x0000, &[0xEA, 0x42, 0xFF]); // LD (0xFF42), A
+    cpu.registers.a = 0x10;
+    cpu.mmap.enable_write_log();
+    
+    let instruction = cpu.decode();
+    cpu.execute(instruction);
+    
+    assert_eq!(cpu.mmap.take_write_log(), vec![(0xFF42, 0x10)]);
+    assert_eq!(cpu.mmap.read(0xFF42), 0x10); // SCY
+    assert!(cpu.mmap.take_write_log().is_empty(), "taking the log clears it");
+}
+
+#[test]
+fn test_stop_consumes_operand_byte() {
+    let mut cpu = CpuBuilder::new().build();
+    
+    cpu.mmap.install_rom_patch(0x0000, &[0x10, 0x00, 0x3C]); // STOP; INC A
+    
+    let instruction = cpu.decode();
+    cpu.execute(instruction);
+    assert_eq!(cpu.pc, 2, "PC skips the 0x00 after STOP");
+    
+    let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::INC(ArgKind::A));
+}
+
+#[test]
+fn test_stop_with_joypad_interrupt_pending_returns_immediately() {
+    let mut cpu = CpuBuilder::new().build();
+    
+    cpu.mmap.install_rom_patch(0x0000, &[0x10, 0x00]); // STOP
+    cpu.mmap.write(0xFF0F, 0x10); // Joypad interrupt flagged
+    
+    let instruction = cpu.decode();
+    cpu.execute(instruction);
+    
+    assert!(!cpu.halted);
+    assert_eq!(cpu.pc, 2);
+}
+
+#[test]
+fn test_stop_toggles_double_speed_when_key1_armed() {
+    let mut cpu = CpuBuilder::new().build();
+    
+    cpu.mmap.install_rom_patch(0x0000, &[0x10, 0x00, 0x10, 0x00]); // STOP; STOP
+    cpu.mmap.write(KEY1_ADDR, 0xFF); // Only the prepare bit sticks
+    assert_eq!(cpu.mmap.read(KEY1_ADDR), 0x7F);
+    
+    let instruction = cpu.decode();
+    cpu.execute(instruction);
+    assert!(!cpu.halted);
+    assert_eq!(cpu.speed_multiplier, 2);
+    assert_eq!(cpu.mmap.read(KEY1_ADDR), 0xFE); // Double speed, request cleared
+    
+    // Switching back needs the prepare bit set again
+    cpu.mmap.write(KEY1_ADDR, 0x01);
+    let instruction = cpu.decode();
+    cpu.execute(instruction);
+    assert_eq!(cpu.speed_multiplier, 1);
+    assert_eq!(cpu.mmap.read(KEY1_ADDR), 0x7E);
+}
+
+#[test]
+fn test_halt_wakes_on_timer_within_frame() {
+    let mut cpu = CpuBuilder::new().build();
+    
+    cpu.mmap.install_rom_patch(0x0000, &[0x76]); // HALT, followed by NOPs
+    cpu.mmap.write(0xFF40, 0x00); // LCD off so the frame ends on the cycle limit
+    cpu.mmap.write(0xFFFF, 0x04); // IE: timer
+    cpu.mmap.write(0xFF06, 0x00); // TMA
+    cpu.mmap.write(0xFF05, 0xFE); // TIMA close to overflow
+    cpu.mmap.write(0xFF07, 0x05); // TAC: enabled, 16 cycles per tick
+    
+    let result = cpu.execute_frame_until_vblank(1000);
+    
+    assert_eq!(result.end, FrameEnd::CycleLimit);
+    assert!(!cpu.halted);
+    assert!(result.cycles >= 1000);
+    // HALT plus NOPs executed after the timer woke the CPU
+    assert!(result.instructions > 1);
+    assert!(cpu.pc > 1);
+}
+
+#[test]
+fn test_frame_ends_on_vblank() {
+    let mut cpu = CpuBuilder::new().build();
+    
+    cpu.mmap.write(0xFF40, 0x91); // LCD on
+    
+    let result = cpu.execute_frame_until_vblank(70224 * 2);
+    
+    assert_eq!(result.end, FrameEnd::VBlank);
+    assert!(result.cycles <= 70224);
+    assert_ne!(cpu.mmap.read(0xFF0F) & 0x01, 0);
+}
+
+#[test]
+fn test_frame_without_vblank_ends_at_cycle_limit_or_instruction_cap() {
+    // LCD off, so only the cycle limit ends the frame; NOPs fill the fake cartridge
+    let mut cpu = CpuBuilder::new().build();
+    cpu.mmap.write(0xFF40, 0x00);
+    let result = cpu.execute_frame_until_vblank(70224);
+    assert_eq!(result.end, FrameEnd::CycleLimit);
+    assert_eq!(result.instructions, 70224 / 4);
+
+    cpu.max_instructions_per_frame = 100;
+    let result = cpu.execute_frame_until_vblank(70224);
+    assert_eq!(result.end, FrameEnd::InstructionLimit);
+    assert_eq!(result.instructions, 100);
+}
+
+#[test]
+fn test_logical_immediates_are_decoded() {
+    for (opcode, kind) in [
+        (0xE6, InstructionKind::AND(ArgKind::A, ArgKind::Immediate(0x0F))),
+        (0xEE, InstructionKind::XOR(ArgKind::A, ArgKind::Immediate(0x0F))),
+        (0xF6, InstructionKind::OR(ArgKind::A, ArgKind::Immediate(0x0F))),
+        (0xFE, InstructionKind::CP(ArgKind::A, ArgKind::Immediate(0x0F))),
+    ] {
+        assert_eq!(decode_logical_instruction(opcode, Some(0x0F)), Some(kind));
+    }
+
+    let mut cpu = CpuBuilder::new().build();
+    cpu.registers.a = 0x5A;
+    cpu.mmap.install_rom_patch(0x0000, &[0xE6, 0x0F]); // AND A,0x0F
+    
+    let instruction = cpu.decode();
+    cpu.execute(instruction);
+    
+    assert_eq!(cpu.registers.a, 0x0A);
+    assert!(!cpu.registers.f.zero);
+    assert!(cpu.registers.f.half_carry);
+}
+
+#[test]
+fn test_add_sp_r8_negative() {
+    let mut cpu = CpuBuilder::new().with_sp(0x0010).build();
+    
+    cpu.mmap.install_rom_patch(0x0000, &[0xE8, 0xF8]); // ADD SP,-8
+    
+    let instruction = cpu.decode();
+    let cycles = cpu.execute(instruction);
+    
+    assert_eq!(cpu.sp, 0x0008);
+    assert_eq!(cpu.pc, 2);
+    assert_eq!(cycles, 16);
+    // Flags come from the unsigned low-byte add 0x10 + 0xF8
+    assert!(!cpu.registers.f.zero);
+    assert!(!cpu.registers.f.subtract);
+    assert!(!cpu.registers.f.half_carry);
+    assert!(cpu.registers.f.carry);
+}
+
+#[test]
+fn test_add_sp_r8_positive() {
+    assert_eq!(decode_arithmetic_instruction(0xE8, Some(0x10)), Some(InstructionKind::ADD_SP_R8(0x10)));
+    assert_eq!(get_arithmetic_instruction_size(0xE8), Some(2));
+
+    let mut cpu = CpuBuilder::new().with_sp(0x0100).build();
+    
+    cpu.mmap.install_rom_patch(0x0000, &[0xE8, 0x10]); // ADD SP,16
+    
+    let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::ADD_SP_R8(0x10));
+    let cycles = cpu.execute(instruction);
+    
+    assert_eq!(cpu.sp, 0x0110);
+    assert_eq!(cpu.pc, 2);
+    assert_eq!(cycles, 16);
+    assert!(!cpu.registers.f.half_carry);
+    assert!(!cpu.registers.f.carry);
+}
+
+#[test]
+fn test_ld_hl_sp_r8_negative() {
+    let mut cpu = CpuBuilder::new().with_sp(0xFFFF).build();
+    
+    cpu.mmap.install_rom_patch(0x0000, &[0xF8, 0xFF]); // LD HL,SP-1
+    
+    let instruction = cpu.decode();
+    let cycles = cpu.execute(instruction);
+    
+    assert_eq!(cpu.registers.get_hl(), 0xFFFE);
+    assert_eq!(cpu.sp, 0xFFFF);
+    assert_eq!(cycles, 12);
+    // 0xFF + 0xFF carries out of both bit 3 and bit 7
+    assert!(!cpu.registers.f.zero);
+    assert!(!cpu.registers.f.subtract);
+    assert!(cpu.registers.f.half_carry);
+    assert!(cpu.registers.f.carry);
+}
+
+#[test]
+fn test_ld_hl_sp_r8_negative_no_carry() {
+    let mut cpu = CpuBuilder::new().with_sp(0x0000).build();
+    
+    cpu.mmap.install_rom_patch(0x0000, &[0xF8, 0xFE]); // LD HL,SP-2
+    
+    let instruction = cpu.decode();
+    cpu.execute(instruction);
+    
+    // Wraps below zero, but the low-byte add 0x00 + 0xFE carries nowhere
+    assert_eq!(cpu.registers.get_hl(), 0xFFFE);
+    assert!(!cpu.registers.f.half_carry);
+    assert!(!cpu.registers.f.carry);
+}
+
+#[test]
+fn test_push_af_pop_af_round_trip_zeroes_low_nibble() {
+    let mut cpu = CpuBuilder::new().with_sp(0xFFFE).build();
+    
+    cpu.registers.set_bc(0x12FF);
+    cpu.mmap.install_rom_patch(0x0000, &[0xC5, 0xF1, 0xF5, 0xC1]); // PUSH BC; POP AF; PUSH AF; POP BC
+    
+    let instruction = cpu.decode(); // PUSH BC
+    cpu.execute(instruction);
+    let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::POP(ArgKind::AF));
+    cpu.execute(instruction);
+    
+    assert_eq!(cpu.registers.a, 0x12);
+    assert_eq!(u8::from(cpu.registers.f), 0xF0);
+    
+    let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::PUSH(ArgKind::AF));
+    cpu.execute(instruction);
+    assert_eq!(cpu.mmap.read(0xFFFC), 0xF0); // F as pushed
+    assert_eq!(cpu.mmap.read(0xFFFD), 0x12); // A as pushed
+    
+    let instruction = cpu.decode(); // POP BC
+    cpu.execute(instruction);
+    assert_eq!(cpu.registers.get_bc(), 0x12F0);
+    assert_eq!(cpu.sp, 0xFFFE);
+}
+
+#[test]
+fn test_pop_af_masks_flags() {
+    let mut cpu = CpuBuilder::new().with_sp(0xFFFC).build();
+    
+    cpu.mmap.write(0xFFFC, 0xFF); // F
+    cpu.mmap.write(0xFFFD, 0xFF); // A
+    cpu.mmap.install_rom_patch(0x0000, &[0xF1]); // POP AF
+    
+    let instruction = cpu.decode();
+    assert_eq!(instruction.kind, InstructionKind::POP(ArgKind::AF));
+    cpu.execute(instruction);
+    
+    assert_eq!(cpu.registers.a, 0xFF);
+    assert_eq!(u8::from(cpu.registers.f), 0xF0);
+    assert_eq!(cpu.registers.get_af(), 0xFFF0);
+    assert_eq!(cpu.sp, 0xFFFE);
+}
+
+#[test]
+fn test_ld_block_decodes_every_opcode() {
+    // 0x40-0x7F: destination in bits 3-5, source in bits 0-2, index 6 is (HL)
+    let registers = [ArgKind::B, ArgKind::C, ArgKind::D, ArgKind::E, ArgKind::H, ArgKind::L, ArgKind::HL, ArgKind::A];
+    for opcode in (0x40..=0x7Fu8).filter(|&opcode| opcode != 0x76) { // 0x76 is HALT
+        let dest = registers[((opcode >> 3) & 0x07) as usize];
+        let src = registers[(opcode & 0x07) as usize];
+        let expected = match (dest, src) {
+            (ArgKind::HL, src) => InstructionKind::LD_MEM(ArgKind::HL, src),
+            (dest, ArgKind::HL) => InstructionKind::LD_FROM_MEM(dest, ArgKind::HL),
+            (dest, src) => InstructionKind::LD(dest, src),
+        };
+        
+        assert_eq!(decode_instruction(opcode, None, None), expected, "opcode {:02X}", opcode);
+        assert_eq!(get_instruction_size(opcode), 1, "opcode {:02X}", opcode);
+    }
+}
+
+#[test]
+fn test_self_loads_change_nothing() {
+    let self_loads = [
+        (0x40, ArgKind::B), (0x49, ArgKind::C), (0x52, ArgKind::D), (0x5B, ArgKind::E),
+        (0x64, ArgKind::H), (0x6D, ArgKind::L), (0x7F, ArgKind::A),
+    ];
+    for (opcode, register) in self_loads {
+        let mut cpu = CpuBuilder::new().build();
+        cpu.registers.a = 0x11;
+        cpu.registers.b = 0x22;
+        cpu.registers.c = 0x33;
+        cpu.registers.d = 0x44;
+        cpu.registers.e = 0x55;
+        cpu.registers.h = 0x66;
+        cpu.registers.l = 0x77;
+        cpu.registers.f.carry = true;
+        cpu.mmap.install_rom_patch(0x0000, &[opcode]); // LD r, r
+        
+        let instruction = cpu.decode();
+        assert_eq!(instruction.kind, InstructionKind::LD(register, register), "opcode {:02X}", opcode);
+        let cycles = cpu.execute(instruction);
+        
+        assert_eq!(cycles, 4, "opcode {:02X}", opcode);
+        assert_eq!(cpu.pc, 1);
+        assert_eq!(cpu.registers.get_af(), 0x1110, "opcode {:02X}", opcode);
+        assert_eq!(cpu.registers.get_bc(), 0x2233, "opcode {:02X}", opcode);
+        assert_eq!(cpu.registers.get_de(), 0x4455, "opcode {:02X}", opcode);
+        assert_eq!(cpu.registers.get_hl(), 0x6677, "opcode {:02X}", opcode);
+    }
+}
+
+#[test]
+fn test_cpl() {
+    let mut cpu = CpuBuilder::new().build();
+    
+    cpu.registers.a = 0b1010_1010;
+    cpu.mmap.install_rom_patch(0x0000, &[0x2F]); // CPL
+    
+    let instruction = cpu.decode();
+    cpu.execute(instruction);
+    
+    assert_eq!(cpu.registers.a, 0b0101_0101);
+    assert!(cpu.registers.f.subtract);
+    assert!(cpu.registers.f.half_carry);
+    assert_eq!(cpu.pc, 1);
+}
+
+#[test]
+fn test_scf() {
+    let mut cpu = CpuBuilder::new().build();
+    
+    cpu.registers.f.subtract = true;
+    cpu.registers.f.half_carry = true;
+    cpu.registers.f.carry = false;
+    cpu.mmap.install_rom_patch(0x0000, &[0x37]); // SCF
+    
+    let instruction = cpu.decode();
+    cpu.execute(instruction);
+    
+    assert!(cpu.registers.f.carry);
+    assert!(!cpu.registers.f.subtract);
+    assert!(!cpu.registers.f.half_carry);
+    assert_eq!(cpu.pc, 1);
+}
+
+#[test]
+fn test_ccf() {
+    let mut cpu = CpuBuilder::new().build();
+    
+    cpu.registers.f.carry = true;
+    cpu.mmap.install_rom_patch(0x0000, &[0x3F, 0x3F]); // CCF; CCF
+    
+    let instruction = cpu.decode();
+    cpu.execute(instruction);
+    assert!(!cpu.registers.f.carry);
+    
+    let instruction = cpu.decode();
+    cpu.execute(instruction);
+    assert!(cpu.registers.f.carry);
+    assert_eq!(cpu.pc, 2);
+}
+
+#[test]
+fn test_sbc_a_b_with_carry_wraps_to_zero() {
+    let mut cpu = CpuBuilder::new().build();
+    
+    cpu.registers.a = 0x00;
+    cpu.registers.b = 0xFF;
+    cpu.registers.f.carry = true;
+    cpu.mmap.install_rom_patch(0x0000, &[0x98]); // SBC A,B
+    
+    let instruction = cpu.decode();
+    cpu.execute(instruction);
+    
+    // 0x00 - 0xFF - 1 = -0x100, which wraps to 0x00 with a full borrow
+    assert_eq!(cpu.registers.a, 0x00);
+    assert!(cpu.registers.f.zero);
+    assert!(cpu.registers.f.subtract);
+    assert!(cpu.registers.f.half_carry);
+    assert!(cpu.registers.f.carry);
+}
+
+#[test]
+fn test_sbc_a_hl_reads_memory() {
+    let mut cpu = CpuBuilder::new().build();
+    
+    cpu.registers.a = 0x10;
+    cpu.registers.h = 0xC0;
+    cpu.registers.l = 0x00;
+    cpu.registers.f.carry = true;
+    cpu.mmap.write(0xC000, 0x01);
+    cpu.mmap.install_rom_patch(0x0000, &[0x9E]); // SBC A,(HL)
+    
+    let instruction = cpu.decode();
+    cpu.execute(instruction);
+    
+    assert_eq!(cpu.registers.a, 0x0E);
+    assert!(!cpu.registers.f.zero);
+    assert!(cpu.registers.f.half_carry);
+    assert!(!cpu.registers.f.carry);
+}
+
+#[test]
+fn test_instruction_display_mnemonics() {
+    let cases = [
+        (InstructionKind::LD(ArgKind::A, ArgKind::Immediate(42)), "LD A, 0x2A"),
+        (InstructionKind::ADD(ArgKind::HL, ArgKind::BC), "ADD HL, BC"),
+        (InstructionKind::ADD(ArgKind::A, ArgKind::HL), "ADD A, (HL)"),
+        (InstructionKind::BIT(3, ArgKind::H), "BIT 3, H"),
+        (InstructionKind::SET(7, ArgKind::HL), "SET 7, (HL)"),
+        (InstructionKind::JP(JumpCondition::NotZero, 0x1234), "JP NZ, 0x1234"),
+        (InstructionKind::JP(JumpCondition::Always, 0x0150), "JP 0x0150"),
+        (InstructionKind::JR(JumpCondition::Carry, -2), "JR C, -2"),
+        (InstructionKind::RET_COND(JumpCondition::Zero), "RET Z"),
+        (InstructionKind::LD_MEM_INC(ArgKind::HL, ArgKind::A), "LD (HL+), A"),
+        (InstructionKind::LD_FROM_MEM(ArgKind::A, ArgKind::DE), "LD A, (DE)"),
+        (InstructionKind::LD_MEM_16(ArgKind::Immediate16(0xC000), ArgKind::A), "LD (0xC000), A"),
+        (InstructionKind::LDH_FROM_N(ArgKind::A, 0x44), "LDH A, (0x44)"),
+        (InstructionKind::LDHL_SP_R8(-8), "LD HL, SP-8"),
+        (InstructionKind::INC_MEM(ArgKind::HL), "INC (HL)"),
+        (InstructionKind::RST(0x38), "RST 0x38"),
+    ];
+    for (kind, expected) in cases {
+        assert_eq!(kind.to_string(), expected);
+    }
+    
+    assert_eq!(JumpCondition::Always.to_string(), "");
+    assert_eq!(JumpCondition::NotCarry.to_string(), "NC");
+}
+
+#[test]
+fn test_cb_mnemonics_show_hl_operand_as_indirect() {
+    for cb_opcode in 0x00..=0xFFu8 {
+        let text = decode_cb_instruction(cb_opcode).to_string();
+        // Operand index 6 in the low bits is (HL)
+        assert_eq!(text.ends_with("(HL)"), cb_opcode & 0x07 == 6, "CB {:02X} displayed as {:?}", cb_opcode, text);
+    }
+}
+
+#[test]
+fn test_every_opcode_has_exactly_one_decoder() {
+    // Decoded by the fallback match at the end of decode_instruction
+    let fallback = [0x07, 0x08, 0x0F, 0x17, 0x1F, 0x27, 0xD9];
+    let (imm, imm16) = (Some(0x12), Some(0x3456));
+
+    for opcode in 0x00..=0xFFu8 {
+        let decoders = [
+            ("load", decode_load_instruction(opcode, imm, imm16).is_some(), get_load_instruction_size(opcode)),
+            ("arithmetic", decode_arithmetic_instruction(opcode, imm).is_some(), get_arithmetic_instruction_size(opcode)),
+            ("increment", decode_increment_instruction(opcode).is_some(), get_increment_instruction_size(opcode)),
+            ("logical", decode_logical_instruction(opcode, imm).is_some(), get_logical_instruction_size(opcode)),
+            ("memory", decode_memory_instruction(opcode, imm).is_some(), get_memory_instruction_size(opcode)),
+            ("control flow", decode_control_flow_instruction(opcode, imm, imm16).is_some(), get_control_flow_instruction_size(opcode)),
+            ("stack", decode_stack_instruction(opcode).is_some(), get_stack_instruction_size(opcode)),
+            ("cpu control", decode_cpu_control_instruction(opcode).is_some(), get_cpu_control_instruction_size(opcode)),
+        ];
+        let matches: Vec<&str> = decoders.iter().filter(|(_, decoded, _)| *decoded).map(|(name, _, _)| *name).collect();
+        // Each family sizes exactly the opcodes it decodes
+        for (name, decoded, size) in decoders {
+            assert_eq!(decoded, size.is_some(), "opcode {:02X}: {} decoder and size table disagree", opcode, name);
+        }
+
+        if opcode == 0xCB || is_illegal_opcode(opcode) || fallback.contains(&opcode) {
+            assert!(matches.is_empty(), "opcode {:02X} also decoded by {:?}", opcode, matches);
+        } else {
+            assert_eq!(matches.len(), 1, "opcode {:02X} decoded by {:?}", opcode, matches);
+        }
+        if opcode != 0xCB && !is_illegal_opcode(opcode) {
+            decode_instruction(opcode, imm, imm16); // Panics on gaps
+        }
+    }
+}
+
+#[test]
+fn test_opcode_table_matches_pan_docs() {
+    // Mnemonic for every opcode with 0x12 / 0x3456 as immediates, 8 per row, checked against
+    // the Pan Docs opcode table. "" marks the CB prefix and the illegal opcodes.
+    let expected = [
+        "NOP", "LD BC, 0x3456", "LD (BC), A", "INC BC", "INC B", "DEC B", "LD B, 0x12", "RLCA",
+        "LD (0x3456), SP", "ADD HL, BC", "LD A, (BC)", "DEC BC", "INC C", "DEC C", "LD C, 0x12", "RRCA",
+        "STOP", "LD DE, 0x3456", "LD (DE), A", "INC DE", "INC D", "DEC D", "LD D, 0x12", "RLA",
+        "JR +18", "ADD HL, DE", "LD A, (DE)", "DEC DE", "INC E", "DEC E", "LD E, 0x12", "RRA",
+        "JR NZ, +18", "LD HL, 0x3456", "LD (HL+), A", "INC HL", "INC H", "DEC H", "LD H, 0x12", "DAA",
+        "JR Z, +18", "ADD HL, HL", "LD A, (HL+)", "DEC HL", "INC L", "DEC L", "LD L, 0x12", "CPL",
+        "JR NC, +18", "LD SP, 0x3456", "LD (HL-), A", "INC SP", "INC (HL)", "DEC (HL)", "LD (HL), 0x12", "SCF",
+        "JR C, +18", "ADD HL, SP", "LD A, (HL-)", "DEC SP", "INC A", "DEC A", "LD A, 0x12", "CCF",
+        "LD B, B", "LD B, C", "LD B, D", "LD B, E", "LD B, H", "LD B, L", "LD B, (HL)", "LD B, A",
+        "LD C, B", "LD C, C", "LD C, D", "LD C, E", "LD C, H", "LD C, L", "LD C, (HL)", "LD C, A",
+        "LD D, B", "LD D, C", "LD D, D", "LD D, E", "LD D, H", "LD D, L", "LD D, (HL)", "LD D, A",
+        "LD E, B", "LD E, C", "LD E, D", "LD E, E", "LD E, H", "LD E, L", "LD E, (HL)", "LD E, A",
+        "LD H, B", "LD H, C", "LD H, D", "LD H, E", "LD H, H", "LD H, L", "LD H, (HL)", "LD H, A",
+        "LD L, B", "LD L, C", "LD L, D", "LD L, E", "LD L, H", "LD L, L", "LD L, (HL)", "LD L, A",
+        "LD (HL), B", "LD (HL), C", "LD (HL), D", "LD (HL), E", "LD (HL), H", "LD (HL), L", "HALT", "LD (HL), A",
+        "LD A, B", "LD A, C", "LD A, D", "LD A, E", "LD A, H", "LD A, L", "LD A, (HL)", "LD A, A",
+        "ADD A, B", "ADD A, C", "ADD A, D", "ADD A, E", "ADD A, H", "ADD A, L", "ADD A, (HL)", "ADD A, A",
+        "ADC A, B", "ADC A, C", "ADC A, D", "ADC A, E", "ADC A, H", "ADC A, L", "ADC A, (HL)", "ADC A, A",
+        "SUB A, B", "SUB A, C", "SUB A, D", "SUB A, E", "SUB A, H", "SUB A, L", "SUB A, (HL)", "SUB A, A",
+        "SBC A, B", "SBC A, C", "SBC A, D", "SBC A, E", "SBC A, H", "SBC A, L", "SBC A, (HL)", "SBC A, A",
+        "AND A, B", "AND A, C", "AND A, D", "AND A, E", "AND A, H", "AND A, L", "AND A, (HL)", "AND A, A",
+        "XOR A, B", "XOR A, C", "XOR A, D", "XOR A, E", "XOR A, H", "XOR A, L", "XOR A, (HL)", "XOR A, A",
+        "OR A, B", "OR A, C", "OR A, D", "OR A, E", "OR A, H", "OR A, L", "OR A, (HL)", "OR A, A",
+        "CP A, B", "CP A, C", "CP A, D", "CP A, E", "CP A, H", "CP A, L", "CP A, (HL)", "CP A, A",
+        "RET NZ", "POP BC", "JP NZ, 0x3456", "JP 0x3456", "CALL NZ, 0x3456", "PUSH BC", "ADD A, 0x12", "RST 0x00",
+        "RET Z", "RET", "JP Z, 0x3456", "", "CALL Z, 0x3456", "CALL 0x3456", "ADC A, 0x12", "RST 0x08",
+        "RET NC", "POP DE", "JP NC, 0x3456", "", "CALL NC, 0x3456", "PUSH DE", "SUB A, 0x12", "RST 0x10",
+        "RET C", "RETI", "JP C, 0x3456", "", "CALL C, 0x3456", "", "SBC A, 0x12", "RST 0x18",
+        "LDH (0x12), A", "POP HL", "LD (C), A", "", "", "PUSH HL", "AND A, 0x12", "RST 0x20",
+        "ADD SP, +18", "JP HL", "LD (0x3456), A", "", "", "", "XOR A, 0x12", "RST 0x28",
+        "LDH A, (0x12)", "POP AF", "LD A, (C)", "DI", "", "PUSH AF", "OR A, 0x12", "RST 0x30",
+        "LD HL, SP+18", "LD SP, HL", "LD A, (0x3456)", "EI", "", "", "CP A, 0x12", "RST 0x38",
+    ];
+    for (opcode, &mnemonic) in expected.iter().enumerate() {
+        let opcode = opcode as u8;
+        if opcode == 0xCB || is_illegal_opcode(opcode) {
+            assert_eq!(mnemonic, "", "opcode {:02X}", opcode);
+            continue;
+        }
+        assert_eq!(decode_instruction(opcode, Some(0x12), Some(0x3456)).to_string(), mnemonic, "opcode {:02X}", opcode);
+    }
+}
+
+#[test]
+fn test_disassemble_range() {
+    let mut cpu = CpuBuilder::new().build();
+    cpu.mmap.install_rom_patch(0x0200, &[
+        0x00,             // NOP
+        0x3E, 0x42,       // LD A, 0x42
+        0x01, 0x34, 0x12, // LD BC, 0x1234
+        0xCB, 0x37,       // SWAP A
+        0x04,             // INC B
+        0xC3, 0x00, 0x01, // JP 0x0100
+        0xAF,             // XOR A
+        0xCB, 0x7C,       // BIT 7, H
+        0x18, 0xFE,       // JR -2
+        0x76,             // HALT
+    ]);
+    cpu.pc = 0x1234;
+
+    let listing = cpu.disassemble_range(0x0200, 10);
+    assert_eq!(listing, vec![
+        (0x0200, InstructionKind::NOP),
+        (0x0201, InstructionKind::LD(ArgKind::A, ArgKind::Immediate(0x42))),
+        (0x0203, InstructionKind::LD(ArgKind::BC, ArgKind::Immediate16(0x1234))),
+        (0x0206, InstructionKind::SWAP(ArgKind::A)),
+        (0x0208, InstructionKind::INC(ArgKind::B)),
+        (0x0209, InstructionKind::JP(JumpCondition::Always, 0x0100)),
+        (0x020C, InstructionKind::XOR(ArgKind::A, ArgKind::A)),
+        (0x020D, InstructionKind::BIT(7, ArgKind::H)),
+        (0x020F, InstructionKind::JR(JumpCondition::Always, -2)),
+        (0x0211, InstructionKind::HALT),
+    ]);
+
+    // Nothing ran: PC and registers are untouched
+    assert_eq!(cpu.pc, 0x1234);
+    assert_eq!(cpu.registers.a, CpuBuilder::new().build().registers.a);
+}
+
+#[test]
+fn test_decode_at_returns_kind_and_size_without_moving_pc() {
+    let mut cpu = CpuBuilder::new().build();
+    cpu.mmap.install_rom_patch(0x0000, &[
+        0x31, 0xFE, 0xFF, // LD SP, 0xFFFE
+        0xAF,             // XOR A
+        0xCB, 0x7C,       // BIT 7, H
+        0x20, 0xFB,       // JR NZ, -5
+    ]);
+    cpu.pc = 0x0150;
+
+    assert_eq!(cpu.decode_at(0x0000), (InstructionKind::LD(ArgKind::SP, ArgKind::Immediate16(0xFFFE)), 3));
+    assert_eq!(cpu.decode_at(0x0003), (InstructionKind::XOR(ArgKind::A, ArgKind::A), 1));
+    assert_eq!(cpu.decode_at(0x0004), (InstructionKind::BIT(7, ArgKind::H), 2));
+    assert_eq!(cpu.decode_at(0x0006), (InstructionKind::JR(JumpCondition::NotZero, -5), 2));
+    assert_eq!(cpu.pc, 0x0150);
+
+    // decode agrees, advancing PC by the same size
+    cpu.pc = 0x0000;
+    let instruction = cpu.decode();
+    assert_eq!(instruction.kind, cpu.decode_at(0x0000).0);
+    assert_eq!(cpu.pc, 3);
+}
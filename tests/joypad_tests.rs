@@ -0,0 +1,80 @@
+use macroquad::input::KeyCode;
+use rgb::rgb::joypad::{Joypad, JoypadButtons, KeyMapping, SgbMode};
+
+#[test]
+fn test_default_key_mapping() {
+    let mapping = KeyMapping::default();
+    let held = [KeyCode::Z, KeyCode::RightShift, KeyCode::Left];
+    let buttons = JoypadButtons::from_keyboard_state(&mapping, &|key| held.contains(&key));
+
+    assert!(buttons.a && buttons.select && buttons.left);
+    assert!(!buttons.b && !buttons.start && !buttons.up && !buttons.down && !buttons.right);
+}
+
+#[test]
+fn test_custom_key_mapping() {
+    let mut mapping = KeyMapping::default();
+    assert!(mapping.set_binding("a", "Space"));
+    assert!(mapping.set_binding("up", "W"));
+    assert!(!mapping.set_binding("a", "NotAKey")); // Unknown key keeps the binding
+    assert!(!mapping.set_binding("turbo", "T"));
+
+    assert_eq!(mapping.a_key, KeyCode::Space);
+    let held = [KeyCode::Space, KeyCode::W, KeyCode::Z, KeyCode::Up];
+    let buttons = JoypadButtons::from_keyboard_state(&mapping, &|key| held.contains(&key));
+
+    assert!(buttons.a && buttons.up);
+    assert!(!buttons.b && !buttons.down);
+
+    let buttons = JoypadButtons::from_keyboard_state(&mapping, &|key| key == KeyCode::Z);
+    assert!(!buttons.a); // Z is no longer bound
+}
+
+// Sends one SGB packet through P1: reset pulse, 128 bits LSB first, then the stop bit
+fn send_sgb_packet(joypad: &mut Joypad, packet: &[u8; 16]) {
+    joypad.write_register(0x00);
+    joypad.write_register(0x30);
+    for bit in 0..128 {
+        let one = packet[bit / 8] & (1 << (bit % 8)) != 0;
+        joypad.write_register(if one { 0x10 } else { 0x20 });
+        joypad.write_register(0x30);
+    }
+    joypad.write_register(0x20);
+    joypad.write_register(0x30);
+}
+
+// The usual detection: request two players, then pulse P15 and watch the ID in P1
+fn read_controller_ids(joypad: &mut Joypad) -> Vec<u8> {
+    let mut mlt_req = [0u8; 16];
+    mlt_req[0] = 0x89; // MLT_REQ, 1 packet
+    mlt_req[1] = 0x01; // 2 players
+    send_sgb_packet(joypad, &mlt_req);
+
+    (0..3).map(|_| {
+        joypad.write_register(0x10);
+        joypad.write_register(0x30);
+        joypad.read_register() & 0x0F
+    }).collect()
+}
+
+#[test]
+fn test_sgb_disabled_reports_a_single_controller() {
+    let mut joypad = Joypad::new();
+    assert_eq!(read_controller_ids(&mut joypad), vec![0x0F, 0x0F, 0x0F]);
+    assert_eq!(joypad.sgb_player_count(), 1);
+}
+
+#[test]
+fn test_sgb_enabled_answers_mlt_req() {
+    let mut joypad = Joypad::new();
+    joypad.set_sgb_mode(SgbMode::Enabled);
+    assert_eq!(read_controller_ids(&mut joypad), vec![0x0E, 0x0F, 0x0E]);
+    assert_eq!(joypad.sgb_player_count(), 2);
+
+    // Buttons still read normally with a group selected
+    let mut buttons = JoypadButtons::new();
+    buttons.start = true;
+    joypad.update_buttons(buttons);
+    joypad.write_register(0x10);
+    assert_eq!(joypad.read_register() & 0x0F, 0x07);
+}
@@ -0,0 +1,48 @@
+use rgb::rgb::memory_dump::{MemoryDump, HEADER_SIZE, OAM_MAGIC, VRAM_MAGIC};
+use rgb::rgb::ppu::{LcdcFlags, Ppu};
+
+#[test]
+fn test_vram_dump_header_layout() {
+    let mut ppu = Ppu::new();
+    ppu.vram[0] = 0x3C;
+    ppu.vram[0x1FFF] = 0xA5;
+    ppu.lcdc = LcdcFlags::from_byte(0x91);
+    ppu.bgp = 0xE4;
+
+    let bytes = MemoryDump::vram(&ppu, 0x1234).to_bytes();
+
+    assert_eq!(bytes.len(), HEADER_SIZE + 0x2000);
+    assert_eq!(&bytes[0..4], b"VRAM");
+    assert_eq!(&bytes[4..6], &[0x34, 0x12]); // Frame number, little-endian
+    assert_eq!(bytes[6], 0x91);
+    assert_eq!(bytes[7], 0xE4);
+    assert_eq!(bytes[HEADER_SIZE], 0x3C);
+    assert_eq!(bytes[HEADER_SIZE + 0x1FFF], 0xA5);
+}
+
+#[test]
+fn test_oam_dump_round_trip() {
+    let mut ppu = Ppu::new();
+    ppu.oam[0] = 0x10;
+    ppu.oam[159] = 0x20;
+
+    let dump = MemoryDump::oam(&ppu, 7);
+    let parsed = MemoryDump::from_bytes(&dump.to_bytes()).unwrap();
+
+    assert_eq!(parsed, dump);
+    assert_eq!(parsed.magic, OAM_MAGIC);
+    assert_eq!(parsed.data.len(), 160);
+    assert_eq!(parsed.data[0], 0x10);
+    assert_eq!(parsed.data[159], 0x20);
+}
+
+#[test]
+fn test_dump_rejects_bad_header() {
+    assert_eq!(MemoryDump::from_bytes(b"VRAM"), None); // Truncated header
+    assert_eq!(MemoryDump::from_bytes(b"ABCD\x00\x00\x91\xE4"), None);
+
+    let empty = MemoryDump::from_bytes(b"VRAM\x01\x00\x91\xE4").unwrap();
+    assert_eq!(empty.magic, VRAM_MAGIC);
+    assert_eq!(empty.frame, 1);
+    assert!(empty.data.is_empty());
+}
@@ -0,0 +1,426 @@
+use rgb::rgb::config::EmulatorConfig;
+use rgb::rgb::cpu::CpuBuilder;
+use rgb::rgb::memory::{MemAccess, MemoryMap, StepInterrupts, WatchKind, DMA_ADDR, HRAM_END, HRAM_START};
+
+fn post_boot_mmap() -> MemoryMap {
+    MemoryMap::new_with_config(&EmulatorConfig { skip_boot: true, ..EmulatorConfig::default() })
+}
+
+#[test]
+fn test_read_range_matches_read() {
+    let mut mmap = post_boot_mmap();
+    for i in 0..0x300u16 {
+        mmap.write(0xDF00 + i, i as u8);
+    }
+    
+    // Crosses from WRAM into echo RAM and beyond
+    let data = mmap.read_range(0xDF80, 0x100);
+    assert_eq!(data.len(), 0x100);
+    for (i, &byte) in data.iter().enumerate() {
+        assert_eq!(byte, mmap.read(0xDF80 + i as u16));
+    }
+}
+
+#[test]
+fn test_read_range_wraps_at_ffff() {
+    let mut mmap = post_boot_mmap();
+    mmap.write(0xFFFE, 0x12);
+    mmap.write(0xFFFF, 0x1F);
+    
+    let data = mmap.read_range(0xFFFE, 4);
+    assert_eq!(data, vec![0x12, 0x1F, mmap.read(0x0000), mmap.read(0x0001)]);
+}
+
+#[test]
+fn test_write_range_wraps_at_ffff() {
+    let mut mmap = post_boot_mmap();
+    let rom = mmap.read_range(0x0000, 2);
+    
+    mmap.write_range(0xFFFE, &[0xAA, 0x1B, 0x3C, 0x4D]);
+    assert_eq!(mmap.read(0xFFFE), 0xAA);
+    assert_eq!(mmap.read(0xFFFF), 0x1B);
+    // The wrapped bytes reach ROM, which is read-only
+    assert_eq!(mmap.read_range(0x0000, 2), rom);
+}
+
+#[test]
+fn test_write_range_round_trips_wram() {
+    let mut mmap = post_boot_mmap();
+    let patch: Vec<u8> = (0..=255).collect();
+    
+    mmap.write_range(0xC0F0, &patch);
+    assert_eq!(mmap.read_range(0xC0F0, 256), patch);
+}
+
+#[test]
+fn test_serial_transfer_without_device_reads_ff() {
+    let mut mmap = post_boot_mmap();
+    mmap.write(0xFF01, 0x42);
+    mmap.write(0xFF02, 0x81); // Start, internal clock
+    
+    assert!(!mmap.step_serial(4000));
+    assert!(mmap.step_serial(96));
+    assert_eq!(mmap.read(0xFF01), 0xFF);
+    assert_eq!(mmap.read(0xFF02) & 0x80, 0);
+}
+
+#[test]
+fn test_install_rom_patch_runs_code() {
+    let mut cpu = CpuBuilder::new().build();
+    cpu.mmap.install_rom_patch(0x0000, &[0x3E, 0x42, 0x76]); // LD A, 0x42; HALT
+    
+    cpu.run_until_pc(0x0003, 1_000).unwrap();
+    assert_eq!(cpu.registers.a, 0x42);
+    assert!(cpu.halted);
+}
+
+#[test]
+fn test_install_bootstrap_patch_overrides_cartridge() {
+    let mut mmap = MemoryMap::new();
+    mmap.install_rom_patch(0x0150, &[0x11]);
+    mmap.install_bootstrap_patch(0x0000, &[0x3E, 0x42]);
+    mmap.disable_bootstrap();
+    mmap.load_cartridge_from_bytes(vec![0xFF; 0x8000]); // 32 KiB ROM-only cartridge filled with 0xFF
+    
+    // Plain patches are hidden by the cartridge, bootstrap patches are not
+    assert_eq!(mmap.read(0x0150), 0xFF);
+    assert_eq!(mmap.read_range(0x0000, 3), vec![0x3E, 0x42, 0xFF]);
+    
+    // The patch is read-only
+    mmap.write(0x0001, 0x07);
+    assert_eq!(mmap.read(0x0001), 0x42);
+}
+
+#[test]
+fn test_install_bootstrap_patch_keeps_mbc_registers() {
+    let mut rom = vec![0u8; 0x10000]; // 64 KiB MBC1 cartridge, each bank filled with its number
+    for (bank, data) in rom.chunks_mut(0x4000).enumerate() {
+        data.fill(bank as u8);
+    }
+    rom[0x0147] = 0x01; // MBC1
+    rom[0x0148] = 0x01; // 64 KiB
+    let mut mmap = MemoryMap::new();
+    mmap.install_bootstrap_patch(0x2000, &[0x18, 0xFE]); // Over the ROM bank register
+    mmap.disable_bootstrap();
+    mmap.load_cartridge_from_bytes(rom);
+
+    mmap.write(0x2000, 0x02);
+    assert_eq!(mmap.read(0x2000), 0x18);
+    assert_eq!(mmap.read(0x4000), 0x02); // Bank 2 is switched in
+}
+
+#[test]
+fn test_access_log_disabled_by_default() {
+    let mut mmap = MemoryMap::new();
+    mmap.write(0xC000, 0x42);
+    mmap.read(0xC000);
+    assert!(mmap.get_access_log().is_empty());
+}
+
+#[test]
+fn test_read_range_has_no_side_effects() {
+    let mut mmap = MemoryMap::new();
+    mmap.enable_access_log(16);
+    mmap.add_watchpoint(0xFF44, WatchKind::Read);
+    mmap.add_watchpoint(0xC010, WatchKind::Read);
+
+    mmap.read_range(0xFF40, 8);
+    mmap.read_range(0xC000, 0x20);
+    assert!(mmap.get_access_log().is_empty());
+    assert_eq!(mmap.take_watchpoint_hit(), None);
+}
+
+#[test]
+fn test_access_log_keeps_last_entries_with_pc() {
+    let mut cpu = CpuBuilder::new().build();
+    cpu.mmap.install_rom_patch(0x0000, &[0x3E, 0x42, 0xEA, 0x00, 0xC0]); // LD A, 0x42; LD (0xC000), A
+    cpu.mmap.enable_access_log(2);
+    
+    let instruction = cpu.decode();
+    cpu.execute(instruction);
+    let instruction = cpu.decode();
+    cpu.execute(instruction);
+    
+    // Only the last two accesses survive: the operand high byte fetch and the store
+    let log = cpu.mmap.get_access_log();
+    assert_eq!(log.len(), 2);
+    assert_eq!(log[0], MemAccess { kind: WatchKind::Read, addr: 0x0004, val: 0xC0, pc: 0x0002 });
+    assert_eq!(log[1], MemAccess { kind: WatchKind::Write, addr: 0xC000, val: 0x42, pc: 0x0002 });
+}
+
+#[test]
+fn test_load_cartridge_from_bytes_runs_synthetic_rom() {
+    let mut rom = vec![0x00; 0x8000];
+    rom[0x0100..0x0104].copy_from_slice(&[0x3E, 0x42, 0x18, 0xFE]); // LD A, 0x42; JR -2
+    
+    let mut cpu = CpuBuilder::new().with_pc(0x0100).build();
+    cpu.mmap.disable_bootstrap();
+    cpu.mmap.load_cartridge_from_bytes(rom.clone());
+    
+    assert_eq!(cpu.mmap.get_cart_rom(), Some(rom.as_slice()));
+    assert_eq!(cpu.run_until_pc(0x0102, 100), Ok(8));
+    assert_eq!(cpu.registers.a, 0x42);
+}
+
+#[test]
+fn test_hram_round_trips_boundary_bytes() {
+    let mut mmap = MemoryMap::new();
+    mmap.write(HRAM_START, 0x12);
+    mmap.write(HRAM_END, 0x34);
+    mmap.write(0xFF7F, 0x56); // Last I/O address, outside HRAM
+    mmap.write(0xFFFF, 0x1F); // IE, outside HRAM
+
+    assert_eq!(mmap.read(HRAM_START), 0x12);
+    assert_eq!(mmap.read(HRAM_END), 0x34);
+    assert_eq!(mmap.read(0xFFFF), 0x1F);
+
+    let hram: Vec<(u16, u8)> = mmap.iter_hram().collect();
+    assert_eq!(hram.len(), 127);
+    assert_eq!(hram[0], (0xFF80, 0x12));
+    assert_eq!(hram[126], (0xFFFE, 0x34));
+    assert!(hram[1..126].iter().all(|&(_, value)| value == 0));
+}
+
+#[test]
+fn test_ie_upper_bits_read_as_zero() {
+    let mut mmap = post_boot_mmap();
+    mmap.write(0xFFFF, 0xFF);
+    assert_eq!(mmap.read(0xFFFF), 0x1F);
+
+    // IF keeps its upper bits set instead
+    mmap.write(0xFF0F, 0x00);
+    assert_eq!(mmap.read(0xFF0F), 0xE0);
+}
+
+#[test]
+fn test_oam_dma_blocks_bus_until_complete() {
+    let mut mmap = post_boot_mmap();
+    for i in 0..160u16 {
+        mmap.write(0xC000 + i, i as u8 + 1);
+    }
+    mmap.write(0xFF80, 0x42);
+    assert!(!mmap.dma_in_progress());
+
+    mmap.write(DMA_ADDR, 0xC0);
+    assert!(mmap.dma_in_progress());
+    // HRAM and I/O stay reachable; the rest of the bus shows the byte being moved
+    assert_eq!(mmap.read(0xFF80), 0x42);
+    assert_eq!(mmap.read(0xFF0F), 0xE0);
+
+    mmap.step_dma(4);
+    assert_eq!(mmap.get_ppu().get_oam()[0], 0x01);
+    assert_eq!(mmap.read(0xC050), 0x01);
+    assert_eq!(mmap.read(0x0000), 0x01);
+
+    mmap.step_dma(8);
+    assert_eq!(mmap.read(0xD000), 0x03);
+
+    // 160 bytes at 4 cycles each
+    mmap.step_dma(640 - 12 - 4);
+    assert!(mmap.dma_in_progress());
+    mmap.step_dma(4);
+    assert!(!mmap.dma_in_progress());
+    assert_eq!(mmap.get_ppu().get_oam()[159], 160);
+    assert_eq!(mmap.read(0xC050), 0x51);
+}
+
+#[test]
+fn test_echo_ram_mirrors_wram() {
+    let mut mmap = post_boot_mmap();
+    mmap.write(0xC123, 0x42);
+    assert_eq!(mmap.read(0xE123), 0x42);
+
+    mmap.write(0xFDFF, 0x99);
+    assert_eq!(mmap.read(0xDDFF), 0x99);
+}
+
+#[test]
+fn test_prohibited_echo_ram_reads_ff() {
+    let mut mmap = post_boot_mmap();
+    mmap.prohibit_echo_ram = true;
+    mmap.write(0xC123, 0x42);
+    assert_eq!(mmap.read(0xE123), 0xFF);
+
+    // Writes are dropped rather than reaching WRAM
+    mmap.write(0xE200, 0x99);
+    assert_eq!(mmap.read(0xC200), 0x00);
+}
+
+#[test]
+fn test_new_with_config_selects_boot_state_and_echo_ram() {
+    let power_on = MemoryMap::new_with_config(&EmulatorConfig::default());
+    assert!(power_on.bootstrap_enabled);
+    assert!(!power_on.is_bootstrap_loaded(), "only the CPU loads the boot ROM");
+    assert!(!power_on.prohibit_echo_ram);
+
+    let config = EmulatorConfig { skip_boot: true, echo_ram_mirrors: false, ..EmulatorConfig::default() };
+    let mut post_boot = MemoryMap::new_with_config(&config);
+    assert!(!post_boot.bootstrap_enabled);
+    assert_eq!(post_boot.read(0xFF50), 0x01);
+    post_boot.write(0xC000, 0x42);
+    assert_eq!(post_boot.read(0xE000), 0xFF);
+}
+
+#[test]
+fn test_unused_oam_area_reads_by_ppu_mode() {
+    let mut mmap = post_boot_mmap();
+    mmap.write(0xFEA0, 0x42);
+    assert_eq!(mmap.read(0xFEA0), 0xFF);
+    assert_eq!(mmap.read(0xFEFF), 0xFF);
+
+    mmap.accurate_unused_memory = true;
+    for (i, byte) in mmap.get_ppu_mut().oam.iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+    mmap.write(0xFF40, 0x00);
+    assert_eq!(mmap.read(0xFEA0), 0x00); // LCD off
+    mmap.write(0xFF40, 0x91);
+
+    // OAM scan: the row being fetched, repeated every 8 bytes
+    assert_eq!(mmap.read(0xFEA0), 0x00);
+    assert_eq!(mmap.read(0xFEAD), 0x05);
+    mmap.get_ppu_mut().step(40); // Dot 40: row 10
+    assert_eq!(mmap.read(0xFEA3), 83);
+    assert_eq!(mmap.read(0xFEFB), 83);
+    mmap.get_ppu_mut().step(39); // Dot 79: row 19, the last one
+    assert_eq!(mmap.read(0xFEA7), 159);
+
+    mmap.get_ppu_mut().step(1); // Drawing
+    assert_eq!(mmap.read(0xFEFF), 0xFF);
+    mmap.get_ppu_mut().step(172); // HBlank
+    assert_eq!(mmap.read(0xFEA0), 0x00);
+
+    // VBlank
+    mmap.get_ppu_mut().step(204);
+    for _ in 1..144 {
+        mmap.get_ppu_mut().step(456);
+    }
+    assert_eq!(mmap.get_ppu().ly, 144);
+    assert_eq!(mmap.read(0xFEA0), 0x00);
+}
+
+#[test]
+fn test_step_reports_hardware_interrupts() {
+    let mut mmap = post_boot_mmap();
+    mmap.write(0xFF06, 0x00); // TMA
+    mmap.write(0xFF05, 0xFF); // TIMA about to overflow
+    mmap.write(0xFF07, 0x05); // Timer on, 16 cycles per tick
+    assert_eq!(mmap.step(16, 1), StepInterrupts { timer: true, ..StepInterrupts::default() });
+
+    // One VBlank per frame; in double speed a frame takes twice the CPU cycles
+    mmap.write(0xFF07, 0x00);
+    for (speed_multiplier, frames) in [(1u8, 2u32), (2, 1)] {
+        let vblanks = (0..70224 * 2 / 4)
+            .filter(|_| mmap.step(4, speed_multiplier).vblank)
+            .count();
+        assert_eq!(vblanks as u32, frames, "speed multiplier {}", speed_multiplier);
+    }
+}
+
+#[test]
+fn test_new_leaves_bootstrap_blank_until_loaded() {
+    let mut mmap = MemoryMap::new();
+    assert!(!mmap.is_bootstrap_loaded());
+    assert_eq!(mmap.read(0x0000), 0x00);
+
+    let path = std::env::temp_dir().join(format!("rgb_bootstrap_{}.bin", std::process::id()));
+    let mut bootstrap = [0u8; 256];
+    bootstrap[0] = 0x31; // LD SP, d16
+    std::fs::write(&path, bootstrap).unwrap();
+    mmap.load_bootstrap(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(mmap.is_bootstrap_loaded());
+    assert_eq!(mmap.read(0x0000), 0x31);
+}
+
+#[test]
+fn test_post_boot_vram_holds_boot_logo() {
+    let mmap = post_boot_mmap();
+    let vram = &mmap.get_ppu().vram;
+
+    // First logo byte 0xCE: nibble 0xC doubles to 0xF0, 0xE to 0xFC, each on two rows
+    assert_eq!(&vram[0x0010..0x0018], &[0xF0, 0x00, 0xF0, 0x00, 0xFC, 0x00, 0xFC, 0x00]);
+    assert_eq!(vram[0x0190], 0x3C, "(R) in tile 25");
+
+    // Tile map rows at 0x9904 and 0x9924, (R) at 0x9910
+    assert_eq!(&vram[0x1904..0x1910], &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]);
+    assert_eq!(vram[0x1910], 25);
+    assert_eq!(vram[0x1924], 13);
+    assert_eq!(vram[0x192F], 24);
+}
+
+#[test]
+fn test_memory_map_report_lists_regions_and_title() {
+    let mut mmap = post_boot_mmap();
+    let mut rom = vec![0u8; 0x8000];
+    rom[0x0100..0x0104].copy_from_slice(&[0x00, 0xC3, 0x50, 0x01]); // NOP; JP 0x0150
+    rom[0x0134..0x013B].copy_from_slice(b"TESTROM");
+    mmap.load_cartridge_from_bytes(rom);
+
+    let report = mmap.memory_map_report();
+    let rom_line = report.lines().next().unwrap();
+    assert!(rom_line.starts_with("[0x0000-0x3FFF] ROM Bank 0: C3 50 01 "), "{}", rom_line);
+    assert!(rom_line.contains("@0x0101"), "{}", rom_line);
+    assert!(rom_line.ends_with("(32768 bytes, title: \"TESTROM\")"), "{}", rom_line);
+    assert!(report.contains("[0x8000-0x97FF] VRAM Tiles: "), "{}", report);
+    assert!(report.contains("[0xC000-0xDFFF] WRAM: all zero (8192 bytes)"), "{}", report);
+    assert!(report.contains("LCDC=91"), "{}", report);
+    assert_eq!(report.lines().count(), 9);
+}
+
+#[test]
+fn test_snapshot_restores_ram_and_ppu_registers() {
+    let mut mmap = post_boot_mmap();
+    mmap.write(0xC000, 0x11);
+    mmap.write(HRAM_START, 0x22);
+    mmap.write(0xFF42, 0x08); // SCY
+    let snapshot = mmap.snapshot();
+
+    mmap.write(0xC000, 0x99);
+    mmap.write(HRAM_START, 0x99);
+    mmap.write(0xFF42, 0x40);
+    mmap.write(0x8000, 0x77); // VRAM isn't part of the snapshot
+    mmap.restore_from_snapshot(&snapshot);
+
+    assert_eq!(mmap.read(0xC000), 0x11);
+    assert_eq!(mmap.read(HRAM_START), 0x22);
+    assert_eq!(mmap.read(0xFF42), 0x08);
+    assert_eq!(mmap.read(0x8000), 0x77);
+    assert_eq!(mmap.snapshot().contents, snapshot.contents);
+}
+
+// Access counters only exist with the memory_profiling feature: cargo test --features memory_profiling
+#[cfg(feature = "memory_profiling")]
+#[test]
+fn test_access_counters_count_only_while_tracking() {
+    let mut mmap = MemoryMap::new();
+    mmap.read(0xC000);
+    mmap.write(0xC000, 0x01);
+    assert_eq!(mmap.read_count(0xC000), 0);
+
+    mmap.track_accesses = true;
+    mmap.read(0xC000);
+    mmap.read(0xC000);
+    mmap.peek(0xC000); // Peeks aren't CPU accesses
+    mmap.write(0xC000, 0x02);
+    assert_eq!(mmap.read_count(0xC000), 2);
+    assert_eq!(mmap.write_count(0xC000), 1);
+}
+
+#[cfg(feature = "memory_profiling")]
+#[test]
+fn test_hot_reads_are_sorted_by_count() {
+    let mut mmap = MemoryMap::new();
+    mmap.track_accesses = true;
+    for (addr, reads) in [(0xFF44, 5), (0xC000, 1), (0xFF00, 3)] {
+        for _ in 0..reads {
+            mmap.read(addr);
+        }
+    }
+    mmap.write(0xD000, 0x00);
+
+    assert_eq!(mmap.hot_reads(2), vec![(0xFF44, 5), (0xFF00, 3)]);
+    assert_eq!(mmap.hot_reads(10), vec![(0xFF44, 5), (0xFF00, 3), (0xC000, 1)]);
+    assert_eq!(mmap.hot_writes(10), vec![(0xD000, 1)]);
+}
@@ -0,0 +1,458 @@
+use rgb::rgb::ppu::{shade_rgba, Ppu, PpuLayer, LcdcFlags, BG_MAP_SIZE, BG_MAP_VIEWPORT_MARKER, DMG_SHADES, DMG_SHADES_CORRECTED,
+                    LYC_ADDR, STAT_ADDR};
+
+// Fill every row of a tile so all its pixels use the given color index
+fn fill_tile(vram: &mut [u8], tile_addr: usize, color: u8) {
+    let low = if (color & 1) != 0 { 0xFF } else { 0x00 };
+    let high = if (color & 2) != 0 { 0xFF } else { 0x00 };
+    for row in 0..8 {
+        vram[tile_addr + row * 2] = low;
+        vram[tile_addr + row * 2 + 1] = high;
+    }
+}
+
+#[test]
+fn test_full_bg_map_unsigned_addressing() {
+    let mut vram = vec![0u8; 0x2000];
+    fill_tile(&mut vram, 16, 3);
+    // Map 0 entry (5, 2) points at tile 1
+    vram[0x1800 + 2 * 32 + 5] = 0x01;
+
+    // LCDC bit 4 set (unsigned), bit 3 clear (map 0)
+    let lcdc = LcdcFlags::from_byte(0x91);
+    // Viewport placed away from the tile under test
+    let map = Ppu::render_full_background_map(&vram, &lcdc, 0, 100, 0xE4);
+
+    assert_eq!(map[(2 * 8 + 3) * BG_MAP_SIZE + 5 * 8 + 3], 3);
+    assert_eq!(map[(2 * 8 + 3) * BG_MAP_SIZE + 6 * 8 + 3], 0);
+}
+
+#[test]
+fn test_full_bg_map_signed_addressing_and_map_1() {
+    let mut vram = vec![0u8; 0x2000];
+    // Tile ID 0xFF in signed mode lives at 0x8FF0 (VRAM offset 0x0FF0)
+    fill_tile(&mut vram, 0x0FF0, 1);
+    // Map 1 entry (0, 20) points at tile 0xFF
+    vram[0x1C00 + 20 * 32] = 0xFF;
+
+    // LCDC bit 4 clear (signed), bit 3 set (map 1)
+    let lcdc = LcdcFlags::from_byte(0x89);
+    // BGP maps color 1 to shade 2
+    let map = Ppu::render_full_background_map(&vram, &lcdc, 100, 0, 0xE8);
+
+    assert_eq!(map[(20 * 8 + 4) * BG_MAP_SIZE + 4], 2);
+}
+
+#[test]
+fn test_full_bg_map_viewport_outline_wraps() {
+    let vram = vec![0u8; 0x2000];
+    let lcdc = LcdcFlags::from_byte(0x91);
+    let map = Ppu::render_full_background_map(&vram, &lcdc, 200, 150, 0xE4);
+
+    // Top-left corner of the viewport
+    assert_eq!(map[150 * BG_MAP_SIZE + 200], BG_MAP_VIEWPORT_MARKER);
+    // Right edge wraps to x = (200 + 159) % 256 = 103
+    assert_eq!(map[150 * BG_MAP_SIZE + 103], BG_MAP_VIEWPORT_MARKER);
+    // Bottom edge wraps to y = (150 + 143) % 256 = 37
+    assert_eq!(map[37 * BG_MAP_SIZE + 200], BG_MAP_VIEWPORT_MARKER);
+    // Inside the viewport stays background
+    assert_eq!(map[160 * BG_MAP_SIZE + 210], 0);
+}
+
+// Set up a window using map 1 filled with a tile whose row 0 is color 3 and row 1 is color 2
+fn setup_window_ppu() -> Ppu {
+    let mut ppu = Ppu::new();
+    ppu.vram[16] = 0xFF; // Tile 1, row 0: color 3
+    ppu.vram[17] = 0xFF;
+    ppu.vram[19] = 0xFF; // Tile 1, row 1: color 2
+    for entry in 0..(32 * 32) {
+        ppu.vram[0x1C00 + entry] = 0x01;
+    }
+    ppu.bgp = 0xE4;
+    ppu.wy = 0;
+    ppu.wx = 7;
+    ppu
+}
+
+#[test]
+fn test_window_line_counter_skips_disabled_lines() {
+    let mut ppu = setup_window_ppu();
+    let window_on = 0xF1;  // LCD, window map 1, window, unsigned tiles, BG
+    let window_off = 0xD1;
+
+    // Line 0 draws window row 0
+    ppu.lcdc = LcdcFlags::from_byte(window_on);
+    ppu.step(456);
+    assert_eq!(ppu.frame_buffer[20], 3);
+    assert_eq!(ppu.window_line_counter, 1);
+
+    // Lines 1-7 with the window disabled must not advance the counter
+    ppu.lcdc = LcdcFlags::from_byte(window_off);
+    for _ in 1..8 {
+        ppu.step(456);
+    }
+    assert_eq!(ppu.window_line_counter, 1);
+
+    // Line 8 resumes at window row 1, not row 8
+    ppu.lcdc = LcdcFlags::from_byte(window_on);
+    ppu.step(456);
+    assert_eq!(ppu.frame_buffer[8 * 160 + 20], 2);
+}
+
+#[test]
+fn test_window_line_counter_resets_each_frame() {
+    let mut ppu = setup_window_ppu();
+    ppu.lcdc = LcdcFlags::from_byte(0xF1);
+
+    for _ in 0..144 {
+        ppu.step(456);
+    }
+    assert_eq!(ppu.window_line_counter, 144);
+
+    // VBlank lines 144-153, then back to line 0
+    for _ in 0..10 {
+        ppu.step(456);
+    }
+    assert_eq!(ppu.ly, 0);
+    assert_eq!(ppu.window_line_counter, 0);
+}
+
+#[test]
+fn test_window_signed_addressing_tile_0x80() {
+    let mut ppu = Ppu::new();
+    fill_tile(&mut ppu.vram, 0x0800, 2); // Tile 0x80 in signed mode: 0x9000 - 128 * 16 = 0x8800
+    for entry in 0..(32 * 32) {
+        ppu.vram[0x1C00 + entry] = 0x80;
+    }
+    ppu.bgp = 0xE4;
+    ppu.wy = 0;
+    ppu.wx = 7;
+    ppu.lcdc = LcdcFlags::from_byte(0xE1); // LCD, window map 1, window, signed tiles, BG
+
+    ppu.step(456);
+    assert_eq!(ppu.frame_buffer[0], 2);
+    assert_eq!(ppu.frame_buffer[159], 2);
+}
+
+#[test]
+fn test_render_to_rgb_buffer_applies_palette() {
+    let mut ppu = Ppu::new();
+    let palette = [(0xE0, 0xF8, 0xD0), (0x88, 0xC0, 0x70), (0x34, 0x68, 0x56), (0x08, 0x18, 0x20)];
+
+    // A blank frame is all shade 0
+    let mut rgb = Box::new([0u8; 160 * 144 * 3]);
+    ppu.render_to_rgb_buffer(&mut rgb, &palette);
+    assert!(rgb.chunks_exact(3).all(|pixel| pixel == [0xE0, 0xF8, 0xD0]));
+
+    ppu.frame_buffer[10 * 160 + 20] = 2;
+    ppu.render_to_rgb_buffer(&mut rgb, &palette);
+    assert_eq!(rgb[10 * 160 * 3 + 20 * 3..][..3], [0x34, 0x68, 0x56]);
+
+    let mut rgba = Box::new([0u8; 160 * 144 * 4]);
+    ppu.render_to_rgba_buffer(&mut rgba, &palette);
+    assert_eq!(rgba[..4], [0xE0, 0xF8, 0xD0, 0xFF]);
+    assert_eq!(rgba[(10 * 160 + 20) * 4..][..4], [0x34, 0x68, 0x56, 0xFF]);
+}
+
+#[test]
+fn test_dump_frame_as_png() {
+    let mut ppu = Ppu::new();
+    // Synthetic frame: one pixel of each shade in the top row
+    ppu.frame_buffer[1] = 1;
+    ppu.frame_buffer[2] = 2;
+    ppu.frame_buffer[3] = 3;
+    ppu.frame_buffer[143 * 160 + 159] = 3;
+
+    let palette = [(0xE0, 0xF8, 0xD0), (0x88, 0xC0, 0x70), (0x34, 0x68, 0x56), (0x08, 0x18, 0x20)];
+    let path = std::env::temp_dir().join(format!("rgb_frame_dump_{}.png", std::process::id()));
+    ppu.dump_frame_as_png(&path, &palette).unwrap();
+
+    let decoder = png::Decoder::new(std::fs::File::open(&path).unwrap());
+    let mut reader = decoder.read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+    std::fs::remove_file(&path).ok();
+
+    assert_eq!((info.width, info.height), (160, 144));
+    let pixel = |x: usize, y: usize| {
+        let i = (y * 160 + x) * 4;
+        (pixels[i], pixels[i + 1], pixels[i + 2], pixels[i + 3])
+    };
+    assert_eq!(pixel(0, 0), (0xE0, 0xF8, 0xD0, 0xFF));
+    assert_eq!(pixel(1, 0), (0x88, 0xC0, 0x70, 0xFF));
+    assert_eq!(pixel(2, 0), (0x34, 0x68, 0x56, 0xFF));
+    assert_eq!(pixel(3, 0), (0x08, 0x18, 0x20, 0xFF));
+    assert_eq!(pixel(159, 143), (0x08, 0x18, 0x20, 0xFF));
+}
+
+// Steps one scanline at a time and records whether each produced a STAT interrupt
+fn stat_interrupts_per_line(ppu: &mut Ppu, lines: usize) -> Vec<bool> {
+    (0..lines).map(|_| {
+        ppu.step(456);
+        ppu.take_stat_interrupt()
+    }).collect()
+}
+
+#[test]
+fn test_stat_oam_source_fires_every_line() {
+    let mut ppu = Ppu::new();
+    ppu.write_register(STAT_ADDR, 0x20); // OAM scan source only
+    ppu.take_stat_interrupt();
+
+    let fired = stat_interrupts_per_line(&mut ppu, 6);
+    assert!(fired[1..].iter().all(|&f| f), "{:?}", fired);
+}
+
+#[test]
+fn test_stat_blocking_lyc_and_oam_fire_once() {
+    let mut ppu = Ppu::new();
+    ppu.write_register(LYC_ADDR, 3);
+    ppu.write_register(STAT_ADDR, 0x60); // LYC=LY and OAM scan sources
+    ppu.take_stat_interrupt();
+
+    let fired = stat_interrupts_per_line(&mut ppu, 6);
+    // Line 2's step raises LY to 3 (LYC match), so the line goes high there;
+    // line 3's OAM scan finds it already high and must not fire again
+    assert!(fired[2], "{:?}", fired);
+    assert!(!fired[3], "{:?}", fired);
+    assert!(fired[4], "{:?}", fired);
+}
+
+#[test]
+fn test_stat_disabled_sources_never_fire() {
+    let mut ppu = Ppu::new();
+    ppu.write_register(LYC_ADDR, 3);
+    ppu.write_register(STAT_ADDR, 0x00);
+
+    let fired = stat_interrupts_per_line(&mut ppu, 10);
+    assert!(fired.iter().all(|&f| !f), "{:?}", fired);
+}
+
+// Render line 0 with solid sprite tiles 1 (color 1) and 2 (color 2) placed at the given OAM
+// (x, tile) entries; the background is color 0 everywhere
+fn render_sprites(sprites: &[(u8, u8)]) -> Ppu {
+    let mut ppu = Ppu::new();
+    fill_tile(&mut ppu.vram, 16, 1);
+    fill_tile(&mut ppu.vram, 32, 2);
+    for (i, &(x, tile)) in sprites.iter().enumerate() {
+        ppu.oam[i * 4] = 16; // Top row on line 0
+        ppu.oam[i * 4 + 1] = x;
+        ppu.oam[i * 4 + 2] = tile;
+    }
+    ppu.bgp = 0xE4;
+    ppu.obp0 = 0xE4;
+    ppu.lcdc = LcdcFlags::from_byte(0x93); // LCD, unsigned tiles, sprites, BG
+    ppu.step(456);
+    ppu
+}
+
+#[test]
+fn test_sprite_priority_same_x_uses_oam_order() {
+    let ppu = render_sprites(&[(20, 1), (20, 2)]);
+    assert_eq!(ppu.frame_buffer[12], 1);
+    assert_eq!(ppu.frame_buffer[19], 1);
+}
+
+#[test]
+fn test_sprite_priority_lower_x_wins() {
+    // The later OAM entry sits further left, so it wins where they overlap
+    let ppu = render_sprites(&[(24, 1), (20, 2)]);
+    assert_eq!(ppu.frame_buffer[12], 2);
+    assert_eq!(ppu.frame_buffer[18], 2);
+    assert_eq!(ppu.frame_buffer[22], 1);
+}
+
+#[test]
+fn test_sprite_priority_offscreen_x_zero() {
+    // X=0 is fully offscreen and draws nothing; X=4 shows only its right half
+    let ppu = render_sprites(&[(0, 2), (4, 1)]);
+    assert_eq!(ppu.frame_buffer[0], 1);
+    assert_eq!(ppu.frame_buffer[3], 1);
+    assert_eq!(ppu.frame_buffer[4], 0);
+}
+
+#[test]
+fn test_sprite_left_edge_clips_partial_columns() {
+    // Tile 1 has only its leftmost column set (color 3)
+    for x in 1..8u8 {
+        for flip_x in [false, true] {
+            let mut ppu = Ppu::new();
+            for row in 0..8 {
+                ppu.vram[16 + row * 2] = 0x80;
+                ppu.vram[16 + row * 2 + 1] = 0x80;
+            }
+            ppu.oam[0] = 16;
+            ppu.oam[1] = x;
+            ppu.oam[2] = 1;
+            ppu.oam[3] = if flip_x { 0x20 } else { 0x00 };
+            ppu.bgp = 0xE4;
+            ppu.obp0 = 0xE4;
+            ppu.lcdc = LcdcFlags::from_byte(0x93);
+            ppu.step(456);
+
+            // Only the rightmost X columns are on screen; the flipped tile's set column is the
+            // last one, so it lands on screen column X-1, while the unflipped one is clipped away
+            let drawn: Vec<usize> = (0..8).filter(|&screen_x| ppu.frame_buffer[screen_x] != 0).collect();
+            let expected = if flip_x { vec![x as usize - 1] } else { vec![] };
+            assert_eq!(drawn, expected, "X={} flip_x={}", x, flip_x);
+        }
+    }
+}
+
+#[test]
+fn test_oam_scan_keeps_first_ten_sprites_in_oam_order() {
+    // 12 sprites on line 0; the last two sit furthest left but are past the 10-sprite limit
+    let sprites: Vec<(u8, u8)> = (0..12u8).map(|i| (if i < 10 { 40 + i * 8 } else { 8 + (i - 10) * 8 }, 1)).collect();
+    let ppu = render_sprites(&sprites);
+
+    let mut collected: Vec<u8> = ppu.scanline_sprites.iter().map(|sprite| sprite.oam_index).collect();
+    collected.sort();
+    assert_eq!(collected, (0..10).collect::<Vec<u8>>());
+    assert_eq!(ppu.frame_buffer[8], 0); // Sprite 10 isn't drawn
+    assert_eq!(ppu.frame_buffer[32], 1); // Sprite 0 is
+}
+
+#[test]
+fn test_oam_scan_clears_sprites_each_line() {
+    let mut ppu = render_sprites(&[(20, 1), (40, 1)]);
+    assert_eq!(ppu.scanline_sprites.len(), 2);
+
+    // Line 8 is below both 8x8 sprites
+    for _ in 0..8 {
+        ppu.step(456);
+    }
+    assert!(ppu.scanline_sprites.is_empty());
+}
+
+#[test]
+fn test_8x16_sprite_flip_y_swaps_tiles() {
+    let mut ppu = Ppu::new();
+    // Tile 2 (top) is color 1 with a color 3 last row, tile 3 (bottom) is color 2
+    fill_tile(&mut ppu.vram, 32, 1);
+    ppu.vram[32 + 14] = 0xFF;
+    ppu.vram[32 + 15] = 0xFF;
+    fill_tile(&mut ppu.vram, 48, 2);
+    ppu.oam[0] = 16;
+    ppu.oam[1] = 8;
+    ppu.oam[2] = 0x03; // Odd index is masked to the even top tile
+    ppu.oam[3] = 0x40; // Y-flip
+    ppu.bgp = 0xE4;
+    ppu.obp0 = 0xE4;
+    ppu.lcdc = LcdcFlags::from_byte(0x97); // LCD, unsigned tiles, 8x16 sprites, BG
+
+    for _ in 0..16 {
+        ppu.step(456);
+    }
+
+    // Flipped: the bottom tile shows on lines 0-7, the top tile on lines 8-15
+    assert_eq!(ppu.frame_buffer[0], 2);
+    assert_eq!(ppu.frame_buffer[7 * 160], 2);
+    assert_eq!(ppu.frame_buffer[8 * 160], 3); // Top tile's last row comes first
+    assert_eq!(ppu.frame_buffer[9 * 160], 1);
+    assert_eq!(ppu.frame_buffer[15 * 160], 1);
+}
+
+#[test]
+fn test_color_at_screen_position_reports_sprite_oam_index() {
+    // The later OAM entry sits further left, so it wins where they overlap
+    let ppu = render_sprites(&[(24, 1), (20, 2)]);
+    assert_eq!(ppu.get_color_at_screen_position(12, 0), (2, PpuLayer::Sprite(1)));
+    assert_eq!(ppu.get_color_at_screen_position(22, 0), (1, PpuLayer::Sprite(0)));
+    assert_eq!(ppu.get_color_at_screen_position(40, 0), (0, PpuLayer::Background(0)));
+}
+
+#[test]
+fn test_color_at_screen_position_reports_bg_and_window_tiles() {
+    let mut ppu = Ppu::new();
+    fill_tile(&mut ppu.vram, 16 * 5, 1);
+    fill_tile(&mut ppu.vram, 16 * 9, 3);
+    for entry in 0..(32 * 32) {
+        ppu.vram[0x1800 + entry] = 5; // Background map 0
+        ppu.vram[0x1C00 + entry] = 9; // Window map 1
+    }
+    ppu.bgp = 0xE4;
+    ppu.wy = 0;
+    ppu.wx = 87; // Window covers x >= 80
+    ppu.lcdc = LcdcFlags::from_byte(0xF1); // LCD, window map 1, window, unsigned tiles, BG
+
+    ppu.step(456);
+    assert_eq!(ppu.get_color_at_screen_position(10, 0), (1, PpuLayer::Background(5)));
+    assert_eq!(ppu.get_color_at_screen_position(80, 0), (3, PpuLayer::Window(9)));
+    assert_eq!(ppu.pixel_source[159], PpuLayer::Window(9));
+}
+
+#[test]
+fn test_color_correction_shades() {
+    let mut ppu = Ppu::new();
+    assert!(!ppu.color_correction_enabled());
+    assert_eq!(ppu.display_color(2), DMG_SHADES[2]);
+
+    ppu.color_correction(true);
+    assert_eq!(ppu.display_color(2), DMG_SHADES_CORRECTED[2]);
+
+    // White and black stay put; the gamma curve only moves the middle shades
+    for shade in [0, 3] {
+        assert_eq!(shade_rgba(shade, true), shade_rgba(shade, false));
+    }
+    for shade in [1, 2] {
+        assert_ne!(shade_rgba(shade, true), shade_rgba(shade, false));
+    }
+    // Still ordered from lightest to darkest
+    assert!(DMG_SHADES_CORRECTED.windows(2).all(|pair| pair[0][1] > pair[1][1]));
+}
+
+#[test]
+fn test_frames_rendered_counts_full_frames() {
+    let mut ppu = Ppu::new();
+    // One scanline at a time: 154 lines make a 70224-cycle frame
+    for _ in 0..154 * 5 {
+        ppu.step(456);
+    }
+    assert_eq!(ppu.frames_rendered(), 5);
+
+    ppu.step(456 * 100); // Part of a frame doesn't count yet
+    assert_eq!(ppu.frames_rendered(), 5);
+
+    ppu.reset_frame_counter();
+    assert_eq!(ppu.frames_rendered(), 0);
+    ppu.step(456 * 54);
+    assert_eq!(ppu.frames_rendered(), 1);
+}
+
+#[test]
+fn test_lcdc_tile_area_switch_leaves_vram_alone() {
+    let mut ppu = Ppu::new();
+    ppu.vram[0x0000] = 0xAA;
+    ppu.write_register(0xFF40, 0x81); // Signed tile addressing
+    assert_eq!(ppu.vram[0x1000], 0x00, "no tiles are copied into the 0x9000 area");
+}
+
+#[test]
+fn test_scx_write_during_drawing_applies_from_current_column() {
+    let mut ppu = Ppu::new();
+    fill_tile(&mut ppu.vram, 16, 3);
+    for tile_x in (1..32).step_by(2) {
+        ppu.vram[0x1800 + tile_x] = 0x01; // Odd tiles are color 3, even ones color 0
+    }
+    ppu.bgp = 0xE4;
+    ppu.write_register(0xFF40, 0x91);
+
+    // 80 OAM scan dots plus 80 drawing dots puts the write at column 80
+    ppu.step(160);
+    ppu.write_register(0xFF43, 8);
+    assert_eq!(ppu.read_register(0xFF43), 8);
+    ppu.step(296);
+
+    let expected = |x: usize, scx: usize| if ((x + scx) / 8) % 2 == 1 { 3 } else { 0 };
+    for x in 0..160 {
+        let scx = if x < 80 { 0 } else { 8 };
+        assert_eq!(ppu.frame_buffer[x], expected(x, scx), "line 0, x={}", x);
+    }
+
+    // The next line uses the new value throughout
+    ppu.step(456);
+    for x in 0..160 {
+        assert_eq!(ppu.frame_buffer[160 + x], expected(x, 8), "line 1, x={}", x);
+    }
+    assert_eq!(ppu.scx, 8);
+}
@@ -0,0 +1,65 @@
+use rgb::rgb::printer::{GameBoyPrinter, STATUS_CHECKSUM_ERROR, STATUS_UNPROCESSED_DATA};
+use rgb::rgb::serial::SerialDevice;
+use std::path::PathBuf;
+
+// Sends one framed packet and returns the (alive, status) response bytes
+fn send_packet(printer: &mut GameBoyPrinter, command: u8, compression: u8, data: &[u8], corrupt_checksum: bool) -> (u8, u8) {
+    let length = data.len() as u16;
+    let header = [command, compression, length as u8, (length >> 8) as u8];
+    let mut checksum = header.iter().chain(data).fold(0u16, |sum, &b| sum.wrapping_add(b as u16));
+    if corrupt_checksum {
+        checksum = checksum.wrapping_add(1);
+    }
+
+    for &byte in [0x88, 0x33].iter().chain(&header).chain(data) {
+        assert_eq!(printer.exchange(byte), 0x00);
+    }
+    printer.exchange(checksum as u8);
+    printer.exchange((checksum >> 8) as u8);
+    let alive = printer.exchange(0x00);
+    let status = printer.exchange(0x00);
+    (alive, status)
+}
+
+fn output_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rgb_printer_{}_{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_printer_status_responses() {
+    let mut printer = GameBoyPrinter::new(&output_dir("status"));
+
+    assert_eq!(send_packet(&mut printer, 0x01, 0, &[], false), (0x81, 0x00)); // INIT
+    let (_, status) = send_packet(&mut printer, 0x04, 0, &[0u8; 640], false); // DATA
+    assert_eq!(status & STATUS_UNPROCESSED_DATA, STATUS_UNPROCESSED_DATA);
+    let (_, status) = send_packet(&mut printer, 0x0F, 0, &[], true); // INQUIRY, bad checksum
+    assert_eq!(status & STATUS_CHECKSUM_ERROR, STATUS_CHECKSUM_ERROR);
+}
+
+#[test]
+fn test_printer_prints_png() {
+    let dir = output_dir("print");
+    let mut printer = GameBoyPrinter::new(&dir);
+
+    // Two rows of 20 tiles; tile 0 is solid color 3, the rest color 0.
+    // Sent compressed: a 16-byte run of 0xFF, then 624 zeros as runs of 129 + 129 + 129 + 129 + 108
+    let compressed = [0x80 | 14, 0xFF, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0x80 | 106, 0x00];
+    send_packet(&mut printer, 0x01, 0, &[], false);
+    send_packet(&mut printer, 0x04, 1, &compressed, false);
+    send_packet(&mut printer, 0x04, 0, &[], false);
+    let (_, status) = send_packet(&mut printer, 0x02, 0, &[0x01, 0x13, 0xE4, 0x40], false);
+    assert_eq!(status & STATUS_UNPROCESSED_DATA, 0);
+
+    let path = printer.last_print().expect("print should be saved").to_path_buf();
+    let decoder = png::Decoder::new(std::io::BufReader::new(std::fs::File::open(&path).unwrap()));
+    let mut reader = decoder.read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    reader.next_frame(&mut pixels).unwrap();
+
+    assert_eq!((reader.info().width, reader.info().height), (160, 16));
+    assert_eq!(pixels[0], 0x00); // Color 3 through palette 0xE4 is black
+    assert_eq!(pixels[8], 0xFF); // Neighbouring tile is white
+    std::fs::remove_dir_all(&dir).unwrap();
+}
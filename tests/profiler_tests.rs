@@ -0,0 +1,54 @@
+use rgb::rgb::config::EmulatorConfig;
+use rgb::rgb::memory::MemoryMap;
+use rgb::rgb::profiler::OpcodeProfile;
+
+#[test]
+fn test_profile_counts_regular_and_cb_opcodes() {
+    let mut profile = OpcodeProfile::new();
+    for _ in 0..3 {
+        profile.record(0x00, 0x00);
+    }
+    profile.record(0x3E, 0xCB); // The next byte only matters after a CB prefix
+    profile.record(0xCB, 0x37);
+    profile.record(0xCB, 0x37);
+
+    assert_eq!(profile.count(0x00, false), 3);
+    assert_eq!(profile.count(0x3E, false), 1);
+    assert_eq!(profile.count(0xCB, false), 0);
+    assert_eq!(profile.count(0x37, true), 2);
+    assert_eq!(profile.count(0x37, false), 0);
+
+    assert_eq!(profile.top(2), vec![(0x00, 3), (256 + 0x37, 2)]);
+    assert_eq!(profile.top(10).len(), 3);
+}
+
+#[test]
+fn test_profile_report_lists_mnemonics() {
+    let mut profile = OpcodeProfile::new();
+    profile.record(0x04, 0x00);
+    profile.record(0x04, 0x00);
+    profile.record(0xCB, 0x7C);
+
+    let report = profile.report(30);
+    let lines: Vec<&str> = report.lines().collect();
+    assert!(lines[0].contains("3 instructions"));
+    assert!(lines[2].starts_with("04 "));
+    assert!(lines[2].ends_with("INC B"));
+    assert!(lines[2].contains("66.7"));
+    assert!(lines[3].starts_with("CB 7C"));
+    assert!(lines[3].ends_with("BIT 7, H"));
+    assert_eq!(lines.len(), 4);
+}
+
+#[test]
+fn test_profile_record_at_reads_without_watchpoints() {
+    let mut mmap = MemoryMap::new_with_config(&EmulatorConfig { skip_boot: true, ..EmulatorConfig::default() });
+    mmap.write(0xC000, 0xCB);
+    mmap.write(0xC001, 0x11);
+    mmap.add_watchpoint(0xC000, rgb::rgb::memory::WatchKind::Read);
+
+    let mut profile = OpcodeProfile::new();
+    profile.record_at(&mmap, 0xC000);
+    assert_eq!(profile.count(0x11, true), 1);
+    assert_eq!(mmap.take_watchpoint_hit(), None);
+}
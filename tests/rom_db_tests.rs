@@ -0,0 +1,35 @@
+use rgb::rgb::rom_db::{identify_rom, identify_rom_in, sha256_hex, RomInfo};
+
+// Sorted by hash; the first entry is a synthetic 32 KiB ROM counting 0x00-0xFF
+static TEST_DATABASE: &[RomInfo] = &[
+    RomInfo { sha256: "c35020473aed1b4642cd726cad727b63fff2824ad68cedd7ffb73c7cbd890479", title: "Blank ROM", region: "World", revision: 0 },
+    RomInfo { sha256: "e11360251d1173650cdcd20f111d8f1ca2e412f572e8b36a4dc067121c1799b8", title: "Counting ROM", region: "Japan", revision: 1 },
+    RomInfo { sha256: "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855", title: "Empty ROM", region: "USA", revision: 2 },
+];
+
+fn counting_rom() -> Vec<u8> {
+    (0..0x8000).map(|i| i as u8).collect()
+}
+
+#[test]
+fn test_sha256_hex() {
+    assert_eq!(sha256_hex(&[]), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+}
+
+#[test]
+fn test_identify_rom_finds_entry() {
+    let info = identify_rom_in(&counting_rom(), TEST_DATABASE).expect("counting ROM is in the database");
+    assert_eq!(info.title, "Counting ROM");
+    assert_eq!(info.region, "Japan");
+    assert_eq!(info.revision, 1);
+
+    assert_eq!(identify_rom_in(&vec![0u8; 0x8000], TEST_DATABASE).map(|info| info.title), Some("Blank ROM"));
+}
+
+#[test]
+fn test_identify_rom_unknown() {
+    let mut rom = counting_rom();
+    rom[0x0150] ^= 0xFF; // One corrupted byte changes the hash
+    assert_eq!(identify_rom_in(&rom, TEST_DATABASE), None);
+    assert_eq!(identify_rom(&rom), None);
+}
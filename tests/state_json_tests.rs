@@ -0,0 +1,57 @@
+use rgb::rgb::cpu::CpuBuilder;
+use rgb::rgb::state_json::{export_state_as_json, import_state_from_json, StateJsonError};
+
+#[test]
+fn test_compact_export_fields() {
+    let mut cpu = CpuBuilder::new().with_sp(0xFFFE).build();
+    cpu.registers.a = 0x42;
+    cpu.pc = 0x0150;
+    cpu.mmap.write(0xC001, 0x99);
+    let json = export_state_as_json(&cpu, false);
+
+    assert!(!json.contains('\n'));
+    assert!(json.starts_with(r#"{"registers":{"a":66,"f":0,"#));
+    assert!(json.contains(r#""sp":65534,"pc":336,"ime":false,"halted":false}"#));
+    assert!(json.contains(r#""wram":[0,153,0,"#));
+    assert!(json.contains(r#""ppu":{"mode":"#));
+}
+
+#[test]
+fn test_pretty_export_round_trips() {
+    let mut cpu = CpuBuilder::new().with_sp(0xD000).build();
+    cpu.registers.b = 0x12;
+    cpu.registers.f = 0xB0.into();
+    cpu.pc = 0x4000;
+    cpu.ime = true;
+    cpu.mmap.write(0xC0FF, 0x55);
+    cpu.mmap.write(0xFF47, 0xE4); // BGP
+    cpu.mmap.write(0xFF42, 0x10); // SCY
+    let json = export_state_as_json(&cpu, true);
+    assert!(json.contains("\n  \"registers\": {\n    \"a\": 0,"));
+
+    let mut other = CpuBuilder::new().build();
+    import_state_from_json(&mut other, &json).unwrap();
+    assert_eq!(other.registers.b, 0x12);
+    assert_eq!(u8::from(other.registers.f), 0xB0);
+    assert_eq!((other.sp, other.pc, other.ime), (0xD000, 0x4000, true));
+    assert_eq!(other.mmap.read(0xC0FF), 0x55);
+    assert_eq!(other.mmap.read(0xFF47), 0xE4);
+    assert_eq!(other.mmap.read(0xFF42), 0x10);
+    // STAT's writable bits come back; its LYC flag is recomputed by the PPU
+    assert_eq!(other.mmap.read(0xFF41) & 0x78, cpu.mmap.read(0xFF41) & 0x78);
+}
+
+#[test]
+fn test_import_rejects_bad_json_without_changes() {
+    let mut cpu = CpuBuilder::new().build();
+    cpu.registers.a = 0x01;
+
+    assert_eq!(import_state_from_json(&mut cpu, "{\"registers\": "), Err(StateJsonError::Syntax(1, 14)));
+    let json = export_state_as_json(&cpu, false).replace("\"a\":1", "\"a\":300").replace("\"b\":0", "\"b\":7");
+    assert!(matches!(import_state_from_json(&mut cpu, &json), Err(StateJsonError::Invalid(message)) if message.contains("integer `300`")));
+    let json = export_state_as_json(&cpu, false).replace(",\"ie\":0", "");
+    assert!(matches!(import_state_from_json(&mut cpu, &json), Err(StateJsonError::Invalid(message)) if message.contains("missing field `ie`")));
+    let json = export_state_as_json(&cpu, false).replace("\"wram\":[0,", "\"wram\":[");
+    assert_eq!(import_state_from_json(&mut cpu, &json), Err(StateJsonError::Invalid("wram holds 255 bytes, expected 256".to_string())));
+    assert_eq!((cpu.registers.a, cpu.registers.b), (0x01, 0x00));
+}
@@ -0,0 +1,55 @@
+use rgb::rgb::timer::Timer;
+
+#[test]
+fn test_tima_increments_on_selected_bit_falling_edge() {
+    let mut timer = Timer::new();
+    timer.write_register(0xFF07, 0x05); // Enabled, 262144 Hz (counter bit 3)
+
+    timer.step(15);
+    assert_eq!(timer.read_register(0xFF05), 0);
+
+    timer.step(1);
+    assert_eq!(timer.read_register(0xFF05), 1);
+}
+
+#[test]
+fn test_div_reset_ticks_tima_when_selected_bit_set() {
+    let mut timer = Timer::new();
+    timer.write_register(0xFF07, 0x05); // Enabled, 262144 Hz (counter bit 3)
+
+    // Counter = 8: bit 3 set, no falling edge yet
+    timer.step(8);
+    assert_eq!(timer.read_register(0xFF05), 0);
+
+    // Resetting DIV drops bit 3 from 1 to 0
+    timer.write_register(0xFF04, 0);
+    assert_eq!(timer.read_register(0xFF05), 1);
+    assert_eq!(timer.read_register(0xFF04), 0);
+}
+
+#[test]
+fn test_div_reset_no_tick_when_selected_bit_clear() {
+    let mut timer = Timer::new();
+    timer.write_register(0xFF07, 0x05);
+
+    // Counter = 4: bit 3 clear
+    timer.step(4);
+    timer.write_register(0xFF04, 0);
+    assert_eq!(timer.read_register(0xFF05), 0);
+}
+
+#[test]
+fn test_div_reset_overflow_requests_interrupt() {
+    let mut timer = Timer::new();
+    timer.write_register(0xFF06, 0xAB); // TMA
+    timer.write_register(0xFF05, 0xFF); // TIMA about to overflow
+    timer.write_register(0xFF07, 0x05);
+
+    timer.step(8);
+    timer.write_register(0xFF04, 0);
+    assert_eq!(timer.read_register(0xFF05), 0xAB);
+
+    // The overflow is reported on the next step
+    assert!(timer.step(0));
+    assert!(!timer.step(0));
+}
@@ -0,0 +1,28 @@
+use rgb::rgb::cpu::CpuBuilder;
+use rgb::rgb::trace_ring::{RingTraceBuffer, TraceEntry};
+
+#[test]
+fn test_trace_entry_uses_trace_format() {
+    let mut cpu = CpuBuilder::new().with_sp(0xFFFE).build();
+    cpu.mmap.install_rom_patch(0x0000, &[0x3E, 0x42, 0x00, 0xC3]); // LD A,0x42; NOP; JP ...
+    cpu.registers.a = 0x01;
+
+    assert_eq!(TraceEntry::capture(&cpu).to_string(),
+               "A: 01 F: 00 B: 00 C: 00 D: 00 E: 00 H: 00 L: 00 SP: FFFE PC: 00:0000 (3E 42 00 C3)");
+}
+
+#[test]
+fn test_ring_keeps_the_newest_entries() {
+    let mut cpu = CpuBuilder::new().build();
+    let mut ring = RingTraceBuffer::new(3);
+    for pc in 0..5 {
+        cpu.pc = pc;
+        ring.push(TraceEntry::capture(&cpu));
+    }
+
+    let pcs: Vec<u16> = ring.entries().map(|entry| entry.pc).collect();
+    assert_eq!(pcs, vec![2, 3, 4]);
+    let report = ring.report();
+    assert!(report.starts_with("Last 3 instructions (oldest first):\n"));
+    assert_eq!(report.lines().count(), 4);
+}